@@ -0,0 +1,100 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! `cider-mpd`: serve the MPD protocol so MPD clients can control Cider.
+//!
+//! ```text
+//! cider-mpd [--host HOST] [--port PORT] [--token TOKEN] [--listen ADDR]
+//! ```
+//!
+//! The token may also be given in `CIDER_TOKEN`. `--listen` defaults to
+//! `127.0.0.1:6600`.
+
+use std::process::ExitCode;
+
+use cider_api::{CiderClient, MpdServer};
+
+const USAGE: &str = "usage: cider-mpd [--host HOST] [--port PORT] [--token TOKEN] [--listen ADDR]";
+
+const DEFAULT_LISTEN: &str = "127.0.0.1:6600";
+
+struct Args {
+    host: Option<String>,
+    port: Option<u16>,
+    token: Option<String>,
+    listen: String,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        host: None,
+        port: None,
+        token: std::env::var("CIDER_TOKEN").ok().filter(|t| !t.is_empty()),
+        listen: DEFAULT_LISTEN.to_owned(),
+    };
+    let mut argv = std::env::args().skip(1);
+    while let Some(flag) = argv.next() {
+        if flag == "-h" || flag == "--help" {
+            return Err(USAGE.to_owned());
+        }
+        let value = argv
+            .next()
+            .ok_or_else(|| format!("{flag} needs a value\n{USAGE}"))?;
+        match flag.as_str() {
+            "--host" => args.host = Some(value),
+            "--port" => {
+                args.port = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid port {value:?}"))?,
+                );
+            }
+            "--token" => args.token = Some(value),
+            "--listen" => args.listen = value,
+            other => return Err(format!("unknown argument {other:?}\n{USAGE}")),
+        }
+    }
+    Ok(args)
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut builder = CiderClient::builder();
+    if let Some(host) = args.host {
+        builder = builder.host(host);
+    }
+    if let Some(port) = args.port {
+        builder = builder.port(port);
+    }
+    if let Some(token) = args.token {
+        builder = builder.token(token);
+    }
+    let client = match builder.build() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("cider-mpd: {e}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match MpdServer::new(client).start(args.listen.as_str()).await {
+        Ok(handle) => {
+            eprintln!("cider-mpd: listening on {}", handle.local_addr());
+            handle.wait().await;
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("cider-mpd: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,119 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! `cider-mpris`: expose Cider as an MPRIS player on the D-Bus session bus.
+//!
+//! ```text
+//! cider-mpris [--host HOST] [--port PORT] [--token TOKEN] [--name NAME] [--interval MS]
+//! ```
+//!
+//! The token may also be given in `CIDER_TOKEN`.
+
+use std::process::ExitCode;
+use std::time::Duration;
+
+use cider_api::{CiderClient, MprisServer};
+
+const USAGE: &str =
+    "usage: cider-mpris [--host HOST] [--port PORT] [--token TOKEN] [--name NAME] [--interval MS]";
+
+struct Args {
+    host: Option<String>,
+    port: Option<u16>,
+    token: Option<String>,
+    name: Option<String>,
+    interval: Option<Duration>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        host: None,
+        port: None,
+        token: std::env::var("CIDER_TOKEN").ok().filter(|t| !t.is_empty()),
+        name: None,
+        interval: None,
+    };
+    let mut argv = std::env::args().skip(1);
+    while let Some(flag) = argv.next() {
+        if flag == "-h" || flag == "--help" {
+            return Err(USAGE.to_owned());
+        }
+        let value = argv
+            .next()
+            .ok_or_else(|| format!("{flag} needs a value\n{USAGE}"))?;
+        match flag.as_str() {
+            "--host" => args.host = Some(value),
+            "--port" => {
+                args.port = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid port {value:?}"))?,
+                );
+            }
+            "--token" => args.token = Some(value),
+            "--name" => args.name = Some(value),
+            "--interval" => {
+                let ms: u64 = value
+                    .parse()
+                    .ok()
+                    .filter(|&ms| ms > 0)
+                    .ok_or_else(|| format!("invalid interval {value:?}"))?;
+                args.interval = Some(Duration::from_millis(ms));
+            }
+            other => return Err(format!("unknown argument {other:?}\n{USAGE}")),
+        }
+    }
+    Ok(args)
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut builder = CiderClient::builder();
+    if let Some(host) = args.host {
+        builder = builder.host(host);
+    }
+    if let Some(port) = args.port {
+        builder = builder.port(port);
+    }
+    if let Some(token) = args.token {
+        builder = builder.token(token);
+    }
+    let client = match builder.build() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("cider-mpris: {e}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut server = MprisServer::new(client);
+    if let Some(name) = args.name {
+        server = server.name(name);
+    }
+    if let Some(interval) = args.interval {
+        server = server.poll_interval(interval);
+    }
+    let result = match server.start().await {
+        Ok(handle) => {
+            eprintln!("cider-mpris: serving {}", handle.bus_name());
+            handle.wait().await
+        }
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("cider-mpris: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
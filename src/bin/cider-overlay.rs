@@ -0,0 +1,183 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! `cider-overlay`: now-playing files and an HTML overlay for OBS.
+//!
+//! ```text
+//! cider-overlay [--listen ADDR | --no-server] [--css FILE]
+//!               [--dir DIR] [--format TEMPLATE] [--artwork-size PX]
+//!               [--host HOST] [--port PORT] [--token TOKEN]
+//! ```
+//!
+//! The overlay is served on `--listen`, `127.0.0.1:10769` by default;
+//! `--css` adds a stylesheet to it. `--dir` also writes `now-playing.txt`
+//! (from `--format`), `now-playing.json` and, with `--artwork-size`,
+//! `artwork.jpg` there. The token may also be given in `CIDER_TOKEN`.
+
+use std::process::ExitCode;
+
+use cider_api::{CiderClient, NowPlayingFiles, OverlayServer};
+
+const USAGE: &str = "usage: cider-overlay [--listen ADDR | --no-server] [--css FILE] \
+                     [--dir DIR] [--format TEMPLATE] [--artwork-size PX] \
+                     [--host HOST] [--port PORT] [--token TOKEN]";
+
+const DEFAULT_LISTEN: &str = "127.0.0.1:10769";
+
+struct Args {
+    host: Option<String>,
+    port: Option<u16>,
+    token: Option<String>,
+    listen: Option<String>,
+    css: Option<String>,
+    dir: Option<String>,
+    format: Option<String>,
+    artwork_size: Option<u32>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        host: None,
+        port: None,
+        token: std::env::var("CIDER_TOKEN").ok().filter(|t| !t.is_empty()),
+        listen: Some(DEFAULT_LISTEN.to_owned()),
+        css: None,
+        dir: None,
+        format: None,
+        artwork_size: None,
+    };
+    let mut argv = std::env::args().skip(1);
+    while let Some(flag) = argv.next() {
+        match flag.as_str() {
+            "-h" | "--help" => return Err(USAGE.to_owned()),
+            "--no-server" => {
+                args.listen = None;
+                continue;
+            }
+            _ => {}
+        }
+        let value = argv
+            .next()
+            .ok_or_else(|| format!("{flag} needs a value\n{USAGE}"))?;
+        match flag.as_str() {
+            "--host" => args.host = Some(value),
+            "--port" => {
+                args.port = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid port {value:?}"))?,
+                );
+            }
+            "--token" => args.token = Some(value),
+            "--listen" => args.listen = Some(value),
+            "--css" => args.css = Some(value),
+            "--dir" => args.dir = Some(value),
+            "--format" => args.format = Some(value),
+            "--artwork-size" => {
+                args.artwork_size = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|&size| size > 0)
+                        .ok_or_else(|| format!("invalid artwork size {value:?}"))?,
+                );
+            }
+            other => return Err(format!("unknown argument {other:?}\n{USAGE}")),
+        }
+    }
+    if args.listen.is_none() && args.dir.is_none() {
+        return Err(format!("--no-server needs --dir\n{USAGE}"));
+    }
+    if args.dir.is_none() && (args.format.is_some() || args.artwork_size.is_some()) {
+        return Err(format!("--format and --artwork-size need --dir\n{USAGE}"));
+    }
+    Ok(args)
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut builder = CiderClient::builder();
+    if let Some(host) = args.host {
+        builder = builder.host(host);
+    }
+    if let Some(port) = args.port {
+        builder = builder.port(port);
+    }
+    if let Some(token) = args.token {
+        builder = builder.token(token);
+    }
+    let client = match builder.build() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("cider-overlay: {e}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let files = match args.dir {
+        Some(dir) => {
+            let mut files = NowPlayingFiles::new(client.clone(), &dir);
+            if let Some(format) = args.format {
+                match format.parse() {
+                    Ok(template) => files = files.text_template(template),
+                    Err(e) => {
+                        eprintln!("cider-overlay: {e}");
+                        return ExitCode::from(2);
+                    }
+                }
+            }
+            if let Some(size) = args.artwork_size {
+                files = files.artwork(size);
+            }
+            match files.start() {
+                Ok(handle) => {
+                    eprintln!("cider-overlay: writing to {dir}");
+                    Some(handle)
+                }
+                Err(e) => {
+                    eprintln!("cider-overlay: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        None => None,
+    };
+
+    let Some(listen) = args.listen else {
+        if let Some(files) = files {
+            files.wait().await;
+        }
+        return ExitCode::SUCCESS;
+    };
+    let mut overlay = OverlayServer::new(client);
+    if let Some(path) = args.css {
+        match std::fs::read_to_string(&path) {
+            Ok(css) => overlay = overlay.stylesheet(css),
+            Err(e) => {
+                eprintln!("cider-overlay: cannot read {path}: {e}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+    match overlay.start(listen.as_str()).await {
+        Ok(handle) => {
+            eprintln!("cider-overlay: serving http://{}/", handle.local_addr());
+            handle.wait().await;
+            drop(files);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("cider-overlay: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,126 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! `cider-proxy`: re-expose Cider's playback API on the LAN behind scoped
+//! per-client tokens.
+//!
+//! ```text
+//! cider-proxy --tokens FILE [--audit-log FILE] [--listen ADDR]
+//!             [--host HOST] [--port PORT] [--token TOKEN]
+//! ```
+//!
+//! `--tokens` lists the proxy's tokens, one `name token scopes` line each
+//! (see `ProxyServer::load_tokens`). `--token` (or `CIDER_TOKEN`) is
+//! Cider's own token. `--listen` defaults to `127.0.0.1:10768`; use
+//! `0.0.0.0:10768` to serve the LAN.
+
+use std::process::ExitCode;
+
+use cider_api::{CiderClient, ProxyServer};
+
+const USAGE: &str = "usage: cider-proxy --tokens FILE [--audit-log FILE] [--listen ADDR] \
+                     [--host HOST] [--port PORT] [--token TOKEN]";
+
+const DEFAULT_LISTEN: &str = "127.0.0.1:10768";
+
+struct Args {
+    host: Option<String>,
+    port: Option<u16>,
+    token: Option<String>,
+    listen: String,
+    tokens: String,
+    audit_log: Option<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut host = None;
+    let mut port = None;
+    let mut token = std::env::var("CIDER_TOKEN").ok().filter(|t| !t.is_empty());
+    let mut listen = DEFAULT_LISTEN.to_owned();
+    let mut tokens = None;
+    let mut audit_log = None;
+    let mut argv = std::env::args().skip(1);
+    while let Some(flag) = argv.next() {
+        if flag == "-h" || flag == "--help" {
+            return Err(USAGE.to_owned());
+        }
+        let value = argv
+            .next()
+            .ok_or_else(|| format!("{flag} needs a value\n{USAGE}"))?;
+        match flag.as_str() {
+            "--host" => host = Some(value),
+            "--port" => {
+                port = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid port {value:?}"))?,
+                );
+            }
+            "--token" => token = Some(value),
+            "--listen" => listen = value,
+            "--tokens" => tokens = Some(value),
+            "--audit-log" => audit_log = Some(value),
+            other => return Err(format!("unknown argument {other:?}\n{USAGE}")),
+        }
+    }
+    Ok(Args {
+        host,
+        port,
+        token,
+        listen,
+        tokens: tokens.ok_or_else(|| format!("--tokens is required\n{USAGE}"))?,
+        audit_log,
+    })
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut builder = CiderClient::builder();
+    if let Some(host) = args.host {
+        builder = builder.host(host);
+    }
+    if let Some(port) = args.port {
+        builder = builder.port(port);
+    }
+    if let Some(token) = args.token {
+        builder = builder.token(token);
+    }
+    let client = match builder.build() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("cider-proxy: {e}");
+            return ExitCode::from(2);
+        }
+    };
+    let mut proxy = match ProxyServer::new(client).load_tokens(&args.tokens) {
+        Ok(proxy) => proxy,
+        Err(e) => {
+            eprintln!("cider-proxy: {e}");
+            return ExitCode::from(2);
+        }
+    };
+    if let Some(path) = args.audit_log {
+        proxy = proxy.audit_log(path);
+    }
+
+    match proxy.start(args.listen.as_str()).await {
+        Ok(handle) => {
+            eprintln!("cider-proxy: listening on {}", handle.local_addr());
+            handle.wait().await;
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("cider-proxy: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
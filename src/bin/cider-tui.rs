@@ -0,0 +1,851 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! `cider-tui`: a terminal now-playing dashboard for Cider.
+//!
+//! ```text
+//! cider-tui [--host HOST] [--port PORT] [--token TOKEN]
+//! ```
+//!
+//! The token may also be given in `CIDER_TOKEN`.
+//!
+//! | Key | Action |
+//! |---|---|
+//! | `Space` | Play / pause |
+//! | `n` / `p` | Next / previous track |
+//! | `←` / `→` | Seek back / forward 10 seconds |
+//! | `-` / `+` | Volume down / up 5% |
+//! | `↑` / `↓` (`k` / `j`) | Select an upcoming queue item |
+//! | `K` / `J` | Move the selected item up / down |
+//! | `Del` / `x` | Remove the selected item |
+//! | `l` / `u` | Like / dislike the current track |
+//! | `a` | Add the current track to the library |
+//! | `r` | Refresh now |
+//! | `q` / `Esc` | Quit |
+
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use cider_api::{
+    format_duration, whole_percent, CiderClient, CiderError, NowPlaying, QueueItem, Rating,
+};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Gauge, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use tokio::sync::mpsc;
+
+const USAGE: &str = "usage: cider-tui [--host HOST] [--port PORT] [--token TOKEN]";
+
+/// Time between player polls.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Polls between queue refreshes when the track has not changed.
+const QUEUE_EVERY: u32 = 5;
+
+/// Time between redraws, which advance the progress bar.
+const FRAME_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long a status message stays visible.
+const MESSAGE_TIMEOUT: Duration = Duration::from_secs(4);
+
+/// Seek step for `←` / `→`, in milliseconds.
+const SEEK_STEP_MS: i64 = 10_000;
+
+/// Volume step for `-` / `+`.
+const VOLUME_STEP: f32 = 0.05;
+
+/// History items shown above the current track.
+const HISTORY_SHOWN: usize = 3;
+
+struct Args {
+    host: Option<String>,
+    port: Option<u16>,
+    token: Option<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        host: None,
+        port: None,
+        token: std::env::var("CIDER_TOKEN").ok().filter(|t| !t.is_empty()),
+    };
+    let mut argv = std::env::args().skip(1);
+    while let Some(flag) = argv.next() {
+        if flag == "-h" || flag == "--help" {
+            return Err(USAGE.to_owned());
+        }
+        let value = argv
+            .next()
+            .ok_or_else(|| format!("{flag} needs a value\n{USAGE}"))?;
+        match flag.as_str() {
+            "--host" => args.host = Some(value),
+            "--port" => {
+                args.port = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid port {value:?}"))?,
+                );
+            }
+            "--token" => args.token = Some(value),
+            other => return Err(format!("unknown argument {other:?}\n{USAGE}")),
+        }
+    }
+    Ok(args)
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut builder = CiderClient::builder();
+    if let Some(host) = args.host {
+        builder = builder.host(host);
+    }
+    if let Some(port) = args.port {
+        builder = builder.port(port);
+    }
+    if let Some(token) = args.token {
+        builder = builder.token(token);
+    }
+    let client = match builder.build() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("cider-tui: {e}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let terminal = ratatui::init();
+    let result = run(terminal, client).await;
+    ratatui::restore();
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("cider-tui: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Something for the UI loop to handle.
+enum Update {
+    Key(KeyEvent),
+    /// A poll of the player, and the queue if it was fetched.
+    Polled(Box<Result<Poll, CiderError>>),
+    /// A command finished.
+    Done(Result<String, CiderError>),
+}
+
+/// Player state from one poll.
+struct Poll {
+    track: Option<NowPlaying>,
+    is_playing: bool,
+    volume: f32,
+    queue: Option<Vec<QueueItem>>,
+}
+
+async fn run(mut terminal: DefaultTerminal, client: CiderClient) -> std::io::Result<()> {
+    let (tx, mut updates) = mpsc::unbounded_channel();
+    let keys = tx.clone();
+    std::thread::spawn(move || read_keys(&keys));
+    let (refresh, refreshes) = mpsc::unbounded_channel();
+    tokio::spawn(poll(client.clone(), tx.clone(), refreshes));
+
+    let mut app = App::default();
+    let mut frames = tokio::time::interval(FRAME_INTERVAL);
+    loop {
+        terminal.draw(|frame| app.render(frame, Instant::now()))?;
+        tokio::select! {
+            _ = frames.tick() => {}
+            Some(update) = updates.recv() => match update {
+                Update::Key(key) => {
+                    let Some(action) = App::action(key) else { continue };
+                    if action == Action::Quit {
+                        return Ok(());
+                    }
+                    if action == Action::Refresh {
+                        let _ = refresh.send(true);
+                    } else if let Some(command) = app.command(action) {
+                        let client = client.clone();
+                        let tx = tx.clone();
+                        let refresh = refresh.clone();
+                        tokio::spawn(async move {
+                            let result = command.run(&client).await;
+                            let _ = tx.send(Update::Done(result));
+                            let _ = refresh.send(command.changes_queue());
+                        });
+                    }
+                }
+                Update::Polled(result) => app.polled(*result, Instant::now()),
+                Update::Done(result) => app.done(result, Instant::now()),
+            },
+        }
+    }
+}
+
+/// Forward key presses until the UI goes away.
+fn read_keys(tx: &mpsc::UnboundedSender<Update>) {
+    loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                if tx.send(Update::Key(key)).is_err() {
+                    return;
+                }
+            }
+            Ok(_) => {}
+            Err(_) => return,
+        }
+    }
+}
+
+/// Poll the player every second, and the queue on track changes, every few
+/// polls, or when asked to (`true` on `refreshes`).
+async fn poll(
+    client: CiderClient,
+    tx: mpsc::UnboundedSender<Update>,
+    mut refreshes: mpsc::UnboundedReceiver<bool>,
+) {
+    let mut ticker = tokio::time::interval(POLL_INTERVAL);
+    let mut last_track = None;
+    let mut polls = 0;
+    loop {
+        let mut with_queue = polls % QUEUE_EVERY == 0;
+        tokio::select! {
+            _ = ticker.tick() => {}
+            refresh = refreshes.recv() => match refresh {
+                Some(queue) => with_queue |= queue,
+                None => return,
+            },
+        }
+        polls += 1;
+        let result = async {
+            let track = client.now_playing().await?;
+            let track_id = track.as_ref().and_then(|t| t.song_id().map(str::to_owned));
+            with_queue |= track_id != last_track;
+            last_track = track_id;
+            Ok(Poll {
+                track,
+                is_playing: client.is_playing().await?,
+                volume: client.get_volume().await?,
+                queue: if with_queue {
+                    Some(client.get_queue().await?)
+                } else {
+                    None
+                },
+            })
+        }
+        .await;
+        if result.is_err() {
+            // Fetch everything once Cider is back.
+            last_track = None;
+        }
+        if tx.send(Update::Polled(Box::new(result))).is_err() {
+            return;
+        }
+    }
+}
+
+/// What a key asks for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    Quit,
+    Refresh,
+    PlayPause,
+    Next,
+    Previous,
+    SeekBy(i64),
+    VolumeBy(f32),
+    Select(isize),
+    MoveSelected(isize),
+    RemoveSelected,
+    Rate(Rating),
+    AddToLibrary,
+}
+
+/// A call to make on Cider.
+#[derive(Debug, Clone, PartialEq)]
+enum Command {
+    PlayPause,
+    Next,
+    Previous,
+    SeekBy(i64),
+    SetVolume(f32),
+    /// 1-based queue positions.
+    Move(u32, u32),
+    Remove(u32),
+    Rate(Rating),
+    AddToLibrary,
+}
+
+impl Command {
+    /// Run the command, returning a message for the status line.
+    async fn run(&self, client: &CiderClient) -> Result<String, CiderError> {
+        let message = match *self {
+            Self::PlayPause => {
+                client.play_pause().await?;
+                String::new()
+            }
+            Self::Next => {
+                client.next().await?;
+                String::new()
+            }
+            Self::Previous => {
+                client.previous().await?;
+                String::new()
+            }
+            Self::SeekBy(offset_ms) => {
+                let landed = client.seek_by(offset_ms).await?;
+                format!("Seeked to {}", format_duration(landed))
+            }
+            Self::SetVolume(volume) => {
+                client.set_volume(volume).await?;
+                format!("Volume {}%", whole_percent(f64::from(volume)))
+            }
+            Self::Move(from, to) => {
+                client.queue_move_to_position(from, to).await?;
+                String::new()
+            }
+            Self::Remove(index) => {
+                client.queue_remove_by_index(index).await?;
+                "Removed from the queue".to_owned()
+            }
+            Self::Rate(rating) => {
+                client.set_rating(rating).await?;
+                match rating {
+                    Rating::Like => "Liked",
+                    Rating::Dislike => "Disliked",
+                    Rating::None => "Rating cleared",
+                }
+                .to_owned()
+            }
+            Self::AddToLibrary => {
+                client.add_to_library().await?;
+                "Added to the library".to_owned()
+            }
+        };
+        Ok(message)
+    }
+
+    fn changes_queue(&self) -> bool {
+        matches!(
+            self,
+            Self::Next | Self::Previous | Self::Move(..) | Self::Remove(_)
+        )
+    }
+}
+
+/// Dashboard state.
+#[derive(Default)]
+struct App {
+    track: Option<NowPlaying>,
+    is_playing: bool,
+    volume: f32,
+    queue: Vec<QueueItem>,
+    /// When `track` was fetched, to advance its position locally.
+    fetched_at: Option<Instant>,
+    /// Index into `queue` of the selected upcoming item.
+    selected: Option<usize>,
+    /// The last poll error while Cider is unreachable.
+    offline: Option<String>,
+    message: Option<(String, Instant)>,
+}
+
+impl App {
+    fn action(key: KeyEvent) -> Option<Action> {
+        let action = match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+            KeyCode::Char('r') => Action::Refresh,
+            KeyCode::Char(' ') => Action::PlayPause,
+            KeyCode::Char('n') => Action::Next,
+            KeyCode::Char('p') => Action::Previous,
+            KeyCode::Left => Action::SeekBy(-SEEK_STEP_MS),
+            KeyCode::Right => Action::SeekBy(SEEK_STEP_MS),
+            KeyCode::Char('-' | '_') => Action::VolumeBy(-VOLUME_STEP),
+            KeyCode::Char('+' | '=') => Action::VolumeBy(VOLUME_STEP),
+            KeyCode::Up | KeyCode::Char('k') => Action::Select(-1),
+            KeyCode::Down | KeyCode::Char('j') => Action::Select(1),
+            KeyCode::Char('K') => Action::MoveSelected(-1),
+            KeyCode::Char('J') => Action::MoveSelected(1),
+            KeyCode::Delete | KeyCode::Char('x') => Action::RemoveSelected,
+            KeyCode::Char('l') => Action::Rate(Rating::Like),
+            KeyCode::Char('u') => Action::Rate(Rating::Dislike),
+            KeyCode::Char('a') => Action::AddToLibrary,
+            _ => return None,
+        };
+        Some(action)
+    }
+
+    /// Index of the current queue item, if Cider marks one.
+    fn current_index(&self) -> Option<usize> {
+        self.queue.iter().position(QueueItem::is_current)
+    }
+
+    /// Indices of the items after the current one.
+    fn up_next(&self) -> std::ops::Range<usize> {
+        let start = self.current_index().map_or(0, |i| i + 1);
+        start..self.queue.len()
+    }
+
+    /// Keep the selection on an upcoming item.
+    fn clamp_selection(&mut self) {
+        let up_next = self.up_next();
+        self.selected = if up_next.is_empty() {
+            None
+        } else {
+            Some(
+                self.selected
+                    .unwrap_or(up_next.start)
+                    .clamp(up_next.start, up_next.end - 1),
+            )
+        };
+    }
+
+    /// Apply a key action locally and return the call it needs, if any.
+    fn command(&mut self, action: Action) -> Option<Command> {
+        match action {
+            Action::Quit | Action::Refresh => None,
+            Action::PlayPause => {
+                self.is_playing = !self.is_playing;
+                Some(Command::PlayPause)
+            }
+            Action::Next => Some(Command::Next),
+            Action::Previous => Some(Command::Previous),
+            Action::SeekBy(offset_ms) => Some(Command::SeekBy(offset_ms)),
+            Action::VolumeBy(delta) => {
+                self.volume = (self.volume + delta).clamp(0.0, 1.0);
+                Some(Command::SetVolume(self.volume))
+            }
+            Action::Select(step) => {
+                let up_next = self.up_next();
+                if let Some(selected) = self.selected {
+                    let target = selected.saturating_add_signed(step);
+                    if up_next.contains(&target) {
+                        self.selected = Some(target);
+                    }
+                }
+                None
+            }
+            Action::MoveSelected(step) => {
+                let selected = self.selected?;
+                let target = selected.saturating_add_signed(step);
+                if !self.up_next().contains(&target) {
+                    return None;
+                }
+                self.queue.swap(selected, target);
+                self.selected = Some(target);
+                Some(Command::Move(position(selected)?, position(target)?))
+            }
+            Action::RemoveSelected => {
+                let selected = self.selected?;
+                self.queue.remove(selected);
+                self.clamp_selection();
+                Some(Command::Remove(position(selected)?))
+            }
+            Action::Rate(rating) => Some(Command::Rate(rating)),
+            Action::AddToLibrary => Some(Command::AddToLibrary),
+        }
+    }
+
+    fn polled(&mut self, result: Result<Poll, CiderError>, now: Instant) {
+        match result {
+            Ok(poll) => {
+                self.track = poll.track;
+                self.is_playing = poll.is_playing;
+                self.volume = poll.volume;
+                self.fetched_at = Some(now);
+                if let Some(queue) = poll.queue {
+                    self.queue = queue;
+                    self.clamp_selection();
+                }
+                self.offline = None;
+            }
+            Err(e) => self.offline = Some(e.to_string()),
+        }
+    }
+
+    fn done(&mut self, result: Result<String, CiderError>, now: Instant) {
+        let message = match result {
+            Ok(message) if message.is_empty() => return,
+            Ok(message) => message,
+            Err(e) => format!("Error: {e}"),
+        };
+        self.message = Some((message, now));
+    }
+
+    /// The track position, advanced by the time since the last poll while
+    /// playing.
+    fn position(&self, now: Instant) -> Duration {
+        let Some(track) = &self.track else {
+            return Duration::ZERO;
+        };
+        let mut position = track.position();
+        if self.is_playing {
+            if let Some(fetched_at) = self.fetched_at {
+                position += now.saturating_duration_since(fetched_at);
+            }
+        }
+        if track.duration_in_millis > 0 {
+            position = position.min(track.duration());
+        }
+        position
+    }
+
+    fn render(&self, frame: &mut Frame, now: Instant) {
+        let [top, middle, bottom] = Layout::vertical([
+            Constraint::Length(8),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        self.render_track(frame, top, now);
+        self.render_queue(frame, middle);
+        let footer = match &self.message {
+            Some((message, at)) if now.saturating_duration_since(*at) < MESSAGE_TIMEOUT => {
+                Line::from(message.as_str()).yellow()
+            }
+            _ => Line::from(
+                "space play/pause · n/p next/prev · ←/→ seek · -/+ volume · \
+                 j/k select · J/K move · x remove · l/u like/dislike · a add · q quit",
+            )
+            .dark_gray(),
+        };
+        frame.render_widget(Paragraph::new(footer), bottom);
+    }
+
+    fn render_track(&self, frame: &mut Frame, area: Rect, now: Instant) {
+        let block = Block::bordered().title(" Now playing ");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        if let Some(error) = &self.offline {
+            let text = vec![
+                Line::from("Cider is not reachable").red().bold(),
+                Line::from(error.as_str()).dark_gray(),
+            ];
+            frame.render_widget(Paragraph::new(text), inner);
+            return;
+        }
+        let Some(track) = &self.track else {
+            frame.render_widget(Paragraph::new("Nothing playing").dark_gray(), inner);
+            return;
+        };
+
+        let [text_area, gauge_area, state_area] = Layout::vertical([
+            Constraint::Length(4),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+        let mut lines = vec![
+            Line::from(track.name.as_str()).bold(),
+            Line::from(track.artist_name.as_str()),
+            Line::from(track.album_name.as_str()).dark_gray(),
+        ];
+        let badges = badges(track);
+        if !badges.is_empty() {
+            lines.push(Line::from(
+                badges
+                    .into_iter()
+                    .flat_map(|badge| {
+                        [
+                            Span::styled(
+                                format!(" {badge} "),
+                                Style::new().fg(Color::Black).bg(Color::Cyan),
+                            ),
+                            Span::raw(" "),
+                        ]
+                    })
+                    .collect::<Vec<_>>(),
+            ));
+        }
+        frame.render_widget(Paragraph::new(lines), text_area);
+
+        let position = self.position(now);
+        let duration = track.duration();
+        let ratio = if duration.is_zero() {
+            0.0
+        } else {
+            (position.as_secs_f64() / duration.as_secs_f64()).clamp(0.0, 1.0)
+        };
+        let gauge = Gauge::default()
+            .ratio(ratio)
+            .label(format!(
+                "{} / {}",
+                format_duration(position),
+                format_duration(duration)
+            ))
+            .gauge_style(Style::new().fg(Color::Magenta).bg(Color::DarkGray));
+        frame.render_widget(gauge, gauge_area);
+
+        let state = format!(
+            "{}  ·  Volume {}%",
+            if self.is_playing {
+                "▶ Playing"
+            } else {
+                "⏸ Paused"
+            },
+            whole_percent(f64::from(self.volume))
+        );
+        frame.render_widget(Paragraph::new(state), state_area);
+    }
+
+    fn render_queue(&self, frame: &mut Frame, area: Rect) {
+        let current = self.current_index();
+        let history_start = current.map_or(0, |i| i.saturating_sub(HISTORY_SHOWN));
+        let mut items = Vec::new();
+        let mut selected_row = None;
+        if let Some(current) = current {
+            if current > 0 {
+                items.push(ListItem::new(Line::from("History").dark_gray().italic()));
+            }
+            for item in &self.queue[history_start..current] {
+                items.push(ListItem::new(
+                    Line::from(format!("   {}", describe(item))).dark_gray(),
+                ));
+            }
+            items.push(ListItem::new(
+                Line::from(format!(" ▶ {}", describe(&self.queue[current])))
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        let up_next = self.up_next();
+        items.push(ListItem::new(Line::from("Up next").dark_gray().italic()));
+        if up_next.is_empty() {
+            items.push(ListItem::new(Line::from("   (empty)").dark_gray()));
+        }
+        for i in up_next {
+            if self.selected == Some(i) {
+                selected_row = Some(items.len());
+            }
+            items.push(ListItem::new(format!(
+                "{:>2} {}",
+                i + 1,
+                describe(&self.queue[i])
+            )));
+        }
+
+        let list = List::new(items)
+            .block(Block::bordered().title(" Queue "))
+            .highlight_style(Style::new().reversed());
+        let mut state = ListState::default().with_selected(selected_row);
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+/// A queue index as Cider's 1-based position.
+fn position(index: usize) -> Option<u32> {
+    u32::try_from(index + 1).ok()
+}
+
+/// Labels for the track's audio traits and mastering.
+fn badges(track: &NowPlaying) -> Vec<String> {
+    let mut badges: Vec<String> = track
+        .audio_traits
+        .iter()
+        .filter_map(|t| match t.as_str() {
+            "lossless" => Some("Lossless".to_owned()),
+            "hi-res-lossless" => Some("Hi-Res Lossless".to_owned()),
+            "atmos" => Some("Dolby Atmos".to_owned()),
+            "spatial" => Some("Spatial".to_owned()),
+            "surround" => Some("Surround".to_owned()),
+            // Every track has a lossy stream.
+            "lossy-stereo" => None,
+            other => Some(other.to_owned()),
+        })
+        .collect();
+    if track.is_apple_digital_master {
+        badges.push("Apple Digital Master".to_owned());
+    }
+    badges
+}
+
+fn describe(item: &QueueItem) -> String {
+    match &item.attributes {
+        Some(attributes) => format!(
+            "{} — {}  {}",
+            attributes.name,
+            attributes.artist_name,
+            format_duration(Duration::from_millis(attributes.duration_in_millis))
+        ),
+        None => item.id.clone().unwrap_or_default(),
+    }
+}
+
+/// `M:SS`, or `H:MM:SS` from an hour up.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn track() -> NowPlaying {
+        serde_json::from_value(serde_json::json!({
+            "name": "Never Be Like You",
+            "artistName": "Flume",
+            "albumName": "Skin",
+            "durationInMillis": 234_000,
+            "currentPlaybackTime": 42.5,
+            "audioTraits": ["lossless", "lossy-stereo", "atmos"],
+            "isAppleDigitalMaster": true,
+        }))
+        .unwrap()
+    }
+
+    fn queue(current: usize, len: usize) -> Vec<QueueItem> {
+        (0..len)
+            .map(|i| {
+                let mut item = serde_json::json!({
+                    "id": i.to_string(),
+                    "attributes": {
+                        "name": format!("Song {i}"),
+                        "artistName": "Flume",
+                        "durationInMillis": 200_000,
+                    },
+                });
+                if i == current {
+                    item["_state"] = serde_json::json!({ "current": 2 });
+                }
+                serde_json::from_value(item).unwrap()
+            })
+            .collect()
+    }
+
+    fn app(now: Instant) -> App {
+        let mut app = App::default();
+        app.polled(
+            Ok(Poll {
+                track: Some(track()),
+                is_playing: true,
+                volume: 0.5,
+                queue: Some(queue(1, 4)),
+            }),
+            now,
+        );
+        app
+    }
+
+    fn screen(app: &App, now: Instant) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|frame| app.render(frame, now)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn position_is_interpolated_while_playing() {
+        let now = Instant::now();
+        let mut app = app(now);
+        let later = now + Duration::from_secs(3);
+        assert_eq!(app.position(later), Duration::from_millis(45_500));
+        // Never past the end.
+        assert_eq!(
+            app.position(now + Duration::from_secs(600)),
+            Duration::from_secs(234)
+        );
+        app.is_playing = false;
+        assert_eq!(app.position(later), Duration::from_millis(42_500));
+    }
+
+    #[test]
+    fn dashboard_shows_track_and_queue() {
+        let now = Instant::now();
+        let text = screen(&app(now), now);
+        for expected in [
+            "Never Be Like You",
+            "Lossless",
+            "Dolby Atmos",
+            "Apple Digital Master",
+            "0:42 / 3:54",
+            "▶ Playing  ·  Volume 50%",
+            "History",
+            "Song 0",
+            "▶ Song 1",
+            "Up next",
+            " 3 Song 2",
+            " 4 Song 3",
+        ] {
+            assert!(text.contains(expected), "missing {expected:?} in\n{text}");
+        }
+        assert!(!text.contains("lossy-stereo"));
+    }
+
+    #[test]
+    fn offline_is_shown() {
+        let now = Instant::now();
+        let mut app = app(now);
+        app.polled(Err(CiderError::Config("gone".to_owned())), now);
+        let text = screen(&app, now);
+        assert!(text.contains("Cider is not reachable"), "{text}");
+    }
+
+    #[test]
+    fn selection_stays_in_up_next() {
+        let now = Instant::now();
+        let mut app = app(now);
+        assert_eq!(app.selected, Some(2));
+        assert_eq!(app.command(Action::Select(-1)), None);
+        assert_eq!(app.selected, Some(2));
+        app.command(Action::Select(1));
+        app.command(Action::Select(1));
+        assert_eq!(app.selected, Some(3));
+    }
+
+    #[test]
+    fn queue_edits_use_one_based_positions() {
+        let now = Instant::now();
+        let mut app = app(now);
+        assert_eq!(
+            app.command(Action::MoveSelected(1)),
+            Some(Command::Move(3, 4))
+        );
+        assert_eq!(app.selected, Some(3));
+        assert_eq!(app.queue[3].attributes.as_ref().unwrap().name, "Song 2");
+        // Cannot move above the current track.
+        app.command(Action::Select(-1));
+        assert_eq!(app.command(Action::MoveSelected(-1)), None);
+
+        assert_eq!(
+            app.command(Action::RemoveSelected),
+            Some(Command::Remove(3))
+        );
+        assert_eq!(app.queue.len(), 3);
+        assert_eq!(app.selected, Some(2));
+    }
+
+    #[test]
+    fn volume_is_clamped() {
+        let now = Instant::now();
+        let mut app = app(now);
+        app.volume = 0.98;
+        assert_eq!(
+            app.command(Action::VolumeBy(VOLUME_STEP)),
+            Some(Command::SetVolume(1.0))
+        );
+    }
+
+    #[test]
+    fn keys() {
+        let key = |code| App::action(KeyEvent::from(code));
+        assert_eq!(key(KeyCode::Char(' ')), Some(Action::PlayPause));
+        assert_eq!(key(KeyCode::Left), Some(Action::SeekBy(-SEEK_STEP_MS)));
+        assert_eq!(key(KeyCode::Char('J')), Some(Action::MoveSelected(1)));
+        assert_eq!(key(KeyCode::Char('?')), None);
+    }
+}
@@ -0,0 +1,806 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! `cider`: control Cider from the shell.
+//!
+//! ```text
+//! cider status
+//! cider play https://music.apple.com/ca/album/skin/1719860281
+//! cider seek +15s
+//! cider queue list|move FROM TO|rm INDEX|clear
+//! cider vol 40%
+//! cider repeat all
+//! cider amapi /v1/catalog/ca/search?term=flume
+//! cider status-line --waybar --follow
+//! ```
+//!
+//! `--json` switches the output to JSON. The exit status tells what went
+//! wrong:
+//!
+//! | Code | Meaning |
+//! |---|---|
+//! | 0 | Success |
+//! | 1 | Cider rejected the request or answered unexpectedly |
+//! | 2 | Invalid arguments |
+//! | 3 | Cider is not running or did not answer |
+//! | 4 | The API token is missing or wrong |
+//! | 5 | Nothing is playing |
+
+use std::fmt::Write as _;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use cider_api::{
+    format_duration, whole_percent, AppleMusicUrl, CiderClient, CiderError, FadeCurve, ItemKind,
+    ItemRef, NowPlaying, QueueItem, Rating, RepeatMode, ShuffleMode, StatusEscape, StatusLine,
+    WatchEvent,
+};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde_json::json;
+
+const EXIT_ERROR: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_NOT_RUNNING: u8 = 3;
+const EXIT_UNAUTHORIZED: u8 = 4;
+const EXIT_NOTHING_PLAYING: u8 = 5;
+
+const DEFAULT_STATUS_FORMAT: &str = "{artist} — {name}";
+const DEFAULT_STATUS_TOOLTIP: &str = "{name}\n{artist}\n{album}\n{position} / {duration}";
+
+/// Control Cider from the command line.
+#[derive(Debug, Parser)]
+#[command(name = "cider", version)]
+struct Cli {
+    /// Host running Cider.
+    #[arg(long, global = true)]
+    host: Option<String>,
+
+    /// Cider's RPC port [default: 10767].
+    #[arg(long, global = true)]
+    port: Option<u16>,
+
+    /// API token from Cider's settings.
+    #[arg(long, env = "CIDER_TOKEN", hide_env_values = true, global = true)]
+    token: Option<String>,
+
+    /// Print JSON instead of text.
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Show the track, play state, volume, repeat and shuffle.
+    Status,
+    /// Check that Cider is running.
+    Active,
+    /// Show the current track (exit status 5 if there is none).
+    NowPlaying,
+    /// Print whether a track is playing.
+    IsPlaying,
+    /// Resume playback, or play an Apple Music link or `kind:id` item.
+    Play {
+        /// A `music.apple.com` link or an item such as `songs:1719861213`.
+        item: Option<String>,
+    },
+    /// Play an Apple Music API href such as `/v1/catalog/ca/songs/1719861213`.
+    PlayHref { href: String },
+    /// Add a link or `kind:id` item right after the current track.
+    PlayNext { item: String },
+    /// Add a link or `kind:id` item to the end of the queue.
+    PlayLater { item: String },
+    /// Pause playback.
+    Pause,
+    /// Toggle between playing and paused.
+    Toggle,
+    /// Stop playback.
+    Stop,
+    /// Skip to the next track.
+    Next,
+    /// Go back to the previous track.
+    #[command(alias = "prev")]
+    Previous,
+    /// Seek to a position (`90`, `1:30`) or by an offset (`+15s`, `-1m`).
+    Seek {
+        #[arg(allow_hyphen_values = true, value_parser = parse_seek)]
+        position: Seek,
+    },
+    /// List or edit the queue.
+    #[command(subcommand)]
+    Queue(QueueCommand),
+    /// Show or set the volume (`40%`, `0.4`, `+10%`, `-5%`).
+    #[command(alias = "volume")]
+    Vol {
+        #[arg(allow_hyphen_values = true, value_parser = parse_volume)]
+        level: Option<Volume>,
+        /// Ramp to the new level over this long (`3s`, `1m`).
+        #[arg(long, value_parser = parse_duration)]
+        fade: Option<Duration>,
+    },
+    /// Show or set the repeat mode.
+    Repeat { mode: Option<RepeatArg> },
+    /// Show or set shuffle.
+    Shuffle { mode: Option<SwitchArg> },
+    /// Show or set autoplay.
+    Autoplay { mode: Option<SwitchArg> },
+    /// Rate the current track.
+    Rate { rating: RatingArg },
+    /// Add the current track to the library.
+    #[command(alias = "add")]
+    AddToLibrary,
+    /// Run an Apple Music API request through Cider and print the JSON.
+    Amapi {
+        /// API path such as `/v1/catalog/ca/search?term=flume&types=songs`.
+        path: String,
+    },
+    /// Print a templated line for waybar, polybar or tmux.
+    StatusLine(StatusLineArgs),
+}
+
+#[derive(Debug, Args)]
+struct StatusLineArgs {
+    /// Template such as `{artist} — {name} [{position}/{duration}] {lossless?}`.
+    #[arg(long, short, default_value = DEFAULT_STATUS_FORMAT)]
+    format: String,
+    /// Tooltip template for `--waybar`.
+    #[arg(long, default_value = DEFAULT_STATUS_TOOLTIP)]
+    tooltip: String,
+    /// Text when nothing is loaded.
+    #[arg(long, default_value = "")]
+    stopped: String,
+    /// Text when Cider is not reachable.
+    #[arg(long, default_value = "")]
+    offline: String,
+    /// Cut the line to this many characters.
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    max_width: Option<u16>,
+    /// Scroll lines longer than `--max-width` instead of cutting them.
+    #[arg(long, requires = "max_width")]
+    marquee: bool,
+    /// Escape the output [default: pango with `--waybar`, otherwise none].
+    #[arg(long, value_enum)]
+    escape: Option<EscapeArg>,
+    /// Print waybar JSON with a tooltip and a playing/paused/stopped/offline class.
+    #[arg(long)]
+    waybar: bool,
+    /// Keep running and print a new line whenever the output changes.
+    #[arg(long)]
+    follow: bool,
+    /// Time between polls with `--follow`.
+    #[arg(long, default_value = "1s", value_parser = parse_duration)]
+    interval: Duration,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum EscapeArg {
+    None,
+    Pango,
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+enum QueueCommand {
+    /// List the queue; `▶` marks the current track.
+    #[command(alias = "ls")]
+    List,
+    /// Move the item at 1-based index FROM to TO.
+    Move { from: u32, to: u32 },
+    /// Remove the item at a 1-based index.
+    #[command(alias = "remove")]
+    Rm { index: u32 },
+    /// Remove every item.
+    Clear,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum RepeatArg {
+    Off,
+    One,
+    All,
+    Toggle,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SwitchArg {
+    On,
+    Off,
+    Toggle,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum RatingArg {
+    Like,
+    Dislike,
+    None,
+}
+
+/// A `seek` target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Seek {
+    To(Duration),
+    /// Milliseconds from the current position.
+    By(i64),
+}
+
+/// A `vol` argument.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Volume {
+    To(f32),
+    By(f32),
+}
+
+/// Why a command failed.
+enum Failure {
+    Cider(Box<CiderError>),
+    /// The command needs a track and none is loaded.
+    NothingPlaying,
+    /// An argument was valid for clap but not for the command.
+    Usage(String),
+}
+
+impl From<CiderError> for Failure {
+    fn from(e: CiderError) -> Self {
+        Self::Cider(Box::new(e))
+    }
+}
+
+impl Failure {
+    fn exit_code(&self) -> u8 {
+        let e = match self {
+            Self::Cider(e) => e,
+            Self::NothingPlaying => return EXIT_NOTHING_PLAYING,
+            Self::Usage(_) => return EXIT_USAGE,
+        };
+        match **e {
+            _ if e.is_auth() => EXIT_UNAUTHORIZED,
+            _ if e.is_nothing_playing() => EXIT_NOTHING_PLAYING,
+            CiderError::NotReachable { .. }
+            | CiderError::Timeout { .. }
+            | CiderError::Http { .. } => EXIT_NOT_RUNNING,
+            CiderError::InvalidItem(_) | CiderError::Config(_) => EXIT_USAGE,
+            _ => EXIT_ERROR,
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            Self::Cider(e) => e.to_string(),
+            Self::NothingPlaying => "nothing is playing".to_owned(),
+            Self::Usage(message) => message.clone(),
+        }
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let json = cli.json;
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            let code = failure.exit_code();
+            if json {
+                eprintln!(
+                    "{}",
+                    json!({ "error": failure.message(), "exit_code": code })
+                );
+            } else {
+                eprintln!("cider: {}", failure.message());
+            }
+            ExitCode::from(code)
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Failure> {
+    let mut builder = CiderClient::builder();
+    if let Some(host) = cli.host {
+        builder = builder.host(host);
+    }
+    if let Some(port) = cli.port {
+        builder = builder.port(port);
+    }
+    if let Some(token) = cli.token {
+        builder = builder.token(token);
+    }
+    let client = builder.build()?;
+    let out = Output { json: cli.json };
+
+    match cli.command {
+        Command::Status => status(&client, out).await?,
+        Command::Active => {
+            client.is_active().await?;
+            out.print(&json!({ "active": true }), || "Cider is running".to_owned());
+        }
+        Command::NowPlaying => {
+            let track = client.now_playing().await?.ok_or(Failure::NothingPlaying)?;
+            out.print(&json!(track), || describe_track(&track));
+        }
+        Command::IsPlaying => {
+            let playing = client.is_playing().await?;
+            out.print(&json!({ "is_playing": playing }), || {
+                if playing { "playing" } else { "paused" }.to_owned()
+            });
+        }
+        Command::Play { item: None } => client.play().await?,
+        Command::Play { item: Some(item) } => match parse_target(&item)? {
+            Target::Url(url) => {
+                client.play_parsed(&url.to_string()).await?;
+            }
+            Target::Item(item) => client.play_item(&item).await?,
+        },
+        Command::PlayHref { href } => client.play_item_href(&href).await?,
+        Command::PlayNext { item } => client.play_next(&queueable(&item)?).await?,
+        Command::PlayLater { item } => client.play_later(&queueable(&item)?).await?,
+        Command::Pause => client.pause().await?,
+        Command::Toggle => client.play_pause().await?,
+        Command::Stop => client.stop().await?,
+        Command::Next => client.next().await?,
+        Command::Previous => client.previous().await?,
+        Command::Seek {
+            position: Seek::To(position),
+        } => client.seek_to(position).await?,
+        Command::Seek {
+            position: Seek::By(offset_ms),
+        } => {
+            let landed = client.seek_by(offset_ms).await?;
+            out.print(&json!({ "position": landed.as_secs_f64() }), || {
+                format_duration(landed)
+            });
+        }
+        Command::Queue(command) => queue(&client, out, command).await?,
+        Command::Vol { level, fade } => volume(&client, out, level, fade).await?,
+        Command::Repeat { mode } => repeat(&client, out, mode).await?,
+        Command::Shuffle { mode } => shuffle(&client, out, mode).await?,
+        Command::Autoplay { mode } => autoplay(&client, out, mode).await?,
+        Command::Rate { rating } => {
+            let rating = match rating {
+                RatingArg::Like => Rating::Like,
+                RatingArg::Dislike => Rating::Dislike,
+                RatingArg::None => Rating::None,
+            };
+            client.set_rating(rating).await?;
+        }
+        Command::AddToLibrary => client.add_to_library().await?,
+        Command::Amapi { path } => {
+            let response = client.amapi_run_v3(&path).await?;
+            out.print(&response, || {
+                serde_json::to_string_pretty(&response).unwrap_or_default()
+            });
+        }
+        Command::StatusLine(args) => status_line(&client, out, args).await?,
+    }
+    Ok(())
+}
+
+/// Where command results go.
+#[derive(Debug, Clone, Copy)]
+struct Output {
+    json: bool,
+}
+
+impl Output {
+    /// Print `value` with `--json`, otherwise the text from `text`.
+    fn print(self, value: &serde_json::Value, text: impl FnOnce() -> String) {
+        if self.json {
+            println!("{value}");
+        } else {
+            println!("{}", text());
+        }
+    }
+}
+
+async fn status(client: &CiderClient, out: Output) -> Result<(), Failure> {
+    let track = client.now_playing().await?;
+    let playing = client.is_playing().await?;
+    let volume = client.get_volume().await?;
+    let repeat = client.get_repeat_mode().await?;
+    let shuffle = client.get_shuffle_mode().await?;
+    let value = json!({
+        "is_playing": playing,
+        "volume": volume,
+        "repeat": repeat.to_string(),
+        "shuffle": switch(shuffle.is_on()),
+        "track": track,
+    });
+    out.print(&value, || {
+        let mut text = match &track {
+            Some(track) => {
+                let icon = if playing { "▶" } else { "⏸" };
+                format!("{icon} {}\n", describe_track(track).replace('\n', "\n  "))
+            }
+            None => "Nothing playing\n".to_owned(),
+        };
+        let _ = write!(
+            text,
+            "  volume {}% · repeat {repeat} · shuffle {}",
+            whole_percent(f64::from(volume)),
+            switch(shuffle.is_on())
+        );
+        text
+    });
+    Ok(())
+}
+
+async fn queue(client: &CiderClient, out: Output, command: QueueCommand) -> Result<(), Failure> {
+    match command {
+        QueueCommand::List => {
+            let items = client.get_queue().await?;
+            out.print(&json!(items), || describe_queue(&items));
+        }
+        QueueCommand::Move { from, to } => client.queue_move_to_position(from, to).await?,
+        QueueCommand::Rm { index } => client.queue_remove_by_index(index).await?,
+        QueueCommand::Clear => client.clear_queue().await?,
+    }
+    Ok(())
+}
+
+async fn volume(
+    client: &CiderClient,
+    out: Output,
+    level: Option<Volume>,
+    fade: Option<Duration>,
+) -> Result<(), Failure> {
+    let target = match level {
+        None => {
+            let volume = client.get_volume().await?;
+            out.print(&json!({ "volume": volume }), || {
+                format!("{}%", whole_percent(f64::from(volume)))
+            });
+            return Ok(());
+        }
+        Some(Volume::To(volume)) => volume,
+        Some(Volume::By(delta)) => client.get_volume().await? + delta,
+    };
+    match fade {
+        Some(duration) => {
+            client
+                .ramp_volume(target, duration, FadeCurve::default())
+                .await?;
+        }
+        None => client.set_volume(target).await?,
+    }
+    Ok(())
+}
+
+async fn repeat(client: &CiderClient, out: Output, mode: Option<RepeatArg>) -> Result<(), Failure> {
+    let mode = match mode {
+        None => {
+            let mode = client.get_repeat_mode().await?;
+            out.print(&json!({ "repeat": mode.to_string() }), || mode.to_string());
+            return Ok(());
+        }
+        Some(RepeatArg::Toggle) => return Ok(client.toggle_repeat().await?),
+        Some(RepeatArg::Off) => RepeatMode::Off,
+        Some(RepeatArg::One) => RepeatMode::One,
+        Some(RepeatArg::All) => RepeatMode::All,
+    };
+    Ok(client.set_repeat_mode(mode).await?)
+}
+
+async fn shuffle(
+    client: &CiderClient,
+    out: Output,
+    mode: Option<SwitchArg>,
+) -> Result<(), Failure> {
+    let mode = match mode {
+        None => {
+            let on = client.get_shuffle_mode().await?.is_on();
+            out.print(&json!({ "shuffle": switch(on) }), || switch(on).to_owned());
+            return Ok(());
+        }
+        Some(SwitchArg::Toggle) => return Ok(client.toggle_shuffle().await?),
+        Some(SwitchArg::On) => ShuffleMode::On,
+        Some(SwitchArg::Off) => ShuffleMode::Off,
+    };
+    Ok(client.set_shuffle_mode(mode).await?)
+}
+
+async fn autoplay(
+    client: &CiderClient,
+    out: Output,
+    mode: Option<SwitchArg>,
+) -> Result<(), Failure> {
+    let on = client.get_autoplay().await?;
+    let wanted = match mode {
+        None => {
+            out.print(&json!({ "autoplay": switch(on) }), || switch(on).to_owned());
+            return Ok(());
+        }
+        Some(SwitchArg::Toggle) => !on,
+        Some(SwitchArg::On) => true,
+        Some(SwitchArg::Off) => false,
+    };
+    if wanted != on {
+        client.toggle_autoplay().await?;
+    }
+    Ok(())
+}
+
+/// Print the status line once, or on every change with `--follow`.
+///
+/// An unreachable Cider is not an error here: bars get the offline text
+/// and class instead.
+async fn status_line(
+    client: &CiderClient,
+    out: Output,
+    args: StatusLineArgs,
+) -> Result<(), Failure> {
+    if args.interval.is_zero() {
+        return Err(Failure::Usage("--interval must be positive".to_owned()));
+    }
+    let waybar = args.waybar || out.json;
+    let escape = match args.escape {
+        None if waybar => StatusEscape::Pango,
+        Some(EscapeArg::Pango) => StatusEscape::Pango,
+        Some(EscapeArg::Json) => StatusEscape::Json,
+        Some(EscapeArg::None) | None => StatusEscape::None,
+    };
+    let mut line = StatusLine::new(args.format.parse()?)
+        .tooltip(args.tooltip.parse()?)
+        .stopped_text(args.stopped)
+        .offline_text(args.offline)
+        .marquee(args.marquee)
+        .escape(escape);
+    if let Some(width) = args.max_width {
+        line = line.max_width(width.into());
+    }
+
+    let mut watcher = client.watch();
+    let mut ticker = tokio::time::interval(args.interval);
+    let mut away = false;
+    let mut last = None;
+    loop {
+        ticker.tick().await;
+        for event in watcher.poll().await {
+            match event {
+                WatchEvent::CiderWentAway(_) => away = true,
+                WatchEvent::CiderCameBack => away = false,
+                _ => {}
+            }
+        }
+        let output = line.render(watcher.snapshot().filter(|_| !away));
+        let rendered = if waybar {
+            output.to_waybar_json()
+        } else {
+            output.text
+        };
+        if last.as_ref() != Some(&rendered) {
+            println!("{rendered}");
+            last = Some(rendered);
+        }
+        if !args.follow {
+            return Ok(());
+        }
+    }
+}
+
+/// What `play` was given.
+enum Target {
+    Url(AppleMusicUrl),
+    Item(ItemRef),
+}
+
+/// Parse a `music.apple.com` link or a `kind:id` item.
+fn parse_target(arg: &str) -> Result<Target, Failure> {
+    if arg.starts_with("http://") || arg.starts_with("https://") {
+        return Ok(Target::Url(arg.parse()?));
+    }
+    let (kind, id) = arg.split_once(':').ok_or_else(|| {
+        Failure::Usage(format!(
+            "expected a music.apple.com link or kind:id (e.g. songs:1719861213), got {arg:?}"
+        ))
+    })?;
+    Ok(Target::Item(ItemRef::new(kind.parse::<ItemKind>()?, id)?))
+}
+
+/// Parse an item for `play-next` / `play-later`, which need an ID.
+fn queueable(arg: &str) -> Result<ItemRef, Failure> {
+    match parse_target(arg)? {
+        Target::Item(item) => Ok(item),
+        Target::Url(url) => url
+            .item_ref()
+            .ok_or_else(|| Failure::Usage(format!("{url} cannot be queued"))),
+    }
+}
+
+/// Parse `90`, `90s`, `1:30`, `1m`, `500ms`, or any of them with `+`/`-`
+/// for an offset.
+fn parse_seek(arg: &str) -> Result<Seek, String> {
+    let (sign, rest) = match arg.as_bytes().first() {
+        Some(b'+') => (1, &arg[1..]),
+        Some(b'-') => (-1, &arg[1..]),
+        _ => (0, arg),
+    };
+    let duration = if rest.contains(':') {
+        parse_clock(rest)
+    } else {
+        parse_duration(rest)
+    }?;
+    if sign == 0 {
+        return Ok(Seek::To(duration));
+    }
+    let ms = i64::try_from(duration.as_millis()).map_err(|_| format!("{arg:?} is too long"))?;
+    Ok(Seek::By(sign * ms))
+}
+
+/// Parse `M:SS` or `H:MM:SS`.
+fn parse_clock(arg: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid time {arg:?}, expected M:SS or H:MM:SS");
+    let parts: Vec<&str> = arg.split(':').collect();
+    if parts.len() > 3 {
+        return Err(invalid());
+    }
+    let mut secs = 0.0;
+    for (i, part) in parts.into_iter().enumerate() {
+        let value: f64 = part.parse().map_err(|_| invalid())?;
+        // Only the leading field may exceed 59.
+        if value < 0.0 || (i > 0 && value >= 60.0) {
+            return Err(invalid());
+        }
+        secs = secs * 60.0 + value;
+    }
+    Duration::try_from_secs_f64(secs).map_err(|_| invalid())
+}
+
+/// Parse a number of seconds with an optional `ms`, `s`, `m` or `h` unit.
+fn parse_duration(arg: &str) -> Result<Duration, String> {
+    let (number, scale) = [("ms", 0.001), ("s", 1.0), ("m", 60.0), ("h", 3600.0)]
+        .into_iter()
+        .find_map(|(unit, scale)| arg.strip_suffix(unit).map(|n| (n, scale)))
+        .unwrap_or((arg, 1.0));
+    number
+        .parse::<f64>()
+        .ok()
+        .and_then(|n| Duration::try_from_secs_f64(n * scale).ok())
+        .ok_or_else(|| format!("invalid duration {arg:?}, expected e.g. 90, 15s or 2m"))
+}
+
+/// Parse `40%`, `0.4`, `+10%`, `-0.05`.
+fn parse_volume(arg: &str) -> Result<Volume, String> {
+    let invalid = || format!("invalid volume {arg:?}, expected e.g. 40%, 0.4 or +10%");
+    let relative = arg.starts_with(['+', '-']);
+    let level = match arg.strip_suffix('%') {
+        Some(percent) => percent.parse::<f32>().map_err(|_| invalid())? / 100.0,
+        None => arg.parse::<f32>().map_err(|_| invalid())?,
+    };
+    if !level.is_finite() {
+        return Err(invalid());
+    }
+    if relative {
+        Ok(Volume::By(level))
+    } else if (0.0..=1.0).contains(&level) {
+        Ok(Volume::To(level))
+    } else {
+        Err(format!(
+            "volume {arg:?} is out of range, expected 0%–100% or 0.0–1.0"
+        ))
+    }
+}
+
+/// `M:SS`, or `H:MM:SS` from an hour up.
+fn switch(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// Title and artist, album, then progress and audio traits.
+fn describe_track(track: &NowPlaying) -> String {
+    let mut text = format!(
+        "{} — {}\n{}\n{} / {}",
+        track.name,
+        track.artist_name,
+        track.album_name,
+        format_duration(track.position()),
+        format_duration(track.duration()),
+    );
+    if let Some(fraction) = track.progress_fraction() {
+        let _ = write!(text, " ({:.0}%)", fraction * 100.0);
+    }
+    if !track.audio_traits.is_empty() {
+        let _ = write!(text, " · {}", track.audio_traits.join(", "));
+    }
+    text
+}
+
+fn describe_queue(items: &[QueueItem]) -> String {
+    if items.is_empty() {
+        return "Queue is empty".to_owned();
+    }
+    let width = items.len().to_string().len();
+    let lines: Vec<String> = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let marker = if item.is_current() { "▶" } else { " " };
+            let title = match &item.attributes {
+                Some(attributes) => format!(
+                    "{} — {} ({})",
+                    attributes.name,
+                    attributes.artist_name,
+                    format_duration(Duration::from_millis(attributes.duration_in_millis))
+                ),
+                None => item.id.clone().unwrap_or_default(),
+            };
+            format!("{marker} {:>width$}  {title}", i + 1)
+        })
+        .collect();
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seek_arguments() {
+        assert_eq!(parse_seek("90"), Ok(Seek::To(Duration::from_secs(90))));
+        assert_eq!(parse_seek("1:30"), Ok(Seek::To(Duration::from_secs(90))));
+        assert_eq!(
+            parse_seek("1:02:03"),
+            Ok(Seek::To(Duration::from_secs(3723)))
+        );
+        assert_eq!(parse_seek("+15s"), Ok(Seek::By(15_000)));
+        assert_eq!(parse_seek("-1m"), Ok(Seek::By(-60_000)));
+        assert_eq!(parse_seek("+500ms"), Ok(Seek::By(500)));
+        assert_eq!(parse_seek("-0:10"), Ok(Seek::By(-10_000)));
+        assert!(parse_seek("1:75").is_err());
+        assert!(parse_seek("soon").is_err());
+        assert!(parse_seek("+").is_err());
+    }
+
+    #[test]
+    fn volume_arguments() {
+        assert_eq!(parse_volume("40%"), Ok(Volume::To(0.4)));
+        assert_eq!(parse_volume("0.25"), Ok(Volume::To(0.25)));
+        assert_eq!(parse_volume("+10%"), Ok(Volume::By(0.1)));
+        assert_eq!(parse_volume("-0.05"), Ok(Volume::By(-0.05)));
+        assert!(parse_volume("40").is_err());
+        assert!(parse_volume("150%").is_err());
+        assert!(parse_volume("loud").is_err());
+    }
+
+    #[test]
+    fn targets() {
+        assert!(matches!(
+            parse_target("songs:1719861213"),
+            Ok(Target::Item(item)) if item.kind() == ItemKind::Songs
+        ));
+        assert!(matches!(
+            parse_target("https://music.apple.com/ca/album/skin/1719860281"),
+            Ok(Target::Url(_))
+        ));
+        assert!(matches!(parse_target("skin"), Err(Failure::Usage(_))));
+        assert_eq!(
+            parse_target("songs:pl.abc").err().map(|e| e.exit_code()),
+            Some(EXIT_USAGE)
+        );
+    }
+
+    #[test]
+    fn exit_codes() {
+        let endpoint = cider_api::Endpoint {
+            method: reqwest::Method::POST,
+            path: "/api/v1/playback/seek".to_owned(),
+        };
+        let failure = Failure::from(CiderError::NothingPlaying { endpoint });
+        assert_eq!(failure.exit_code(), EXIT_NOTHING_PLAYING);
+        assert_eq!(
+            Failure::from(CiderError::Config(String::new())).exit_code(),
+            EXIT_USAGE
+        );
+    }
+
+    #[test]
+    fn cli_definition_is_valid() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+    }
+}
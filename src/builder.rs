@@ -0,0 +1,649 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Builder for [`CiderClient`] with configurable transport settings.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::header::HeaderMap;
+use reqwest::{Certificate, Client, Url};
+
+use crate::client::{CiderClient, DecodeMode, DEFAULT_PORT};
+use crate::error::CiderError;
+use crate::retry::RetryPolicy;
+
+/// Default connection timeout — short because the server is usually localhost.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Default per-request timeout.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Default maximum number of idle connections kept per host.
+const POOL_MAX_IDLE_PER_HOST: usize = 2;
+
+/// Default time an idle pooled connection is kept open.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default host — Cider's RPC server listens on loopback.
+const DEFAULT_HOST: &str = "127.0.0.1";
+
+/// Default `User-Agent` header value.
+const DEFAULT_USER_AGENT: &str = concat!("cider-api/", env!("CARGO_PKG_VERSION"));
+
+/// Builder for a [`CiderClient`] with custom transport settings.
+///
+/// The defaults match [`CiderClient::new`]: `http://127.0.0.1:10767`, a 1s
+/// connect timeout, a 2s request timeout, at most 2 idle pooled connections
+/// and no TCP keepalive. Override only what you need.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use cider_api::CiderClient;
+///
+/// // Generous timeouts for a slow VPN link
+/// let client = CiderClient::builder()
+///     .port(10767)
+///     .token("my-token")
+///     .connect_timeout(Duration::from_secs(5))
+///     .timeout(Duration::from_secs(15))
+///     .tcp_keepalive(Duration::from_secs(30))
+///     .user_agent("my-app/1.0")
+///     .build()?;
+/// # Ok::<(), cider_api::CiderError>(())
+/// ```
+///
+/// # Remote instances
+///
+/// Use [`host`](Self::host) and [`https`](Self::https) to reach Cider on
+/// another machine or behind a TLS-terminating reverse proxy. Self-signed
+/// certificates can be trusted with
+/// [`add_root_certificate`](Self::add_root_certificate).
+///
+/// The `apptoken` header grants full control over Cider, so the client
+/// refuses to send it over plain HTTP to a non-loopback host. Use HTTPS, or
+/// opt out explicitly with
+/// [`allow_insecure_token`](Self::allow_insecure_token) on trusted networks.
+///
+/// ```
+/// use cider_api::CiderClient;
+///
+/// let client = CiderClient::builder()
+///     .host("music.home.example")
+///     .port(443)
+///     .https(true)
+///     .token("my-token")
+///     .build()?;
+/// # Ok::<(), cider_api::CiderError>(())
+/// ```
+///
+/// An existing [`reqwest::Client`] can be injected with
+/// [`http_client`](Self::http_client) to share a connection pool with the
+/// rest of an application. In that case the transport settings on this
+/// builder are ignored — configure them on the injected client instead.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct CiderClientBuilder {
+    host: String,
+    port: u16,
+    https: bool,
+    base_url: Option<String>,
+    api_token: Option<String>,
+    allow_insecure_token: bool,
+    decode_mode: DecodeMode,
+    retry_policy: Option<RetryPolicy>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<crate::metrics::Metrics>,
+    /// PEM or DER encoded.
+    root_certificates: Vec<Vec<u8>>,
+    accept_invalid_certs: bool,
+    connect_timeout: Duration,
+    timeout: Option<Duration>,
+    pool_max_idle_per_host: usize,
+    pool_idle_timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+    user_agent: Option<String>,
+    default_headers: HeaderMap,
+    http_client: Option<Client>,
+}
+
+impl CiderClientBuilder {
+    /// Create a builder with the default settings.
+    pub fn new() -> Self {
+        Self {
+            host: DEFAULT_HOST.to_string(),
+            port: DEFAULT_PORT,
+            https: false,
+            base_url: None,
+            api_token: None,
+            allow_insecure_token: false,
+            decode_mode: DecodeMode::Lenient,
+            retry_policy: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+            root_certificates: Vec::new(),
+            accept_invalid_certs: false,
+            connect_timeout: CONNECTION_TIMEOUT,
+            timeout: Some(REQUEST_TIMEOUT),
+            pool_max_idle_per_host: POOL_MAX_IDLE_PER_HOST,
+            pool_idle_timeout: Some(POOL_IDLE_TIMEOUT),
+            tcp_keepalive: None,
+            user_agent: Some(DEFAULT_USER_AGENT.to_string()),
+            default_headers: HeaderMap::new(),
+            http_client: None,
+        }
+    }
+
+    /// Host name or IP address of the machine running Cider. Defaults to
+    /// `127.0.0.1`.
+    ///
+    /// IPv6 addresses may be given with or without brackets. Ignored if
+    /// [`base_url`](Self::base_url) is set.
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    /// Port of Cider's RPC server. Defaults to [`DEFAULT_PORT`].
+    ///
+    /// Ignored if [`base_url`](Self::base_url) is set.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Use `https://` instead of `http://`. Defaults to `false`.
+    ///
+    /// Cider itself only speaks plain HTTP; enable this when it sits behind a
+    /// TLS-terminating reverse proxy. Ignored if [`base_url`](Self::base_url)
+    /// is set.
+    pub fn https(mut self, enabled: bool) -> Self {
+        self.https = enabled;
+        self
+    }
+
+    /// Target an arbitrary base URL (e.g. `"https://music.home.example"`).
+    ///
+    /// Takes precedence over [`host`](Self::host), [`port`](Self::port) and
+    /// [`https`](Self::https). A trailing `/` is removed.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Attach an API token, sent in the `apptoken` header on every request.
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.api_token = Some(token.into());
+        self
+    }
+
+    /// Allow sending the API token over plain HTTP to a non-loopback host.
+    /// Defaults to `false`.
+    ///
+    /// Anyone on the network path can read the token and take full control
+    /// of Cider. Only enable this on networks you trust.
+    pub fn allow_insecure_token(mut self, allow: bool) -> Self {
+        self.allow_insecure_token = allow;
+        self
+    }
+
+    /// How [`CiderClient::now_playing`] and [`CiderClient::get_queue`]
+    /// handle undecodable responses. Defaults to [`DecodeMode::Lenient`].
+    pub fn decode_mode(mut self, mode: DecodeMode) -> Self {
+        self.decode_mode = mode;
+        self
+    }
+
+    /// Retry transient failures of idempotent requests. Defaults to no
+    /// retries.
+    ///
+    /// See [`RetryPolicy`] for which calls are retried.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Record every request in `metrics` (`metrics` feature).
+    #[cfg(feature = "metrics")]
+    pub fn metrics(mut self, metrics: crate::metrics::Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Trust an additional root certificate, PEM or DER encoded, e.g. the
+    /// self-signed certificate of a reverse proxy.
+    ///
+    /// The certificate is trusted for requests and for the event stream
+    /// (`events` feature). It is parsed by [`build`](Self::build).
+    ///
+    /// ```no_run
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let pem = std::fs::read("proxy-ca.pem")?;
+    /// let client = cider_api::CiderClient::builder()
+    ///     .host("music.home.example")
+    ///     .port(443)
+    ///     .https(true)
+    ///     .add_root_certificate(pem)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn add_root_certificate(mut self, certificate: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(certificate.into());
+        self
+    }
+
+    /// Accept any TLS certificate, including expired, self-signed and
+    /// wrong-host ones, for requests and the event stream. Defaults to
+    /// `false`.
+    ///
+    /// This disables protection against man-in-the-middle attacks. Prefer
+    /// [`add_root_certificate`](Self::add_root_certificate).
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Timeout for establishing a TCP connection. Defaults to 1s.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Total timeout for each request, from connecting until the response
+    /// body has been read. Defaults to 2s.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Disable the per-request timeout entirely.
+    ///
+    /// The connect timeout still applies.
+    pub fn no_timeout(mut self) -> Self {
+        self.timeout = None;
+        self
+    }
+
+    /// Maximum number of idle connections kept per host. Defaults to 2.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = max;
+        self
+    }
+
+    /// How long an idle pooled connection is kept open. Defaults to 10s.
+    ///
+    /// Pass `None` to keep idle connections indefinitely.
+    pub fn pool_idle_timeout(mut self, timeout: impl Into<Option<Duration>>) -> Self {
+        self.pool_idle_timeout = timeout.into();
+        self
+    }
+
+    /// TCP keepalive interval. Defaults to `None` (disabled).
+    pub fn tcp_keepalive(mut self, interval: impl Into<Option<Duration>>) -> Self {
+        self.tcp_keepalive = interval.into();
+        self
+    }
+
+    /// `User-Agent` header value. Defaults to `cider-api/<version>`.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Headers sent on every request, in addition to `apptoken`.
+    ///
+    /// Replaces any headers set by a previous call.
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.default_headers = headers;
+        self
+    }
+
+    /// Use an existing [`reqwest::Client`] instead of building one.
+    ///
+    /// Timeouts, pooling, keepalive, user agent, default headers and TLS
+    /// settings on this builder are ignored; the `apptoken` header is still
+    /// added per request.
+    pub fn http_client(mut self, client: Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Build the [`CiderClient`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::Config`] if the base URL is not a valid
+    /// `http`/`https` URL, a token would be sent over plain HTTP to a
+    /// non-loopback host without
+    /// [`allow_insecure_token`](Self::allow_insecure_token), or the
+    /// underlying HTTP client cannot be constructed (e.g. TLS initialisation
+    /// fails at the OS level).
+    pub fn build(self) -> Result<CiderClient, CiderError> {
+        let base_url = self.resolve_base_url();
+        let parsed = Url::parse(&base_url)
+            .map_err(|e| CiderError::Config(format!("invalid base URL {base_url:?} ({e})")))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(CiderError::Config(format!(
+                "unsupported URL scheme {:?} (expected http or https)",
+                parsed.scheme()
+            )));
+        }
+        if parsed.host().is_none() {
+            return Err(CiderError::Config(format!("base URL {base_url:?} has no host")));
+        }
+        let plaintext_remote = is_plaintext_remote(&parsed);
+        if plaintext_remote && self.api_token.is_some() && !self.allow_insecure_token {
+            return Err(CiderError::Config(format!(
+                "refusing to send the API token over plain HTTP to {base_url}; \
+                 use HTTPS or allow_insecure_token(true)"
+            )));
+        }
+
+        #[cfg(feature = "events")]
+        let tls = crate::events::tls_connector(&self.root_certificates, self.accept_invalid_certs)?;
+        let http = if let Some(client) = self.http_client {
+            client
+        } else {
+            let mut builder = Client::builder()
+                .connect_timeout(self.connect_timeout)
+                .pool_max_idle_per_host(self.pool_max_idle_per_host)
+                .pool_idle_timeout(self.pool_idle_timeout)
+                .tcp_keepalive(self.tcp_keepalive)
+                .default_headers(self.default_headers);
+            if let Some(timeout) = self.timeout {
+                builder = builder.timeout(timeout);
+            }
+            if let Some(user_agent) = self.user_agent {
+                builder = builder.user_agent(user_agent);
+            }
+            for certificate in &self.root_certificates {
+                let parsed = if is_pem(certificate) {
+                    Certificate::from_pem(certificate)
+                } else {
+                    Certificate::from_der(certificate)
+                };
+                let certificate = parsed
+                    .map_err(|e| CiderError::Config(format!("invalid root certificate ({e})")))?;
+                builder = builder.add_root_certificate(certificate);
+            }
+            if self.accept_invalid_certs {
+                builder = builder.danger_accept_invalid_certs(true);
+            }
+            builder
+                .build()
+                .map_err(|e| CiderError::Config(format!("failed to build HTTP client ({e})")))?
+        };
+
+        Ok(CiderClient {
+            http,
+            base_url,
+            api_token: self.api_token,
+            plaintext_remote,
+            allow_insecure_token: self.allow_insecure_token,
+            decode_mode: self.decode_mode,
+            retry_policy: self.retry_policy,
+            retry_commands: false,
+            volume: Arc::default(),
+            #[cfg(feature = "metrics")]
+            metrics: self.metrics,
+            #[cfg(feature = "events")]
+            tls,
+        })
+    }
+
+    /// The explicit base URL, or one assembled from scheme, host and port.
+    fn resolve_base_url(&self) -> String {
+        if let Some(url) = &self.base_url {
+            return url.trim_end_matches('/').to_string();
+        }
+        let scheme = if self.https { "https" } else { "http" };
+        let host = self.host.trim_start_matches('[').trim_end_matches(']');
+        if host.contains(':') {
+            format!("{scheme}://[{host}]:{}", self.port)
+        } else {
+            format!("{scheme}://{host}:{}", self.port)
+        }
+    }
+}
+
+/// Returns `true` if `certificate` is PEM rather than DER encoded.
+pub(crate) fn is_pem(certificate: &[u8]) -> bool {
+    certificate
+        .windows(PEM_MARKER.len())
+        .any(|window| window == PEM_MARKER)
+}
+
+/// Start of a PEM block.
+const PEM_MARKER: &[u8] = b"-----BEGIN ";
+
+/// Returns `true` if `url` uses plain HTTP to a host other than loopback.
+pub(crate) fn is_plaintext_remote(url: &Url) -> bool {
+    if url.scheme() != "http" {
+        return false;
+    }
+    let Some(host) = url.host_str() else {
+        return true;
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.eq_ignore_ascii_case("localhost") {
+        return false;
+    }
+    host.parse::<IpAddr>().map_or(true, |ip| !ip.is_loopback())
+}
+
+impl Default for CiderClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_match_new() {
+        let built = CiderClientBuilder::new().build().unwrap();
+        let plain = CiderClient::new();
+        assert_eq!(built.base_url, plain.base_url);
+        assert_eq!(built.api_token, plain.api_token);
+    }
+
+    #[test]
+    fn port_sets_localhost_url() {
+        let client = CiderClientBuilder::new().port(9999).build().unwrap();
+        assert_eq!(client.base_url, "http://127.0.0.1:9999");
+    }
+
+    #[test]
+    fn base_url_overrides_port() {
+        let client = CiderClientBuilder::new()
+            .port(9999)
+            .base_url("http://example.com:1234")
+            .build()
+            .unwrap();
+        assert_eq!(client.base_url, "http://example.com:1234");
+    }
+
+    #[test]
+    fn base_url_trailing_slash_is_trimmed() {
+        let client = CiderClientBuilder::new()
+            .base_url("http://example.com:1234/")
+            .build()
+            .unwrap();
+        assert_eq!(client.base_url, "http://example.com:1234");
+    }
+
+    #[test]
+    fn token_is_stored() {
+        let client = CiderClientBuilder::new().token("tok").build().unwrap();
+        assert_eq!(client.api_token, Some("tok".to_string()));
+    }
+
+    #[test]
+    fn builder_is_default() {
+        let a = CiderClientBuilder::default().build().unwrap();
+        assert_eq!(a.base_url, "http://127.0.0.1:10767");
+    }
+
+    #[test]
+    fn host_and_https_build_url() {
+        let client = CiderClientBuilder::new()
+            .host("192.168.1.20")
+            .port(8443)
+            .https(true)
+            .build()
+            .unwrap();
+        assert_eq!(client.base_url, "https://192.168.1.20:8443");
+    }
+
+    #[test]
+    fn ipv6_host_is_bracketed() {
+        let a = CiderClientBuilder::new().host("::1").build().unwrap();
+        let b = CiderClientBuilder::new().host("[::1]").build().unwrap();
+        assert_eq!(a.base_url, "http://[::1]:10767");
+        assert_eq!(b.base_url, "http://[::1]:10767");
+    }
+
+    #[test]
+    fn invalid_base_url_is_rejected() {
+        let err = CiderClientBuilder::new().base_url("not a url").build().unwrap_err();
+        assert!(matches!(err, CiderError::Config(_)));
+    }
+
+    #[test]
+    fn non_http_scheme_is_rejected() {
+        let err = CiderClientBuilder::new()
+            .base_url("ftp://127.0.0.1:10767")
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, CiderError::Config(_)));
+    }
+
+    #[test]
+    fn token_over_plaintext_remote_is_rejected() {
+        let err = CiderClientBuilder::new()
+            .host("192.168.1.20")
+            .token("secret")
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, CiderError::Config(_)));
+    }
+
+    #[test]
+    fn token_over_plaintext_remote_allowed_when_opted_in() {
+        let client = CiderClientBuilder::new()
+            .host("192.168.1.20")
+            .token("secret")
+            .allow_insecure_token(true)
+            .build()
+            .unwrap();
+        assert_eq!(client.api_token, Some("secret".to_string()));
+    }
+
+    #[test]
+    fn token_over_https_remote_is_allowed() {
+        CiderClientBuilder::new()
+            .host("music.home.example")
+            .https(true)
+            .token("secret")
+            .build()
+            .unwrap();
+    }
+
+    #[test]
+    fn remote_without_token_is_allowed() {
+        CiderClientBuilder::new().host("192.168.1.20").build().unwrap();
+    }
+
+    #[test]
+    fn plaintext_remote_detection() {
+        let check = |u: &str| is_plaintext_remote(&Url::parse(u).unwrap());
+        assert!(!check("http://127.0.0.1:10767"));
+        assert!(!check("http://127.8.0.1:10767"));
+        assert!(!check("http://localhost:10767"));
+        assert!(!check("http://LOCALHOST:10767"));
+        assert!(!check("http://[::1]:10767"));
+        assert!(!check("https://192.168.1.20"));
+        assert!(check("http://192.168.1.20:10767"));
+        assert!(check("http://music.home.example"));
+        assert!(check("http://[fe80::1]:10767"));
+    }
+
+    #[test]
+    fn decode_mode_is_passed_through() {
+        let client = CiderClientBuilder::new()
+            .decode_mode(DecodeMode::Strict)
+            .build()
+            .unwrap();
+        assert_eq!(client.decode_mode, DecodeMode::Strict);
+    }
+
+    #[test]
+    fn retry_policy_is_passed_through() {
+        let client = CiderClientBuilder::new().build().unwrap();
+        assert!(client.retry_policy.is_none());
+
+        let client = CiderClientBuilder::new()
+            .retry_policy(RetryPolicy::new().max_attempts(5))
+            .build()
+            .unwrap();
+        assert_eq!(client.retry_policy.unwrap().attempts(), 5);
+    }
+
+    /// A self-signed certificate for `cider.test`.
+    const TEST_CA: &str = "-----BEGIN CERTIFICATE-----
+MIIBgTCCASegAwIBAgIURZogksA5RgZcZyZntN9HtR9m+vowCgYIKoZIzj0EAwIw
+FTETMBEGA1UEAwwKY2lkZXIudGVzdDAgFw0yNjEwMTcwMzU5NDdaGA8yMTI2MDky
+MzAzNTk0N1owFTETMBEGA1UEAwwKY2lkZXIudGVzdDBZMBMGByqGSM49AgEGCCqG
+SM49AwEHA0IABMJdskvtjHnwHpZHEgOP+VSgYB0BBfotcIXoxHD4O9fRS6KYEWYK
+y9pu1aQ/mbZfHoj02mRPFzGWRsLQS5FIJdijUzBRMB0GA1UdDgQWBBRIkAob8EPX
+91nZrHL47zfNx+SFATAfBgNVHSMEGDAWgBRIkAob8EPX91nZrHL47zfNx+SFATAP
+BgNVHRMBAf8EBTADAQH/MAoGCCqGSM49BAMCA0gAMEUCIQDqRY/w4Cku78+hkOeY
+JipuqRU8tLA3A+dBRKyJbi+9ywIgMpA4DzpDRMURYhsHIpHWY049vrIg4qjAOEA1
+Sp/kP3o=
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn root_certificates_are_parsed() {
+        assert!(is_pem(TEST_CA.as_bytes()));
+        let client = CiderClientBuilder::new()
+            .add_root_certificate(TEST_CA)
+            .build()
+            .unwrap();
+        // The event stream trusts it too.
+        #[cfg(feature = "events")]
+        assert!(client.tls.is_some());
+        #[cfg(not(feature = "events"))]
+        let _ = client;
+
+        let err = CiderClientBuilder::new()
+            .add_root_certificate(b"not a certificate".to_vec())
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, CiderError::Config(_)), "{err}");
+    }
+
+    #[test]
+    fn transport_settings_build() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-test", "1".parse().unwrap());
+        CiderClientBuilder::new()
+            .connect_timeout(Duration::from_secs(5))
+            .no_timeout()
+            .pool_max_idle_per_host(0)
+            .pool_idle_timeout(None)
+            .tcp_keepalive(Duration::from_secs(30))
+            .user_agent("test/1.0")
+            .default_headers(headers)
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+    }
+}
@@ -4,73 +4,64 @@
 
 //! Async HTTP client for the Cider REST API.
 
+use std::future::Future;
+use std::sync::Arc;
 use std::time::Duration;
 
-use reqwest::Client;
-use thiserror::Error;
+use reqwest::{Client, Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use tracing::{debug, instrument, warn};
 
+use crate::builder::CiderClientBuilder;
+use crate::error::{CiderError, Endpoint};
+use crate::item::ItemRef;
+use crate::retry::RetryPolicy;
+use crate::url::AppleMusicUrl;
+use crate::volume::VolumeControl;
 use crate::types::{
-    AmApiRequest, ApiResponse, AutoplayResponse, IsPlayingResponse, NowPlaying,
+    AmApiRequest, AutoplayResponse, ErrorResponse, IsPlayingResponse, NowPlaying,
     NowPlayingResponse, PlayItemHrefRequest, PlayItemRequest, PlayUrlRequest, QueueItem,
-    QueueMoveRequest, QueueRemoveRequest, RatingRequest, RepeatModeResponse, SeekRequest,
-    ShuffleModeResponse, VolumeRequest, VolumeResponse,
+    QueueMoveRequest, QueueRemoveRequest, Rating, RatingRequest, RepeatMode, RepeatModeResponse,
+    SeekRequest, ShuffleMode, ShuffleModeResponse, VolumeRequest, VolumeResponse,
 };
 
 /// Default Cider RPC port.
 pub const DEFAULT_PORT: u16 = 10767;
 
-/// Connection timeout — short because the server is localhost.
-const CONNECTION_TIMEOUT: Duration = Duration::from_secs(1);
+/// Maximum number of response body bytes kept in [`CiderError::Decode`].
+const MAX_ERROR_BODY_LEN: usize = 512;
 
-/// Per-request timeout.
-const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+/// How many times a setter re-reads a mode after toggling it, waiting for
+/// Cider to report the change.
+const SETTLE_POLLS: u32 = 10;
 
-/// Errors returned by [`CiderClient`] methods.
-///
-/// # Examples
-///
-/// ```no_run
-/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-/// use cider_api::{CiderClient, CiderError};
+/// Delay between the re-reads done by [`settle`].
+const SETTLE_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How [`CiderClient::now_playing`] and [`CiderClient::get_queue`] handle
+/// responses that cannot be decoded.
 ///
-/// let client = CiderClient::new();
-/// match client.is_active().await {
-///     Ok(()) => println!("Cider is running"),
-///     Err(CiderError::Unauthorized) => println!("Bad API token"),
-///     Err(CiderError::Http(e)) if e.is_connect() => println!("Cider not running"),
-///     Err(e) => println!("Error: {e}"),
-/// }
-/// # Ok(())
-/// # }
-/// ```
-#[derive(Debug, Error)]
-pub enum CiderError {
-    /// An HTTP-level error from [`reqwest`].
-    #[error("HTTP request failed: {0}")]
-    Http(#[from] reqwest::Error),
-
-    /// Cider is not running or the port is unreachable.
-    #[error("Cider is not running or not reachable")]
-    NotReachable,
-
-    /// The API token was rejected (HTTP 401/403).
-    #[error("Invalid API token")]
-    Unauthorized,
-
-    /// No track is currently loaded.
-    #[error("No track currently playing")]
-    NothingPlaying,
-
-    /// Catch-all for unexpected API responses.
-    #[error("API error: {0}")]
-    Api(String),
+/// Other methods always return [`CiderError::Decode`] for malformed
+/// responses, since they have no sensible "empty" value to fall back to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DecodeMode {
+    /// Log a warning and return `Ok(None)` / `Ok(vec![])`.
+    ///
+    /// A schema change in Cider looks like "nothing playing" / "empty queue".
+    #[default]
+    Lenient,
+
+    /// Return [`CiderError::Decode`] with the offending field path and body.
+    Strict,
 }
 
 /// Async client for the [Cider](https://cider.sh) music player REST API.
 ///
-/// Communicates with Cider's local HTTP server (default `http://127.0.0.1:10767`)
+/// Communicates with Cider's HTTP server (default `http://127.0.0.1:10767`)
 /// to control playback, manage the queue, and query track information.
+/// Remote instances, HTTPS and custom certificates are configured through
+/// [`CiderClient::builder`].
 ///
 /// # Construction
 ///
@@ -85,6 +76,12 @@ pub enum CiderError {
 ///
 /// // With authentication
 /// let client = CiderClient::new().with_token("my-token");
+///
+/// // Custom timeouts, pooling, user agent, ...
+/// let client = CiderClient::builder()
+///     .timeout(std::time::Duration::from_secs(10))
+///     .build()?;
+/// # Ok::<(), cider_api::CiderError>(())
 /// ```
 ///
 /// The client is cheaply [`Clone`]able — it shares an inner connection pool.
@@ -93,14 +90,37 @@ pub enum CiderError {
 ///
 /// All async methods return `Result<_, CiderError>`. Common error cases:
 ///
-/// - [`CiderError::Http`] — network or connection failure.
+/// - [`CiderError::NotReachable`] — Cider is not running or unreachable.
+/// - [`CiderError::Timeout`] — Cider did not answer in time.
 /// - [`CiderError::Unauthorized`] — invalid API token (HTTP 401/403).
-/// - [`CiderError::Api`] — unexpected response from Cider.
+/// - [`CiderError::Api`] — Cider returned an error status or error body.
+/// - [`CiderError::Decode`] — the response did not match the expected schema.
+/// - [`CiderError::Config`] — the token would be sent over plain HTTP to a
+///   remote host.
+///
+/// Every request error carries the [`Endpoint`] it came from; see
+/// [`CiderError`] for classification helpers such as
+/// [`is_retryable`](CiderError::is_retryable).
 #[derive(Debug, Clone)]
 pub struct CiderClient {
-    http: Client,
-    base_url: String,
-    api_token: Option<String>,
+    pub(crate) http: Client,
+    pub(crate) base_url: String,
+    pub(crate) api_token: Option<String>,
+    /// `true` if the base URL is plain HTTP to a non-loopback host.
+    pub(crate) plaintext_remote: bool,
+    pub(crate) allow_insecure_token: bool,
+    pub(crate) decode_mode: DecodeMode,
+    pub(crate) retry_policy: Option<RetryPolicy>,
+    /// `true` if non-idempotent commands are retried too.
+    pub(crate) retry_commands: bool,
+    /// Mute and duck state, shared with clones.
+    pub(crate) volume: Arc<VolumeControl>,
+    #[cfg(feature = "metrics")]
+    pub(crate) metrics: Option<crate::metrics::Metrics>,
+    /// TLS settings for a `wss://` event stream; `None` uses the system's
+    /// root certificates.
+    #[cfg(feature = "events")]
+    pub(crate) tls: Option<native_tls::TlsConnector>,
 }
 
 impl CiderClient {
@@ -118,42 +138,40 @@ impl CiderClient {
     /// possible if TLS initialisation fails at the OS level).
     #[must_use]
     pub fn with_port(port: u16) -> Self {
-        let http = Client::builder()
-            .connect_timeout(CONNECTION_TIMEOUT)
-            .timeout(REQUEST_TIMEOUT)
-            .pool_max_idle_per_host(2)
-            .pool_idle_timeout(Duration::from_secs(10))
-            .tcp_keepalive(None)
+        CiderClientBuilder::new()
+            .port(port)
             .build()
-            .expect("Failed to build HTTP client");
-
-        Self {
-            http,
-            base_url: format!("http://127.0.0.1:{port}"),
-            api_token: None,
-        }
+            .expect("Failed to build HTTP client")
     }
 
-    /// Create a client targeting an arbitrary base URL.
+    /// Create a new client targeting an arbitrary base URL.
+    ///
+    /// Useful when Cider is reachable under a different address, or for
+    /// pointing the client at a mock server in tests.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` — scheme, host and port, e.g. `"http://127.0.0.1:10767"`.
+    ///
+    /// # Panics
     ///
-    /// This is intended for testing (e.g. pointing at a mock server).
-    #[doc(hidden)]
+    /// Panics if `base_url` is not a valid `http`/`https` URL, or if the
+    /// underlying HTTP client cannot be constructed. Use
+    /// [`builder`](Self::builder) to handle these cases as errors.
     #[must_use]
     pub fn with_base_url(base_url: impl Into<String>) -> Self {
-        let http = Client::builder()
-            .connect_timeout(CONNECTION_TIMEOUT)
-            .timeout(REQUEST_TIMEOUT)
-            .pool_max_idle_per_host(2)
-            .pool_idle_timeout(Duration::from_secs(10))
-            .tcp_keepalive(None)
+        CiderClientBuilder::new()
+            .base_url(base_url)
             .build()
-            .expect("Failed to build HTTP client");
+            .expect("Invalid base URL or HTTP client configuration")
+    }
 
-        Self {
-            http,
-            base_url: base_url.into(),
-            api_token: None,
-        }
+    /// Start configuring a client with custom transport settings.
+    ///
+    /// See [`CiderClientBuilder`] for the available options.
+    #[must_use = "a CiderClientBuilder does nothing until `build` is called"]
+    pub fn builder() -> CiderClientBuilder {
+        CiderClientBuilder::new()
     }
 
     /// Attach an API token for authentication.
@@ -161,32 +179,228 @@ impl CiderClient {
     /// The token is sent in the `apptoken` header on every request.
     /// Generate one in Cider under **Settings > Connectivity > Manage External
     /// Application Access**.
+    ///
+    /// If the client targets a non-loopback host over plain HTTP, every
+    /// request fails with [`CiderError::Config`] rather than leak the token.
+    /// Use [`CiderClientBuilder::allow_insecure_token`] to opt out.
     #[must_use]
     pub fn with_token(mut self, token: impl Into<String>) -> Self {
         self.api_token = Some(token.into());
         self
     }
 
+    /// Choose how [`now_playing`](Self::now_playing) and
+    /// [`get_queue`](Self::get_queue) handle undecodable responses.
+    ///
+    /// Defaults to [`DecodeMode::Lenient`].
+    #[must_use]
+    pub fn with_decode_mode(mut self, mode: DecodeMode) -> Self {
+        self.decode_mode = mode;
+        self
+    }
+
+    /// Retry transient failures of idempotent requests under `policy`.
+    ///
+    /// Only `GET` calls are retried automatically; see
+    /// [`retrying`](Self::retrying) for commands.
+    #[must_use]
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Return a handle that also retries non-idempotent commands.
+    ///
+    /// Commands like [`next`](Self::next) or
+    /// [`queue_remove_by_index`](Self::queue_remove_by_index) are not
+    /// retried by default: if Cider applied the command but the response was
+    /// lost, a retry would skip two tracks or remove two items. Use this for
+    /// calls where a duplicate is acceptable. The handle shares the
+    /// connection pool and uses the configured [`RetryPolicy`], or
+    /// [`RetryPolicy::default`] if none is set.
+    ///
+    /// ```no_run
+    /// # async fn example() -> Result<(), cider_api::CiderError> {
+    /// # let client = cider_api::CiderClient::new();
+    /// client.retrying().next().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn retrying(&self) -> Self {
+        let mut client = self.clone();
+        client.retry_policy.get_or_insert_with(RetryPolicy::default);
+        client.retry_commands = true;
+        client
+    }
+
     // ── Internal helpers ─────────────────────────────────────────────────
 
     /// Build a request under `/api/v1/playback`.
-    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
-        let url = format!("{}/api/v1/playback{}", self.base_url, path);
-        let mut req = self.http.request(method, &url);
-        if let Some(token) = &self.api_token {
-            req = req.header("apptoken", token);
-        }
-        req
+    fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+    ) -> Result<reqwest::RequestBuilder, CiderError> {
+        self.request_raw(method, &format!("/api/v1/playback{path}"))
     }
 
     /// Build a request under an arbitrary API path.
-    fn request_raw(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+    ///
+    /// Fails instead of attaching the token if it would travel over plain
+    /// HTTP to a remote host without explicit opt-in.
+    fn request_raw(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+    ) -> Result<reqwest::RequestBuilder, CiderError> {
         let url = format!("{}{}", self.base_url, path);
         let mut req = self.http.request(method, &url);
-        if let Some(token) = &self.api_token {
+        if let Some(token) = self.token()? {
             req = req.header("apptoken", token);
         }
-        req
+        Ok(req)
+    }
+
+    /// The API token, if it may be sent to the configured host.
+    pub(crate) fn token(&self) -> Result<Option<&str>, CiderError> {
+        match &self.api_token {
+            Some(_) if self.plaintext_remote && !self.allow_insecure_token => {
+                Err(CiderError::Config(format!(
+                    "refusing to send the API token over plain HTTP to {}",
+                    self.base_url
+                )))
+            }
+            token => Ok(token.as_deref()),
+        }
+    }
+
+    /// Send a request, retrying transient failures if a [`RetryPolicy`]
+    /// applies to it.
+    async fn send(
+        &self,
+        endpoint: &Endpoint,
+        req: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, CiderError> {
+        let policy = match &self.retry_policy {
+            Some(policy) if endpoint.method == reqwest::Method::GET || self.retry_commands => {
+                policy
+            }
+            _ => return self.send_once(endpoint, req).await,
+        };
+
+        let mut attempt = 1;
+        loop {
+            // Bodies are always buffered JSON, so cloning only fails for
+            // streams, which are never retried.
+            let Some(next) = req.try_clone() else {
+                return self.send_once(endpoint, req).await;
+            };
+            match self.send_once(endpoint, next).await {
+                Err(e) if policy.should_retry(&e, attempt) => {
+                    let delay = policy.delay(attempt);
+                    debug!("{endpoint} attempt {attempt} failed ({e}), retrying in {delay:?}");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Send a request once, recording it in the attached metrics.
+    async fn send_once(
+        &self,
+        endpoint: &Endpoint,
+        req: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, CiderError> {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            let started = std::time::Instant::now();
+            let result = self.send_attempt(endpoint, req).await;
+            metrics.record_request(endpoint, started.elapsed(), result.as_ref().err());
+            return result;
+        }
+        self.send_attempt(endpoint, req).await
+    }
+
+    /// Send a request once, mapping transport failures and non-success
+    /// statuses to [`CiderError`] variants tagged with `endpoint`.
+    async fn send_attempt(
+        &self,
+        endpoint: &Endpoint,
+        req: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, CiderError> {
+        let resp = req.send().await.map_err(|e| {
+            warn!("{endpoint} failed: {e}");
+            CiderError::from_transport(endpoint.clone(), e)
+        })?;
+
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(resp);
+        }
+        let text = resp.text().await.unwrap_or_default();
+        Err(CiderError::from_status(
+            endpoint.clone(),
+            status,
+            ErrorResponse::parse(&text),
+        ))
+    }
+
+    /// Send a command under `/api/v1/playback`, optionally with a JSON body.
+    ///
+    /// Cider sometimes reports failures as `{"status": "error"}` with a
+    /// success status, so the body is checked too.
+    async fn execute(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<&(impl Serialize + ?Sized)>,
+    ) -> Result<(), CiderError> {
+        let endpoint = Endpoint::playback(method.clone(), path);
+        let mut req = self.request(method, path)?;
+        if let Some(body) = body {
+            req = req.json(body);
+        }
+        let resp = self.send(&endpoint, req).await?;
+
+        let status = resp.status();
+        let text = resp
+            .text()
+            .await
+            .map_err(|e| CiderError::from_transport(endpoint.clone(), e))?;
+        match ErrorResponse::parse(&text) {
+            Some(body) if body.is_error() => Err(CiderError::Api {
+                endpoint,
+                status,
+                body: Some(body),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Send `POST` under `/api/v1/playback` without a body.
+    async fn post(&self, path: &str) -> Result<(), CiderError> {
+        self.execute(reqwest::Method::POST, path, None::<&()>).await
+    }
+
+    /// Send `POST` under `/api/v1/playback` with a JSON body.
+    async fn post_json(&self, path: &str, body: &impl Serialize) -> Result<(), CiderError> {
+        self.execute(reqwest::Method::POST, path, Some(body)).await
+    }
+
+    /// Send `GET` under `/api/v1/playback` and decode the JSON response.
+    ///
+    /// Callers decode the endpoint payload (e.g. [`VolumeResponse`]) rather
+    /// than [`ApiResponse`](crate::ApiResponse): the payload's fields sit at
+    /// the top level next to `status`, and `#[serde(flatten)]` would hide the
+    /// failing field path.
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T, CiderError> {
+        let endpoint = Endpoint::playback(reqwest::Method::GET, path);
+        let req = self.request(reqwest::Method::GET, path)?;
+        let resp = self.send(&endpoint, req).await?;
+        read_json(endpoint, resp).await
     }
 
     // ── Status ───────────────────────────────────────────────────────────
@@ -197,36 +411,27 @@ impl CiderClient {
     ///
     /// # Errors
     ///
+    /// - [`CiderError::NotReachable`] if the connection is refused.
+    /// - [`CiderError::Timeout`] if Cider does not answer in time.
     /// - [`CiderError::Unauthorized`] if the token is wrong.
-    /// - [`CiderError::Api`] if the connection is refused or times out.
+    /// - [`CiderError::Api`] on any status other than `200`/`204`.
     #[instrument(skip(self), fields(base_url = %self.base_url))]
     pub async fn is_active(&self) -> Result<(), CiderError> {
         debug!("Checking Cider connection");
 
-        let resp = self
-            .request(reqwest::Method::GET, "/active")
-            .send()
-            .await
-            .map_err(|e| {
-                warn!("Connection error: {e:?}");
-                if e.is_connect() {
-                    CiderError::Api(format!("Connection refused ({e})"))
-                } else if e.is_timeout() {
-                    CiderError::Api("Connection timed out".to_string())
-                } else {
-                    CiderError::Api(format!("Network error ({e})"))
-                }
-            })?;
+        let endpoint = Endpoint::playback(reqwest::Method::GET, "/active");
+        let req = self.request(reqwest::Method::GET, "/active")?;
+        let resp = self.send(&endpoint, req).await?;
 
         debug!("Response status: {}", resp.status());
 
-        match resp.status().as_u16() {
-            200 | 204 => Ok(()),
-            401 | 403 => Err(CiderError::Unauthorized),
-            _ => Err(CiderError::Api(format!(
-                "Unexpected response (HTTP {})",
-                resp.status().as_u16()
-            ))),
+        match resp.status() {
+            StatusCode::OK | StatusCode::NO_CONTENT => Ok(()),
+            status => Err(CiderError::Api {
+                endpoint,
+                status,
+                body: None,
+            }),
         }
     }
 
@@ -238,14 +443,9 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the response cannot be parsed.
     pub async fn is_playing(&self) -> Result<bool, CiderError> {
-        let resp: ApiResponse<IsPlayingResponse> = self
-            .request(reqwest::Method::GET, "/is-playing")
-            .send()
-            .await?
-            .json()
-            .await?;
+        let resp: IsPlayingResponse = self.get_json("/is-playing").await?;
 
-        Ok(resp.data.is_playing)
+        Ok(resp.is_playing)
     }
 
     /// Get the currently playing track.
@@ -258,21 +458,27 @@ impl CiderClient {
     ///
     /// # Errors
     ///
-    /// Returns [`CiderError`] on network failure. Returns `Ok(None)` (not an
-    /// error) if nothing is playing or the response cannot be parsed.
+    /// Returns [`CiderError`] if the request fails or Cider returns an error
+    /// status. Returns `Ok(None)` (not an error) if nothing is playing. A
+    /// response that cannot be parsed returns [`CiderError::Decode`] with
+    /// [`DecodeMode::Strict`], and `Ok(None)` by default.
     pub async fn now_playing(&self) -> Result<Option<NowPlaying>, CiderError> {
-        let resp = self
-            .request(reqwest::Method::GET, "/now-playing")
-            .send()
-            .await?;
-
-        if resp.status() == 404 || resp.status() == 204 {
-            return Ok(None);
-        }
-
-        match resp.json::<ApiResponse<NowPlayingResponse>>().await {
-            Ok(data) => Ok(Some(data.data.info)),
-            Err(_) => Ok(None),
+        let endpoint = Endpoint::playback(reqwest::Method::GET, "/now-playing");
+        let req = self.request(reqwest::Method::GET, "/now-playing")?;
+        let resp = match self.send(&endpoint, req).await {
+            Ok(resp) if resp.status() == StatusCode::NO_CONTENT => return Ok(None),
+            Ok(resp) => resp,
+            Err(e) if e.status_code() == Some(StatusCode::NOT_FOUND) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        match read_json::<NowPlayingResponse>(endpoint, resp).await {
+            Ok(data) => Ok(Some(data.info)),
+            Err(e @ CiderError::Decode { .. }) if self.decode_mode == DecodeMode::Lenient => {
+                warn!("Ignoring undecodable now-playing response: {e}");
+                Ok(None)
+            }
+            Err(e) => Err(e),
         }
     }
 
@@ -287,11 +493,7 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
     pub async fn play(&self) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/play")
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+        self.post("/play").await
     }
 
     /// Pause the current track. No-op if already paused or nothing is playing.
@@ -300,11 +502,7 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
     pub async fn pause(&self) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/pause")
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+        self.post("/pause").await
     }
 
     /// Toggle between playing and paused.
@@ -313,11 +511,7 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
     pub async fn play_pause(&self) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/playpause")
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+        self.post("/playpause").await
     }
 
     /// Stop playback and unload the current track. Queue items are kept.
@@ -326,11 +520,7 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
     pub async fn stop(&self) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/stop")
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+        self.post("/stop").await
     }
 
     /// Skip to the next track in the queue.
@@ -341,11 +531,7 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
     pub async fn next(&self) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/next")
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+        self.post("/next").await
     }
 
     /// Go back to the previously played track (from playback history).
@@ -354,11 +540,7 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
     pub async fn previous(&self) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/previous")
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+        self.post("/previous").await
     }
 
     /// Seek to a position in the current track.
@@ -369,16 +551,17 @@ impl CiderClient {
     ///
     /// # Errors
     ///
-    /// Returns [`CiderError`] if the request fails or the server rejects it.
+    /// Returns [`CiderError::NothingPlaying`] if no track is loaded, or
+    /// another [`CiderError`] if the request fails or the server rejects it.
     pub async fn seek(&self, position_secs: f64) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/seek")
-            .json(&SeekRequest {
+        self.post_json(
+            "/seek",
+            &SeekRequest {
                 position: position_secs,
-            })
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+            },
+        )
+        .await
+        .map_err(nothing_playing_on_404)
     }
 
     /// Convenience wrapper for [`seek`](Self::seek) that accepts milliseconds.
@@ -392,12 +575,74 @@ impl CiderClient {
         self.seek(secs).await
     }
 
+    /// Seek to a position in the current track.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::NothingPlaying`] if no track is loaded, or
+    /// another [`CiderError`] if the request fails or the server rejects it.
+    pub async fn seek_to(&self, position: Duration) -> Result<(), CiderError> {
+        self.seek(position.as_secs_f64()).await
+    }
+
+    /// Seek relative to the current position by `offset_ms` milliseconds
+    /// (negative to rewind).
+    ///
+    /// Reads the current position first and clamps the target to the start
+    /// and end of the track (only the start if Cider reports no duration).
+    /// Returns the position Cider reports after the seek, which can differ
+    /// from the target; if nothing is playing by then, the clamped target.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn example() -> Result<(), cider_api::CiderError> {
+    /// # let client = cider_api::CiderClient::new();
+    /// // Skip the intro, then rewind ten seconds.
+    /// client.seek_by(30_000).await?;
+    /// let landed = client.seek_by(-10_000).await?;
+    /// println!("now at {landed:?}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::NothingPlaying`] if no track is loaded, or
+    /// another [`CiderError`] if a request fails.
+    pub async fn seek_by(&self, offset_ms: i64) -> Result<Duration, CiderError> {
+        let track = self
+            .now_playing()
+            .await?
+            .ok_or_else(|| CiderError::NothingPlaying {
+                endpoint: Endpoint::playback(Method::POST, "/seek"),
+            })?;
+
+        let position = track.position();
+        let offset = Duration::from_millis(offset_ms.unsigned_abs());
+        let mut target = if offset_ms < 0 {
+            position.saturating_sub(offset)
+        } else {
+            position.saturating_add(offset)
+        };
+        if track.duration_in_millis > 0 {
+            target = target.min(track.duration());
+        }
+
+        self.seek_to(target).await?;
+        Ok(self
+            .now_playing()
+            .await?
+            .map_or(target, |track| track.position()))
+    }
+
     // ── Play items ───────────────────────────────────────────────────────
 
     /// Start playback of an Apple Music URL.
     ///
     /// The URL can be obtained from **Share > Apple Music** in Cider or the
-    /// Apple Music web player.
+    /// Apple Music web player. It is forwarded as-is; use
+    /// [`play_parsed`](Self::play_parsed) to validate it first.
     ///
     /// # Arguments
     ///
@@ -407,36 +652,49 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
     pub async fn play_url(&self, url: &str) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/play-url")
-            .json(&PlayUrlRequest {
+        self.post_json(
+            "/play-url",
+            &PlayUrlRequest {
                 url: url.to_string(),
-            })
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+            },
+        )
+        .await
+    }
+
+    /// Validate an Apple Music link locally, then play it.
+    ///
+    /// Links that resolve to an [`ItemRef`] (albums, songs, playlists,
+    /// stations, music videos) are sent with [`play_item`](Self::play_item).
+    /// Malformed, non-Apple Music and unplayable (artist) links fail without
+    /// contacting Cider.
+    ///
+    /// Returns the parsed link.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::InvalidItem`] if `url` is not a playable Apple
+    /// Music link this crate understands, or another [`CiderError`] if the
+    /// request fails or the server rejects it.
+    pub async fn play_parsed(&self, url: &str) -> Result<AppleMusicUrl, CiderError> {
+        let parsed: AppleMusicUrl = url.parse()?;
+        let item = parsed.item_ref().ok_or_else(|| {
+            CiderError::InvalidItem(format!("{} links cannot be played", parsed.kind.as_str()))
+        })?;
+        self.play_item(&item).await?;
+        Ok(parsed)
     }
 
-    /// Start playback of an item by Apple Music type and catalog ID.
+    /// Start playback of a catalog or library item.
     ///
     /// # Arguments
     ///
-    /// * `item_type` — Apple Music type: `"songs"`, `"albums"`, `"playlists"`, etc.
-    /// * `id` — catalog ID as a **string** (e.g. `"1719861213"`).
+    /// * `item` — e.g. `&ItemRef::song("1719861213")?`.
     ///
     /// # Errors
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
-    pub async fn play_item(&self, item_type: &str, id: &str) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/play-item")
-            .json(&PlayItemRequest {
-                item_type: item_type.to_string(),
-                id: id.to_string(),
-            })
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+    pub async fn play_item(&self, item: &ItemRef) -> Result<(), CiderError> {
+        self.post_json("/play-item", &PlayItemRequest::from(item)).await
     }
 
     /// Start playback of an item by its Apple Music API href.
@@ -449,58 +707,31 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
     pub async fn play_item_href(&self, href: &str) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/play-item-href")
-            .json(&PlayItemHrefRequest {
+        self.post_json(
+            "/play-item-href",
+            &PlayItemHrefRequest {
                 href: href.to_string(),
-            })
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+            },
+        )
+        .await
     }
 
     /// Add an item to the **start** of the queue (plays next).
     ///
-    /// # Arguments
-    ///
-    /// * `item_type` — `"songs"`, `"albums"`, etc.
-    /// * `id` — catalog ID as a string.
-    ///
     /// # Errors
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
-    pub async fn play_next(&self, item_type: &str, id: &str) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/play-next")
-            .json(&PlayItemRequest {
-                item_type: item_type.to_string(),
-                id: id.to_string(),
-            })
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+    pub async fn play_next(&self, item: &ItemRef) -> Result<(), CiderError> {
+        self.post_json("/play-next", &PlayItemRequest::from(item)).await
     }
 
     /// Add an item to the **end** of the queue (plays last).
     ///
-    /// # Arguments
-    ///
-    /// * `item_type` — `"songs"`, `"albums"`, etc.
-    /// * `id` — catalog ID as a string.
-    ///
     /// # Errors
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
-    pub async fn play_later(&self, item_type: &str, id: &str) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/play-later")
-            .json(&PlayItemRequest {
-                item_type: item_type.to_string(),
-                id: id.to_string(),
-            })
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+    pub async fn play_later(&self, item: &ItemRef) -> Result<(), CiderError> {
+        self.post_json("/play-later", &PlayItemRequest::from(item)).await
     }
 
     // ── Queue ────────────────────────────────────────────────────────────
@@ -511,28 +742,32 @@ impl CiderClient {
     /// playing track, and upcoming items. Use [`QueueItem::is_current`] to
     /// find the active track.
     ///
-    /// Returns an empty `Vec` if the queue is empty or the response format is
-    /// unexpected.
+    /// Returns an empty `Vec` if the queue is empty or, by default, if the
+    /// response format is unexpected.
     ///
     /// # Errors
     ///
-    /// Returns [`CiderError`] on network failure. Returns `Ok(vec![])` (not an
-    /// error) if the queue is empty or the format is unrecognised.
+    /// Returns [`CiderError`] if the request fails or Cider returns an error
+    /// status. Returns `Ok(vec![])` (not an error) if the queue is empty. An
+    /// unrecognised format returns [`CiderError::Decode`] with
+    /// [`DecodeMode::Strict`], and `Ok(vec![])` by default.
     pub async fn get_queue(&self) -> Result<Vec<QueueItem>, CiderError> {
-        let resp = self
-            .request(reqwest::Method::GET, "/queue")
-            .send()
-            .await?;
-
-        let status = resp.status();
-        if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::NO_CONTENT {
-            return Ok(vec![]);
-        }
-
-        let text = resp.text().await?;
-        match serde_json::from_str::<Vec<QueueItem>>(&text) {
+        let endpoint = Endpoint::playback(reqwest::Method::GET, "/queue");
+        let req = self.request(reqwest::Method::GET, "/queue")?;
+        let resp = match self.send(&endpoint, req).await {
+            Ok(resp) if resp.status() == StatusCode::NO_CONTENT => return Ok(vec![]),
+            Ok(resp) => resp,
+            Err(e) if e.status_code() == Some(StatusCode::NOT_FOUND) => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+
+        match read_json::<Vec<QueueItem>>(endpoint, resp).await {
             Ok(items) => Ok(items),
-            Err(_) => Ok(vec![]),
+            Err(e @ CiderError::Decode { .. }) if self.decode_mode == DecodeMode::Lenient => {
+                warn!("Ignoring undecodable queue response: {e}");
+                Ok(vec![])
+            }
+            Err(e) => Err(e),
         }
     }
 
@@ -549,16 +784,15 @@ impl CiderClient {
         start_index: u32,
         destination_index: u32,
     ) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/queue/move-to-position")
-            .json(&QueueMoveRequest {
+        self.post_json(
+            "/queue/move-to-position",
+            &QueueMoveRequest {
                 start_index,
                 destination_index,
                 return_queue: None,
-            })
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+            },
+        )
+        .await
     }
 
     /// Remove a queue item by its **1-based** index.
@@ -567,12 +801,7 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
     pub async fn queue_remove_by_index(&self, index: u32) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/queue/remove-by-index")
-            .json(&QueueRemoveRequest { index })
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+        self.post_json("/queue/remove-by-index", &QueueRemoveRequest { index }).await
     }
 
     /// Clear all items from the queue.
@@ -581,11 +810,7 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
     pub async fn clear_queue(&self) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/queue/clear-queue")
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+        self.post("/queue/clear-queue").await
     }
 
     // ── Volume ───────────────────────────────────────────────────────────
@@ -596,14 +821,9 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the response cannot be parsed.
     pub async fn get_volume(&self) -> Result<f32, CiderError> {
-        let resp: ApiResponse<VolumeResponse> = self
-            .request(reqwest::Method::GET, "/volume")
-            .send()
-            .await?
-            .json()
-            .await?;
+        let resp: VolumeResponse = self.get_json("/volume").await?;
 
-        Ok(resp.data.volume)
+        Ok(resp.volume)
     }
 
     /// Set the volume. Values are clamped to `0.0..=1.0`.
@@ -612,14 +832,13 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
     pub async fn set_volume(&self, volume: f32) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/volume")
-            .json(&VolumeRequest {
+        self.post_json(
+            "/volume",
+            &VolumeRequest {
                 volume: volume.clamp(0.0, 1.0),
-            })
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+            },
+        )
+        .await
     }
 
     // ── Library / ratings ────────────────────────────────────────────────
@@ -630,57 +849,39 @@ impl CiderClient {
     ///
     /// # Errors
     ///
-    /// Returns [`CiderError`] if the request fails or the server rejects it.
+    /// Returns [`CiderError::NothingPlaying`] if no track is loaded, or
+    /// another [`CiderError`] if the request fails or the server rejects it.
     pub async fn add_to_library(&self) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/add-to-library")
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+        self.post("/add-to-library")
+            .await
+            .map_err(nothing_playing_on_404)
     }
 
     /// Rate the currently playing track.
     ///
-    /// * `-1` — dislike
-    /// * `0` — remove rating
-    /// * `1` — like
-    ///
-    /// The value is clamped to `-1..=1`.
+    /// [`Rating::None`] removes an existing rating.
     ///
     /// # Errors
     ///
-    /// Returns [`CiderError`] if the request fails or the server rejects it.
-    pub async fn set_rating(&self, rating: i8) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/set-rating")
-            .json(&RatingRequest {
-                rating: rating.clamp(-1, 1),
-            })
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+    /// Returns [`CiderError::NothingPlaying`] if no track is loaded, or
+    /// another [`CiderError`] if the request fails or the server rejects it.
+    pub async fn set_rating(&self, rating: Rating) -> Result<(), CiderError> {
+        self.post_json("/set-rating", &RatingRequest { rating })
+            .await
+            .map_err(nothing_playing_on_404)
     }
 
     // ── Repeat / shuffle / autoplay ──────────────────────────────────────
 
     /// Get the current repeat mode.
     ///
-    /// * `0` — off
-    /// * `1` — repeat this song
-    /// * `2` — repeat all
-    ///
     /// # Errors
     ///
     /// Returns [`CiderError`] if the request fails or the response cannot be parsed.
-    pub async fn get_repeat_mode(&self) -> Result<u8, CiderError> {
-        let resp: ApiResponse<RepeatModeResponse> = self
-            .request(reqwest::Method::GET, "/repeat-mode")
-            .send()
-            .await?
-            .json()
-            .await?;
+    pub async fn get_repeat_mode(&self) -> Result<RepeatMode, CiderError> {
+        let resp: RepeatModeResponse = self.get_json("/repeat-mode").await?;
 
-        Ok(resp.data.value)
+        Ok(resp.value)
     }
 
     /// Cycle repeat mode: **repeat one > repeat all > off**.
@@ -689,27 +890,50 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
     pub async fn toggle_repeat(&self) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/toggle-repeat")
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+        self.post("/toggle-repeat").await
+    }
+
+    /// Set the repeat mode.
+    ///
+    /// Cider only exposes a toggle, so this reads the current mode and calls
+    /// [`toggle_repeat`](Self::toggle_repeat) until `mode` is reached, waiting
+    /// for each toggle to show up before the next. No-op if the mode is
+    /// already set.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::Config`] for [`RepeatMode::Unknown`],
+    /// [`CiderError::NotApplied`] if Cider does not end up in `mode`, or
+    /// another [`CiderError`] if a request fails.
+    pub async fn set_repeat_mode(&self, mode: RepeatMode) -> Result<(), CiderError> {
+        if let RepeatMode::Unknown(value) = mode {
+            return Err(CiderError::Config(format!(
+                "cannot set unknown repeat mode {value}"
+            )));
+        }
+
+        // Off -> One -> All -> Off: at most two toggles from any known mode,
+        // plus one to get out of an unknown one.
+        let mut current = self.get_repeat_mode().await?;
+        for _ in 0..3 {
+            if current == mode {
+                return Ok(());
+            }
+            self.toggle_repeat().await?;
+            current = settle(current, || self.get_repeat_mode()).await?;
+        }
+        verify(Endpoint::playback(Method::GET, "/repeat-mode"), mode, current)
     }
 
-    /// Get the current shuffle mode (`0` = off, `1` = on).
+    /// Get the current shuffle mode.
     ///
     /// # Errors
     ///
     /// Returns [`CiderError`] if the request fails or the response cannot be parsed.
-    pub async fn get_shuffle_mode(&self) -> Result<u8, CiderError> {
-        let resp: ApiResponse<ShuffleModeResponse> = self
-            .request(reqwest::Method::GET, "/shuffle-mode")
-            .send()
-            .await?
-            .json()
-            .await?;
+    pub async fn get_shuffle_mode(&self) -> Result<ShuffleMode, CiderError> {
+        let resp: ShuffleModeResponse = self.get_json("/shuffle-mode").await?;
 
-        Ok(resp.data.value)
+        Ok(resp.value)
     }
 
     /// Toggle shuffle on/off.
@@ -718,11 +942,34 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
     pub async fn toggle_shuffle(&self) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/toggle-shuffle")
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+        self.post("/toggle-shuffle").await
+    }
+
+    /// Turn shuffle on or off.
+    ///
+    /// Reads the current mode and calls [`toggle_shuffle`](Self::toggle_shuffle)
+    /// only if it differs, then verifies the change. Accepts anything that
+    /// converts into a [`ShuffleMode`], including `bool`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::Config`] for [`ShuffleMode::Unknown`],
+    /// [`CiderError::NotApplied`] if Cider does not end up in `mode`, or
+    /// another [`CiderError`] if a request fails.
+    pub async fn set_shuffle_mode(&self, mode: impl Into<ShuffleMode>) -> Result<(), CiderError> {
+        let mode = mode.into();
+        if let ShuffleMode::Unknown(value) = mode {
+            return Err(CiderError::Config(format!(
+                "cannot set unknown shuffle mode {value}"
+            )));
+        }
+
+        let mut current = self.get_shuffle_mode().await?;
+        if current != mode {
+            self.toggle_shuffle().await?;
+            current = settle(current, || self.get_shuffle_mode()).await?;
+        }
+        verify(Endpoint::playback(Method::GET, "/shuffle-mode"), mode, current)
     }
 
     /// Get the current autoplay status (`true` = on).
@@ -731,14 +978,9 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the response cannot be parsed.
     pub async fn get_autoplay(&self) -> Result<bool, CiderError> {
-        let resp: ApiResponse<AutoplayResponse> = self
-            .request(reqwest::Method::GET, "/autoplay")
-            .send()
-            .await?
-            .json()
-            .await?;
+        let resp: AutoplayResponse = self.get_json("/autoplay").await?;
 
-        Ok(resp.data.value)
+        Ok(resp.value)
     }
 
     /// Toggle autoplay on/off.
@@ -747,11 +989,7 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the server rejects it.
     pub async fn toggle_autoplay(&self) -> Result<(), CiderError> {
-        self.request(reqwest::Method::POST, "/toggle-autoplay")
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+        self.post("/toggle-autoplay").await
     }
 
     // ── Apple Music API passthrough ──────────────────────────────────────
@@ -770,19 +1008,113 @@ impl CiderClient {
     ///
     /// Returns [`CiderError`] if the request fails or the response cannot be parsed.
     pub async fn amapi_run_v3(&self, path: &str) -> Result<serde_json::Value, CiderError> {
-        let resp = self
-            .request_raw(reqwest::Method::POST, "/api/v1/amapi/run-v3")
+        let endpoint = Endpoint {
+            method: reqwest::Method::POST,
+            path: "/api/v1/amapi/run-v3".to_string(),
+        };
+        let req = self
+            .request_raw(reqwest::Method::POST, &endpoint.path)?
             .json(&AmApiRequest {
                 path: path.to_string(),
-            })
-            .send()
-            .await?
-            .error_for_status()?;
+            });
+        let resp = self.send(&endpoint, req).await?;
+        read_json(endpoint, resp).await
+    }
+}
+
+/// Map a `404` from a command that acts on the current track to
+/// [`CiderError::NothingPlaying`].
+fn nothing_playing_on_404(e: CiderError) -> CiderError {
+    match e {
+        CiderError::Api {
+            endpoint,
+            status: StatusCode::NOT_FOUND,
+            ..
+        } => CiderError::NothingPlaying { endpoint },
+        e => e,
+    }
+}
+
+/// Re-read a mode after a toggle until it differs from `previous`, giving
+/// Cider up to [`SETTLE_POLLS`] × [`SETTLE_INTERVAL`] to apply it.
+///
+/// Returns the last value read, which equals `previous` if nothing changed.
+async fn settle<T, F, Fut>(previous: T, mut read: F) -> Result<T, CiderError>
+where
+    T: PartialEq,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, CiderError>>,
+{
+    let mut current = read().await?;
+    for _ in 1..SETTLE_POLLS {
+        if current != previous {
+            break;
+        }
+        tokio::time::sleep(SETTLE_INTERVAL).await;
+        current = read().await?;
+    }
+    Ok(current)
+}
 
-        resp.json().await.map_err(CiderError::from)
+/// Check that a setter reached `expected`, or report what Cider has instead.
+fn verify<T>(endpoint: Endpoint, expected: T, actual: T) -> Result<(), CiderError>
+where
+    T: Copy + PartialEq + std::fmt::Display,
+{
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(CiderError::NotApplied {
+            endpoint,
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        })
     }
 }
 
+/// Read a response body and decode it as JSON, recording the failing field
+/// path on error.
+async fn read_json<T: DeserializeOwned>(
+    endpoint: Endpoint,
+    resp: reqwest::Response,
+) -> Result<T, CiderError> {
+    let status = resp.status();
+    let text = resp
+        .text()
+        .await
+        .map_err(|e| CiderError::from_transport(endpoint.clone(), e))?;
+    decode_json(endpoint, Some(status), &text)
+}
+
+/// Decode `text` as JSON, mapping failures to [`CiderError::Decode`].
+pub(crate) fn decode_json<T: DeserializeOwned>(
+    endpoint: Endpoint,
+    status: Option<StatusCode>,
+    text: &str,
+) -> Result<T, CiderError> {
+    let de = &mut serde_json::Deserializer::from_str(text);
+    serde_path_to_error::deserialize(de).map_err(|e| CiderError::Decode {
+        endpoint,
+        status,
+        path: e.path().to_string(),
+        body: truncate_body(text),
+        source: e.into_inner(),
+    })
+}
+
+/// Truncate a response body to [`MAX_ERROR_BODY_LEN`] bytes on a character
+/// boundary, marking the cut with `…`.
+fn truncate_body(text: &str) -> String {
+    if text.len() <= MAX_ERROR_BODY_LEN {
+        return text.to_string();
+    }
+    let mut end = MAX_ERROR_BODY_LEN;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…", &text[..end])
+}
+
 impl Default for CiderClient {
     fn default() -> Self {
         Self::new()
@@ -851,7 +1183,7 @@ mod tests {
     #[test]
     fn request_builds_correct_url() {
         let client = CiderClient::with_port(9999);
-        let req = client.request(reqwest::Method::GET, "/active");
+        let req = client.request(reqwest::Method::GET, "/active").unwrap();
         let built = req.build().unwrap();
         assert_eq!(
             built.url().as_str(),
@@ -862,7 +1194,9 @@ mod tests {
     #[test]
     fn request_raw_builds_correct_url() {
         let client = CiderClient::with_port(9999);
-        let req = client.request_raw(reqwest::Method::POST, "/api/v1/amapi/run-v3");
+        let req = client
+            .request_raw(reqwest::Method::POST, "/api/v1/amapi/run-v3")
+            .unwrap();
         let built = req.build().unwrap();
         assert_eq!(
             built.url().as_str(),
@@ -873,7 +1207,7 @@ mod tests {
     #[test]
     fn request_includes_token_header() {
         let client = CiderClient::new().with_token("my-secret");
-        let req = client.request(reqwest::Method::GET, "/active");
+        let req = client.request(reqwest::Method::GET, "/active").unwrap();
         let built = req.build().unwrap();
         assert_eq!(built.headers().get("apptoken").unwrap(), "my-secret");
     }
@@ -881,15 +1215,74 @@ mod tests {
     #[test]
     fn request_omits_token_header_when_none() {
         let client = CiderClient::new();
-        let req = client.request(reqwest::Method::GET, "/active");
+        let req = client.request(reqwest::Method::GET, "/active").unwrap();
         let built = req.build().unwrap();
         assert!(built.headers().get("apptoken").is_none());
     }
 
+    #[test]
+    fn request_refuses_token_over_plaintext_remote() {
+        let client = CiderClient::with_base_url("http://192.168.1.20:10767").with_token("secret");
+        let err = client.request(reqwest::Method::GET, "/active").unwrap_err();
+        assert!(matches!(err, CiderError::Config(_)));
+    }
+
+    #[test]
+    fn request_allows_plaintext_remote_without_token() {
+        let client = CiderClient::with_base_url("http://192.168.1.20:10767");
+        let req = client.request(reqwest::Method::GET, "/active").unwrap();
+        assert!(req.build().unwrap().headers().get("apptoken").is_none());
+    }
+
+    #[test]
+    fn with_decode_mode_sets_mode() {
+        let client = CiderClient::new();
+        assert_eq!(client.decode_mode, DecodeMode::Lenient);
+        let client = client.with_decode_mode(DecodeMode::Strict);
+        assert_eq!(client.decode_mode, DecodeMode::Strict);
+    }
+
+    #[test]
+    fn decode_json_reports_field_path() {
+        let endpoint = Endpoint::playback(reqwest::Method::GET, "/now-playing");
+        let body = r#"{"status":"ok","info":{"durationInMillis":"long"}}"#;
+        let err = decode_json::<NowPlayingResponse>(endpoint, Some(StatusCode::OK), body).unwrap_err();
+        match err {
+            CiderError::Decode {
+                endpoint,
+                status,
+                path,
+                body: raw,
+                ..
+            } => {
+                assert_eq!(endpoint.path, "/api/v1/playback/now-playing");
+                assert_eq!(status, Some(StatusCode::OK));
+                assert_eq!(path, "info.durationInMillis");
+                assert_eq!(raw, body);
+            }
+            other => panic!("expected Decode, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn truncate_body_keeps_short_bodies() {
+        assert_eq!(truncate_body("short"), "short");
+    }
+
+    #[test]
+    fn truncate_body_cuts_on_char_boundary() {
+        let body = "é".repeat(MAX_ERROR_BODY_LEN);
+        let cut = truncate_body(&body);
+        assert!(cut.ends_with('…'));
+        assert!(cut.len() <= MAX_ERROR_BODY_LEN + '…'.len_utf8());
+    }
+
     #[test]
     fn request_raw_includes_token_header() {
         let client = CiderClient::new().with_token("secret");
-        let req = client.request_raw(reqwest::Method::POST, "/api/v1/amapi/run-v3");
+        let req = client
+            .request_raw(reqwest::Method::POST, "/api/v1/amapi/run-v3")
+            .unwrap();
         let built = req.build().unwrap();
         assert_eq!(built.headers().get("apptoken").unwrap(), "secret");
     }
@@ -0,0 +1,370 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Cron expressions for recurring [`Job`](crate::Job)s.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{
+    DateTime, Datelike, Duration as ChronoDuration, Local, NaiveDate, TimeZone, Timelike,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::CiderError;
+
+/// How far ahead [`Cron::next_after`] looks before giving up (eight years,
+/// enough for `0 0 29 2 *` across a century boundary: 2096 to 2104).
+const MAX_DAYS_AHEAD: u32 = 8 * 366;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A five-field cron expression: `minute hour day-of-month month
+/// day-of-week`, evaluated in local time.
+///
+/// Fields accept `*`, numbers, ranges (`1-5`), lists (`1,15`) and steps
+/// (`*/15`, `8-18/2`). Months and weekdays also accept three-letter names
+/// (`jan`, `mon`); Sunday is `0` or `7`. As in classic cron, when both the
+/// day-of-month and day-of-week are restricted, a day matching either one
+/// is used. A field starting with `*`, such as `*/2`, does not count as
+/// restricted there, so `0 0 */2 * mon` runs on Mondays with an odd day of
+/// the month. `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly` are
+/// shorthands.
+///
+/// ```
+/// # use cider_api::Cron;
+/// let weekdays: Cron = "30 7 * * mon-fri".parse()?;
+/// assert_eq!(weekdays.to_string(), "30 7 * * mon-fri");
+/// # Ok::<(), cider_api::CiderError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Cron {
+    source: String,
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    /// The first time strictly after `after` that matches, or `None` if
+    /// nothing matches within eight years (e.g. `0 0 31 2 *`).
+    ///
+    /// Times skipped by a daylight-saving jump do not match; a repeated hour
+    /// matches its first occurrence.
+    #[must_use]
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let after_naive = after.naive_local();
+        let mut date = after_naive.date();
+        for _ in 0..MAX_DAYS_AHEAD {
+            if self.matches_day(date) {
+                for hour in 0..24 {
+                    if self.hours & (1 << hour) == 0 {
+                        continue;
+                    }
+                    for minute in 0..60 {
+                        if self.minutes & (1 << minute) == 0 {
+                            continue;
+                        }
+                        let naive = date.and_hms_opt(hour, minute, 0)?;
+                        if naive <= after_naive {
+                            continue;
+                        }
+                        if let Some(time) = Local.from_local_datetime(&naive).earliest() {
+                            if time > after {
+                                return Some(time);
+                            }
+                        }
+                    }
+                }
+            }
+            date = date.checked_add_signed(ChronoDuration::days(1))?;
+        }
+        None
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month0()) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        if self.any_day || self.any_weekday {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+
+    /// Whether `time` (to the minute) matches.
+    #[must_use]
+    pub fn matches(&self, time: DateTime<Local>) -> bool {
+        self.matches_day(time.date_naive())
+            && self.hours & (1 << time.hour()) != 0
+            && self.minutes & (1 << time.minute()) != 0
+    }
+}
+
+impl FromStr for Cron {
+    type Err = CiderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let source = s.trim();
+        let expanded = match source {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(invalid(
+                source,
+                "expected 5 fields: minute hour day-of-month month day-of-week",
+            ));
+        };
+
+        let weekdays = parse_field(source, weekday, 0, 7, &WEEKDAYS)?;
+        // Fold 7 (Sunday) onto 0.
+        let weekdays = (weekdays | (weekdays >> 7)) & 0x7f;
+        Ok(Self {
+            source: source.to_owned(),
+            minutes: parse_field(source, minute, 0, 59, &[])?,
+            #[allow(clippy::cast_possible_truncation)]
+            hours: parse_field(source, hour, 0, 23, &[])? as u32,
+            #[allow(clippy::cast_possible_truncation)]
+            days: parse_field(source, day, 1, 31, &[])? as u32,
+            // Months are stored zero-based.
+            #[allow(clippy::cast_possible_truncation)]
+            months: (parse_field(source, month, 1, 12, &MONTHS)? >> 1) as u16,
+            #[allow(clippy::cast_possible_truncation)]
+            weekdays: weekdays as u8,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+}
+
+/// Parse one field into a bit set of the values it matches.
+///
+/// `names[i]` stands for `i`, or `i + 1` when `min` is 1 (months).
+fn parse_field(
+    source: &str,
+    field: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+) -> Result<u64, CiderError> {
+    let value = |s: &str| -> Result<u32, CiderError> {
+        let lower = s.to_ascii_lowercase();
+        let by_name = names
+            .iter()
+            .position(|&name| name == lower)
+            .and_then(|i| u32::try_from(i).ok())
+            .map(|i| i + min.min(1));
+        let n = match by_name {
+            Some(n) => n,
+            None => s
+                .parse()
+                .map_err(|_| invalid(source, &format!("`{s}` is not a number")))?,
+        };
+        if (min..=max).contains(&n) {
+            Ok(n)
+        } else {
+            Err(invalid(source, &format!("{n} is outside {min}-{max}")))
+        }
+    };
+
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|&step| step > 0)
+                    .ok_or_else(|| invalid(source, &format!("bad step in `{part}`")))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `5/15` means "from 5 to the end, every 15".
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let n = value(range)?;
+                    (n, n)
+                }
+            },
+        };
+        if start > end {
+            return Err(invalid(source, &format!("empty range `{range}`")));
+        }
+        for n in (start..=end).step_by(step as usize) {
+            bits |= 1 << n;
+        }
+    }
+    Ok(bits)
+}
+
+fn invalid(source: &str, reason: &str) -> CiderError {
+    CiderError::Config(format!("invalid cron expression `{source}`: {reason}"))
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl TryFrom<String> for Cron {
+    type Error = CiderError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Cron> for String {
+    fn from(cron: Cron) -> Self {
+        cron.source
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Local> {
+        Local
+            .from_local_datetime(
+                &NaiveDate::from_ymd_opt(y, mo, d)
+                    .unwrap()
+                    .and_hms_opt(h, mi, 0)
+                    .unwrap(),
+            )
+            .earliest()
+            .unwrap()
+    }
+
+    fn next(expr: &str, after: DateTime<Local>) -> DateTime<Local> {
+        expr.parse::<Cron>().unwrap().next_after(after).unwrap()
+    }
+
+    #[test]
+    fn every_minute_is_strictly_after() {
+        let now = local(2026, 3, 10, 12, 0);
+        assert_eq!(next("* * * * *", now), local(2026, 3, 10, 12, 1));
+    }
+
+    #[test]
+    fn weekday_mornings() {
+        // 2026-10-16 is a Friday.
+        let friday_noon = local(2026, 10, 16, 12, 0);
+        assert_eq!(
+            next("30 7 * * mon-fri", friday_noon),
+            local(2026, 10, 19, 7, 30)
+        );
+        assert_eq!(
+            next("30 7 * * 1-5", local(2026, 10, 16, 7, 0)),
+            local(2026, 10, 16, 7, 30)
+        );
+    }
+
+    #[test]
+    fn steps_and_lists() {
+        let now = local(2026, 1, 1, 8, 7);
+        assert_eq!(next("*/15 * * * *", now), local(2026, 1, 1, 8, 15));
+        assert_eq!(next("0 8-18/4 * * *", now), local(2026, 1, 1, 12, 0));
+        assert_eq!(next("5,50 * * * *", now), local(2026, 1, 1, 8, 50));
+    }
+
+    #[test]
+    fn day_of_month_or_weekday() {
+        // The 13th, or any Friday: 2026-02-06 is a Friday, before the 13th.
+        assert_eq!(
+            next("0 0 13 * fri", local(2026, 2, 1, 0, 0)),
+            local(2026, 2, 6, 0, 0)
+        );
+        // A stepped `*` is not a restriction: odd days that are Mondays.
+        assert_eq!(
+            next("0 0 */2 * mon", local(2026, 2, 1, 0, 0)),
+            local(2026, 2, 9, 0, 0)
+        );
+    }
+
+    #[test]
+    fn sunday_is_zero_or_seven() {
+        let a: Cron = "0 9 * * 0".parse().unwrap();
+        let b: Cron = "0 9 * * 7".parse().unwrap();
+        let now = local(2026, 10, 16, 0, 0);
+        assert_eq!(a.next_after(now), b.next_after(now));
+        assert_eq!(a.next_after(now), Some(local(2026, 10, 18, 9, 0)));
+    }
+
+    #[test]
+    fn shorthands_and_month_names() {
+        let now = local(2026, 10, 16, 12, 0);
+        assert_eq!(next("@daily", now), local(2026, 10, 17, 0, 0));
+        assert_eq!(next("0 0 1 jan *", now), local(2027, 1, 1, 0, 0));
+        assert!(next("@hourly", now) == local(2026, 10, 16, 13, 0));
+    }
+
+    #[test]
+    fn leap_days_across_a_century() {
+        assert_eq!(
+            next("0 0 29 2 *", local(2097, 1, 1, 0, 0)),
+            local(2104, 2, 29, 0, 0)
+        );
+    }
+
+    #[test]
+    fn impossible_dates_give_up() {
+        let cron: Cron = "0 0 31 2 *".parse().unwrap();
+        assert_eq!(cron.next_after(local(2026, 1, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for expr in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "* * * foo *",
+        ] {
+            let err = expr.parse::<Cron>().unwrap_err();
+            assert!(matches!(err, CiderError::Config(_)), "{expr}: {err}");
+        }
+    }
+
+    #[test]
+    fn matches_minute() {
+        let cron: Cron = "30 7 * * *".parse().unwrap();
+        assert!(cron.matches(local(2026, 10, 16, 7, 30)));
+        assert!(!cron.matches(local(2026, 10, 16, 7, 31)));
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let cron: Cron = serde_json::from_str(r#""0 22 * * sat""#).unwrap();
+        assert_eq!(serde_json::to_string(&cron).unwrap(), r#""0 22 * * sat""#);
+        assert!(serde_json::from_str::<Cron>(r#""nope""#).is_err());
+    }
+}
@@ -0,0 +1,572 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Error types returned by [`CiderClient`](crate::CiderClient).
+
+use std::fmt;
+
+use reqwest::StatusCode;
+use thiserror::Error;
+
+use crate::types::ErrorResponse;
+
+/// Identifies the API call an error originated from.
+///
+/// Displays as `"GET /api/v1/playback/now-playing"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Endpoint {
+    /// HTTP method.
+    pub method: reqwest::Method,
+
+    /// Request path including the API prefix (e.g. `"/api/v1/playback/queue"`).
+    pub path: String,
+}
+
+impl Endpoint {
+    /// An endpoint under `/api/v1/playback`.
+    pub(crate) fn playback(method: reqwest::Method, path: &str) -> Self {
+        Self {
+            method,
+            path: format!("/api/v1/playback{path}"),
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.path)
+    }
+}
+
+/// Errors returned by [`CiderClient`](crate::CiderClient) methods.
+///
+/// Every variant produced by a request carries the [`Endpoint`] it came
+/// from. Use the classification helpers ([`is_retryable`](Self::is_retryable),
+/// [`is_auth`](Self::is_auth), [`is_not_running`](Self::is_not_running)) to
+/// branch on error classes without matching every variant.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// use cider_api::{CiderClient, CiderError};
+///
+/// let client = CiderClient::new();
+/// match client.is_active().await {
+///     Ok(()) => println!("Cider is running"),
+///     Err(e) if e.is_not_running() => println!("Cider not running"),
+///     Err(e) if e.is_auth() => println!("Bad API token"),
+///     Err(CiderError::Api { status, body, .. }) => {
+///         let message = body.as_ref().and_then(|b| b.description());
+///         println!("Cider returned HTTP {status}: {message:?}");
+///     }
+///     Err(e) => println!("Error: {e}"),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum CiderError {
+    /// Cider is not running or the host/port is unreachable (connection
+    /// refused, DNS failure, connect timeout).
+    #[error("Cider is not running or not reachable ({endpoint}): {source}")]
+    NotReachable {
+        /// The API call that failed.
+        endpoint: Endpoint,
+
+        /// The underlying connection error.
+        #[source]
+        source: reqwest::Error,
+    },
+
+    /// The connection was established but Cider did not answer in time.
+    #[error("{endpoint} timed out")]
+    Timeout {
+        /// The API call that timed out.
+        endpoint: Endpoint,
+
+        /// The underlying timeout error.
+        #[source]
+        source: reqwest::Error,
+    },
+
+    /// Any other transport-level failure (connection reset, body read
+    /// error, TLS error, ...).
+    #[error("{endpoint} failed: {source}")]
+    Http {
+        /// The API call that failed.
+        endpoint: Endpoint,
+
+        /// The underlying HTTP error.
+        #[source]
+        source: reqwest::Error,
+    },
+
+    /// The API token was rejected (HTTP 401/403).
+    #[error("{endpoint}: API token rejected (HTTP {status})")]
+    Unauthorized {
+        /// The API call that was rejected.
+        endpoint: Endpoint,
+
+        /// `401` or `403`.
+        status: StatusCode,
+
+        /// Error body returned by Cider, if any.
+        body: Option<ErrorResponse>,
+    },
+
+    /// The command needs a loaded track, but nothing is playing.
+    #[error("{endpoint}: no track currently playing")]
+    NothingPlaying {
+        /// The API call that needed a track.
+        endpoint: Endpoint,
+    },
+
+    /// Cider answered with an error status, or with a
+    /// `{"status": "error", ...}` body.
+    #[error("{endpoint} returned HTTP {status}{}", describe(body.as_ref()))]
+    Api {
+        /// The API call that failed.
+        endpoint: Endpoint,
+
+        /// HTTP status of the response.
+        status: StatusCode,
+
+        /// Error body returned by Cider, if it could be parsed.
+        body: Option<ErrorResponse>,
+    },
+
+    /// A response body did not match the expected schema.
+    ///
+    /// Usually means Cider changed the shape of an endpoint's response.
+    #[error("Failed to decode {endpoint} response{} at `{path}`: {source}", http_status(*status))]
+    Decode {
+        /// The API call whose response failed to decode.
+        endpoint: Endpoint,
+
+        /// HTTP status of the response; `None` for event stream messages.
+        status: Option<StatusCode>,
+
+        /// Path to the offending field (e.g. `"info.durationInMillis"`), or
+        /// `"."` for the document root.
+        path: String,
+
+        /// Raw response body, truncated to 512 bytes.
+        body: String,
+
+        /// The underlying JSON error.
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// A command was accepted, but the player did not end up in the
+    /// requested state (e.g. [`set_repeat_mode`](crate::CiderClient::set_repeat_mode)
+    /// read back a different mode after toggling).
+    #[error("{endpoint}: expected {expected}, Cider reports {actual}")]
+    NotApplied {
+        /// The API call used to verify the state.
+        endpoint: Endpoint,
+
+        /// The requested state.
+        expected: String,
+
+        /// The state Cider reported instead.
+        actual: String,
+    },
+
+    /// The real-time event connection could not be established or was lost.
+    #[error("{endpoint}: event stream failed: {message}")]
+    EventStream {
+        /// The Socket.IO endpoint.
+        endpoint: Endpoint,
+
+        /// What went wrong.
+        message: String,
+
+        /// The underlying WebSocket or I/O error, if any.
+        #[source]
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    },
+
+    /// An Apple Music item kind or ID is malformed, e.g. a library ID
+    /// (`i.…`) passed with a catalog kind. See [`ItemRef`](crate::ItemRef).
+    #[error("Invalid Apple Music item: {0}")]
+    InvalidItem(String),
+
+    /// The client configuration is invalid or unsafe (e.g. an API token
+    /// would be sent over plain HTTP to a remote host).
+    #[error("Invalid client configuration: {0}")]
+    Config(String),
+
+    /// The MPRIS bridge could not use D-Bus (no session bus, bus name
+    /// taken, connection lost).
+    #[error("D-Bus: {message}")]
+    DBus {
+        /// What went wrong.
+        message: String,
+
+        /// The underlying D-Bus error, if any.
+        #[source]
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    },
+
+    /// The MQTT bridge could not reach the broker, or the broker refused
+    /// the connection.
+    #[error("MQTT: {message}")]
+    Mqtt {
+        /// What went wrong.
+        message: String,
+
+        /// The underlying MQTT error, if any.
+        #[source]
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    },
+
+    /// A scrobbling service (ListenBrainz, Last.fm) could not be reached or
+    /// rejected a request.
+    #[error("{service} {endpoint} failed: {message}")]
+    Service {
+        /// The service, e.g. `"ListenBrainz"`.
+        service: &'static str,
+
+        /// The service API call that failed.
+        endpoint: Endpoint,
+
+        /// HTTP status of the response, if one was received.
+        status: Option<StatusCode>,
+
+        /// What went wrong, as reported by the service when possible.
+        message: String,
+
+        /// Whether the service asked to try again later (rate limits,
+        /// outages, dropped connections).
+        retryable: bool,
+
+        /// The underlying HTTP error, if any.
+        #[source]
+        source: Option<reqwest::Error>,
+    },
+
+    /// Reading or writing a local file failed (e.g. a persisted scrobble
+    /// queue).
+    #[error("{context}: {source}")]
+    Io {
+        /// What was being done, including the path.
+        context: String,
+
+        /// The underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// Format the optional Cider error message as `": message"`.
+/// ` (HTTP 200)` for a status, nothing without one.
+fn http_status(status: Option<StatusCode>) -> String {
+    status
+        .map(|status| format!(" (HTTP {status})"))
+        .unwrap_or_default()
+}
+
+fn describe(body: Option<&ErrorResponse>) -> String {
+    body.and_then(ErrorResponse::description)
+        .map(|message| format!(": {message}"))
+        .unwrap_or_default()
+}
+
+impl CiderError {
+    /// Classify a transport error from [`reqwest`].
+    pub(crate) fn from_transport(endpoint: Endpoint, source: reqwest::Error) -> Self {
+        if source.is_connect() {
+            Self::NotReachable { endpoint, source }
+        } else if source.is_timeout() {
+            Self::Timeout { endpoint, source }
+        } else {
+            Self::Http { endpoint, source }
+        }
+    }
+
+    /// Classify a non-success HTTP status.
+    pub(crate) fn from_status(
+        endpoint: Endpoint,
+        status: StatusCode,
+        body: Option<ErrorResponse>,
+    ) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Self::Unauthorized {
+                endpoint,
+                status,
+                body,
+            },
+            _ => Self::Api {
+                endpoint,
+                status,
+                body,
+            },
+        }
+    }
+
+    /// The API call this error originated from, if it came from a request.
+    #[must_use]
+    pub fn endpoint(&self) -> Option<&Endpoint> {
+        match self {
+            Self::NotReachable { endpoint, .. }
+            | Self::Timeout { endpoint, .. }
+            | Self::Http { endpoint, .. }
+            | Self::Unauthorized { endpoint, .. }
+            | Self::NothingPlaying { endpoint }
+            | Self::Api { endpoint, .. }
+            | Self::Decode { endpoint, .. }
+            | Self::NotApplied { endpoint, .. }
+            | Self::EventStream { endpoint, .. }
+            | Self::Service { endpoint, .. } => Some(endpoint),
+            Self::InvalidItem(_)
+            | Self::Config(_)
+            | Self::DBus { .. }
+            | Self::Mqtt { .. }
+            | Self::Io { .. } => None,
+        }
+    }
+
+    /// The HTTP status Cider answered with, if a response was received.
+    #[must_use]
+    pub fn status_code(&self) -> Option<StatusCode> {
+        match self {
+            Self::Unauthorized { status, .. } | Self::Api { status, .. } => Some(*status),
+            Self::Http { source, .. } => source.status(),
+            Self::Decode { status, .. } | Self::Service { status, .. } => *status,
+            _ => None,
+        }
+    }
+
+    /// The error body Cider returned, if any.
+    #[must_use]
+    pub fn body(&self) -> Option<&ErrorResponse> {
+        match self {
+            Self::Unauthorized { body, .. } | Self::Api { body, .. } => body.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if retrying the same request may succeed.
+    ///
+    /// Covers unreachable servers, timeouts, dropped connections (including
+    /// the event stream), `408`, `429` and `5xx` statuses, and scrobbling
+    /// service errors the service marks as temporary. Auth, decode and
+    /// configuration errors are never retryable.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::NotReachable { .. } | Self::Timeout { .. } | Self::EventStream { .. } => true,
+            Self::Service { retryable, .. } => *retryable,
+            Self::Http { source, .. } => source.is_request() || source.is_body(),
+            Self::Api { status, .. } => {
+                status.is_server_error()
+                    || *status == StatusCode::REQUEST_TIMEOUT
+                    || *status == StatusCode::TOO_MANY_REQUESTS
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the API token (or a scrobbling service's
+    /// credentials) is missing or was rejected.
+    #[must_use]
+    pub fn is_auth(&self) -> bool {
+        match self {
+            Self::Unauthorized { .. } => true,
+            Self::Service { status, .. } => {
+                matches!(*status, Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN))
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if Cider does not appear to be running at the
+    /// configured address.
+    #[must_use]
+    pub fn is_not_running(&self) -> bool {
+        match self {
+            Self::NotReachable { .. } => true,
+            Self::EventStream {
+                source: Some(source),
+                ..
+            } => source
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|e| e.kind() == std::io::ErrorKind::ConnectionRefused),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the command failed because no track is loaded.
+    #[must_use]
+    pub fn is_nothing_playing(&self) -> bool {
+        matches!(self, Self::NothingPlaying { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint() -> Endpoint {
+        Endpoint::playback(reqwest::Method::POST, "/play")
+    }
+
+    fn decode_error() -> CiderError {
+        CiderError::Decode {
+            endpoint: endpoint(),
+            status: Some(StatusCode::OK),
+            path: ".".into(),
+            body: "{}".into(),
+            source: serde_json::from_str::<u8>("x").unwrap_err(),
+        }
+    }
+
+    #[test]
+    fn endpoint_display() {
+        let endpoint = Endpoint::playback(reqwest::Method::GET, "/now-playing");
+        assert_eq!(endpoint.to_string(), "GET /api/v1/playback/now-playing");
+    }
+
+    #[test]
+    fn status_401_and_403_are_unauthorized() {
+        for code in [401, 403] {
+            let status = StatusCode::from_u16(code).unwrap();
+            let err = CiderError::from_status(endpoint(), status, None);
+            assert!(err.is_auth());
+            assert!(!err.is_retryable());
+        }
+    }
+
+    #[test]
+    fn status_5xx_is_retryable_api_error() {
+        let err = CiderError::from_status(endpoint(), StatusCode::SERVICE_UNAVAILABLE, None);
+        assert!(matches!(err, CiderError::Api { .. }));
+        assert!(err.is_retryable());
+        assert_eq!(err.status_code(), Some(StatusCode::SERVICE_UNAVAILABLE));
+    }
+
+    #[test]
+    fn status_429_is_retryable() {
+        let err = CiderError::from_status(endpoint(), StatusCode::TOO_MANY_REQUESTS, None);
+        assert!(err.is_retryable());
+    }
+
+    #[test]
+    fn status_4xx_is_not_retryable() {
+        let err = CiderError::from_status(endpoint(), StatusCode::BAD_REQUEST, None);
+        assert!(!err.is_retryable());
+        assert!(!err.is_auth());
+    }
+
+    #[test]
+    fn api_error_display_includes_message() {
+        let body = ErrorResponse {
+            status: "error".into(),
+            message: Some("Invalid item type".into()),
+            error: None,
+        };
+        let err = CiderError::from_status(endpoint(), StatusCode::BAD_REQUEST, Some(body));
+        assert_eq!(
+            err.to_string(),
+            "POST /api/v1/playback/play returned HTTP 400 Bad Request: Invalid item type"
+        );
+        assert_eq!(err.body().unwrap().description(), Some("Invalid item type"));
+    }
+
+    #[test]
+    fn api_error_display_without_body() {
+        let err = CiderError::from_status(endpoint(), StatusCode::INTERNAL_SERVER_ERROR, None);
+        assert_eq!(
+            err.to_string(),
+            "POST /api/v1/playback/play returned HTTP 500 Internal Server Error"
+        );
+    }
+
+    #[test]
+    fn service_error_classification() {
+        let service = |status, retryable| CiderError::Service {
+            service: "ListenBrainz",
+            endpoint: endpoint(),
+            status,
+            message: "nope".into(),
+            retryable,
+            source: None,
+        };
+        let err = service(Some(StatusCode::FORBIDDEN), false);
+        assert!(err.is_auth());
+        assert!(!err.is_retryable());
+        assert_eq!(err.status_code(), Some(StatusCode::FORBIDDEN));
+        assert_eq!(
+            err.to_string(),
+            "ListenBrainz POST /api/v1/playback/play failed: nope"
+        );
+        let err = service(None, true);
+        assert!(err.is_retryable());
+        assert!(!err.is_auth());
+        assert!(!err.is_not_running());
+    }
+
+    #[test]
+    fn decode_error_is_not_retryable() {
+        let err = decode_error();
+        assert!(!err.is_retryable());
+        assert_eq!(err.endpoint(), Some(&endpoint()));
+        assert_eq!(err.status_code(), Some(StatusCode::OK));
+    }
+
+    #[test]
+    fn nothing_playing_classification() {
+        let err = CiderError::NothingPlaying {
+            endpoint: endpoint(),
+        };
+        assert!(err.is_nothing_playing());
+        assert!(!err.is_retryable());
+        assert!(err.status_code().is_none());
+    }
+
+    #[test]
+    fn event_stream_connection_refused_is_not_running() {
+        let err = CiderError::EventStream {
+            endpoint: endpoint(),
+            message: "connection failed".into(),
+            source: Some(Box::new(std::io::Error::from(
+                std::io::ErrorKind::ConnectionRefused,
+            ))),
+        };
+        assert!(err.is_not_running());
+        assert!(err.is_retryable());
+
+        let err = CiderError::EventStream {
+            endpoint: endpoint(),
+            message: "closed by Cider".into(),
+            source: None,
+        };
+        assert!(!err.is_not_running());
+    }
+
+    #[test]
+    fn not_applied_display() {
+        let err = CiderError::NotApplied {
+            endpoint: Endpoint::playback(reqwest::Method::GET, "/repeat-mode"),
+            expected: "all".into(),
+            actual: "off".into(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "GET /api/v1/playback/repeat-mode: expected all, Cider reports off"
+        );
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn config_error_has_no_endpoint() {
+        let err = CiderError::Config("bad".into());
+        assert!(err.endpoint().is_none());
+        assert!(!err.is_retryable());
+        assert!(!err.is_not_running());
+    }
+}
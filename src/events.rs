@@ -0,0 +1,698 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Real-time playback events pushed over Cider's Socket.IO channel.
+//!
+//! Cider runs a Socket.IO server on the same port as the REST API and emits
+//! `API:Playback` events whenever the track, play state, position, volume or
+//! player modes change. This module speaks the small subset of Engine.IO v4
+//! and Socket.IO v5 needed to receive them over a WebSocket.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_core::Stream;
+use futures_util::{SinkExt, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_tungstenite::{Connector, MaybeTlsStream, WebSocketStream};
+use tracing::{debug, warn};
+
+use crate::builder::is_pem;
+use crate::client::{decode_json, CiderClient, DecodeMode};
+use crate::error::{CiderError, Endpoint};
+use crate::retry::RetryPolicy;
+use crate::types::{ErrorResponse, NowPlaying, RepeatMode, ShuffleMode};
+
+/// Path of Cider's Socket.IO server.
+const SOCKET_PATH: &str = "/socket.io/";
+
+/// Socket.IO event name carrying playback updates.
+const PLAYBACK_EVENT: &str = "API:Playback";
+
+/// Time allowed for the WebSocket and Socket.IO handshakes.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Events buffered before the connection task waits for the consumer.
+const CHANNEL_CAPACITY: usize = 64;
+
+/// Default delay before the first reconnection attempt.
+const RECONNECT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Default upper bound between reconnection attempts.
+const RECONNECT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// A playback event pushed by Cider.
+///
+/// Cider sends several event types this crate does not model yet; they are
+/// reported as [`Other`](Self::Other) with their raw payload.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum PlaybackEvent {
+    /// The connection to Cider was (re-)established.
+    Connected,
+
+    /// The connection to Cider was lost. The stream reconnects on its own;
+    /// a [`Connected`](Self::Connected) event follows once it succeeds.
+    Disconnected,
+
+    /// A different track started (`playbackStatus.nowPlayingItemDidChange`).
+    NowPlayingChanged(Box<NowPlaying>),
+
+    /// Playback started, paused or stopped
+    /// (`playbackStatus.playbackStateDidChange`).
+    StateChanged {
+        /// The new playback state.
+        state: PlaybackState,
+
+        /// The track the state applies to, if Cider sent it.
+        track: Option<Box<NowPlaying>>,
+    },
+
+    /// Periodic position update (`playbackStatus.playbackTimeDidChange`).
+    TimeChanged(PlaybackTime),
+
+    /// The volume changed (`playerStatus.volumeDidChange`), `0.0`–`1.0`.
+    VolumeChanged(f32),
+
+    /// The repeat mode changed (`playerStatus.repeatModeDidChange`).
+    RepeatModeChanged(RepeatMode),
+
+    /// The shuffle mode changed (`playerStatus.shuffleModeDidChange`).
+    ShuffleModeChanged(ShuffleMode),
+
+    /// An event this crate does not model, or (in
+    /// [`DecodeMode::Lenient`]) one whose payload could not be decoded.
+    Other {
+        /// Cider's event type, e.g. `"playerStatus.autoplayDidChange"`.
+        kind: String,
+
+        /// The raw event payload.
+        data: Value,
+    },
+}
+
+/// Playback state reported by [`PlaybackEvent::StateChanged`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum PlaybackState {
+    /// A track is playing.
+    Playing,
+    /// Playback is paused.
+    Paused,
+    /// Playback is stopped.
+    Stopped,
+    /// The track is loading.
+    Loading,
+    /// The player is seeking.
+    Seeking,
+    /// The player is waiting for data.
+    Waiting,
+    /// The track ended.
+    Ended,
+    /// A state this crate does not know about.
+    #[serde(other)]
+    Unknown,
+}
+
+/// Position update reported by [`PlaybackEvent::TimeChanged`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackTime {
+    /// Track duration in seconds.
+    #[serde(default)]
+    pub current_playback_duration: f64,
+
+    /// Playback position in seconds.
+    #[serde(default)]
+    pub current_playback_time: f64,
+
+    /// Remaining time in seconds.
+    #[serde(default)]
+    pub current_playback_time_remaining: f64,
+
+    /// Whether a track is playing.
+    #[serde(default)]
+    pub is_playing: bool,
+}
+
+/// Payload of `playbackStatus.playbackStateDidChange`.
+#[derive(Deserialize)]
+struct StateChange {
+    state: PlaybackState,
+    #[serde(default)]
+    attributes: Option<NowPlaying>,
+}
+
+/// The `{ "type": ..., "data": ... }` argument of an `API:Playback` event.
+#[derive(Deserialize)]
+struct RawEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    data: Value,
+}
+
+/// The Engine.IO `open` packet.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenPacket {
+    ping_interval: u64,
+    ping_timeout: u64,
+}
+
+impl OpenPacket {
+    /// How long the connection may stay silent before it counts as lost.
+    fn liveness(&self) -> Duration {
+        Duration::from_millis(self.ping_interval.saturating_add(self.ping_timeout))
+    }
+}
+
+/// A [`Stream`] of [`PlaybackEvent`]s, returned by [`CiderClient::events`].
+///
+/// The connection runs on a background task that reconnects after failures.
+/// `Err` items report connection attempts that failed; they are not
+/// terminal. The stream ends once the reconnection policy gives up.
+/// Dropping the stream closes the connection.
+#[derive(Debug)]
+pub struct EventStream {
+    rx: mpsc::Receiver<Result<PlaybackEvent, CiderError>>,
+    task: JoinHandle<()>,
+}
+
+impl Stream for EventStream {
+    type Item = Result<PlaybackEvent, CiderError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl CiderClient {
+    /// Subscribe to playback events pushed by Cider.
+    ///
+    /// Connects to Cider's Socket.IO server and keeps reconnecting with
+    /// exponential backoff (0.5s up to 30s) when the connection drops or
+    /// cannot be made. Errors that are not
+    /// [retryable](CiderError::is_retryable), such as a rejected token, are
+    /// yielded and end the stream. Use [`events_with`](Self::events_with) to
+    /// limit reconnection attempts.
+    ///
+    /// HTTPS base URLs connect over `wss://`, trusting the system's root
+    /// certificates and those added with
+    /// [`CiderClientBuilder::add_root_certificate`](crate::CiderClientBuilder::add_root_certificate).
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn example() {
+    /// use futures_util::StreamExt;
+    /// use cider_api::{CiderClient, PlaybackEvent};
+    ///
+    /// let client = CiderClient::new();
+    /// let mut events = client.events();
+    /// while let Some(event) = events.next().await {
+    ///     match event {
+    ///         Ok(PlaybackEvent::NowPlayingChanged(track)) => {
+    ///             println!("{} — {}", track.name, track.artist_name);
+    ///         }
+    ///         Ok(PlaybackEvent::VolumeChanged(volume)) => println!("Volume: {volume}"),
+    ///         Ok(_) => {}
+    ///         Err(e) => eprintln!("Event stream: {e}"),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn events(&self) -> EventStream {
+        self.events_with(
+            RetryPolicy::new()
+                .max_attempts(u32::MAX)
+                .initial_backoff(RECONNECT_INITIAL_BACKOFF)
+                .max_backoff(RECONNECT_MAX_BACKOFF),
+        )
+    }
+
+    /// Subscribe to playback events, reconnecting under `reconnect`.
+    ///
+    /// `reconnect` decides how often and how fast the stream reconnects
+    /// after consecutive failures; once it gives up, the last error is
+    /// yielded and the stream ends. A successful connection resets the
+    /// attempt count.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    #[must_use]
+    pub fn events_with(&self, reconnect: RetryPolicy) -> EventStream {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let task = tokio::spawn(run(self.clone(), reconnect, tx));
+        EventStream { rx, task }
+    }
+}
+
+/// Keep a connection open, forwarding events until the consumer goes away
+/// or `reconnect` gives up.
+async fn run(
+    client: CiderClient,
+    reconnect: RetryPolicy,
+    tx: mpsc::Sender<Result<PlaybackEvent, CiderError>>,
+) {
+    let mut attempt = 1;
+    loop {
+        let err = match connect(&client).await {
+            Ok(socket) => {
+                attempt = 1;
+                if tx.send(Ok(PlaybackEvent::Connected)).await.is_err() {
+                    return;
+                }
+                let Some(err) = pump(&client, socket, &tx).await else {
+                    return;
+                };
+                warn!("Cider event stream lost: {err}");
+                if tx.send(Ok(PlaybackEvent::Disconnected)).await.is_err() {
+                    return;
+                }
+                if !reconnect.should_retry(&err, attempt) {
+                    let _ = tx.send(Err(err)).await;
+                    return;
+                }
+                tokio::time::sleep(reconnect.delay(attempt)).await;
+                attempt += 1;
+                continue;
+            }
+            Err(err) => err,
+        };
+
+        let retry = reconnect.should_retry(&err, attempt);
+        let delay = reconnect.delay(attempt);
+        debug!("Cider event stream attempt {attempt} failed: {err}");
+        if tx.send(Err(err)).await.is_err() || !retry {
+            return;
+        }
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// The endpoint event stream errors are reported against.
+fn endpoint() -> Endpoint {
+    Endpoint {
+        method: reqwest::Method::GET,
+        path: SOCKET_PATH.to_string(),
+    }
+}
+
+/// Build an [`CiderError::EventStream`].
+fn stream_error(
+    message: impl Into<String>,
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
+) -> CiderError {
+    CiderError::EventStream {
+        endpoint: endpoint(),
+        message: message.into(),
+        source,
+    }
+}
+
+/// Map a WebSocket error, keeping I/O errors inspectable for
+/// [`CiderError::is_not_running`].
+fn from_ws(err: tungstenite::Error) -> CiderError {
+    match err {
+        tungstenite::Error::Http(resp) => {
+            let body = resp
+                .body()
+                .as_deref()
+                .and_then(|b| ErrorResponse::parse(&String::from_utf8_lossy(b)));
+            CiderError::from_status(endpoint(), resp.status(), body)
+        }
+        tungstenite::Error::Io(e) => stream_error("connection failed", Some(Box::new(e))),
+        e => stream_error("WebSocket error", Some(Box::new(e))),
+    }
+}
+
+/// The Socket.IO WebSocket URL for `base_url`.
+fn socket_url(base_url: &str) -> String {
+    let ws = match base_url.strip_prefix("https://") {
+        Some(rest) => format!("wss://{rest}"),
+        None => format!("ws://{}", base_url.trim_start_matches("http://")),
+    };
+    format!("{ws}{SOCKET_PATH}?EIO=4&transport=websocket")
+}
+
+/// The `wss://` connector for a client built with extra root certificates
+/// or without certificate checks, or `None` for the system defaults.
+pub(crate) fn tls_connector(
+    root_certificates: &[Vec<u8>],
+    accept_invalid_certs: bool,
+) -> Result<Option<native_tls::TlsConnector>, CiderError> {
+    if root_certificates.is_empty() && !accept_invalid_certs {
+        return Ok(None);
+    }
+    let mut builder = native_tls::TlsConnector::builder();
+    for certificate in root_certificates {
+        let parsed = if is_pem(certificate) {
+            native_tls::Certificate::from_pem(certificate)
+        } else {
+            native_tls::Certificate::from_der(certificate)
+        };
+        let certificate =
+            parsed.map_err(|e| CiderError::Config(format!("invalid root certificate ({e})")))?;
+        builder.add_root_certificate(certificate);
+    }
+    builder
+        .danger_accept_invalid_certs(accept_invalid_certs)
+        .build()
+        .map(Some)
+        .map_err(|e| CiderError::Config(format!("failed to build TLS connector ({e})")))
+}
+
+/// Open the WebSocket and complete the Engine.IO / Socket.IO handshakes.
+///
+/// Returns the socket and how long it may stay silent before the connection
+/// is considered dead.
+async fn connect(client: &CiderClient) -> Result<(Socket, Duration), CiderError> {
+    let mut request = socket_url(&client.base_url)
+        .into_client_request()
+        .map_err(|e| CiderError::Config(format!("invalid event stream URL ({e})")))?;
+    if let Some(token) = client.token()? {
+        let value = token
+            .parse()
+            .map_err(|_| CiderError::Config("API token is not a valid header value".into()))?;
+        request.headers_mut().insert("apptoken", value);
+    }
+
+    tokio::time::timeout(HANDSHAKE_TIMEOUT, async {
+        let connector = client.tls.clone().map(Connector::NativeTls);
+        let (mut socket, _) =
+            tokio_tungstenite::connect_async_tls_with_config(request, None, false, connector)
+                .await
+                .map_err(from_ws)?;
+        let liveness = handshake(&mut socket).await?;
+        Ok((socket, liveness))
+    })
+    .await
+    .map_err(|_| stream_error("handshake timed out", None))?
+}
+
+/// Wait for the Engine.IO `open` packet, then join the default namespace.
+async fn handshake(socket: &mut Socket) -> Result<Duration, CiderError> {
+    let mut liveness = None;
+    loop {
+        let text = match socket.next().await {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(from_ws(e)),
+            None => return Err(stream_error("closed during handshake", None)),
+        };
+        match text.as_bytes().first() {
+            Some(b'0') => {
+                let open: OpenPacket = serde_json::from_str(&text[1..])
+                    .map_err(|e| stream_error("invalid open packet", Some(Box::new(e))))?;
+                liveness = Some(open.liveness());
+                send(socket, "40").await?;
+            }
+            Some(b'2') => send(socket, "3").await?,
+            Some(b'4') if text.starts_with("40") => {
+                return liveness.ok_or_else(|| stream_error("connected before open", None));
+            }
+            Some(b'4') if text.starts_with("44") => {
+                return Err(stream_error(
+                    format!("connection refused by Cider: {}", &text[2..]),
+                    None,
+                ));
+            }
+            _ => debug!("ignoring handshake packet {text:?}"),
+        }
+    }
+}
+
+/// Forward events until the connection drops (returning the reason) or the
+/// consumer goes away (returning `None`).
+async fn pump(
+    client: &CiderClient,
+    (mut socket, liveness): (Socket, Duration),
+    tx: &mpsc::Sender<Result<PlaybackEvent, CiderError>>,
+) -> Option<CiderError> {
+    loop {
+        let text = match tokio::time::timeout(liveness, socket.next()).await {
+            Err(_) => {
+                let message = format!("no ping from Cider in {liveness:?}");
+                return Some(stream_error(message, None));
+            }
+            Ok(None | Some(Ok(Message::Close(_)))) => {
+                return Some(stream_error("closed by Cider", None));
+            }
+            Ok(Some(Err(e))) => return Some(from_ws(e)),
+            Ok(Some(Ok(Message::Text(text)))) => text,
+            Ok(Some(Ok(_))) => continue,
+        };
+
+        let event = match text.as_bytes() {
+            [b'2', ..] => {
+                if let Err(e) = send(&mut socket, "3").await {
+                    return Some(e);
+                }
+                continue;
+            }
+            [b'1', ..] | [b'4', b'1', ..] => return Some(stream_error("closed by Cider", None)),
+            [b'4', b'2', ..] => match decode_event(&text[2..], client.decode_mode) {
+                Some(event) => event,
+                None => continue,
+            },
+            _ => continue,
+        };
+        if tx.send(event).await.is_err() {
+            return None;
+        }
+    }
+}
+
+/// Send a text packet.
+async fn send(socket: &mut Socket, packet: &str) -> Result<(), CiderError> {
+    socket
+        .send(Message::Text(packet.to_string()))
+        .await
+        .map_err(from_ws)
+}
+
+/// Decode a Socket.IO `EVENT` payload (`42` prefix stripped).
+///
+/// Returns `None` for events other than `API:Playback`.
+fn decode_event(payload: &str, mode: DecodeMode) -> Option<Result<PlaybackEvent, CiderError>> {
+    // Skip an optional ack id before the JSON array; Cider only uses the
+    // default namespace.
+    let json = payload.trim_start_matches(|c: char| c.is_ascii_digit());
+    let (name, raw) = match serde_json::from_str::<(String, RawEvent)>(json) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            debug!("ignoring undecodable Socket.IO event: {e}");
+            return None;
+        }
+    };
+    if name != PLAYBACK_EVENT {
+        debug!("ignoring Socket.IO event {name:?}");
+        return None;
+    }
+
+    let result = match raw.kind.as_str() {
+        "playbackStatus.nowPlayingItemDidChange" => {
+            decode::<NowPlaying>(&raw.data).map(|t| PlaybackEvent::NowPlayingChanged(Box::new(t)))
+        }
+        "playbackStatus.playbackStateDidChange" => {
+            decode::<StateChange>(&raw.data).map(|s| PlaybackEvent::StateChanged {
+                state: s.state,
+                track: s.attributes.map(Box::new),
+            })
+        }
+        "playbackStatus.playbackTimeDidChange" => {
+            decode::<PlaybackTime>(&raw.data).map(PlaybackEvent::TimeChanged)
+        }
+        "playerStatus.volumeDidChange" => decode(&raw.data).map(PlaybackEvent::VolumeChanged),
+        "playerStatus.repeatModeDidChange" => {
+            decode(&raw.data).map(PlaybackEvent::RepeatModeChanged)
+        }
+        "playerStatus.shuffleModeDidChange" => {
+            decode(&raw.data).map(PlaybackEvent::ShuffleModeChanged)
+        }
+        _ => return Some(Ok(other(raw))),
+    };
+
+    Some(match (result, mode) {
+        (Ok(event), _) => Ok(event),
+        (Err(e), DecodeMode::Strict) => Err(e),
+        (Err(e), DecodeMode::Lenient) => {
+            warn!("{e}");
+            Ok(other(raw))
+        }
+    })
+}
+
+/// Decode an event payload, reporting the failing field path.
+///
+/// Socket frames have no HTTP status, so the error carries none.
+fn decode<T: DeserializeOwned>(data: &Value) -> Result<T, CiderError> {
+    decode_json(endpoint(), None, &data.to_string())
+}
+
+/// Wrap an unmodelled event.
+fn other(raw: RawEvent) -> PlaybackEvent {
+    PlaybackEvent::Other {
+        kind: raw.kind,
+        data: raw.data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: &str, data: &Value) -> String {
+        serde_json::json!([PLAYBACK_EVENT, { "type": kind, "data": data }]).to_string()
+    }
+
+    #[test]
+    fn socket_url_from_base_url() {
+        assert_eq!(
+            socket_url("http://127.0.0.1:10767"),
+            "ws://127.0.0.1:10767/socket.io/?EIO=4&transport=websocket"
+        );
+        assert_eq!(
+            socket_url("https://music.home.example"),
+            "wss://music.home.example/socket.io/?EIO=4&transport=websocket"
+        );
+    }
+
+    #[test]
+    fn open_packet_liveness_saturates() {
+        let open: OpenPacket = serde_json::from_str(&format!(
+            r#"{{"sid":"abc","pingInterval":{max},"pingTimeout":{max}}}"#,
+            max = u64::MAX
+        ))
+        .unwrap();
+        assert_eq!(open.liveness(), Duration::from_millis(u64::MAX));
+    }
+
+    #[test]
+    fn decodes_volume_change() {
+        let payload = event("playerStatus.volumeDidChange", &serde_json::json!(0.25));
+        let decoded = decode_event(&payload, DecodeMode::Strict).unwrap().unwrap();
+        assert!(matches!(decoded, PlaybackEvent::VolumeChanged(v) if (v - 0.25).abs() < 1e-6));
+    }
+
+    #[test]
+    fn decodes_state_change() {
+        let data = serde_json::json!({ "state": "paused", "attributes": { "name": "Skin" } });
+        let payload = event("playbackStatus.playbackStateDidChange", &data);
+        let decoded = decode_event(&payload, DecodeMode::Strict).unwrap().unwrap();
+        let PlaybackEvent::StateChanged { state, track } = decoded else {
+            panic!("unexpected event {decoded:?}");
+        };
+        assert_eq!(state, PlaybackState::Paused);
+        assert_eq!(track.unwrap().name, "Skin");
+    }
+
+    #[test]
+    fn unknown_state_is_tolerated() {
+        let data = serde_json::json!({ "state": "buffering" });
+        let payload = event("playbackStatus.playbackStateDidChange", &data);
+        let decoded = decode_event(&payload, DecodeMode::Strict).unwrap().unwrap();
+        assert!(matches!(
+            decoded,
+            PlaybackEvent::StateChanged {
+                state: PlaybackState::Unknown,
+                track: None
+            }
+        ));
+    }
+
+    #[test]
+    fn decodes_time_change() {
+        let data = serde_json::json!({
+            "currentPlaybackDuration": 234.0,
+            "currentPlaybackTime": 42.5,
+            "currentPlaybackTimeRemaining": 191.5,
+            "isPlaying": true
+        });
+        let payload = event("playbackStatus.playbackTimeDidChange", &data);
+        let decoded = decode_event(&payload, DecodeMode::Strict).unwrap().unwrap();
+        let PlaybackEvent::TimeChanged(time) = decoded else {
+            panic!("unexpected event {decoded:?}");
+        };
+        assert!((time.current_playback_time - 42.5).abs() < f64::EPSILON);
+        assert!(time.is_playing);
+    }
+
+    #[test]
+    fn unknown_kind_is_other() {
+        let payload = event("playerStatus.autoplayDidChange", &serde_json::json!(true));
+        let decoded = decode_event(&payload, DecodeMode::Strict).unwrap().unwrap();
+        assert!(matches!(
+            decoded,
+            PlaybackEvent::Other { kind, .. } if kind == "playerStatus.autoplayDidChange"
+        ));
+    }
+
+    #[test]
+    fn other_event_names_are_ignored() {
+        let payload = serde_json::json!(["API:Lyrics", {}]).to_string();
+        assert!(decode_event(&payload, DecodeMode::Strict).is_none());
+    }
+
+    #[test]
+    fn ack_id_is_skipped() {
+        let payload = format!(
+            "12{}",
+            event("playerStatus.repeatModeDidChange", &serde_json::json!(2))
+        );
+        let decoded = decode_event(&payload, DecodeMode::Strict).unwrap().unwrap();
+        assert!(matches!(
+            decoded,
+            PlaybackEvent::RepeatModeChanged(RepeatMode::All)
+        ));
+    }
+
+    #[test]
+    fn malformed_payload_lenient_is_other() {
+        let payload = event("playerStatus.volumeDidChange", &serde_json::json!("loud"));
+        let decoded = decode_event(&payload, DecodeMode::Lenient)
+            .unwrap()
+            .unwrap();
+        assert!(matches!(decoded, PlaybackEvent::Other { .. }));
+    }
+
+    #[test]
+    fn malformed_payload_strict_is_decode_error() {
+        let data = serde_json::json!({ "currentPlaybackTime": "soon" });
+        let payload = event("playbackStatus.playbackTimeDidChange", &data);
+        let err = decode_event(&payload, DecodeMode::Strict)
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(
+            &err,
+            CiderError::Decode { path, status: None, .. } if path == "currentPlaybackTime"
+        ));
+        assert!(!err.to_string().contains("HTTP"), "{err}");
+    }
+}
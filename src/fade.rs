@@ -0,0 +1,190 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Easing curves for gradual volume changes.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Shape of a gradual volume change.
+///
+/// A curve maps progress through the change (`0.0` at the start, `1.0` at
+/// the end) to how much of the change has been applied. Ears perceive
+/// loudness roughly logarithmically, so [`EaseIn`](Self::EaseIn) tends to
+/// sound the most even for fade-outs.
+///
+/// ```
+/// # use cider_api::FadeCurve;
+/// assert_eq!(FadeCurve::Linear.apply(0.25), 0.25);
+/// assert!(FadeCurve::EaseIn.apply(0.25) < 0.25);
+/// assert!(FadeCurve::EaseOut.apply(0.25) > 0.25);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum FadeCurve {
+    /// Constant rate of change.
+    #[default]
+    Linear,
+    /// Starts slowly and accelerates (quadratic).
+    EaseIn,
+    /// Starts quickly and slows down (quadratic).
+    EaseOut,
+    /// Slow at both ends, fastest in the middle (smoothstep).
+    EaseInOut,
+}
+
+impl FadeCurve {
+    /// Fraction of the change applied at `progress`, both in `0.0..=1.0`.
+    ///
+    /// `progress` is clamped to that range.
+    #[must_use]
+    pub fn apply(self, progress: f64) -> f64 {
+        let t = progress.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// How many values [`ramp_steps`] yields for `duration` and `step`: one per
+/// started `step`, at least one and at most `u32::MAX`.
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn ramp_step_count(duration: Duration, step: Duration) -> u32 {
+    if step.is_zero() {
+        1
+    } else {
+        duration
+            .as_nanos()
+            .div_ceil(step.as_nanos())
+            .clamp(1, u128::from(u32::MAX)) as u32
+    }
+}
+
+/// The volumes to set, one per `step`, to go from `from` to `to` over
+/// `duration` along `curve`.
+///
+/// The first value is one step into the change and the last is exactly `to`.
+/// A zero `duration` yields just `to`. Values are computed as they are taken,
+/// so long ramps cost nothing up front.
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn ramp_steps(
+    from: f32,
+    to: f32,
+    duration: Duration,
+    step: Duration,
+    curve: FadeCurve,
+) -> impl Iterator<Item = f32> {
+    let count = ramp_step_count(duration, step);
+    (1..=count).map(move |k| {
+        if k == count {
+            return to;
+        }
+        let fraction = curve.apply(f64::from(k) / f64::from(count));
+        from + (to - from) * fraction as f32
+    })
+}
+
+/// Time between two of the `steps` values from [`ramp_steps`].
+pub(crate) fn step_interval(duration: Duration, steps: u32) -> Duration {
+    if steps == 0 {
+        Duration::ZERO
+    } else {
+        duration / steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURVES: [FadeCurve; 4] = [
+        FadeCurve::Linear,
+        FadeCurve::EaseIn,
+        FadeCurve::EaseOut,
+        FadeCurve::EaseInOut,
+    ];
+
+    #[test]
+    fn curves_start_at_zero_and_end_at_one() {
+        for curve in CURVES {
+            assert!(curve.apply(0.0).abs() < f64::EPSILON, "{curve:?}");
+            assert!((curve.apply(1.0) - 1.0).abs() < f64::EPSILON, "{curve:?}");
+            assert!((curve.apply(2.0) - 1.0).abs() < f64::EPSILON, "{curve:?}");
+        }
+    }
+
+    #[test]
+    fn curves_are_monotonic() {
+        for curve in CURVES {
+            let mut last = 0.0;
+            for i in 0..=100 {
+                let value = curve.apply(f64::from(i) / 100.0);
+                assert!(value >= last, "{curve:?} at {i}");
+                last = value;
+            }
+        }
+    }
+
+    #[test]
+    fn ramp_steps_end_exactly_at_target() {
+        let steps: Vec<f32> = ramp_steps(
+            0.8,
+            0.0,
+            Duration::from_secs(2),
+            Duration::from_millis(500),
+            FadeCurve::Linear,
+        )
+        .collect();
+        assert_eq!(steps.len(), 4);
+        assert!((steps[0] - 0.6).abs() < 1e-6);
+        assert!((steps[1] - 0.4).abs() < 1e-6);
+        assert!((steps[2] - 0.2).abs() < 1e-6);
+        assert!(steps[3].abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn ramp_steps_round_partial_steps_up() {
+        let count = ramp_steps(
+            1.0,
+            0.0,
+            Duration::from_millis(1100),
+            Duration::from_millis(500),
+            FadeCurve::EaseIn,
+        )
+        .count();
+        assert_eq!(count, 3);
+        assert_eq!(
+            ramp_step_count(Duration::from_millis(1100), Duration::from_millis(500)),
+            3
+        );
+    }
+
+    #[test]
+    fn long_ramps_are_lazy() {
+        let step = Duration::from_millis(50);
+        assert_eq!(ramp_step_count(Duration::MAX, step), u32::MAX);
+        let mut steps = ramp_steps(1.0, 0.0, Duration::MAX, step, FadeCurve::Linear);
+        assert!(steps.next().is_some_and(|level| level <= 1.0));
+        assert!(step_interval(Duration::MAX, u32::MAX) > step);
+    }
+
+    #[test]
+    fn zero_duration_jumps_to_target() {
+        let steps: Vec<f32> = ramp_steps(
+            0.2,
+            0.9,
+            Duration::ZERO,
+            Duration::from_millis(100),
+            FadeCurve::Linear,
+        )
+        .collect();
+        assert_eq!(steps, [0.9]);
+        assert_eq!(step_interval(Duration::ZERO, 1), Duration::ZERO);
+    }
+}
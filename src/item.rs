@@ -0,0 +1,455 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Typed Apple Music item kinds and validated item references.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::CiderError;
+use crate::types::PlayParams;
+
+/// Apple Music resource type accepted by
+/// [`play_item`](crate::CiderClient::play_item),
+/// [`play_next`](crate::CiderClient::play_next) and
+/// [`play_later`](crate::CiderClient::play_later).
+///
+/// Serialized as the plural Apple Music API type (`"songs"`,
+/// `"library-playlists"`, ...).
+///
+/// ```
+/// # use cider_api::ItemKind;
+/// assert_eq!(ItemKind::MusicVideos.as_str(), "music-videos");
+/// assert_eq!("library-songs".parse::<ItemKind>().unwrap(), ItemKind::LibrarySongs);
+/// assert!("song".parse::<ItemKind>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "&'static str")]
+#[non_exhaustive]
+pub enum ItemKind {
+    /// Catalog song (`songs`). IDs are numeric.
+    Songs,
+    /// Catalog album (`albums`). IDs are numeric.
+    Albums,
+    /// Catalog playlist (`playlists`). IDs start with `pl.`.
+    Playlists,
+    /// Radio station (`stations`). IDs start with `ra.`.
+    Stations,
+    /// Catalog music video (`music-videos`). IDs are numeric.
+    MusicVideos,
+    /// Song in the user's library (`library-songs`). IDs start with `i.`.
+    LibrarySongs,
+    /// Album in the user's library (`library-albums`). IDs start with `l.`.
+    LibraryAlbums,
+    /// Playlist in the user's library (`library-playlists`). IDs start
+    /// with `p.`.
+    LibraryPlaylists,
+    /// Music video in the user's library (`library-music-videos`). IDs
+    /// start with `i.`.
+    LibraryMusicVideos,
+}
+
+impl ItemKind {
+    /// Every kind, in declaration order.
+    pub const ALL: [Self; 9] = [
+        Self::Songs,
+        Self::Albums,
+        Self::Playlists,
+        Self::Stations,
+        Self::MusicVideos,
+        Self::LibrarySongs,
+        Self::LibraryAlbums,
+        Self::LibraryPlaylists,
+        Self::LibraryMusicVideos,
+    ];
+
+    /// The Apple Music API type name (e.g. `"library-albums"`).
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Songs => "songs",
+            Self::Albums => "albums",
+            Self::Playlists => "playlists",
+            Self::Stations => "stations",
+            Self::MusicVideos => "music-videos",
+            Self::LibrarySongs => "library-songs",
+            Self::LibraryAlbums => "library-albums",
+            Self::LibraryPlaylists => "library-playlists",
+            Self::LibraryMusicVideos => "library-music-videos",
+        }
+    }
+
+    /// Returns `true` for the `library-*` kinds.
+    #[must_use]
+    pub fn is_library(self) -> bool {
+        self.catalog().is_some()
+    }
+
+    /// The library counterpart of a catalog kind (`Songs` →
+    /// `LibrarySongs`), or `None` if there is none.
+    #[must_use]
+    pub fn library(self) -> Option<Self> {
+        match self {
+            Self::Songs => Some(Self::LibrarySongs),
+            Self::Albums => Some(Self::LibraryAlbums),
+            Self::Playlists => Some(Self::LibraryPlaylists),
+            Self::MusicVideos => Some(Self::LibraryMusicVideos),
+            _ => None,
+        }
+    }
+
+    /// The catalog counterpart of a library kind (`LibrarySongs` →
+    /// `Songs`), or `None` for catalog kinds.
+    #[must_use]
+    pub fn catalog(self) -> Option<Self> {
+        match self {
+            Self::LibrarySongs => Some(Self::Songs),
+            Self::LibraryAlbums => Some(Self::Albums),
+            Self::LibraryPlaylists => Some(Self::Playlists),
+            Self::LibraryMusicVideos => Some(Self::MusicVideos),
+            _ => None,
+        }
+    }
+
+    /// Map the singular `kind` found in [`PlayParams`] (`"song"`,
+    /// `"radioStation"`, ...) to its catalog kind.
+    fn from_play_params_kind(kind: &str) -> Option<Self> {
+        match kind {
+            "song" => Some(Self::Songs),
+            "album" => Some(Self::Albums),
+            "playlist" => Some(Self::Playlists),
+            "station" | "radioStation" => Some(Self::Stations),
+            "musicVideo" => Some(Self::MusicVideos),
+            _ => None,
+        }
+    }
+
+    /// The ID prefix required for this kind, or `None` for numeric IDs.
+    fn id_prefix(self) -> Option<&'static str> {
+        match self {
+            Self::Songs | Self::Albums | Self::MusicVideos => None,
+            Self::Playlists => Some("pl."),
+            Self::Stations => Some("ra."),
+            Self::LibrarySongs | Self::LibraryMusicVideos => Some("i."),
+            Self::LibraryAlbums => Some("l."),
+            Self::LibraryPlaylists => Some("p."),
+        }
+    }
+
+    /// Whether `id` has the shape this kind expects.
+    pub(crate) fn accepts(self, id: &str) -> bool {
+        match self.id_prefix() {
+            Some(prefix) => id.len() > prefix.len() && id.starts_with(prefix),
+            None => !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()),
+        }
+    }
+}
+
+impl fmt::Display for ItemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ItemKind {
+    type Err = CiderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| CiderError::InvalidItem(format!("unknown item kind {s:?}")))
+    }
+}
+
+impl TryFrom<String> for ItemKind {
+    type Error = CiderError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ItemKind> for &'static str {
+    fn from(kind: ItemKind) -> Self {
+        kind.as_str()
+    }
+}
+
+/// A validated reference to a playable Apple Music item: an [`ItemKind`]
+/// plus an ID of the matching shape.
+///
+/// Construction checks the ID against the kind, so mixing up catalog and
+/// library items fails before anything is sent to Cider:
+///
+/// * catalog songs, albums and music videos have numeric IDs
+///   (`"1719861213"`);
+/// * catalog playlists start with `pl.`, stations with `ra.`;
+/// * library songs and music videos start with `i.`, library albums with
+///   `l.` and library playlists with `p.`.
+///
+/// # Examples
+///
+/// ```
+/// # use cider_api::{ItemKind, ItemRef};
+/// let song = ItemRef::song("1719861213")?;
+/// assert_eq!(song.to_string(), "songs/1719861213");
+///
+/// let mix = ItemRef::new(ItemKind::LibraryPlaylists, "p.V7VYlrDTxj3B")?;
+/// assert!(mix.kind().is_library());
+///
+/// // A library ID with a catalog kind is rejected.
+/// assert!(ItemRef::song("i.PkdZbQXsPJ4DX").is_err());
+/// # Ok::<(), cider_api::CiderError>(())
+/// ```
+///
+/// Serializes as `{"type": "songs", "id": "1719861213"}`; deserializing
+/// applies the same validation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "RawItemRef")]
+pub struct ItemRef {
+    #[serde(rename = "type")]
+    kind: ItemKind,
+    id: String,
+}
+
+/// Unvalidated wire form of [`ItemRef`].
+#[derive(Deserialize)]
+struct RawItemRef {
+    #[serde(rename = "type")]
+    kind: ItemKind,
+    id: String,
+}
+
+impl TryFrom<RawItemRef> for ItemRef {
+    type Error = CiderError;
+
+    fn try_from(raw: RawItemRef) -> Result<Self, Self::Error> {
+        Self::new(raw.kind, raw.id)
+    }
+}
+
+impl ItemRef {
+    /// Create a reference, validating `id` against `kind`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::InvalidItem`] if `id` does not have the shape
+    /// `kind` expects. The message names the right kind when the ID belongs
+    /// to the library (or the catalog) instead.
+    pub fn new(kind: ItemKind, id: impl Into<String>) -> Result<Self, CiderError> {
+        let id = id.into();
+        if kind.accepts(&id) {
+            return Ok(Self { kind, id });
+        }
+
+        let hint = kind
+            .library()
+            .or_else(|| kind.catalog())
+            .filter(|other| other.accepts(&id))
+            .map(|other| format!(", use {other}"))
+            .unwrap_or_default();
+        let expected = match kind.id_prefix() {
+            Some(prefix) => format!("IDs starting with `{prefix}`"),
+            None => "numeric IDs".to_string(),
+        };
+        Err(CiderError::InvalidItem(format!(
+            "{id:?} is not a valid {kind} ID ({kind} take {expected}){hint}"
+        )))
+    }
+
+    /// A catalog song.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::InvalidItem`] unless `id` is numeric.
+    pub fn song(id: impl Into<String>) -> Result<Self, CiderError> {
+        Self::new(ItemKind::Songs, id)
+    }
+
+    /// A catalog album.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::InvalidItem`] unless `id` is numeric.
+    pub fn album(id: impl Into<String>) -> Result<Self, CiderError> {
+        Self::new(ItemKind::Albums, id)
+    }
+
+    /// A catalog playlist.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::InvalidItem`] unless `id` starts with `pl.`.
+    pub fn playlist(id: impl Into<String>) -> Result<Self, CiderError> {
+        Self::new(ItemKind::Playlists, id)
+    }
+
+    /// A radio station.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::InvalidItem`] unless `id` starts with `ra.`.
+    pub fn station(id: impl Into<String>) -> Result<Self, CiderError> {
+        Self::new(ItemKind::Stations, id)
+    }
+
+    /// The item kind.
+    #[must_use]
+    pub fn kind(&self) -> ItemKind {
+        self.kind
+    }
+
+    /// The item ID.
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl fmt::Display for ItemRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.kind, self.id)
+    }
+}
+
+impl PlayParams {
+    /// The [`ItemRef`] these parameters point to, or `None` if the kind is
+    /// not playable through [`ItemKind`] or the ID is malformed.
+    ///
+    /// Library items report the same `kind` as catalog ones, so the
+    /// library variant is picked from the ID prefix.
+    ///
+    /// ```
+    /// # use cider_api::{ItemKind, PlayParams};
+    /// let pp = PlayParams { id: "i.PkdZbQXsPJ4DX".into(), kind: "song".into() };
+    /// assert_eq!(pp.item_ref().unwrap().kind(), ItemKind::LibrarySongs);
+    /// ```
+    #[must_use]
+    pub fn item_ref(&self) -> Option<ItemRef> {
+        let catalog = ItemKind::from_play_params_kind(&self.kind)?;
+        let kind = match catalog.library() {
+            Some(library) if library.accepts(&self.id) => library,
+            _ => catalog,
+        };
+        ItemRef::new(kind, self.id.clone()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_names_round_trip() {
+        for kind in ItemKind::ALL {
+            assert_eq!(kind.as_str().parse::<ItemKind>().unwrap(), kind);
+            let json = serde_json::to_string(&kind).unwrap();
+            assert_eq!(serde_json::from_str::<ItemKind>(&json).unwrap(), kind);
+        }
+    }
+
+    #[test]
+    fn unknown_kind_is_rejected() {
+        let err = "song".parse::<ItemKind>().unwrap_err();
+        assert!(matches!(err, CiderError::InvalidItem(_)));
+        assert!(serde_json::from_str::<ItemKind>(r#""podcasts""#).is_err());
+    }
+
+    #[test]
+    fn library_and_catalog_counterparts() {
+        for kind in ItemKind::ALL {
+            if let Some(library) = kind.library() {
+                assert!(library.is_library());
+                assert_eq!(library.catalog(), Some(kind));
+            }
+        }
+        assert_eq!(ItemKind::Stations.library(), None);
+        assert!(!ItemKind::Stations.is_library());
+    }
+
+    #[test]
+    fn valid_ids_are_accepted() {
+        let cases = [
+            (ItemKind::Songs, "1719861213"),
+            (ItemKind::Albums, "1719860281"),
+            (ItemKind::MusicVideos, "1445817624"),
+            (ItemKind::Playlists, "pl.f4d106fed2bd41149aaacabb233eb5eb"),
+            (ItemKind::Stations, "ra.978194965"),
+            (ItemKind::LibrarySongs, "i.PkdZbQXsPJ4DX"),
+            (ItemKind::LibraryAlbums, "l.gACheFi"),
+            (ItemKind::LibraryPlaylists, "p.V7VYlrDTxj3B"),
+            (ItemKind::LibraryMusicVideos, "i.8YkM3ReTE6ALZ"),
+        ];
+        for (kind, id) in cases {
+            let item = ItemRef::new(kind, id).unwrap();
+            assert_eq!((item.kind(), item.id()), (kind, id));
+        }
+    }
+
+    #[test]
+    fn library_id_with_catalog_kind_suggests_library_kind() {
+        let err = ItemRef::song("i.PkdZbQXsPJ4DX").unwrap_err();
+        let CiderError::InvalidItem(message) = err else {
+            panic!("expected InvalidItem");
+        };
+        assert!(message.ends_with(", use library-songs"), "{message}");
+    }
+
+    #[test]
+    fn catalog_id_with_library_kind_suggests_catalog_kind() {
+        let err = ItemRef::new(ItemKind::LibraryAlbums, "1719860281").unwrap_err();
+        assert!(err.to_string().ends_with(", use albums"), "{err}");
+    }
+
+    #[test]
+    fn malformed_ids_are_rejected() {
+        assert!(ItemRef::song("").is_err());
+        assert!(ItemRef::song("12a").is_err());
+        assert!(ItemRef::playlist("pl.").is_err());
+        assert!(ItemRef::playlist("p.V7VYlrDTxj3B").is_err());
+        assert!(ItemRef::station("1234").is_err());
+    }
+
+    #[test]
+    fn serde_round_trip_validates() {
+        let item = ItemRef::playlist("pl.f4d106fed2bd41149aaacabb233eb5eb").unwrap();
+        let json = serde_json::to_value(&item).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"type": "playlists", "id": "pl.f4d106fed2bd41149aaacabb233eb5eb"})
+        );
+        assert_eq!(serde_json::from_value::<ItemRef>(json).unwrap(), item);
+
+        let bad = serde_json::json!({"type": "songs", "id": "i.PkdZbQXsPJ4DX"});
+        assert!(serde_json::from_value::<ItemRef>(bad).is_err());
+    }
+
+    #[test]
+    fn play_params_item_ref() {
+        let params = |id: &str, kind: &str| PlayParams {
+            id: id.into(),
+            kind: kind.into(),
+        };
+        assert_eq!(
+            params("1719861213", "song").item_ref(),
+            Some(ItemRef::song("1719861213").unwrap())
+        );
+        assert_eq!(
+            params("l.gACheFi", "album").item_ref().unwrap().kind(),
+            ItemKind::LibraryAlbums
+        );
+        assert_eq!(
+            params("ra.978194965", "radioStation")
+                .item_ref()
+                .unwrap()
+                .kind(),
+            ItemKind::Stations
+        );
+        assert!(params("1", "uploadedVideo").item_ref().is_none());
+        assert!(params("garbage", "song").item_ref().is_none());
+    }
+}
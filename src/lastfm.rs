@@ -0,0 +1,275 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [Last.fm](https://www.last.fm/api) submitter.
+
+use std::time::Duration;
+
+use tracing::{debug, warn};
+
+use crate::error::{CiderError, Endpoint};
+use crate::scrobble::{send_to_service, service_error, Scrobble, ScrobbleSink};
+
+/// The public Last.fm API.
+pub const LASTFM_API: &str = "https://ws.audioscrobbler.com/2.0/";
+
+const SERVICE: &str = "Last.fm";
+
+/// Most scrobbles Last.fm accepts in one `track.scrobble` call.
+const MAX_SCROBBLES_PER_REQUEST: usize = 50;
+
+/// Default per-request timeout.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Last.fm error codes that mean "try again later": operation failed,
+/// service offline, temporarily unavailable, rate limit exceeded.
+const RETRYABLE_CODES: [u64; 4] = [8, 11, 16, 29];
+
+/// Submits plays to Last.fm with `track.updateNowPlaying` and
+/// `track.scrobble`, or to any server implementing its API (e.g. Libre.fm).
+///
+/// Requests are signed with the application's API secret and made on
+/// behalf of the user owning `session_key`, obtained once through
+/// Last.fm's [authentication flow](https://www.last.fm/api/authentication).
+/// Last.fm answers rejected credentials with HTTP 403, reported as
+/// [`is_auth`](CiderError::is_auth).
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), cider_api::CiderError> {
+/// use cider_api::{CiderClient, LastFm, ScrobbleQueue};
+///
+/// let sink = LastFm::new("api-key", "api-secret", "session-key");
+/// let queue = ScrobbleQueue::open("scrobbles.json")?;
+/// let scrobbler = CiderClient::new().scrobbler(sink, queue).start();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct LastFm {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    api_secret: String,
+    session_key: String,
+}
+
+impl std::fmt::Debug for LastFm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LastFm")
+            .field("base_url", &self.base_url)
+            .field("api_key", &self.api_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LastFm {
+    /// A submitter for the public API.
+    #[must_use]
+    pub fn new(
+        api_key: impl Into<String>,
+        api_secret: impl Into<String>,
+        session_key: impl Into<String>,
+    ) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!("cider-api/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_default();
+        Self {
+            http,
+            base_url: LASTFM_API.to_owned(),
+            api_key: api_key.into(),
+            api_secret: api_secret.into(),
+            session_key: session_key.into(),
+        }
+    }
+
+    /// Submit to another server (default [`LASTFM_API`]). This is the full
+    /// endpoint URL, including `/2.0/`.
+    #[must_use]
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Use a preconfigured [`reqwest::Client`] (default: 10s timeout).
+    #[must_use]
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Call a write method with `params`, signed.
+    async fn call(
+        &self,
+        method: &str,
+        mut params: Vec<(String, String)>,
+    ) -> Result<serde_json::Value, CiderError> {
+        let endpoint = Endpoint {
+            method: reqwest::Method::POST,
+            path: format!("/2.0/?method={method}"),
+        };
+        params.push(("method".into(), method.into()));
+        params.push(("api_key".into(), self.api_key.clone()));
+        params.push(("sk".into(), self.session_key.clone()));
+        let signature = sign(&params, &self.api_secret);
+        params.push(("api_sig".into(), signature));
+        params.push(("format".into(), "json".into()));
+
+        let req = self.http.post(&self.base_url).form(&params);
+        let (status, text) = send_to_service(SERVICE, &endpoint, req).await?;
+        let body: Option<serde_json::Value> = serde_json::from_str(&text).ok();
+
+        // Errors come as `{"error": 9, "message": "..."}`, not always with
+        // an error status.
+        let code = body.as_ref().and_then(|b| b.get("error")?.as_u64());
+        if status.is_success() && code.is_none() {
+            return Ok(body.unwrap_or_default());
+        }
+        let message = body
+            .as_ref()
+            .and_then(|b| b.get("message")?.as_str())
+            .map_or_else(|| text.trim().to_owned(), str::to_owned);
+        let mut err = service_error(SERVICE, &endpoint, status, message);
+        if let (CiderError::Service { retryable, .. }, Some(code)) = (&mut err, code) {
+            *retryable |= RETRYABLE_CODES.contains(&code);
+        }
+        Err(err)
+    }
+}
+
+impl ScrobbleSink for LastFm {
+    fn name(&self) -> &str {
+        SERVICE
+    }
+
+    async fn now_playing(&self, track: &Scrobble) -> Result<(), CiderError> {
+        self.call("track.updateNowPlaying", track_params(track, None))
+            .await
+            .map(drop)
+    }
+
+    async fn submit(&self, scrobbles: &[Scrobble]) -> Result<(), CiderError> {
+        if scrobbles.is_empty() {
+            return Ok(());
+        }
+        let params = scrobbles
+            .iter()
+            .enumerate()
+            .flat_map(|(i, scrobble)| track_params(scrobble, Some(i)))
+            .collect();
+        let body = self.call("track.scrobble", params).await?;
+        let ignored = body["scrobbles"]["@attr"]["ignored"]
+            .as_u64()
+            .or_else(|| body["scrobbles"]["@attr"]["ignored"].as_str()?.parse().ok())
+            .unwrap_or(0);
+        if ignored > 0 {
+            warn!(ignored, "Last.fm ignored some scrobbles");
+        }
+        debug!(count = scrobbles.len(), "submitted scrobbles");
+        Ok(())
+    }
+
+    fn max_batch(&self) -> usize {
+        MAX_SCROBBLES_PER_REQUEST
+    }
+}
+
+/// Parameters describing `scrobble`, suffixed with `[index]` for
+/// `track.scrobble` batches.
+fn track_params(scrobble: &Scrobble, index: Option<usize>) -> Vec<(String, String)> {
+    let key = |name: &str| match index {
+        Some(i) => format!("{name}[{i}]"),
+        None => name.to_owned(),
+    };
+    let mut params = vec![
+        (key("artist"), scrobble.artist_name.clone()),
+        (key("track"), scrobble.name.clone()),
+    ];
+    if !scrobble.album_name.is_empty() {
+        params.push((key("album"), scrobble.album_name.clone()));
+    }
+    if !scrobble.duration.is_zero() {
+        params.push((key("duration"), scrobble.duration.as_secs().to_string()));
+    }
+    if index.is_some() {
+        params.push((key("timestamp"), scrobble.started_at_unix().to_string()));
+    }
+    params
+}
+
+/// The `api_sig` for `params`: the MD5 of every `name` and `value`
+/// concatenated in name order, followed by the secret.
+fn sign(params: &[(String, String)], secret: &str) -> String {
+    let mut sorted: Vec<&(String, String)> = params.iter().collect();
+    sorted.sort();
+    let mut input = String::new();
+    for (name, value) in sorted {
+        input.push_str(name);
+        input.push_str(value);
+    }
+    input.push_str(secret);
+    format!("{:x}", md5::compute(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+
+    fn scrobble() -> Scrobble {
+        Scrobble {
+            artist_name: "Artist".into(),
+            name: "Song".into(),
+            album_name: "Album".into(),
+            isrc: None,
+            started_at: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            duration: Duration::from_millis(215_500),
+        }
+    }
+
+    #[test]
+    fn signature_sorts_parameters() {
+        let params = vec![
+            ("track".to_owned(), "Song".to_owned()),
+            ("artist".to_owned(), "Artist".to_owned()),
+        ];
+        // md5("artistArtisttrackSongsecret")
+        assert_eq!(sign(&params, "secret"), "d10865a4295adf76a28d9b6cac6fa657");
+        let reversed: Vec<_> = params.iter().rev().cloned().collect();
+        assert_eq!(sign(&params, "secret"), sign(&reversed, "secret"));
+    }
+
+    #[test]
+    fn batch_parameters_are_indexed() {
+        let params = track_params(&scrobble(), Some(3));
+        let get = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(get("artist[3]"), Some("Artist"));
+        assert_eq!(get("track[3]"), Some("Song"));
+        assert_eq!(get("album[3]"), Some("Album"));
+        assert_eq!(get("duration[3]"), Some("215"));
+        assert_eq!(get("timestamp[3]"), Some("1700000000"));
+    }
+
+    #[test]
+    fn now_playing_parameters_have_no_timestamp() {
+        let params = track_params(&scrobble(), None);
+        assert!(params.iter().any(|(key, _)| key == "artist"));
+        assert!(!params.iter().any(|(key, _)| key.starts_with("timestamp")));
+    }
+
+    #[test]
+    fn debug_hides_secrets() {
+        let debug = format!("{:?}", LastFm::new("key", "secret", "session"));
+        assert!(!debug.contains("secret") && !debug.contains("session"));
+    }
+}
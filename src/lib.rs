@@ -28,8 +28,8 @@
 //!     println!("Artwork: {}", track.artwork_url(600));
 //!     println!(
 //!         "Position: {:.1}s / {:.1}s",
-//!         track.current_playback_time,
-//!         track.duration_in_millis as f64 / 1000.0,
+//!         track.position().as_secs_f64(),
+//!         track.duration().as_secs_f64(),
 //!     );
 //! }
 //!
@@ -53,21 +53,173 @@
 //!
 //! The token is sent in the `apitoken` header — no `Bearer` prefix.
 //!
+//! ## Configuration
+//!
+//! The defaults are tuned for a local Cider instance (1s connect timeout, 2s
+//! request timeout). Use [`CiderClient::builder`] to change timeouts, pooling,
+//! keepalive, the user agent or default headers, or to inject your own
+//! [`reqwest::Client`]:
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use cider_api::CiderClient;
+//! let client = CiderClient::builder()
+//!     .port(10767)
+//!     .timeout(Duration::from_secs(10))
+//!     .build()?;
+//! # Ok::<(), cider_api::CiderError>(())
+//! ```
+//!
+//! Transient failures can be retried automatically with a [`RetryPolicy`]:
+//!
+//! ```no_run
+//! # use cider_api::{CiderClient, RetryPolicy};
+//! let client = CiderClient::builder()
+//!     .retry_policy(RetryPolicy::new().max_attempts(4))
+//!     .build()?;
+//! # Ok::<(), cider_api::CiderError>(())
+//! ```
+//!
+//! ## Features
+//!
+//! - `events` — [`CiderClient::events`]: a stream of playback events pushed
+//!   over Cider's Socket.IO channel, with automatic reconnection.
+//! - `schedule` — [`CiderClient::scheduler`]: run playback actions at
+//!   wall-clock times or on cron schedules loaded from TOML or JSON.
+//! - `scrobble` — [`CiderClient::scrobbler`]: decide when tracks count as
+//!   played and submit them through a persistent retry queue, to
+//!   ListenBrainz, Last.fm or your own sink.
+//! - `mpris` — `MprisServer`: Cider as an MPRIS player on D-Bus for
+//!   `playerctl` and media keys, also available as the `cider-mpris` binary.
+//! - `mpd` — `MpdServer`: an MPD protocol server so `mpc`, `ncmpcpp` and
+//!   phone MPD apps can control Cider, also available as the `cider-mpd`
+//!   binary.
+//! - `mqtt` — `MqttBridge`: Cider state on MQTT topics and commands from
+//!   command topics, with Home Assistant discovery.
+//! - `metrics` — `Metrics` and `MetricsServer`: per-endpoint request counts,
+//!   latencies and error classes plus player gauges, served on `/metrics`
+//!   for Prometheus.
+//! - `proxy` — `ProxyServer`: re-exposes the playback API on the LAN with
+//!   per-client scoped tokens, an audit log and a WebSocket state feed, also
+//!   available as the `cider-proxy` binary.
+//! - `cli` — the `cider` command-line tool: every client method as a
+//!   subcommand, with text or JSON output and documented exit codes.
+//! - `tui` — the `cider-tui` terminal dashboard: now playing with a live
+//!   progress bar, and the queue with playback, seek, volume, queue editing
+//!   and rating keys.
+//! - `status-line` — `StatusLine` and `StatusTemplate`: templated one-line
+//!   now-playing text with truncation, marquee scrolling, Pango/JSON
+//!   escaping and waybar's JSON format, also available as
+//!   `cider status-line`.
+//! - `overlay` — `NowPlayingFiles` and `OverlayServer`: now-playing text,
+//!   JSON and artwork files, and an HTML overlay updated over Server-Sent
+//!   Events, for streaming software; also available as the `cider-overlay`
+//!   binary.
+//!
 //! ## API coverage
 //!
 //! | Category | Methods |
 //! |---|---|
 //! | **Status** | [`is_active`](CiderClient::is_active), [`is_playing`](CiderClient::is_playing), [`now_playing`](CiderClient::now_playing) |
-//! | **Playback** | [`play`](CiderClient::play), [`pause`](CiderClient::pause), [`play_pause`](CiderClient::play_pause), [`stop`](CiderClient::stop), [`next`](CiderClient::next), [`previous`](CiderClient::previous), [`seek`](CiderClient::seek), [`seek_ms`](CiderClient::seek_ms) |
-//! | **Play items** | [`play_url`](CiderClient::play_url), [`play_item`](CiderClient::play_item), [`play_item_href`](CiderClient::play_item_href), [`play_next`](CiderClient::play_next), [`play_later`](CiderClient::play_later) |
+//! | **Playback** | [`play`](CiderClient::play), [`pause`](CiderClient::pause), [`play_pause`](CiderClient::play_pause), [`stop`](CiderClient::stop), [`next`](CiderClient::next), [`previous`](CiderClient::previous), [`seek`](CiderClient::seek), [`seek_ms`](CiderClient::seek_ms), [`seek_to`](CiderClient::seek_to), [`seek_by`](CiderClient::seek_by) |
+//! | **Play items** | [`play_url`](CiderClient::play_url), [`play_parsed`](CiderClient::play_parsed), [`play_item`](CiderClient::play_item), [`play_item_href`](CiderClient::play_item_href), [`play_next`](CiderClient::play_next), [`play_later`](CiderClient::play_later) |
 //! | **Queue** | [`get_queue`](CiderClient::get_queue), [`queue_move_to_position`](CiderClient::queue_move_to_position), [`queue_remove_by_index`](CiderClient::queue_remove_by_index), [`clear_queue`](CiderClient::clear_queue) |
-//! | **Volume** | [`get_volume`](CiderClient::get_volume), [`set_volume`](CiderClient::set_volume) |
-//! | **Settings** | [`get_repeat_mode`](CiderClient::get_repeat_mode), [`toggle_repeat`](CiderClient::toggle_repeat), [`get_shuffle_mode`](CiderClient::get_shuffle_mode), [`toggle_shuffle`](CiderClient::toggle_shuffle), [`get_autoplay`](CiderClient::get_autoplay), [`toggle_autoplay`](CiderClient::toggle_autoplay) |
+//! | **Volume** | [`get_volume`](CiderClient::get_volume), [`set_volume`](CiderClient::set_volume), [`ramp_volume`](CiderClient::ramp_volume), [`mute`](CiderClient::mute), [`unmute`](CiderClient::unmute), [`duck`](CiderClient::duck) |
+//! | **Settings** | [`get_repeat_mode`](CiderClient::get_repeat_mode), [`set_repeat_mode`](CiderClient::set_repeat_mode), [`toggle_repeat`](CiderClient::toggle_repeat), [`get_shuffle_mode`](CiderClient::get_shuffle_mode), [`set_shuffle_mode`](CiderClient::set_shuffle_mode), [`toggle_shuffle`](CiderClient::toggle_shuffle), [`get_autoplay`](CiderClient::get_autoplay), [`toggle_autoplay`](CiderClient::toggle_autoplay) |
 //! | **Library** | [`add_to_library`](CiderClient::add_to_library), [`set_rating`](CiderClient::set_rating) |
 //! | **Apple Music API** | [`amapi_run_v3`](CiderClient::amapi_run_v3) |
+//! | **Events** (`events` feature) | `events`, `events_with` |
+//! | **Watching** | [`watch`](CiderClient::watch), [`PlaybackWatcher`] |
+//! | **Sleep timer** | [`sleep_timer`](CiderClient::sleep_timer), [`SleepTimer`] |
+//! | **Scheduling** (`schedule` feature) | `scheduler`, `Scheduler` |
+//! | **Scrobbling** (`scrobble` feature) | `scrobbler`, `Scrobbler`, `ListenBrainz`, `LastFm` |
+//! | **MPRIS** (`mpris` feature) | `MprisServer` |
+//! | **MPD** (`mpd` feature) | `MpdServer` |
+//! | **MQTT** (`mqtt` feature) | `MqttBridge` |
+//! | **Metrics** (`metrics` feature) | `with_metrics`, `Metrics`, `MetricsServer` |
+//! | **Proxy** (`proxy` feature) | `ProxyServer`, `ProxyScope` |
+//! | **Status line** (`status-line` feature) | `StatusLine`, `StatusTemplate` |
+//! | **Stream overlay** (`overlay` feature) | `NowPlayingFiles`, `OverlayServer` |
 
+mod builder;
 mod client;
+#[cfg(feature = "schedule")]
+mod cron;
+mod error;
+mod item;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "mpd")]
+mod mpd;
+#[cfg(feature = "events")]
+mod events;
+mod fade;
+mod retry;
+#[cfg(feature = "scrobble")]
+mod lastfm;
+#[cfg(feature = "scrobble")]
+mod listenbrainz;
+#[cfg(feature = "mpris")]
+mod mpris;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+#[cfg(feature = "overlay")]
+mod overlay;
+#[cfg(feature = "proxy")]
+mod proxy;
+#[cfg(feature = "scrobble")]
+mod scrobble;
+#[cfg(feature = "schedule")]
+mod schedule;
+mod sleep;
+#[cfg(feature = "status-line")]
+mod status_line;
 mod types;
+mod url;
+mod volume;
+mod watcher;
 
-pub use client::{CiderClient, CiderError, DEFAULT_PORT};
+pub use builder::CiderClientBuilder;
+pub use client::{CiderClient, DecodeMode, DEFAULT_PORT};
+#[cfg(feature = "schedule")]
+pub use cron::Cron;
+pub use error::{CiderError, Endpoint};
+pub use item::{ItemKind, ItemRef};
+#[cfg(feature = "metrics")]
+pub use metrics::{Metrics, MetricsHandle, MetricsServer};
+#[cfg(feature = "mpd")]
+pub use mpd::{MpdHandle, MpdServer};
+#[cfg(feature = "events")]
+pub use events::{EventStream, PlaybackEvent, PlaybackState, PlaybackTime};
+pub use fade::FadeCurve;
+pub use retry::RetryPolicy;
+#[cfg(feature = "scrobble")]
+pub use lastfm::{LastFm, LASTFM_API};
+#[cfg(feature = "scrobble")]
+pub use listenbrainz::{ListenBrainz, LISTENBRAINZ_API};
+#[cfg(feature = "mpris")]
+pub use mpris::{MprisHandle, MprisServer};
+#[cfg(feature = "mqtt")]
+pub use mqtt::{MqttBridge, MqttHandle};
+#[cfg(feature = "overlay")]
+pub use overlay::{NowPlayingFiles, NowPlayingFilesHandle, OverlayHandle, OverlayServer};
+#[cfg(feature = "proxy")]
+pub use proxy::{ProxyHandle, ProxyScope, ProxyServer};
+#[cfg(feature = "scrobble")]
+pub use scrobble::{
+    PlayTracker, Scrobble, ScrobbleEvent, ScrobbleQueue, ScrobbleSink, Scrobbler, ScrobblerHandle,
+};
+#[cfg(feature = "schedule")]
+pub use schedule::{
+    Action, Job, MissReason, Schedule, ScheduleEvent, Scheduler, SchedulerHandle, Trigger,
+};
+pub use sleep::{SleepAfter, SleepOutcome, SleepTimer, SleepTimerHandle};
+#[cfg(feature = "status-line")]
+pub use status_line::{
+    format_duration, whole_percent, StatusClass, StatusEscape, StatusLine, StatusOutput,
+    StatusTemplate,
+};
 pub use types::*;
+pub use url::{AppleMusicUrl, UrlKind};
+pub use volume::DuckGuard;
+pub use watcher::{PlaybackSnapshot, PlaybackWatcher, WatchEvent};
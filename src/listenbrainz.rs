@@ -0,0 +1,278 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [ListenBrainz](https://listenbrainz.org) submitter.
+
+use std::time::Duration;
+
+use serde::Serialize;
+use tracing::debug;
+
+use crate::error::{CiderError, Endpoint};
+use crate::scrobble::{send_to_service, service_error, Scrobble, ScrobbleSink};
+
+/// The public ListenBrainz API.
+pub const LISTENBRAINZ_API: &str = "https://api.listenbrainz.org";
+
+const SERVICE: &str = "ListenBrainz";
+
+/// Path of the `submit-listens` endpoint.
+const SUBMIT_LISTENS: &str = "/1/submit-listens";
+
+/// Most listens ListenBrainz accepts in one `import` submission.
+const MAX_LISTENS_PER_REQUEST: usize = 1000;
+
+/// Default per-request timeout.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Submits plays to ListenBrainz, or any server implementing its API
+/// (e.g. a self-hosted instance or Maloja).
+///
+/// Uses `submit-listens` with `playing_now` for the current track, `single`
+/// for one play and `import` for a backlog. The user token is on the
+/// ListenBrainz settings page.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), cider_api::CiderError> {
+/// use cider_api::{CiderClient, ListenBrainz, ScrobbleQueue};
+///
+/// let sink = ListenBrainz::new("user-token");
+/// let queue = ScrobbleQueue::open("listens.json")?;
+/// let scrobbler = CiderClient::new().scrobbler(sink, queue).start();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ListenBrainz {
+    http: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+impl std::fmt::Debug for ListenBrainz {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ListenBrainz")
+            .field("base_url", &self.base_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ListenBrainz {
+    /// A submitter for the public API, authenticated with a user token.
+    #[must_use]
+    pub fn new(token: impl Into<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!("cider-api/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_default();
+        Self {
+            http,
+            base_url: LISTENBRAINZ_API.to_owned(),
+            token: token.into(),
+        }
+    }
+
+    /// Submit to another server (default [`LISTENBRAINZ_API`]), without the
+    /// `/1/...` path.
+    #[must_use]
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        let mut base_url = base_url.into();
+        base_url.truncate(base_url.trim_end_matches('/').len());
+        self.base_url = base_url;
+        self
+    }
+
+    /// Use a preconfigured [`reqwest::Client`] (default: 10s timeout).
+    #[must_use]
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Send one `submit-listens` request.
+    async fn submit_listens(&self, body: &Submission<'_>) -> Result<(), CiderError> {
+        let endpoint = Endpoint {
+            method: reqwest::Method::POST,
+            path: SUBMIT_LISTENS.to_owned(),
+        };
+        let req = self
+            .http
+            .post(format!("{}{SUBMIT_LISTENS}", self.base_url))
+            .header("Authorization", format!("Token {}", self.token))
+            .json(body);
+        let (status, text) = send_to_service(SERVICE, &endpoint, req).await?;
+        if status.is_success() {
+            debug!(
+                listen_type = body.listen_type,
+                count = body.payload.len(),
+                "submitted listens"
+            );
+            return Ok(());
+        }
+        Err(service_error(
+            SERVICE,
+            &endpoint,
+            status,
+            error_message(&text),
+        ))
+    }
+}
+
+impl ScrobbleSink for ListenBrainz {
+    fn name(&self) -> &str {
+        SERVICE
+    }
+
+    async fn now_playing(&self, track: &Scrobble) -> Result<(), CiderError> {
+        self.submit_listens(&Submission {
+            listen_type: "playing_now",
+            payload: vec![Listen::new(track, false)],
+        })
+        .await
+    }
+
+    async fn submit(&self, scrobbles: &[Scrobble]) -> Result<(), CiderError> {
+        if scrobbles.is_empty() {
+            return Ok(());
+        }
+        self.submit_listens(&Submission {
+            listen_type: if scrobbles.len() == 1 {
+                "single"
+            } else {
+                "import"
+            },
+            payload: scrobbles.iter().map(|s| Listen::new(s, true)).collect(),
+        })
+        .await
+    }
+
+    fn max_batch(&self) -> usize {
+        MAX_LISTENS_PER_REQUEST
+    }
+}
+
+/// The message from a ListenBrainz error body (`{"code": 401, "error":
+/// "..."}`), or the raw body.
+fn error_message(text: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(text)
+        .ok()
+        .and_then(|body| body.get("error")?.as_str().map(str::to_owned))
+        .unwrap_or_else(|| text.trim().to_owned())
+}
+
+#[derive(Debug, Serialize)]
+struct Submission<'a> {
+    listen_type: &'static str,
+    payload: Vec<Listen<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct Listen<'a> {
+    /// Absent for `playing_now`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    listened_at: Option<u64>,
+    track_metadata: TrackMetadata<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct TrackMetadata<'a> {
+    artist_name: &'a str,
+    track_name: &'a str,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    release_name: &'a str,
+    additional_info: AdditionalInfo<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct AdditionalInfo<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    isrc: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u128>,
+    media_player: &'static str,
+    submission_client: &'static str,
+    submission_client_version: &'static str,
+}
+
+impl<'a> Listen<'a> {
+    fn new(scrobble: &'a Scrobble, with_time: bool) -> Self {
+        Self {
+            listened_at: with_time.then(|| scrobble.started_at_unix()),
+            track_metadata: TrackMetadata {
+                artist_name: &scrobble.artist_name,
+                track_name: &scrobble.name,
+                release_name: &scrobble.album_name,
+                additional_info: AdditionalInfo {
+                    isrc: scrobble.isrc.as_deref(),
+                    duration_ms: Some(scrobble.duration.as_millis()).filter(|&ms| ms > 0),
+                    media_player: "Cider",
+                    submission_client: "cider-api",
+                    submission_client_version: env!("CARGO_PKG_VERSION"),
+                },
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+
+    fn scrobble() -> Scrobble {
+        Scrobble {
+            artist_name: "Artist".into(),
+            name: "Song".into(),
+            album_name: String::new(),
+            isrc: Some("USRC17607839".into()),
+            started_at: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            duration: Duration::from_millis(215_000),
+        }
+    }
+
+    #[test]
+    fn listen_payload() {
+        let scrobble = scrobble();
+        let json = serde_json::to_value(Listen::new(&scrobble, true)).unwrap();
+        assert_eq!(json["listened_at"], 1_700_000_000);
+        let metadata = &json["track_metadata"];
+        assert_eq!(metadata["artist_name"], "Artist");
+        assert_eq!(metadata["track_name"], "Song");
+        assert!(metadata.get("release_name").is_none());
+        assert_eq!(metadata["additional_info"]["isrc"], "USRC17607839");
+        assert_eq!(metadata["additional_info"]["duration_ms"], 215_000);
+    }
+
+    #[test]
+    fn playing_now_has_no_timestamp() {
+        let scrobble = scrobble();
+        let json = serde_json::to_value(Listen::new(&scrobble, false)).unwrap();
+        assert!(json.get("listened_at").is_none());
+    }
+
+    #[test]
+    fn error_messages() {
+        assert_eq!(
+            error_message(r#"{"code": 401, "error": "Invalid authorization token."}"#),
+            "Invalid authorization token."
+        );
+        assert_eq!(error_message("Bad Gateway\n"), "Bad Gateway");
+    }
+
+    #[test]
+    fn base_url_drops_trailing_slash() {
+        let sink = ListenBrainz::new("t").base_url("http://localhost:8100/");
+        assert_eq!(sink.base_url, "http://localhost:8100");
+    }
+
+    #[test]
+    fn debug_hides_token() {
+        let debug = format!("{:?}", ListenBrainz::new("user-token"));
+        assert!(!debug.contains("user-token"));
+    }
+}
@@ -0,0 +1,511 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Prometheus metrics: request counts, latencies and error classes for
+//! every call, player gauges, and a `/metrics` endpoint.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::future::IntoFuture;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::http::header::CONTENT_TYPE;
+use axum::routing::get;
+use axum::Router;
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, warn};
+
+use crate::client::CiderClient;
+use crate::error::{CiderError, Endpoint};
+use crate::watcher::{stopped, PlaybackSnapshot, PlaybackWatcher, WatchEvent};
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Default time between player polls.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Content type of the Prometheus text format.
+const TEXT_FORMAT: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Request and player metrics, rendered in the Prometheus text format.
+///
+/// Attach one to a client with [`CiderClient::with_metrics`] or
+/// [`CiderClientBuilder::metrics`](crate::CiderClientBuilder::metrics) and
+/// every HTTP request it sends (retries included) is recorded:
+///
+/// - `cider_requests_total{method, path, outcome}` counts requests by
+///   result: `ok`, or the error class (`not_reachable`, `timeout`,
+///   `transport`, `unauthorized`, `nothing_playing`, `api`).
+/// - `cider_request_duration_seconds{method, path}` is a latency histogram.
+///
+/// [`MetricsServer`] adds player gauges (`cider_up`, `cider_playing`,
+/// `cider_volume`, `cider_position_seconds`,
+/// `cider_track_duration_seconds`) and serves everything on `/metrics`.
+///
+/// Clones share the same counters.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), cider_api::CiderError> {
+/// use cider_api::{CiderClient, Metrics};
+///
+/// let metrics = Metrics::new();
+/// let client = CiderClient::new().with_metrics(metrics.clone());
+/// client.now_playing().await?;
+/// print!("{}", metrics.render());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    inner: Arc<Mutex<Registry>>,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    /// Keyed by (method, path, outcome).
+    requests: BTreeMap<(String, String, &'static str), u64>,
+    /// Keyed by (method, path).
+    latencies: BTreeMap<(String, String), Histogram>,
+    /// `None` until a [`MetricsServer`] has polled the player.
+    player: Option<Player>,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Per-bucket (not cumulative) counts, one per [`BUCKETS`] entry.
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, secs: f64) {
+        if let Some(i) = BUCKETS.iter().position(|&bound| secs <= bound) {
+            self.buckets[i] += 1;
+        }
+        self.sum += secs;
+        self.count += 1;
+    }
+}
+
+/// Player gauges from the last poll.
+#[derive(Debug)]
+struct Player {
+    /// `None` while Cider is unreachable.
+    snapshot: Option<PlayerGauges>,
+}
+
+#[derive(Debug)]
+struct PlayerGauges {
+    playing: bool,
+    volume: f32,
+    position: f64,
+    duration: f64,
+}
+
+impl Metrics {
+    /// Empty metrics.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn registry(&self) -> std::sync::MutexGuard<'_, Registry> {
+        self.inner.lock().expect("metrics lock poisoned")
+    }
+
+    /// Record one HTTP request to `endpoint`.
+    pub(crate) fn record_request(
+        &self,
+        endpoint: &Endpoint,
+        elapsed: Duration,
+        error: Option<&CiderError>,
+    ) {
+        let outcome = error.map_or("ok", error_class);
+        let method = endpoint.method.to_string();
+        let mut registry = self.registry();
+        *registry
+            .requests
+            .entry((method.clone(), endpoint.path.clone(), outcome))
+            .or_default() += 1;
+        registry
+            .latencies
+            .entry((method, endpoint.path.clone()))
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Record a player poll; `None` means Cider was unreachable.
+    fn record_player(&self, snapshot: Option<&PlaybackSnapshot>) {
+        let snapshot = snapshot.map(|s| PlayerGauges {
+            playing: s.is_playing,
+            volume: s.volume,
+            position: s.track.as_ref().map_or(0.0, |t| t.position().as_secs_f64()),
+            duration: s.track.as_ref().map_or(0.0, |t| t.duration().as_secs_f64()),
+        });
+        self.registry().player = Some(Player { snapshot });
+    }
+
+    /// Render every metric in the Prometheus text exposition format.
+    #[must_use]
+    pub fn render(&self) -> String {
+        let registry = self.registry();
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "cider_requests_total",
+            "counter",
+            "HTTP requests sent to Cider, by endpoint and outcome.",
+        );
+        for ((method, path, outcome), count) in &registry.requests {
+            let _ = writeln!(
+                out,
+                "cider_requests_total{{method=\"{}\",path=\"{}\",outcome=\"{outcome}\"}} {count}",
+                escape(method),
+                escape(path)
+            );
+        }
+
+        header(
+            &mut out,
+            "cider_request_duration_seconds",
+            "histogram",
+            "Latency of HTTP requests sent to Cider.",
+        );
+        for ((method, path), histogram) in &registry.latencies {
+            let labels = format!("method=\"{}\",path=\"{}\"", escape(method), escape(path));
+            let mut cumulative = 0;
+            for (bound, count) in BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "cider_request_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {cumulative}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "cider_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+                histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "cider_request_duration_seconds_sum{{{labels}}} {}",
+                histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "cider_request_duration_seconds_count{{{labels}}} {}",
+                histogram.count
+            );
+        }
+
+        let Some(player) = &registry.player else {
+            return out;
+        };
+        gauge(
+            &mut out,
+            "cider_up",
+            "Whether the last player poll reached Cider.",
+            u8::from(player.snapshot.is_some()),
+        );
+        if let Some(gauges) = &player.snapshot {
+            gauge(
+                &mut out,
+                "cider_playing",
+                "Whether a track is playing.",
+                u8::from(gauges.playing),
+            );
+            gauge(&mut out, "cider_volume", "Volume, 0 to 1.", gauges.volume);
+            gauge(
+                &mut out,
+                "cider_position_seconds",
+                "Playback position in the current track.",
+                gauges.position,
+            );
+            gauge(
+                &mut out,
+                "cider_track_duration_seconds",
+                "Duration of the current track, 0 if none.",
+                gauges.duration,
+            );
+        }
+        out
+    }
+}
+
+/// The `outcome` label for a failed request.
+fn error_class(error: &CiderError) -> &'static str {
+    match error {
+        CiderError::NotReachable { .. } => "not_reachable",
+        CiderError::Timeout { .. } => "timeout",
+        CiderError::Http { .. } => "transport",
+        CiderError::Unauthorized { .. } => "unauthorized",
+        CiderError::NothingPlaying { .. } => "nothing_playing",
+        CiderError::Api { .. } => "api",
+        _ => "other",
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: impl std::fmt::Display) {
+    header(out, name, "gauge", help);
+    let _ = writeln!(out, "{name} {value}");
+}
+
+/// Escape a label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+impl CiderClient {
+    /// Record every request in `metrics`.
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+}
+
+/// Serves [`Metrics`] on `/metrics` and keeps the player gauges current.
+///
+/// Uses the client's attached [`Metrics`], or attaches new ones, so the
+/// server's own polls are recorded too. Give the server a clone of the
+/// client your application uses to export its requests as well.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), cider_api::CiderError> {
+/// use cider_api::{CiderClient, Metrics, MetricsServer};
+///
+/// let client = CiderClient::new().with_metrics(Metrics::new());
+/// let server = MetricsServer::new(client.clone()).start("0.0.0.0:9464").await?;
+/// // ... use `client` ...
+/// server.wait().await;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct MetricsServer {
+    client: CiderClient,
+    metrics: Metrics,
+    poll_interval: Duration,
+}
+
+impl MetricsServer {
+    /// Export `client`'s metrics.
+    pub fn new(client: CiderClient) -> Self {
+        let metrics = client.metrics.clone().unwrap_or_default();
+        Self {
+            client: client.with_metrics(metrics.clone()),
+            metrics,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// The metrics being served.
+    #[must_use]
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Time between player polls. Defaults to 15s, a typical scrape
+    /// interval.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "poll interval must be non-zero");
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Listen on `addr` and start serving `/metrics`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::Io`] if the address cannot be bound.
+    pub async fn start(self, addr: impl ToSocketAddrs) -> Result<MetricsHandle, CiderError> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|source| CiderError::Io {
+                context: "cannot bind the metrics server".to_owned(),
+                source,
+            })?;
+        let local_addr = listener.local_addr().map_err(|source| CiderError::Io {
+            context: "cannot read the metrics server address".to_owned(),
+            source,
+        })?;
+        debug!(%local_addr, "serving metrics");
+
+        let metrics = self.metrics.clone();
+        let app = Router::new().route(
+            "/metrics",
+            get(move || {
+                let body = metrics.render();
+                async move { ([(CONTENT_TYPE, TEXT_FORMAT)], body) }
+            }),
+        );
+        let (cancel, cancelled) = watch::channel(false);
+        let mut shutdown = cancelled.clone();
+        let server = axum::serve(listener, app)
+            .with_graceful_shutdown(async move { stopped(&mut shutdown).await });
+        let watcher = self.client.watch().interval(self.poll_interval);
+        let task = tokio::spawn(async move {
+            let (result, ()) = tokio::join!(
+                server.into_future(),
+                poll(watcher, self.metrics, self.poll_interval, cancelled)
+            );
+            if let Err(e) = result {
+                warn!(error = %e, "metrics server failed");
+            }
+            debug!("metrics server stopped");
+        });
+        Ok(MetricsHandle {
+            local_addr,
+            cancel,
+            task,
+        })
+    }
+}
+
+/// A running [`MetricsServer`]. Dropping the handle stops it.
+#[derive(Debug)]
+#[must_use = "dropping a MetricsHandle stops the metrics server"]
+pub struct MetricsHandle {
+    local_addr: SocketAddr,
+    cancel: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl MetricsHandle {
+    /// The address the server listens on.
+    #[must_use]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop serving.
+    pub fn stop(&self) {
+        self.cancel.send_replace(true);
+    }
+
+    /// Returns `true` once the server has stopped.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Wait for the server to stop.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the server task panicked.
+    pub async fn wait(self) {
+        let Self { cancel, task, .. } = self;
+        task.await.expect("metrics server task panicked");
+        drop(cancel);
+    }
+}
+
+/// Update the player gauges until cancelled.
+async fn poll(
+    mut watcher: PlaybackWatcher,
+    metrics: Metrics,
+    interval: Duration,
+    mut cancelled: watch::Receiver<bool>,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut away = false;
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            () = stopped(&mut cancelled) => return,
+        }
+        for event in watcher.poll().await {
+            match event {
+                WatchEvent::CiderWentAway(_) => away = true,
+                WatchEvent::CiderCameBack => away = false,
+                _ => {}
+            }
+        }
+        metrics.record_player(watcher.snapshot().filter(|_| !away));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(path: &str) -> Endpoint {
+        Endpoint::playback(reqwest::Method::GET, path)
+    }
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let metrics = Metrics::new();
+        for ms in [3, 40, 40, 20_000] {
+            metrics.record_request(&endpoint("/volume"), Duration::from_millis(ms), None);
+        }
+        let text = metrics.render();
+        let labels = r#"method="GET",path="/api/v1/playback/volume""#;
+        for line in [
+            format!(r#"cider_requests_total{{{labels},outcome="ok"}} 4"#),
+            format!(r#"cider_request_duration_seconds_bucket{{{labels},le="0.005"}} 1"#),
+            format!(r#"cider_request_duration_seconds_bucket{{{labels},le="0.05"}} 3"#),
+            format!(r#"cider_request_duration_seconds_bucket{{{labels},le="10"}} 3"#),
+            format!(r#"cider_request_duration_seconds_bucket{{{labels},le="+Inf"}} 4"#),
+            format!("cider_request_duration_seconds_count{{{labels}}} 4"),
+        ] {
+            assert!(text.contains(&line), "missing {line} in\n{text}");
+        }
+    }
+
+    #[test]
+    fn errors_are_classified() {
+        let metrics = Metrics::new();
+        let error = CiderError::NothingPlaying {
+            endpoint: endpoint("/seek"),
+        };
+        metrics.record_request(&endpoint("/seek"), Duration::ZERO, Some(&error));
+        assert!(metrics
+            .render()
+            .contains(r#"path="/api/v1/playback/seek",outcome="nothing_playing"} 1"#));
+    }
+
+    #[test]
+    fn player_gauges_only_after_a_poll() {
+        let metrics = Metrics::new();
+        assert!(!metrics.render().contains("cider_up"));
+        metrics.record_player(None);
+        let text = metrics.render();
+        assert!(text.contains("cider_up 0\n"));
+        assert!(!text.contains("cider_volume"));
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(escape("a\"b\\c\nd"), r#"a\"b\\c\nd"#);
+    }
+}
@@ -0,0 +1,1066 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! MPD protocol server: lets MPD clients (`mpc`, `ncmpcpp`, phone apps)
+//! control Cider.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt::{self, Write as _};
+use std::hash::{Hash, Hasher};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::sync::watch;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, warn};
+
+use crate::client::CiderClient;
+use crate::error::CiderError;
+use crate::types::{NowPlaying, QueueItem, RepeatMode, ShuffleMode};
+use crate::watcher::stopped;
+
+/// Protocol version announced in the greeting.
+const PROTOCOL_VERSION: &str = "0.23.5";
+
+/// Default time between player polls while a client is idle.
+const DEFAULT_IDLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Commands understood, listed by `commands`.
+const COMMANDS: &[&str] = &[
+    "clear",
+    "close",
+    "command_list_begin",
+    "command_list_end",
+    "command_list_ok_begin",
+    "commands",
+    "consume",
+    "currentsong",
+    "decoders",
+    "delete",
+    "deleteid",
+    "getvol",
+    "idle",
+    "move",
+    "moveid",
+    "next",
+    "noidle",
+    "notcommands",
+    "outputs",
+    "pause",
+    "ping",
+    "play",
+    "playid",
+    "playlistid",
+    "playlistinfo",
+    "plchanges",
+    "plchangesposid",
+    "previous",
+    "random",
+    "repeat",
+    "replay_gain_status",
+    "seek",
+    "seekcur",
+    "seekid",
+    "setvol",
+    "single",
+    "stats",
+    "status",
+    "stop",
+    "tagtypes",
+    "urlhandlers",
+    "volume",
+];
+
+/// Subsystems reported by `idle`.
+const SUBSYSTEMS: &[&str] = &["player", "mixer", "options", "playlist"];
+
+/// MPD error codes (`ACK [code@index]`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AckCode {
+    NotList = 1,
+    Arg = 2,
+    Unknown = 5,
+    NoExist = 50,
+    System = 52,
+    PlayerSync = 55,
+}
+
+/// A failed command, sent as `ACK [code@index] {command} message`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Ack {
+    code: AckCode,
+    message: String,
+}
+
+impl Ack {
+    fn new(code: AckCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn arg(message: impl Into<String>) -> Self {
+        Self::new(AckCode::Arg, message)
+    }
+
+    /// Format the `ACK` line for `command`, the `index`th of a command list.
+    fn line(&self, index: usize, command: &str) -> String {
+        format!(
+            "ACK [{}@{index}] {{{command}}} {}\n",
+            self.code as u8, self.message
+        )
+    }
+}
+
+impl From<CiderError> for Ack {
+    fn from(e: CiderError) -> Self {
+        let code = if e.is_nothing_playing() {
+            AckCode::PlayerSync
+        } else {
+            AckCode::System
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+/// Serves the MPD protocol over TCP, translating commands to a
+/// [`CiderClient`].
+///
+/// The MPD queue is Cider's queue, history included, so positions are
+/// Cider's 1-based queue indices minus one. Song IDs are derived from each
+/// item's catalog ID, so an ID keeps naming the same song while the queue
+/// changes around it. Supported commands:
+///
+/// | MPD | Cider |
+/// |---|---|
+/// | `status`, `currentsong` | [`now_playing`](CiderClient::now_playing), [`is_playing`](CiderClient::is_playing), [`get_volume`](CiderClient::get_volume), repeat and shuffle modes |
+/// | `playlistinfo`, `playlistid`, `plchanges` | [`get_queue`](CiderClient::get_queue) |
+/// | `move`, `moveid`, `delete`, `deleteid`, `clear` | [`queue_move_to_position`](CiderClient::queue_move_to_position), [`queue_remove_by_index`](CiderClient::queue_remove_by_index), [`clear_queue`](CiderClient::clear_queue) |
+/// | `play`, `pause`, `stop`, `next`, `previous` | the matching playback command |
+/// | `seekcur`, `seek`, `seekid` | [`seek_to`](CiderClient::seek_to), [`seek_by`](CiderClient::seek_by) (current song only) |
+/// | `setvol`, `volume`, `getvol` | [`set_volume`](CiderClient::set_volume) |
+/// | `repeat`, `single`, `random` | [`set_repeat_mode`](CiderClient::set_repeat_mode), [`set_shuffle_mode`](CiderClient::set_shuffle_mode) |
+///
+/// `idle` is supported by polling. Anything else is answered with
+/// `ACK [5@0] {command} unknown command`, and commands Cider cannot carry
+/// out (such as jumping to another queue position) with `ACK [2@0]`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), cider_api::CiderError> {
+/// use cider_api::{CiderClient, MpdServer};
+///
+/// let server = MpdServer::new(CiderClient::new()).start("127.0.0.1:6600").await?;
+/// server.wait().await;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct MpdServer {
+    client: CiderClient,
+    idle_interval: Duration,
+}
+
+impl MpdServer {
+    /// Serve `client`.
+    pub fn new(client: CiderClient) -> Self {
+        Self {
+            client,
+            idle_interval: DEFAULT_IDLE_INTERVAL,
+        }
+    }
+
+    /// Time between player polls while a client waits in `idle`. Defaults
+    /// to 1s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn idle_interval(mut self, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "idle interval must be non-zero");
+        self.idle_interval = interval;
+        self
+    }
+
+    /// Listen on `addr` (MPD's usual port is 6600) and start serving.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::Io`] if the address cannot be bound.
+    pub async fn start(self, addr: impl ToSocketAddrs) -> Result<MpdHandle, CiderError> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|source| CiderError::Io {
+                context: "cannot bind the MPD server".to_owned(),
+                source,
+            })?;
+        let local_addr = listener.local_addr().map_err(|source| CiderError::Io {
+            context: "cannot read the MPD server address".to_owned(),
+            source,
+        })?;
+        debug!(%local_addr, "serving MPD");
+        let (cancel, cancelled) = watch::channel(false);
+        let task = tokio::spawn(accept(listener, self, cancelled));
+        Ok(MpdHandle {
+            local_addr,
+            cancel,
+            task,
+        })
+    }
+}
+
+/// A running [`MpdServer`]. Dropping the handle stops it and closes every
+/// connection.
+#[derive(Debug)]
+#[must_use = "dropping an MpdHandle stops the MPD server"]
+pub struct MpdHandle {
+    local_addr: SocketAddr,
+    cancel: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl MpdHandle {
+    /// The address the server listens on.
+    #[must_use]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop accepting connections and close the open ones.
+    pub fn stop(&self) {
+        self.cancel.send_replace(true);
+    }
+
+    /// Returns `true` once the server has stopped.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Wait for the server to stop.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the server task panicked.
+    pub async fn wait(self) {
+        let Self { cancel, task, .. } = self;
+        task.await.expect("MPD server task panicked");
+        drop(cancel);
+    }
+}
+
+/// Accept connections until cancelled.
+async fn accept(listener: TcpListener, server: MpdServer, mut stop: watch::Receiver<bool>) {
+    let mut connections = JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, peer)) => {
+                    debug!(%peer, "MPD client connected");
+                    let connection = Connection {
+                        client: server.client.clone(),
+                        idle_interval: server.idle_interval,
+                    };
+                    connections.spawn(connection.serve(stream, stop.clone()));
+                }
+                Err(e) => warn!(error = %e, "failed to accept an MPD connection"),
+            },
+            // Reap finished connections.
+            Some(_) = connections.join_next(), if !connections.is_empty() => {}
+            () = stopped(&mut stop) => break,
+        }
+    }
+    connections.shutdown().await;
+    debug!("MPD server stopped");
+}
+
+/// What to do after a command.
+#[derive(Debug, PartialEq, Eq)]
+enum Flow {
+    Continue,
+    Close,
+    /// Wait for changes to these subsystems.
+    Idle(Vec<&'static str>),
+}
+
+/// One client connection.
+struct Connection {
+    client: CiderClient,
+    idle_interval: Duration,
+}
+
+impl Connection {
+    async fn serve(self, stream: TcpStream, mut stop: watch::Receiver<bool>) {
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+        if write
+            .write_all(format!("OK MPD {PROTOCOL_VERSION}\n").as_bytes())
+            .await
+            .is_err()
+        {
+            return;
+        }
+
+        // Commands collected between `command_list_begin` and `_end`, and
+        // whether each should be followed by `list_OK`.
+        let mut list: Option<(Vec<String>, bool)> = None;
+        loop {
+            let line = tokio::select! {
+                line = lines.next_line() => line,
+                () = stopped(&mut stop) => return,
+            };
+            let Ok(Some(line)) = line else {
+                return;
+            };
+
+            let mut out = String::new();
+            let flow = match (&mut list, line.trim()) {
+                (None, "command_list_begin") => {
+                    list = Some((Vec::new(), false));
+                    continue;
+                }
+                (None, "command_list_ok_begin") => {
+                    list = Some((Vec::new(), true));
+                    continue;
+                }
+                (Some(_), "command_list_end") => {
+                    let (commands, list_ok) = list.take().expect("in a command list");
+                    self.run_list(&commands, list_ok, &mut out).await
+                }
+                (Some((commands, _)), _) => {
+                    commands.push(line);
+                    continue;
+                }
+                (None, _) => self.run_list(&[line], false, &mut out).await,
+            };
+
+            let flow = match flow {
+                Flow::Idle(subsystems) => {
+                    let result = tokio::select! {
+                        result = self.idle(&subsystems, &mut lines, &mut out) => result,
+                        () = stopped(&mut stop) => return,
+                    };
+                    match result {
+                        Ok(flow) => flow,
+                        Err(()) => return,
+                    }
+                }
+                flow => flow,
+            };
+            if write.write_all(out.as_bytes()).await.is_err() || flow == Flow::Close {
+                return;
+            }
+        }
+    }
+
+    /// Run `commands`, stopping at the first failure, and finish the
+    /// response with `OK` or the `ACK`.
+    async fn run_list(&self, commands: &[String], list_ok: bool, out: &mut String) -> Flow {
+        for (index, line) in commands.iter().enumerate() {
+            let (command, args) = match tokenize(line) {
+                Ok(tokens) if !tokens.is_empty() => {
+                    let mut tokens = tokens.into_iter();
+                    let command = tokens.next().expect("not empty");
+                    (command, tokens.collect::<Vec<_>>())
+                }
+                Ok(_) => {
+                    out.push_str(&Ack::new(AckCode::Unknown, "No command given").line(index, ""));
+                    return Flow::Continue;
+                }
+                Err(ack) => {
+                    out.push_str(&ack.line(index, ""));
+                    return Flow::Continue;
+                }
+            };
+            match self.execute(&command, &args, out).await {
+                Ok(Flow::Continue) if list_ok => out.push_str("list_OK\n"),
+                Ok(Flow::Continue) => {}
+                Ok(Flow::Idle(_)) if commands.len() > 1 => {
+                    let ack = Ack::new(AckCode::NotList, "idle is not allowed in a command list");
+                    out.push_str(&ack.line(index, &command));
+                    return Flow::Continue;
+                }
+                Ok(flow) => return flow,
+                Err(ack) => {
+                    debug!(command, ?args, ?ack, "MPD command failed");
+                    out.push_str(&ack.line(index, &command));
+                    return Flow::Continue;
+                }
+            }
+        }
+        out.push_str("OK\n");
+        Flow::Continue
+    }
+
+    /// Wait in `idle` until a subsystem changes or the client sends
+    /// `noidle`. `Err` means the connection is gone.
+    async fn idle<R: tokio::io::AsyncBufRead + Unpin>(
+        &self,
+        subsystems: &[&'static str],
+        lines: &mut tokio::io::Lines<R>,
+        out: &mut String,
+    ) -> Result<Flow, ()> {
+        let mut ticker = tokio::time::interval(self.idle_interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut baseline: Option<Observed> = None;
+        loop {
+            tokio::select! {
+                line = lines.next_line() => {
+                    return match line {
+                        Ok(Some(line)) if line.trim() == "noidle" => {
+                            out.push_str("OK\n");
+                            Ok(Flow::Continue)
+                        }
+                        // Anything else while idle is a protocol error.
+                        _ => Err(()),
+                    };
+                }
+                _ = ticker.tick() => {}
+            }
+            let Ok(state) = State::fetch(&self.client).await else {
+                continue;
+            };
+            let observed = Observed::from(&state);
+            let Some(previous) = &baseline else {
+                baseline = Some(observed);
+                continue;
+            };
+            let changed: Vec<&str> = previous
+                .changes(&observed)
+                .into_iter()
+                .filter(|subsystem| subsystems.contains(subsystem))
+                .collect();
+            if !changed.is_empty() {
+                for subsystem in changed {
+                    let _ = writeln!(out, "changed: {subsystem}");
+                }
+                out.push_str("OK\n");
+                return Ok(Flow::Continue);
+            }
+            baseline = Some(observed);
+        }
+    }
+
+    /// Run one command, appending its response lines (without `OK`).
+    #[allow(clippy::too_many_lines)]
+    async fn execute(&self, command: &str, args: &[String], out: &mut String) -> Result<Flow, Ack> {
+        let client = &self.client;
+        match (command, args) {
+            ("close", []) => return Ok(Flow::Close),
+            ("commands", []) => {
+                for command in COMMANDS {
+                    let _ = writeln!(out, "command: {command}");
+                }
+            }
+            ("ping" | "noidle" | "notcommands" | "decoders" | "urlhandlers", []) => {}
+            ("tagtypes", []) => {
+                for tag in ["Artist", "Album", "Title"] {
+                    let _ = writeln!(out, "tagtype: {tag}");
+                }
+            }
+            ("outputs", []) => out.push_str("outputid: 0\noutputname: Cider\nplugin: cider\noutputenabled: 1\n"),
+            ("replay_gain_status", []) => out.push_str("replay_gain_mode: off\n"),
+            ("stats", []) => out.push_str(
+                "artists: 0\nalbums: 0\nsongs: 0\nuptime: 0\nplaytime: 0\ndb_playtime: 0\ndb_update: 0\n",
+            ),
+            ("idle", subsystems) => {
+                // Subsystems Cider never changes, such as `database`, are
+                // accepted and never reported.
+                let mut wanted: Vec<&'static str> = SUBSYSTEMS
+                    .iter()
+                    .copied()
+                    .filter(|subsystem| subsystems.iter().any(|name| name == subsystem))
+                    .collect();
+                if subsystems.is_empty() {
+                    wanted.extend_from_slice(SUBSYSTEMS);
+                }
+                return Ok(Flow::Idle(wanted));
+            }
+
+            ("status", []) => State::fetch(client).await?.write_status(out),
+            ("currentsong", []) => {
+                let state = State::fetch(client).await?;
+                if let Some(track) = &state.track {
+                    let song = state.current.map(|pos| (pos, state.ids[pos]));
+                    write_track(out, track, song);
+                }
+            }
+            ("playlistid", [id]) => {
+                let queue = client.get_queue().await?;
+                let ids = song_ids(&queue);
+                let pos = position_of(&ids, id)?;
+                write_queue_item(out, &queue[pos], pos, ids[pos]);
+            }
+            ("playlistinfo" | "playlistid", range) => {
+                let queue = client.get_queue().await?;
+                let (start, end) = match range {
+                    [] => (0, queue.len()),
+                    [arg] => {
+                        let (start, end) = parse_range(arg)?;
+                        if start >= queue.len() {
+                            return Err(Ack::arg("Bad song index"));
+                        }
+                        (start, end.unwrap_or(start + 1).min(queue.len()))
+                    }
+                    _ => return Err(wrong_args(command)),
+                };
+                let ids = song_ids(&queue);
+                for (pos, item) in queue.iter().enumerate().take(end).skip(start) {
+                    write_queue_item(out, item, pos, ids[pos]);
+                }
+            }
+            ("plchanges" | "plchangesposid", [version, ..]) => {
+                let version: u32 = parse(version)?;
+                let queue = client.get_queue().await?;
+                if version != playlist_version(&queue) {
+                    let ids = song_ids(&queue);
+                    for (pos, (item, id)) in queue.iter().zip(ids).enumerate() {
+                        if command == "plchanges" {
+                            write_queue_item(out, item, pos, id);
+                        } else {
+                            let _ = write!(out, "cpos: {pos}\nId: {id}\n");
+                        }
+                    }
+                }
+            }
+
+            ("play" | "playid", []) => client.play().await?,
+            ("play" | "playid", [song]) => {
+                let state = State::fetch(client).await?;
+                let pos = song_position(command, song, &state.ids)?;
+                if state.current != Some(pos) {
+                    return Err(Ack::arg("Cider cannot jump to another song in the queue"));
+                }
+                client.play().await?;
+            }
+            ("pause", []) => client.play_pause().await?,
+            ("pause", [paused]) => {
+                if parse_bool(paused)? {
+                    client.pause().await?;
+                } else {
+                    client.play().await?;
+                }
+            }
+            ("stop", []) => client.stop().await?,
+            ("next", []) => client.next().await?,
+            ("previous", []) => client.previous().await?,
+
+            ("seekcur", [time]) => {
+                if time.starts_with(['+', '-']) {
+                    let offset: f64 = parse(time)?;
+                    #[allow(clippy::cast_possible_truncation)]
+                    client.seek_by((offset * 1000.0).round() as i64).await?;
+                } else {
+                    client.seek_to(parse_duration(time)?).await?;
+                }
+            }
+            ("seek" | "seekid", [song, time]) => {
+                let state = State::fetch(client).await?;
+                let pos = song_position(command, song, &state.ids)?;
+                if state.current != Some(pos) {
+                    return Err(Ack::arg("Cider can only seek in the current song"));
+                }
+                client.seek_to(parse_duration(time)?).await?;
+            }
+
+            ("setvol", [volume]) => {
+                let volume: u8 = parse(volume).map_err(|_| Ack::arg("Invalid volume value"))?;
+                if volume > 100 {
+                    return Err(Ack::arg("Invalid volume value"));
+                }
+                client.set_volume(f32::from(volume) / 100.0).await?;
+            }
+            ("volume", [change]) => {
+                let change: i16 = parse(change)?;
+                let current = volume_percent(client.get_volume().await?);
+                let volume = i16::from(current).saturating_add(change).clamp(0, 100);
+                client.set_volume(f32::from(volume) / 100.0).await?;
+            }
+            ("getvol", []) => {
+                let volume = volume_percent(client.get_volume().await?);
+                let _ = writeln!(out, "volume: {volume}");
+            }
+            ("repeat", [on]) => {
+                let on = parse_bool(on)?;
+                let current = client.get_repeat_mode().await?;
+                let mode = match (on, current) {
+                    (false, _) => RepeatMode::Off,
+                    (true, RepeatMode::One) => RepeatMode::One,
+                    (true, _) => RepeatMode::All,
+                };
+                client.set_repeat_mode(mode).await?;
+            }
+            ("single", [on]) => {
+                // Cider has no "stop after this song"; single maps to
+                // repeating the current song.
+                let mode = if parse_bool(on)? {
+                    RepeatMode::One
+                } else if client.get_repeat_mode().await? == RepeatMode::One {
+                    RepeatMode::All
+                } else {
+                    return Ok(Flow::Continue);
+                };
+                client.set_repeat_mode(mode).await?;
+            }
+            ("random", [on]) => client.set_shuffle_mode(parse_bool(on)?).await?,
+            ("consume", [on]) => {
+                if parse_bool(on)? {
+                    return Err(Ack::arg("Cider has no consume mode"));
+                }
+            }
+
+            ("move" | "moveid", [from, to]) => {
+                let queue = client.get_queue().await?;
+                let from = song_position(command, from, &song_ids(&queue))?;
+                let to: usize = parse(to)?;
+                if from >= queue.len() || to >= queue.len() {
+                    return Err(Ack::arg("Bad song index"));
+                }
+                client
+                    .queue_move_to_position(cider_index(from)?, cider_index(to)?)
+                    .await?;
+            }
+            ("deleteid", [id]) => {
+                let queue = client.get_queue().await?;
+                let pos = position_of(&song_ids(&queue), id)?;
+                client.queue_remove_by_index(cider_index(pos)?).await?;
+            }
+            ("delete", [range]) => {
+                let (start, end) = parse_range(range)?;
+                let len = client.get_queue().await?.len();
+                if start >= len {
+                    return Err(Ack::arg("Bad song index"));
+                }
+                let end = end.unwrap_or(start + 1).min(len);
+                // From the end, so earlier indices stay valid.
+                for pos in (start..end).rev() {
+                    client.queue_remove_by_index(cider_index(pos)?).await?;
+                }
+            }
+            ("clear", []) => client.clear_queue().await?,
+
+            _ if COMMANDS.contains(&command) => return Err(wrong_args(command)),
+            _ => {
+                return Err(Ack::new(
+                    AckCode::Unknown,
+                    format!("unknown command \"{command}\""),
+                ))
+            }
+        }
+        Ok(Flow::Continue)
+    }
+}
+
+fn wrong_args(command: &str) -> Ack {
+    Ack::arg(format!("wrong number of arguments for \"{command}\""))
+}
+
+/// The queue position a song argument names: a song ID for the `*id`
+/// commands, a position otherwise.
+fn song_position(command: &str, arg: &str, ids: &[u32]) -> Result<usize, Ack> {
+    if command.ends_with("id") {
+        position_of(ids, arg)
+    } else {
+        parse(arg)
+    }
+}
+
+/// The queue position of the song with ID `arg`.
+fn position_of(ids: &[u32], arg: &str) -> Result<usize, Ack> {
+    let id: u32 = parse(arg)?;
+    ids.iter()
+        .position(|&other| other == id)
+        .ok_or_else(|| Ack::new(AckCode::NoExist, "No such song"))
+}
+
+/// Cider's 1-based queue index for an MPD position.
+fn cider_index(pos: usize) -> Result<u32, Ack> {
+    u32::try_from(pos + 1).map_err(|_| Ack::arg("Bad song index"))
+}
+
+/// Everything `status`, `currentsong` and `idle` need.
+struct State {
+    track: Option<NowPlaying>,
+    playing: bool,
+    volume: f32,
+    repeat: RepeatMode,
+    shuffle: ShuffleMode,
+    queue: Vec<QueueItem>,
+    /// Song ID of each item in `queue`.
+    ids: Vec<u32>,
+    /// Position of the current song in `queue`.
+    current: Option<usize>,
+}
+
+impl State {
+    async fn fetch(client: &CiderClient) -> Result<Self, CiderError> {
+        let (track, playing, volume, repeat, shuffle, queue) = tokio::try_join!(
+            client.now_playing(),
+            client.is_playing(),
+            client.get_volume(),
+            client.get_repeat_mode(),
+            client.get_shuffle_mode(),
+            client.get_queue(),
+        )?;
+        let current = queue.iter().position(QueueItem::is_current);
+        let ids = song_ids(&queue);
+        Ok(Self {
+            track,
+            playing,
+            volume,
+            repeat,
+            shuffle,
+            queue,
+            ids,
+            current,
+        })
+    }
+
+    fn mpd_state(&self) -> &'static str {
+        match (&self.track, self.playing) {
+            (Some(_), true) => "play",
+            (Some(_), false) => "pause",
+            (None, _) => "stop",
+        }
+    }
+
+    fn write_status(&self, out: &mut String) {
+        let _ = writeln!(out, "volume: {}", volume_percent(self.volume));
+        let _ = writeln!(out, "repeat: {}", u8::from(self.repeat != RepeatMode::Off));
+        let _ = writeln!(out, "random: {}", u8::from(self.shuffle.is_on()));
+        let _ = writeln!(out, "single: {}", u8::from(self.repeat == RepeatMode::One));
+        out.push_str("consume: 0\n");
+        let _ = writeln!(out, "playlist: {}", playlist_version(&self.queue));
+        let _ = writeln!(out, "playlistlength: {}", self.queue.len());
+        let _ = writeln!(out, "state: {}", self.mpd_state());
+        if let Some(current) = self.current {
+            let _ = writeln!(out, "song: {current}\nsongid: {}", self.ids[current]);
+            if let Some(next_id) = self.ids.get(current + 1) {
+                let next = current + 1;
+                let _ = writeln!(out, "nextsong: {next}\nnextsongid: {next_id}");
+            }
+        }
+        if let Some(track) = &self.track {
+            let elapsed = track.current_playback_time.max(0.0);
+            let duration = track.duration().as_secs_f64();
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let _ = writeln!(
+                out,
+                "time: {}:{}",
+                elapsed.round() as u64,
+                duration.round() as u64
+            );
+            let _ = writeln!(out, "elapsed: {elapsed:.3}\nduration: {duration:.3}");
+        }
+    }
+}
+
+/// The parts of [`State`] that `idle` reports changes to.
+#[derive(Debug, PartialEq)]
+struct Observed {
+    player: (&'static str, Option<String>),
+    mixer: u8,
+    options: (RepeatMode, ShuffleMode),
+    playlist: u32,
+}
+
+impl From<&State> for Observed {
+    fn from(state: &State) -> Self {
+        Self {
+            player: (
+                state.mpd_state(),
+                state
+                    .track
+                    .as_ref()
+                    .map(|t| format!("{}\0{}", t.name, t.artist_name)),
+            ),
+            mixer: volume_percent(state.volume),
+            options: (state.repeat, state.shuffle),
+            playlist: playlist_version(&state.queue),
+        }
+    }
+}
+
+impl Observed {
+    fn changes(&self, new: &Self) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        if self.player != new.player {
+            changed.push("player");
+        }
+        if self.mixer != new.mixer {
+            changed.push("mixer");
+        }
+        if self.options != new.options {
+            changed.push("options");
+        }
+        if self.playlist != new.playlist {
+            changed.push("playlist");
+        }
+        changed
+    }
+}
+
+/// A version number that changes whenever the queue's contents do.
+fn playlist_version(queue: &[QueueItem]) -> u32 {
+    let mut hasher = DefaultHasher::new();
+    for item in queue {
+        item.id.hash(&mut hasher);
+        item.song_id.hash(&mut hasher);
+    }
+    finish31(&hasher)
+}
+
+/// The song ID of each item in `queue`.
+///
+/// An ID hashes the item's catalog ID and how many times the same song
+/// comes before it, so it does not change when other items are added,
+/// removed or moved.
+fn song_ids(queue: &[QueueItem]) -> Vec<u32> {
+    let mut seen: HashMap<Option<&str>, u32> = HashMap::new();
+    queue
+        .iter()
+        .map(|item| {
+            let key = item.id.as_deref().or(item.song_id.as_deref());
+            let earlier = seen.entry(key).or_default();
+            let mut hasher = DefaultHasher::new();
+            key.hash(&mut hasher);
+            earlier.hash(&mut hasher);
+            *earlier += 1;
+            finish31(&hasher)
+        })
+        .collect()
+}
+
+/// The hash so far, kept positive for clients that parse it as a signed
+/// integer.
+fn finish31(hasher: &DefaultHasher) -> u32 {
+    u32::try_from(hasher.finish() & 0x7fff_ffff).expect("masked to 31 bits")
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn volume_percent(volume: f32) -> u8 {
+    (volume.clamp(0.0, 1.0) * 100.0).round() as u8
+}
+
+/// Song fields shared by `currentsong` and `playlistinfo`.
+struct Song<'a> {
+    file: String,
+    title: &'a str,
+    artist: &'a str,
+    album: &'a str,
+    duration: Duration,
+}
+
+impl fmt::Display for Song<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let duration = self.duration.as_secs_f64();
+        writeln!(f, "file: {}", self.file)?;
+        for (tag, value) in [
+            ("Artist", self.artist),
+            ("Album", self.album),
+            ("Title", self.title),
+        ] {
+            if !value.is_empty() {
+                writeln!(f, "{tag}: {value}")?;
+            }
+        }
+        writeln!(f, "Time: {}", self.duration.as_secs())?;
+        writeln!(f, "duration: {duration:.3}")
+    }
+}
+
+/// Write `track`, with its `(position, ID)` in the queue if it is there.
+fn write_track(out: &mut String, track: &NowPlaying, queued: Option<(usize, u32)>) {
+    let song = Song {
+        file: song_file(track.url.as_deref(), track.song_id()),
+        title: &track.name,
+        artist: &track.artist_name,
+        album: &track.album_name,
+        duration: track.duration(),
+    };
+    let _ = write!(out, "{song}");
+    if let Some((pos, id)) = queued {
+        let _ = writeln!(out, "Pos: {pos}\nId: {id}");
+    }
+}
+
+fn write_queue_item(out: &mut String, item: &QueueItem, pos: usize, id: u32) {
+    let attributes = item.attributes.as_ref();
+    let song = Song {
+        file: song_file(
+            attributes.and_then(|a| a.url.as_deref()),
+            item.song_id.as_deref().or(item.id.as_deref()),
+        ),
+        title: attributes.map_or("", |a| a.name.as_str()),
+        artist: attributes.map_or("", |a| a.artist_name.as_str()),
+        album: attributes.map_or("", |a| a.album_name.as_str()),
+        duration: Duration::from_millis(attributes.map_or(0, |a| a.duration_in_millis)),
+    };
+    let _ = write!(out, "{song}Pos: {pos}\nId: {id}\n");
+}
+
+/// The `file` of a song: its Apple Music link, or `cider:<id>`.
+fn song_file(url: Option<&str>, id: Option<&str>) -> String {
+    match (url, id) {
+        (Some(url), _) if !url.is_empty() => url.to_owned(),
+        (_, Some(id)) => format!("cider:{id}"),
+        _ => "cider:unknown".to_owned(),
+    }
+}
+
+/// Split a command line into words, honouring `"quoted strings"` with
+/// backslash escapes.
+fn tokenize(line: &str) -> Result<Vec<String>, Ack> {
+    let mut tokens = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut token = String::new();
+        if c == '"' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some(escaped) => token.push(escaped),
+                        None => return Err(Ack::arg("Unterminated quoted string")),
+                    },
+                    Some(c) => token.push(c),
+                    None => return Err(Ack::arg("Unterminated quoted string")),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+        }
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn parse<T: std::str::FromStr>(arg: &str) -> Result<T, Ack> {
+    arg.parse()
+        .map_err(|_| Ack::arg(format!("Invalid argument \"{arg}\"")))
+}
+
+fn parse_bool(arg: &str) -> Result<bool, Ack> {
+    match arg {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(Ack::arg(format!("Boolean (0/1) expected: {arg}"))),
+    }
+}
+
+fn parse_duration(arg: &str) -> Result<Duration, Ack> {
+    let secs: f64 = parse(arg)?;
+    Duration::try_from_secs_f64(secs).map_err(|_| Ack::arg(format!("Invalid time \"{arg}\"")))
+}
+
+/// Parse `POS` or `START:END` (end exclusive; `START:` runs to the end).
+fn parse_range(arg: &str) -> Result<(usize, Option<usize>), Ack> {
+    match arg.split_once(':') {
+        Some((start, "")) => Ok((parse(start)?, Some(usize::MAX))),
+        Some((start, end)) => {
+            let (start, end) = (parse(start)?, parse(end)?);
+            if end < start {
+                return Err(Ack::arg("Bad song index"));
+            }
+            Ok((start, Some(end)))
+        }
+        None => Ok((parse(arg)?, None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizes_quoted_arguments() {
+        assert_eq!(
+            tokenize(r#"find artist "Guns N' Roses" album "A \"B\"""#).unwrap(),
+            ["find", "artist", "Guns N' Roses", "album", "A \"B\""]
+        );
+        assert_eq!(tokenize("  status  ").unwrap(), ["status"]);
+        assert!(tokenize(r#"find "open"#).is_err());
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_range("3").unwrap(), (3, None));
+        assert_eq!(parse_range("2:5").unwrap(), (2, Some(5)));
+        assert_eq!(parse_range("2:").unwrap(), (2, Some(usize::MAX)));
+        assert!(parse_range("5:2").is_err());
+        assert!(parse_range("x").is_err());
+    }
+
+    #[test]
+    fn ack_lines() {
+        let ack = Ack::new(AckCode::Unknown, "unknown command \"foo\"");
+        assert_eq!(
+            ack.line(0, "foo"),
+            "ACK [5@0] {foo} unknown command \"foo\"\n"
+        );
+        let ack = Ack::from(CiderError::Io {
+            context: "nope".into(),
+            source: std::io::ErrorKind::Other.into(),
+        });
+        assert_eq!(ack.code, AckCode::System);
+    }
+
+    #[test]
+    fn observed_changes() {
+        let a = Observed {
+            player: ("play", Some("a".into())),
+            mixer: 50,
+            options: (RepeatMode::Off, ShuffleMode::Off),
+            playlist: 1,
+        };
+        let b = Observed {
+            player: ("pause", Some("a".into())),
+            mixer: 50,
+            options: (RepeatMode::All, ShuffleMode::Off),
+            playlist: 1,
+        };
+        assert_eq!(a.changes(&b), ["player", "options"]);
+        assert!(a.changes(&a).is_empty());
+    }
+
+    #[test]
+    fn song_ids_follow_songs() {
+        let item = |id: &str| -> QueueItem {
+            serde_json::from_value(serde_json::json!({ "id": id })).unwrap()
+        };
+        let ids = song_ids(&[item("a"), item("b"), item("a")]);
+        assert_ne!(ids[0], ids[2]);
+        // Removing or moving other songs keeps each song's ID.
+        assert_eq!(song_ids(&[item("a"), item("a")]), [ids[0], ids[2]]);
+        assert_eq!(
+            song_ids(&[item("b"), item("a"), item("a")]),
+            [ids[1], ids[0], ids[2]]
+        );
+    }
+
+    #[test]
+    fn song_files() {
+        assert_eq!(song_file(Some("https://x"), Some("1")), "https://x");
+        assert_eq!(song_file(Some(""), Some("1")), "cider:1");
+        assert_eq!(song_file(None, None), "cider:unknown");
+    }
+}
@@ -0,0 +1,711 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! MPRIS bridge: Cider as an `org.mpris.MediaPlayer2` player on D-Bus, for
+//! `playerctl`, media keys and desktop widgets.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{watch, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, warn};
+use zbus::fdo;
+use zbus::object_server::SignalContext;
+use zbus::zvariant::{ObjectPath, Value};
+
+use crate::client::CiderClient;
+use crate::error::CiderError;
+use crate::types::{NowPlaying, RepeatMode, ShuffleMode};
+use crate::watcher::{stopped, PlaybackSnapshot, PlaybackWatcher, WatchEvent};
+
+/// Object path every MPRIS player is served at.
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+
+/// Track ID for "nothing loaded", reserved by the MPRIS specification.
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+/// Default time between player polls.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Artwork size requested for `mpris:artUrl`.
+const ARTWORK_SIZE: u32 = 600;
+
+/// Player state shared by the D-Bus interfaces and the polling task.
+#[derive(Debug, Default)]
+struct Shared {
+    /// The last poll, or `None` while Cider is unreachable.
+    snapshot: Mutex<Option<PlaybackSnapshot>>,
+    /// Wakes the polling task early, after a command changed the state.
+    refresh: Notify,
+}
+
+impl Shared {
+    fn read<T>(&self, f: impl FnOnce(Option<&PlaybackSnapshot>) -> T) -> T {
+        f(self
+            .snapshot
+            .lock()
+            .expect("snapshot lock poisoned")
+            .as_ref())
+    }
+
+    fn update(&self, f: impl FnOnce(&mut PlaybackSnapshot)) {
+        if let Some(snapshot) = self
+            .snapshot
+            .lock()
+            .expect("snapshot lock poisoned")
+            .as_mut()
+        {
+            f(snapshot);
+        }
+    }
+}
+
+/// Serves Cider as an MPRIS media player on D-Bus.
+///
+/// Exposes `org.mpris.MediaPlayer2` and `org.mpris.MediaPlayer2.Player`
+/// at `/org/mpris/MediaPlayer2` under the bus name
+/// `org.mpris.MediaPlayer2.<name>` (default `cider`), so `playerctl`,
+/// desktop media keys and panel widgets can control Cider even when its
+/// own MPRIS support is missing.
+///
+/// The player is polled with a [`PlaybackWatcher`]; properties are served
+/// from the last poll and changes are announced with `PropertiesChanged`
+/// and `Seeked`. While Cider is unreachable the player reports `Stopped`
+/// with no track.
+///
+/// | MPRIS | Cider |
+/// |---|---|
+/// | `PlayPause`, `Play`, `Pause`, `Stop`, `Next`, `Previous` | the matching playback command |
+/// | `Seek`, `SetPosition` | [`seek_by`](CiderClient::seek_by), [`seek_to`](CiderClient::seek_to) |
+/// | `OpenUri` | [`play_url`](CiderClient::play_url) |
+/// | `Volume` | [`get_volume`](CiderClient::get_volume), [`set_volume`](CiderClient::set_volume) |
+/// | `LoopStatus` | [`get_repeat_mode`](CiderClient::get_repeat_mode), [`set_repeat_mode`](CiderClient::set_repeat_mode) |
+/// | `Shuffle` | [`get_shuffle_mode`](CiderClient::get_shuffle_mode), [`set_shuffle_mode`](CiderClient::set_shuffle_mode) |
+/// | `Metadata` | [`now_playing`](CiderClient::now_playing), including [`artwork_url`](NowPlaying::artwork_url) |
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), cider_api::CiderError> {
+/// use cider_api::{CiderClient, MprisServer};
+///
+/// let server = MprisServer::new(CiderClient::new()).start().await?;
+/// server.wait().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+#[must_use]
+pub struct MprisServer {
+    client: CiderClient,
+    name: String,
+    address: Option<String>,
+    poll_interval: Duration,
+}
+
+impl MprisServer {
+    /// Serve `client` on the session bus.
+    pub fn new(client: CiderClient) -> Self {
+        Self {
+            client,
+            name: "cider".to_owned(),
+            address: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// The last part of the bus name, `org.mpris.MediaPlayer2.<name>`.
+    /// Defaults to `cider`; change it to serve several instances.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Connect to the bus at `address` (e.g. `unix:path=/tmp/bus`) instead
+    /// of the session bus.
+    pub fn address(mut self, address: impl Into<String>) -> Self {
+        self.address = Some(address.into());
+        self
+    }
+
+    /// Time between player polls. Defaults to 1s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "poll interval must be non-zero");
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Connect to D-Bus, claim the bus name and start serving.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::DBus`] if the bus cannot be reached or the
+    /// name is already taken.
+    pub async fn start(self) -> Result<MprisHandle, CiderError> {
+        let bus_name = format!("org.mpris.MediaPlayer2.{}", self.name);
+        let shared = Arc::new(Shared::default());
+        let builder = match &self.address {
+            Some(address) => zbus::connection::Builder::address(address.as_str()),
+            None => zbus::connection::Builder::session(),
+        }
+        .map_err(|e| dbus_error("cannot connect to D-Bus", e))?;
+        let connection = builder
+            .serve_at(OBJECT_PATH, Root)
+            .and_then(|b| {
+                b.serve_at(
+                    OBJECT_PATH,
+                    Player {
+                        client: self.client.clone(),
+                        shared: shared.clone(),
+                    },
+                )
+            })
+            .and_then(|b| b.name(bus_name.clone()))
+            .map_err(|e| dbus_error("invalid MPRIS bus name", e))?
+            .build()
+            .await
+            .map_err(|e| dbus_error(&format!("cannot serve {bus_name}"), e))?;
+        debug!(%bus_name, "serving MPRIS");
+
+        let (cancel, cancelled) = watch::channel(false);
+        let watcher = self.client.watch().interval(self.poll_interval);
+        let task = tokio::spawn(run(
+            connection,
+            watcher,
+            shared,
+            self.poll_interval,
+            cancelled,
+        ));
+        Ok(MprisHandle {
+            bus_name,
+            cancel,
+            task,
+        })
+    }
+}
+
+/// A running [`MprisServer`]. Dropping the handle stops it and releases
+/// the bus name.
+#[derive(Debug)]
+#[must_use = "dropping an MprisHandle stops the MPRIS server"]
+pub struct MprisHandle {
+    bus_name: String,
+    cancel: watch::Sender<bool>,
+    task: JoinHandle<Result<(), CiderError>>,
+}
+
+impl MprisHandle {
+    /// The bus name being served, e.g. `org.mpris.MediaPlayer2.cider`.
+    #[must_use]
+    pub fn bus_name(&self) -> &str {
+        &self.bus_name
+    }
+
+    /// Stop serving and release the bus name.
+    pub fn stop(&self) {
+        self.cancel.send_replace(true);
+    }
+
+    /// Returns `true` once the server has stopped.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Wait for the server to stop.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::DBus`] if the player could not be served.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the server task panicked.
+    pub async fn wait(self) -> Result<(), CiderError> {
+        let Self { cancel, task, .. } = self;
+        let result = task.await.expect("MPRIS task panicked");
+        drop(cancel);
+        result
+    }
+}
+
+fn dbus_error(message: &str, source: zbus::Error) -> CiderError {
+    CiderError::DBus {
+        message: message.to_owned(),
+        source: Some(Box::new(source)),
+    }
+}
+
+/// Poll Cider and announce changes until cancelled.
+async fn run(
+    connection: zbus::Connection,
+    mut watcher: PlaybackWatcher,
+    shared: Arc<Shared>,
+    poll_interval: Duration,
+    mut cancelled: watch::Receiver<bool>,
+) -> Result<(), CiderError> {
+    let player = connection
+        .object_server()
+        .interface::<_, Player>(OBJECT_PATH)
+        .await
+        .map_err(|e| dbus_error("MPRIS interface missing", e))?;
+    let ctxt = player.signal_context().clone();
+    let mut ticker = tokio::time::interval(poll_interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            () = shared.refresh.notified() => ticker.reset(),
+            () = stopped(&mut cancelled) => break,
+        }
+        let events = watcher.poll().await;
+        let was_away = shared.read(|snapshot| snapshot.is_none());
+        let went_away = events
+            .iter()
+            .any(|event| matches!(event, WatchEvent::CiderWentAway(_)));
+        *shared.snapshot.lock().expect("snapshot lock poisoned") =
+            watcher.snapshot().filter(|_| !went_away).cloned();
+        let is_away = shared.read(|snapshot| snapshot.is_none());
+
+        let iface = player.get().await;
+        let result = if was_away == is_away {
+            announce(&iface, &ctxt, &events).await
+        } else {
+            announce_all(&iface, &ctxt).await
+        };
+        if let Err(e) = result {
+            warn!(error = %e, "failed to emit MPRIS signals");
+        }
+    }
+    debug!("MPRIS server stopped");
+    Ok(())
+}
+
+/// Emit the signals for `events`.
+async fn announce(
+    player: &Player,
+    ctxt: &SignalContext<'_>,
+    events: &[WatchEvent],
+) -> zbus::Result<()> {
+    for event in events {
+        match event {
+            WatchEvent::TrackChanged { .. } => {
+                player.metadata_changed(ctxt).await?;
+                player.playback_status_changed(ctxt).await?;
+            }
+            WatchEvent::Paused | WatchEvent::Resumed => {
+                player.playback_status_changed(ctxt).await?;
+            }
+            WatchEvent::Seeked { to, .. } => Player::seeked(ctxt, duration_to_micros(*to)).await?,
+            WatchEvent::VolumeChanged { .. } => player.volume_changed(ctxt).await?,
+            WatchEvent::RepeatChanged { .. } => player.loop_status_changed(ctxt).await?,
+            WatchEvent::ShuffleChanged { .. } => player.shuffle_changed(ctxt).await?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Emit every property that can change, after Cider appeared or went away.
+async fn announce_all(player: &Player, ctxt: &SignalContext<'_>) -> zbus::Result<()> {
+    player.playback_status_changed(ctxt).await?;
+    player.metadata_changed(ctxt).await?;
+    player.volume_changed(ctxt).await?;
+    player.loop_status_changed(ctxt).await?;
+    player.shuffle_changed(ctxt).await
+}
+
+/// `org.mpris.MediaPlayer2`.
+struct Root;
+
+// D-Bus methods and properties take `&self` whether they need it or not.
+#[allow(clippy::unused_self)]
+#[zbus::interface(name = "org.mpris.MediaPlayer2")]
+impl Root {
+    fn raise(&self) {}
+
+    fn quit(&self) {}
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_raise(&self) -> bool {
+        false
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn identity(&self) -> &'static str {
+        "Cider"
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn desktop_entry(&self) -> &'static str {
+        "cider"
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        vec!["https".to_owned()]
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// `org.mpris.MediaPlayer2.Player`.
+struct Player {
+    client: CiderClient,
+    shared: Arc<Shared>,
+}
+
+impl Player {
+    /// Map a command result to D-Bus, polling early on success so the
+    /// change is announced promptly.
+    fn done(&self, result: Result<(), CiderError>) -> fdo::Result<()> {
+        result.map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        self.shared.refresh.notify_one();
+        Ok(())
+    }
+
+    fn track_id(&self) -> String {
+        self.shared
+            .read(|snapshot| snapshot.and_then(|s| s.track.as_ref()).map(track_id))
+            .unwrap_or_else(|| NO_TRACK.to_owned())
+    }
+}
+
+#[allow(clippy::unused_self)]
+#[zbus::interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player {
+    async fn next(&self) -> fdo::Result<()> {
+        self.done(self.client.next().await)
+    }
+
+    async fn previous(&self) -> fdo::Result<()> {
+        self.done(self.client.previous().await)
+    }
+
+    async fn pause(&self) -> fdo::Result<()> {
+        self.done(self.client.pause().await)
+    }
+
+    async fn play_pause(&self) -> fdo::Result<()> {
+        self.done(self.client.play_pause().await)
+    }
+
+    async fn stop(&self) -> fdo::Result<()> {
+        self.done(self.client.stop().await)
+    }
+
+    async fn play(&self) -> fdo::Result<()> {
+        self.done(self.client.play().await)
+    }
+
+    /// Seek by `offset` microseconds.
+    async fn seek(
+        &self,
+        offset: i64,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> fdo::Result<()> {
+        let position = self
+            .client
+            .seek_by(offset / 1000)
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        Self::seeked(&ctxt, duration_to_micros(position)).await?;
+        self.done(Ok(()))
+    }
+
+    /// Seek to `position` microseconds, if `track_id` is still playing.
+    async fn set_position(
+        &self,
+        track_id: ObjectPath<'_>,
+        position: i64,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> fdo::Result<()> {
+        let length = self.shared.read(|snapshot| {
+            snapshot
+                .and_then(|s| s.track.as_ref())
+                .map(NowPlaying::duration)
+        });
+        let Ok(position) = u64::try_from(position) else {
+            return Ok(());
+        };
+        let position = Duration::from_micros(position);
+        // Per the specification, stale track IDs and positions past the
+        // end are ignored.
+        if track_id.as_str() != self.track_id() || length.map_or(true, |l| position > l) {
+            debug!(%track_id, ?position, "ignoring SetPosition");
+            return Ok(());
+        }
+        self.client
+            .seek_to(position)
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        Self::seeked(&ctxt, duration_to_micros(position)).await?;
+        self.done(Ok(()))
+    }
+
+    async fn open_uri(&self, uri: &str) -> fdo::Result<()> {
+        self.done(self.client.play_url(uri).await)
+    }
+
+    #[zbus(signal)]
+    async fn seeked(ctxt: &SignalContext<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> &'static str {
+        self.shared.read(|snapshot| match snapshot {
+            Some(s) if s.is_playing => "Playing",
+            Some(s) if s.track.is_some() => "Paused",
+            _ => "Stopped",
+        })
+    }
+
+    #[zbus(property)]
+    fn loop_status(&self) -> &'static str {
+        self.shared
+            .read(|snapshot| match snapshot.map(|s| s.repeat_mode) {
+                Some(RepeatMode::One) => "Track",
+                Some(RepeatMode::All) => "Playlist",
+                _ => "None",
+            })
+    }
+
+    #[zbus(property)]
+    async fn set_loop_status(&mut self, status: &str) -> fdo::Result<()> {
+        let mode = match status {
+            "None" => RepeatMode::Off,
+            "Track" => RepeatMode::One,
+            "Playlist" => RepeatMode::All,
+            other => {
+                return Err(fdo::Error::InvalidArgs(format!(
+                    "unknown loop status {other:?}"
+                )))
+            }
+        };
+        self.done(self.client.set_repeat_mode(mode).await)?;
+        self.shared.update(|s| s.repeat_mode = mode);
+        Ok(())
+    }
+
+    /// Cider plays at normal speed only.
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn set_rate(&mut self, _rate: f64) {}
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn shuffle(&self) -> bool {
+        self.shared
+            .read(|snapshot| snapshot.is_some_and(|s| s.shuffle_mode.is_on()))
+    }
+
+    #[zbus(property)]
+    async fn set_shuffle(&mut self, shuffle: bool) -> fdo::Result<()> {
+        self.done(self.client.set_shuffle_mode(shuffle).await)?;
+        self.shared
+            .update(|s| s.shuffle_mode = ShuffleMode::from(shuffle));
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, Value<'static>> {
+        self.shared.read(|snapshot| {
+            snapshot
+                .and_then(|s| s.track.as_ref())
+                .map_or_else(no_track_metadata, metadata)
+        })
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.shared
+            .read(|snapshot| snapshot.map_or(0.0, |s| f64::from(s.volume)))
+    }
+
+    #[zbus(property)]
+    async fn set_volume(&mut self, volume: f64) -> fdo::Result<()> {
+        #[allow(clippy::cast_possible_truncation)]
+        let volume = volume.clamp(0.0, 1.0) as f32;
+        self.done(self.client.set_volume(volume).await)?;
+        self.shared.update(|s| s.volume = volume);
+        Ok(())
+    }
+
+    /// Read live: clients expect the current position, and it is not
+    /// announced as it advances.
+    #[zbus(property(emits_changed_signal = "false"))]
+    async fn position(&self) -> i64 {
+        match self.client.now_playing().await {
+            Ok(track) => track.map_or(0, |t| secs_to_micros(t.current_playback_time)),
+            Err(_) => self.shared.read(|snapshot| {
+                snapshot
+                    .and_then(|s| s.track.as_ref())
+                    .map_or(0, |t| secs_to_micros(t.current_playback_time))
+            }),
+        }
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_go_next(&self) -> bool {
+        true
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_go_previous(&self) -> bool {
+        true
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_play(&self) -> bool {
+        true
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_pause(&self) -> bool {
+        true
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_seek(&self) -> bool {
+        true
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+/// The MPRIS track ID for `track`: an object path built from its catalog
+/// or library ID, with characters D-Bus does not allow replaced by `_`.
+fn track_id(track: &NowPlaying) -> String {
+    let id: String = track
+        .song_id()
+        .filter(|id| !id.is_empty())
+        .unwrap_or("current")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("/sh/cider/track/{id}")
+}
+
+/// `Metadata` for `track`.
+fn metadata(track: &NowPlaying) -> HashMap<String, Value<'static>> {
+    let mut map = HashMap::new();
+    let id = ObjectPath::try_from(track_id(track)).expect("track IDs are valid object paths");
+    map.insert("mpris:trackid".to_owned(), Value::from(id));
+    map.insert(
+        "mpris:length".to_owned(),
+        Value::from(duration_to_micros(track.duration())),
+    );
+    map.insert("xesam:title".to_owned(), Value::from(track.name.clone()));
+    map.insert(
+        "xesam:artist".to_owned(),
+        Value::from(vec![track.artist_name.clone()]),
+    );
+    map.insert(
+        "xesam:album".to_owned(),
+        Value::from(track.album_name.clone()),
+    );
+    let artwork = track.artwork_url(ARTWORK_SIZE);
+    if !artwork.is_empty() {
+        map.insert("mpris:artUrl".to_owned(), Value::from(artwork));
+    }
+    if let Some(url) = track.url.clone().filter(|url| !url.is_empty()) {
+        map.insert("xesam:url".to_owned(), Value::from(url));
+    }
+    map
+}
+
+fn no_track_metadata() -> HashMap<String, Value<'static>> {
+    let id = ObjectPath::from_static_str_unchecked(NO_TRACK);
+    HashMap::from([("mpris:trackid".to_owned(), Value::from(id))])
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn secs_to_micros(secs: f64) -> i64 {
+    (secs.max(0.0) * 1_000_000.0) as i64
+}
+
+fn duration_to_micros(duration: Duration) -> i64 {
+    i64::try_from(duration.as_micros()).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(json: &str) -> NowPlaying {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn track_ids_are_object_paths() {
+        let library = track(r#"{"playParams": {"id": "i.AbC-12", "kind": "song"}}"#);
+        assert_eq!(track_id(&library), "/sh/cider/track/i_AbC_12");
+        assert!(ObjectPath::try_from(track_id(&library)).is_ok());
+        assert_eq!(track_id(&track("{}")), "/sh/cider/track/current");
+    }
+
+    #[test]
+    fn metadata_fields() {
+        let track = track(
+            r#"{
+                "name": "Song", "artistName": "Artist", "albumName": "Album",
+                "durationInMillis": 215000,
+                "playParams": {"id": "1440818839", "kind": "song"},
+                "artwork": {"url": "https://example.com/{w}x{h}bb.jpg"}
+            }"#,
+        );
+        let map = metadata(&track);
+        assert_eq!(map["xesam:title"], Value::from("Song"));
+        assert_eq!(map["xesam:artist"], Value::from(vec!["Artist".to_owned()]));
+        assert_eq!(map["mpris:length"], Value::from(215_000_000i64));
+        assert_eq!(
+            map["mpris:artUrl"],
+            Value::from("https://example.com/600x600bb.jpg")
+        );
+        assert!(!map.contains_key("xesam:url"));
+    }
+
+    #[test]
+    fn microsecond_conversions() {
+        assert_eq!(secs_to_micros(1.5), 1_500_000);
+        assert_eq!(secs_to_micros(-3.0), 0);
+        assert_eq!(duration_to_micros(Duration::from_millis(2)), 2_000);
+    }
+}
@@ -0,0 +1,697 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! MQTT bridge: Cider state on MQTT topics, commands from command topics,
+//! and Home Assistant discovery.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+use rumqttc::{AsyncClient, Event, EventLoop, LastWill, MqttOptions, Outgoing, Packet, QoS};
+use serde_json::json;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, warn};
+
+use crate::client::CiderClient;
+use crate::error::CiderError;
+use crate::types::RepeatMode;
+use crate::watcher::{stopped, PlaybackSnapshot, PlaybackWatcher, WatchEvent};
+
+/// Default time between player polls.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Wait between reconnection attempts after the broker connection drops.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// How long to wait for the final `offline` message on shutdown.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Artwork size requested for the `artwork` topic.
+const ARTWORK_SIZE: u32 = 600;
+
+/// Command topics, subscribed under the prefix.
+const COMMANDS: &[&str] = &[
+    "play",
+    "pause",
+    "playpause",
+    "stop",
+    "next",
+    "previous",
+    "set_volume",
+    "seek",
+    "set_repeat",
+    "set_shuffle",
+];
+
+/// Broker login.
+#[derive(Clone)]
+struct Credentials {
+    username: String,
+    password: String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Mirrors Cider to an MQTT broker and accepts commands from it.
+///
+/// State is published retained under the topic prefix (default `cider`):
+///
+/// | Topic | Payload |
+/// |---|---|
+/// | `cider/available` | `online`, or `offline` (also the last will) |
+/// | `cider/state` | `playing`, `paused`, `idle` (nothing loaded) or `off` (Cider unreachable) |
+/// | `cider/title`, `cider/artist`, `cider/album` | track metadata |
+/// | `cider/artwork` | artwork URL |
+/// | `cider/duration`, `cider/position` | whole seconds |
+/// | `cider/volume` | `0.00`–`1.00` |
+/// | `cider/repeat` | `off`, `one` or `all` |
+/// | `cider/shuffle` | `on` or `off` |
+/// | `cider/mediatype` | `music` |
+///
+/// Messages on the command topics are mapped onto the client:
+///
+/// | Topic | Payload | Cider |
+/// |---|---|---|
+/// | `cider/play`, `cider/pause`, `cider/playpause`, `cider/stop`, `cider/next`, `cider/previous` | ignored | the matching playback command |
+/// | `cider/set_volume` | `0.0`–`1.0` | [`set_volume`](CiderClient::set_volume) |
+/// | `cider/seek` | seconds | [`seek_to`](CiderClient::seek_to) |
+/// | `cider/set_repeat` | `off`, `one`, `all` | [`set_repeat_mode`](CiderClient::set_repeat_mode) |
+/// | `cider/set_shuffle` | `on`/`off`, `true`/`false`, `1`/`0` | [`set_shuffle_mode`](CiderClient::set_shuffle_mode) |
+///
+/// A `media_player` discovery config is published retained to
+/// `homeassistant/media_player/<prefix>/config`, in the schema of the
+/// `mqtt_media_player` Home Assistant integration.
+///
+/// The player is polled with a [`PlaybackWatcher`], and only changed
+/// topics are republished. If the broker connection drops, the bridge
+/// reconnects, resubscribes and republishes everything.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), cider_api::CiderError> {
+/// use cider_api::{CiderClient, MqttBridge};
+///
+/// let bridge = MqttBridge::new(CiderClient::new(), "broker.local", 1883)
+///     .credentials("cider", "secret")
+///     .start()
+///     .await?;
+/// bridge.wait().await;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct MqttBridge {
+    client: CiderClient,
+    host: String,
+    port: u16,
+    client_id: String,
+    credentials: Option<Credentials>,
+    topic_prefix: String,
+    discovery_prefix: Option<String>,
+    name: String,
+    poll_interval: Duration,
+}
+
+impl MqttBridge {
+    /// Bridge `client` to the broker at `host`:`port` (usually 1883).
+    pub fn new(client: CiderClient, host: impl Into<String>, port: u16) -> Self {
+        Self {
+            client,
+            host: host.into(),
+            port,
+            client_id: "cider-api".to_owned(),
+            credentials: None,
+            topic_prefix: "cider".to_owned(),
+            discovery_prefix: Some("homeassistant".to_owned()),
+            name: "Cider".to_owned(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// MQTT client ID. Defaults to `cider-api`; must be unique on the
+    /// broker.
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = client_id.into();
+        self
+    }
+
+    /// Log in to the broker.
+    pub fn credentials(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.credentials = Some(Credentials {
+            username: username.into(),
+            password: password.into(),
+        });
+        self
+    }
+
+    /// Prefix of every state and command topic. Defaults to `cider`;
+    /// change it to bridge several instances.
+    pub fn topic_prefix(mut self, prefix: impl Into<String>) -> Self {
+        let mut prefix = prefix.into();
+        prefix.truncate(prefix.trim_end_matches('/').len());
+        self.topic_prefix = prefix;
+        self
+    }
+
+    /// Home Assistant discovery prefix. Defaults to `homeassistant`.
+    pub fn discovery_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.discovery_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Do not publish a Home Assistant discovery config.
+    pub fn without_discovery(mut self) -> Self {
+        self.discovery_prefix = None;
+        self
+    }
+
+    /// Display name of the Home Assistant entity. Defaults to `Cider`.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Time between player polls. Defaults to 1s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "poll interval must be non-zero");
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Connect to the broker and start bridging.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::Mqtt`] if the broker cannot be reached or
+    /// refuses the connection. Later connection losses are retried.
+    pub async fn start(self) -> Result<MqttHandle, CiderError> {
+        let mut options = MqttOptions::new(&self.client_id, &self.host, self.port);
+        options.set_last_will(LastWill::new(
+            self.topic("available"),
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
+        if let Some(credentials) = &self.credentials {
+            options.set_credentials(&credentials.username, &credentials.password);
+        }
+        let (mqtt, mut eventloop) = AsyncClient::new(options, 64);
+        loop {
+            match eventloop.poll().await {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => break,
+                Ok(_) => {}
+                Err(e) => {
+                    return Err(mqtt_error(
+                        &format!("cannot connect to {}:{}", self.host, self.port),
+                        e,
+                    ))
+                }
+            }
+        }
+        debug!(host = %self.host, port = self.port, "connected to MQTT broker");
+
+        // Unbounded, so the event loop never waits on the bridge while the
+        // bridge waits on the event loop to drain its publishes.
+        let (incoming_tx, incoming) = mpsc::unbounded_channel();
+        let driver = tokio::spawn(drive(eventloop, incoming_tx));
+        let (cancel, cancelled) = watch::channel(false);
+        let watcher = self.client.watch().interval(self.poll_interval);
+        let task = tokio::spawn(run(self, mqtt, incoming, driver, watcher, cancelled));
+        Ok(MqttHandle { cancel, task })
+    }
+
+    fn topic(&self, name: &str) -> String {
+        format!("{}/{name}", self.topic_prefix)
+    }
+
+    /// The prefix as a Home Assistant object ID (`[a-zA-Z0-9_-]`).
+    fn object_id(&self) -> String {
+        self.topic_prefix
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+
+    /// The discovery topic and config, if discovery is enabled.
+    fn discovery(&self) -> Option<(String, serde_json::Value)> {
+        let prefix = self.discovery_prefix.as_ref()?;
+        let object_id = self.object_id();
+        let topic = |name| self.topic(name);
+        let config = json!({
+            "name": self.name,
+            "unique_id": object_id,
+            "availability": {
+                "topic": topic("available"),
+                "payload_available": "online",
+                "payload_not_available": "offline",
+            },
+            "state_state_topic": topic("state"),
+            "state_title_topic": topic("title"),
+            "state_artist_topic": topic("artist"),
+            "state_album_topic": topic("album"),
+            "state_duration_topic": topic("duration"),
+            "state_position_topic": topic("position"),
+            "state_volume_topic": topic("volume"),
+            "state_mediatype_topic": topic("mediatype"),
+            "command_volume_topic": topic("set_volume"),
+            "command_play_topic": topic("play"),
+            "command_play_payload": "play",
+            "command_pause_topic": topic("pause"),
+            "command_pause_payload": "pause",
+            "command_playpause_topic": topic("playpause"),
+            "command_playpause_payload": "playpause",
+            "command_next_topic": topic("next"),
+            "command_next_payload": "next",
+            "command_previous_topic": topic("previous"),
+            "command_previous_payload": "previous",
+            "device": {
+                "identifiers": [object_id],
+                "name": self.name,
+                "manufacturer": "Cider Collective",
+                "model": "Cider",
+            },
+        });
+        Some((format!("{prefix}/media_player/{object_id}/config"), config))
+    }
+}
+
+/// A running [`MqttBridge`]. Dropping the handle stops it.
+#[derive(Debug)]
+#[must_use = "dropping an MqttHandle stops the MQTT bridge"]
+pub struct MqttHandle {
+    cancel: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl MqttHandle {
+    /// Publish `offline`, disconnect and stop.
+    pub fn stop(&self) {
+        self.cancel.send_replace(true);
+    }
+
+    /// Returns `true` once the bridge has stopped.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Wait for the bridge to stop.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bridge task panicked.
+    pub async fn wait(self) {
+        let Self { cancel, task } = self;
+        task.await.expect("MQTT task panicked");
+        drop(cancel);
+    }
+}
+
+fn mqtt_error(message: &str, source: impl std::error::Error + Send + Sync + 'static) -> CiderError {
+    CiderError::Mqtt {
+        message: message.to_owned(),
+        source: Some(Box::new(source)),
+    }
+}
+
+/// What the connection task hands to the bridge.
+#[derive(Debug)]
+enum Incoming {
+    /// The connection was re-established.
+    Reconnected,
+    /// A message on a command topic.
+    Message { topic: String, payload: Vec<u8> },
+}
+
+/// Drive the MQTT connection, reconnecting after failures, until the
+/// bridge disconnects or goes away.
+async fn drive(mut eventloop: EventLoop, incoming: mpsc::UnboundedSender<Incoming>) {
+    let mut connected = true;
+    loop {
+        let message = match eventloop.poll().await {
+            Ok(Event::Incoming(Packet::Publish(publish))) => Incoming::Message {
+                topic: publish.topic,
+                payload: publish.payload.to_vec(),
+            },
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                debug!("reconnected to MQTT broker");
+                connected = true;
+                Incoming::Reconnected
+            }
+            Ok(Event::Outgoing(Outgoing::Disconnect)) => return,
+            Ok(_) => continue,
+            Err(e) => {
+                if incoming.is_closed() {
+                    return;
+                }
+                if connected {
+                    warn!(error = %e, "MQTT connection lost, reconnecting");
+                    connected = false;
+                } else {
+                    debug!(error = %e, "MQTT broker still unreachable");
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+                continue;
+            }
+        };
+        if incoming.send(message).is_err() {
+            return;
+        }
+    }
+}
+
+/// Publish state and run commands until cancelled.
+async fn run(
+    bridge: MqttBridge,
+    mqtt: AsyncClient,
+    mut incoming: mpsc::UnboundedReceiver<Incoming>,
+    driver: JoinHandle<()>,
+    mut watcher: PlaybackWatcher,
+    mut cancelled: watch::Receiver<bool>,
+) {
+    let mut publisher = Publisher {
+        bridge: &bridge,
+        mqtt: &mqtt,
+        published: HashMap::new(),
+    };
+    publisher.announce().await;
+    let mut ticker = tokio::time::interval(bridge.poll_interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut away = false;
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            message = incoming.recv() => {
+                match message {
+                    Some(Incoming::Reconnected) => {
+                        publisher.published.clear();
+                        publisher.announce().await;
+                    }
+                    Some(Incoming::Message { topic, payload }) => {
+                        handle_message(&bridge, &topic, &payload).await;
+                        // Show the result without waiting a full interval.
+                        ticker.reset_immediately();
+                    }
+                    None => break,
+                }
+                continue;
+            }
+            () = stopped(&mut cancelled) => break,
+        }
+        for event in watcher.poll().await {
+            match event {
+                WatchEvent::CiderWentAway(_) => away = true,
+                WatchEvent::CiderCameBack => away = false,
+                _ => {}
+            }
+        }
+        let snapshot = watcher.snapshot().filter(|_| !away);
+        publisher.publish_state(snapshot).await;
+    }
+
+    publisher.publish("available", "offline").await;
+    if let Err(e) = mqtt.disconnect().await {
+        debug!(error = %e, "cannot disconnect from MQTT broker");
+    }
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, driver)
+        .await
+        .is_err()
+    {
+        debug!("MQTT broker did not acknowledge the disconnect");
+    }
+    debug!("MQTT bridge stopped");
+}
+
+/// Publishes retained state, skipping unchanged topics.
+struct Publisher<'a> {
+    bridge: &'a MqttBridge,
+    mqtt: &'a AsyncClient,
+    /// Last payload sent per state topic.
+    published: HashMap<&'static str, String>,
+}
+
+impl Publisher<'_> {
+    /// Subscribe to the command topics and publish discovery and
+    /// availability, after (re)connecting.
+    async fn announce(&mut self) {
+        for command in COMMANDS {
+            if let Err(e) = self
+                .mqtt
+                .subscribe(self.bridge.topic(command), QoS::AtLeastOnce)
+                .await
+            {
+                warn!(error = %e, command, "cannot subscribe to MQTT command topic");
+            }
+        }
+        if let Some((topic, config)) = self.bridge.discovery() {
+            if let Err(e) = self
+                .mqtt
+                .publish(topic, QoS::AtLeastOnce, true, config.to_string())
+                .await
+            {
+                warn!(error = %e, "cannot publish Home Assistant discovery config");
+            }
+        }
+        self.publish("available", "online").await;
+    }
+
+    async fn publish(&self, name: &str, payload: &str) {
+        if let Err(e) = self
+            .mqtt
+            .publish(self.bridge.topic(name), QoS::AtLeastOnce, true, payload)
+            .await
+        {
+            warn!(error = %e, topic = name, "cannot publish to MQTT");
+        }
+    }
+
+    async fn publish_state(&mut self, snapshot: Option<&PlaybackSnapshot>) {
+        for (name, payload) in state_payloads(snapshot) {
+            if self.published.get(name) == Some(&payload) {
+                continue;
+            }
+            self.publish(name, &payload).await;
+            self.published.insert(name, payload);
+        }
+    }
+}
+
+/// The state topics for `snapshot`. While Cider is unreachable only
+/// `state` is updated, to `off`.
+fn state_payloads(snapshot: Option<&PlaybackSnapshot>) -> Vec<(&'static str, String)> {
+    let Some(snapshot) = snapshot else {
+        return vec![("state", "off".to_owned())];
+    };
+    let state = match (&snapshot.track, snapshot.is_playing) {
+        (None, _) => "idle",
+        (Some(_), true) => "playing",
+        (Some(_), false) => "paused",
+    };
+    let track = snapshot.track.as_ref();
+    let text = |f: fn(&crate::types::NowPlaying) -> String| track.map(f).unwrap_or_default();
+    let repeat = match snapshot.repeat_mode {
+        RepeatMode::One => "one",
+        RepeatMode::All => "all",
+        _ => "off",
+    };
+    vec![
+        ("state", state.to_owned()),
+        ("title", text(|t| t.name.clone())),
+        ("artist", text(|t| t.artist_name.clone())),
+        ("album", text(|t| t.album_name.clone())),
+        ("artwork", text(|t| t.artwork_url(ARTWORK_SIZE))),
+        ("duration", text(|t| t.duration().as_secs().to_string())),
+        ("position", text(|t| t.position().as_secs().to_string())),
+        ("volume", format!("{:.2}", snapshot.volume)),
+        ("repeat", repeat.to_owned()),
+        (
+            "shuffle",
+            if snapshot.shuffle_mode.is_on() {
+                "on"
+            } else {
+                "off"
+            }
+            .to_owned(),
+        ),
+        ("mediatype", "music".to_owned()),
+    ]
+}
+
+/// A message on a command topic.
+#[derive(Debug, PartialEq)]
+enum Command {
+    Play,
+    Pause,
+    PlayPause,
+    Stop,
+    Next,
+    Previous,
+    SetVolume(f32),
+    Seek(Duration),
+    SetRepeat(RepeatMode),
+    SetShuffle(bool),
+}
+
+impl Command {
+    /// Parse the message `payload` sent to command topic `name`.
+    fn parse(name: &str, payload: &str) -> Result<Self, String> {
+        let payload = payload.trim();
+        let command = match name {
+            "play" => Self::Play,
+            "pause" => Self::Pause,
+            "playpause" => Self::PlayPause,
+            "stop" => Self::Stop,
+            "next" => Self::Next,
+            "previous" => Self::Previous,
+            "set_volume" => match payload.parse::<f32>() {
+                Ok(volume) if (0.0..=1.0).contains(&volume) => Self::SetVolume(volume),
+                _ => return Err(format!("invalid volume {payload:?}")),
+            },
+            "seek" => payload
+                .parse::<f64>()
+                .ok()
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                .map(Self::Seek)
+                .ok_or_else(|| format!("invalid position {payload:?}"))?,
+            "set_repeat" => Self::SetRepeat(match payload.to_ascii_lowercase().as_str() {
+                "off" => RepeatMode::Off,
+                "one" => RepeatMode::One,
+                "all" => RepeatMode::All,
+                _ => return Err(format!("invalid repeat mode {payload:?}")),
+            }),
+            "set_shuffle" => Self::SetShuffle(match payload.to_ascii_lowercase().as_str() {
+                "on" | "true" | "1" => true,
+                "off" | "false" | "0" => false,
+                _ => return Err(format!("invalid shuffle mode {payload:?}")),
+            }),
+            _ => return Err(format!("unknown command {name:?}")),
+        };
+        Ok(command)
+    }
+
+    async fn run(self, client: &CiderClient) -> Result<(), CiderError> {
+        match self {
+            Self::Play => client.play().await,
+            Self::Pause => client.pause().await,
+            Self::PlayPause => client.play_pause().await,
+            Self::Stop => client.stop().await,
+            Self::Next => client.next().await,
+            Self::Previous => client.previous().await,
+            Self::SetVolume(volume) => client.set_volume(volume).await,
+            Self::Seek(position) => client.seek_to(position).await,
+            Self::SetRepeat(mode) => client.set_repeat_mode(mode).await,
+            Self::SetShuffle(on) => client.set_shuffle_mode(on).await,
+        }
+    }
+}
+
+/// Run the command in a message, logging failures.
+async fn handle_message(bridge: &MqttBridge, topic: &str, payload: &[u8]) {
+    let Some(name) = topic
+        .strip_prefix(&bridge.topic_prefix)
+        .and_then(|rest| rest.strip_prefix('/'))
+    else {
+        return;
+    };
+    let payload = String::from_utf8_lossy(payload);
+    let command = match Command::parse(name, &payload) {
+        Ok(command) => command,
+        Err(e) => {
+            warn!(topic, "ignoring MQTT command: {e}");
+            return;
+        }
+    };
+    debug!(?command, "MQTT command");
+    if let Err(e) = command.run(&bridge.client).await {
+        warn!(topic, error = %e, "MQTT command failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(Command::parse("play", "").unwrap(), Command::Play);
+        assert_eq!(
+            Command::parse("set_volume", " 0.25\n").unwrap(),
+            Command::SetVolume(0.25)
+        );
+        assert_eq!(
+            Command::parse("seek", "90.5").unwrap(),
+            Command::Seek(Duration::from_millis(90_500))
+        );
+        assert_eq!(
+            Command::parse("set_repeat", "ALL").unwrap(),
+            Command::SetRepeat(RepeatMode::All)
+        );
+        assert_eq!(
+            Command::parse("set_shuffle", "true").unwrap(),
+            Command::SetShuffle(true)
+        );
+        assert!(Command::parse("set_volume", "2").is_err());
+        assert!(Command::parse("seek", "-1").is_err());
+        assert!(Command::parse("set_shuffle", "maybe").is_err());
+        assert!(Command::parse("eject", "").is_err());
+    }
+
+    #[test]
+    fn unreachable_cider_is_off() {
+        assert_eq!(state_payloads(None), [("state", "off".to_owned())]);
+    }
+
+    #[test]
+    fn discovery_config() {
+        let bridge = MqttBridge::new(CiderClient::new(), "localhost", 1883)
+            .topic_prefix("home/living room/")
+            .name("Living room");
+        let (topic, config) = bridge.discovery().unwrap();
+        assert_eq!(topic, "homeassistant/media_player/home_living_room/config");
+        assert_eq!(config["name"], "Living room");
+        assert_eq!(config["state_title_topic"], "home/living room/title");
+        assert_eq!(
+            config["command_volume_topic"],
+            "home/living room/set_volume"
+        );
+        assert_eq!(
+            config["availability"]["topic"],
+            "home/living room/available"
+        );
+
+        assert!(bridge.without_discovery().discovery().is_none());
+    }
+
+    #[test]
+    fn credentials_are_redacted() {
+        let bridge =
+            MqttBridge::new(CiderClient::new(), "localhost", 1883).credentials("user", "hunter2");
+        let debug = format!("{bridge:?}");
+        assert!(debug.contains("user"));
+        assert!(!debug.contains("hunter2"));
+    }
+}
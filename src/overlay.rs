@@ -0,0 +1,678 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Now-playing output for streamers: files for OBS text and image sources,
+//! and an HTML overlay updated over Server-Sent Events.
+
+use std::convert::Infallible;
+use std::future::IntoFuture;
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::Html;
+use axum::routing::get;
+use axum::Router;
+use futures_util::Stream;
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, warn};
+
+use crate::client::CiderClient;
+use crate::error::CiderError;
+use crate::status_line::{StatusClass, StatusLine, StatusTemplate};
+use crate::types::NowPlaying;
+use crate::watcher::{stopped, PlaybackSnapshot, PlaybackWatcher, WatchEvent};
+
+/// Default time between player polls.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default artwork size for the overlay, in pixels.
+const DEFAULT_ARTWORK_SIZE: u32 = 600;
+
+const TEXT_FILE: &str = "now-playing.txt";
+const JSON_FILE: &str = "now-playing.json";
+const ARTWORK_FILE: &str = "artwork.jpg";
+
+/// The overlay page. Custom CSS replaces [`STYLESHEET_MARKER`].
+const OVERLAY_HTML: &str = include_str!("overlay.html");
+const STYLESHEET_MARKER: &str = "/* stylesheet */";
+
+/// Comment sent on idle event streams so proxies keep them open.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Writes the current track to files whenever it changes, for OBS text,
+/// image and browser sources.
+///
+/// Files in the directory, each replaced atomically:
+///
+/// | File | Content |
+/// |---|---|
+/// | `now-playing.txt` | The [`text_template`](Self::text_template), default `{artist} — {name}`; empty when nothing is playing |
+/// | `now-playing.json` | `state` (`playing`, `paused`, `stopped`, `offline`), `track` and `updated_ms`, as served by [`OverlayServer`] |
+/// | `artwork.jpg` | With [`artwork`](Self::artwork): the cover; removed when nothing is playing |
+///
+/// The files are rewritten when the track changes, playback pauses,
+/// resumes or seeks, and when Cider goes away or comes back; volume,
+/// repeat and shuffle changes are ignored. Write failures are logged and
+/// retried on the next change.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), cider_api::CiderError> {
+/// use cider_api::{CiderClient, NowPlayingFiles};
+///
+/// let files = NowPlayingFiles::new(CiderClient::new(), "/home/me/obs")
+///     .text_template("♪ {name} — {artist}".parse()?)
+///     .artwork(500)
+///     .start()?;
+/// files.wait().await;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct NowPlayingFiles {
+    client: CiderClient,
+    dir: PathBuf,
+    text: StatusTemplate,
+    artwork_size: Option<u32>,
+    poll_interval: Duration,
+}
+
+impl NowPlayingFiles {
+    /// Write files for `client`'s player into `dir`.
+    pub fn new(client: CiderClient, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            client,
+            dir: dir.into(),
+            text: StatusTemplate::default(),
+            artwork_size: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Template for `now-playing.txt` (see [`StatusTemplate`]).
+    pub fn text_template(mut self, template: StatusTemplate) -> Self {
+        self.text = template;
+        self
+    }
+
+    /// Also download the artwork to `artwork.jpg`, `size` pixels square.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn artwork(mut self, size: u32) -> Self {
+        assert!(size > 0, "artwork size must be non-zero");
+        self.artwork_size = Some(size);
+        self
+    }
+
+    /// Time between player polls. Defaults to 1s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "poll interval must be non-zero");
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Create the directory and start writing. Must be called within a
+    /// Tokio runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::Io`] if the directory cannot be created.
+    pub fn start(self) -> Result<NowPlayingFilesHandle, CiderError> {
+        std::fs::create_dir_all(&self.dir).map_err(|source| CiderError::Io {
+            context: format!("cannot create {}", self.dir.display()),
+            source,
+        })?;
+        let (cancel, mut cancelled) = watch::channel(false);
+        let mut writer = Writer {
+            dir: self.dir,
+            text: StatusLine::new(self.text),
+            artwork_size: self.artwork_size,
+            artwork_url: None,
+            http: reqwest::Client::new(),
+        };
+        let mut changes = Changes::new(self.client.watch());
+        let interval = self.poll_interval;
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    () = stopped(&mut cancelled) => break,
+                }
+                if changes.poll().await {
+                    writer.write(changes.snapshot()).await;
+                }
+            }
+            debug!("now-playing files stopped");
+        });
+        Ok(NowPlayingFilesHandle { cancel, task })
+    }
+}
+
+/// Running [`NowPlayingFiles`]. Dropping the handle stops them.
+#[derive(Debug)]
+#[must_use = "dropping a NowPlayingFilesHandle stops writing the files"]
+pub struct NowPlayingFilesHandle {
+    cancel: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl NowPlayingFilesHandle {
+    /// Stop writing. The files are left as they are.
+    pub fn stop(&self) {
+        self.cancel.send_replace(true);
+    }
+
+    /// Returns `true` once writing has stopped.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Wait for writing to stop.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the writer task panicked.
+    pub async fn wait(self) {
+        let Self { cancel, task } = self;
+        task.await.expect("now-playing files task panicked");
+        drop(cancel);
+    }
+}
+
+/// The state behind [`NowPlayingFiles`].
+struct Writer {
+    dir: PathBuf,
+    text: StatusLine,
+    artwork_size: Option<u32>,
+    /// The artwork in `artwork.jpg`, to download each cover once.
+    artwork_url: Option<String>,
+    /// Artwork comes from Apple's CDN, so it is fetched without the
+    /// client's token.
+    http: reqwest::Client,
+}
+
+impl Writer {
+    async fn write(&mut self, snapshot: Option<&PlaybackSnapshot>) {
+        let text = self.text.render(snapshot).text;
+        let json = overlay_state(snapshot, self.artwork_size.unwrap_or(DEFAULT_ARTWORK_SIZE));
+        for (name, contents) in [(TEXT_FILE, text), (JSON_FILE, json.to_string())] {
+            if let Err(e) = write_atomic(&self.dir, name, contents.as_bytes()) {
+                warn!(error = %e, "cannot write now-playing file");
+            }
+        }
+        if let Some(size) = self.artwork_size {
+            let track = snapshot.and_then(|s| s.track.as_ref());
+            self.write_artwork(track, size).await;
+        }
+    }
+
+    /// Download `track`'s cover to `artwork.jpg`, or remove the file.
+    async fn write_artwork(&mut self, track: Option<&NowPlaying>, size: u32) {
+        let url = track
+            .filter(|t| !t.artwork.url.is_empty())
+            .map(|t| t.artwork.url_for_size(size));
+        if url == self.artwork_url {
+            return;
+        }
+        self.artwork_url = None;
+        if let Some(url) = url {
+            match self.download(&url).await {
+                Ok(bytes) => match write_atomic(&self.dir, ARTWORK_FILE, &bytes) {
+                    Ok(()) => {
+                        self.artwork_url = Some(url);
+                        return;
+                    }
+                    Err(e) => warn!(error = %e, "cannot write artwork"),
+                },
+                Err(e) => warn!(error = %e, %url, "cannot download artwork"),
+            }
+        }
+        // Never leave the previous track's cover behind.
+        let path = self.dir.join(ARTWORK_FILE);
+        if let Err(e) = std::fs::remove_file(&path) {
+            if e.kind() != ErrorKind::NotFound {
+                warn!(error = %e, path = %path.display(), "cannot remove artwork");
+            }
+        }
+    }
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>, reqwest::Error> {
+        let response = self.http.get(url).send().await?.error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+/// Write `contents` to `dir/name` through a temporary file, so readers
+/// never see a partial file.
+fn write_atomic(dir: &Path, name: &str, contents: &[u8]) -> Result<(), CiderError> {
+    let path = dir.join(name);
+    let tmp = dir.join(format!(".{name}.tmp"));
+    std::fs::write(&tmp, contents)
+        .and_then(|()| std::fs::rename(&tmp, &path))
+        .map_err(|source| CiderError::Io {
+            context: format!("cannot write {}", path.display()),
+            source,
+        })
+}
+
+/// Serves an HTML now-playing overlay for OBS browser sources.
+///
+/// | Route | Content |
+/// |---|---|
+/// | `/` | The overlay page: artwork, title, artist, album and a progress bar |
+/// | `/events` | Server-Sent Events: a `state` event now and on every change |
+/// | `/now-playing.json` | The current state |
+///
+/// The state is the JSON written to `now-playing.json` by
+/// [`NowPlayingFiles`]: `state` (`playing`, `paused`, `stopped` or
+/// `offline`), `updated_ms`, and `track` with `name`, `artist`, `album`,
+/// `duration_ms`, `position_ms`, `artwork_url` and the artwork's `colors`
+/// (`bg`, `text1`–`text4`, `#rrggbb` or `null`).
+///
+/// The page takes its colors from the artwork where Apple provides them,
+/// fades out when nothing is playing, and advances the progress bar
+/// itself between events. Theme it with a [`stylesheet`](Self::stylesheet)
+/// that overrides the `--bg`, `--text`, `--text-secondary`, `--accent`,
+/// `--font`, `--width`, `--artwork-size` and `--radius` properties or
+/// styles `#card`, `#artwork`, `#name`, `#artist`, `#album` and `#bar`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), cider_api::CiderError> {
+/// use cider_api::{CiderClient, OverlayServer};
+///
+/// let overlay = OverlayServer::new(CiderClient::new())
+///     .stylesheet(":root { --accent: #ff9500; --width: 600px; }")
+///     .start("127.0.0.1:10769")
+///     .await?;
+/// // Add http://127.0.0.1:10769/ as a browser source in OBS.
+/// overlay.wait().await;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct OverlayServer {
+    client: CiderClient,
+    stylesheet: String,
+    artwork_size: u32,
+    poll_interval: Duration,
+}
+
+impl OverlayServer {
+    /// Serve an overlay for `client`'s player.
+    pub fn new(client: CiderClient) -> Self {
+        Self {
+            client,
+            stylesheet: String::new(),
+            artwork_size: DEFAULT_ARTWORK_SIZE,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// CSS added after the page's own styles.
+    pub fn stylesheet(mut self, css: impl Into<String>) -> Self {
+        self.stylesheet = css.into();
+        self
+    }
+
+    /// Size of the artwork the page loads, in pixels. Defaults to 600.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn artwork_size(mut self, size: u32) -> Self {
+        assert!(size > 0, "artwork size must be non-zero");
+        self.artwork_size = size;
+        self
+    }
+
+    /// Time between player polls. Defaults to 1s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "poll interval must be non-zero");
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Listen on `addr` and start serving the overlay.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::Io`] if the address cannot be bound.
+    pub async fn start(self, addr: impl ToSocketAddrs) -> Result<OverlayHandle, CiderError> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|source| CiderError::Io {
+                context: "cannot bind the overlay server".to_owned(),
+                source,
+            })?;
+        let local_addr = listener.local_addr().map_err(|source| CiderError::Io {
+            context: "cannot read the overlay server address".to_owned(),
+            source,
+        })?;
+        debug!(%local_addr, "serving overlay");
+
+        let (cancel, cancelled) = watch::channel(false);
+        let (state, _) = watch::channel(overlay_state(None, self.artwork_size).to_string());
+        let page = OVERLAY_HTML.replacen(STYLESHEET_MARKER, &self.stylesheet, 1);
+        let shared = Shared {
+            state: state.clone(),
+            cancelled: cancelled.clone(),
+        };
+        let app = Router::new()
+            .route("/", get(move || async move { Html(page) }))
+            .route("/events", get(events))
+            .route(
+                "/now-playing.json",
+                get(|State(shared): State<Shared>| async move {
+                    let body = shared.state.borrow().clone();
+                    ([(CONTENT_TYPE, "application/json")], body)
+                }),
+            )
+            .with_state(shared);
+        let mut shutdown = cancelled.clone();
+        let server = axum::serve(listener, app)
+            .with_graceful_shutdown(async move { stopped(&mut shutdown).await });
+        let changes = Changes::new(self.client.watch());
+        let task = tokio::spawn(async move {
+            let (result, ()) = tokio::join!(
+                server.into_future(),
+                publish(
+                    changes,
+                    state,
+                    self.artwork_size,
+                    self.poll_interval,
+                    cancelled
+                )
+            );
+            if let Err(e) = result {
+                warn!(error = %e, "overlay server failed");
+            }
+            debug!("overlay server stopped");
+        });
+        Ok(OverlayHandle {
+            local_addr,
+            cancel,
+            task,
+        })
+    }
+}
+
+/// A running [`OverlayServer`]. Dropping the handle stops it.
+#[derive(Debug)]
+#[must_use = "dropping an OverlayHandle stops the overlay server"]
+pub struct OverlayHandle {
+    local_addr: SocketAddr,
+    cancel: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl OverlayHandle {
+    /// The address the server listens on.
+    #[must_use]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop serving. Open event streams are closed.
+    pub fn stop(&self) {
+        self.cancel.send_replace(true);
+    }
+
+    /// Returns `true` once the server has stopped.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Wait for the server to stop.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the server task panicked.
+    pub async fn wait(self) {
+        let Self { cancel, task, .. } = self;
+        task.await.expect("overlay server task panicked");
+        drop(cancel);
+    }
+}
+
+/// State shared with the overlay's handlers.
+#[derive(Clone)]
+struct Shared {
+    /// The current state as JSON.
+    state: watch::Sender<String>,
+    cancelled: watch::Receiver<bool>,
+}
+
+/// `GET /events`: the current state, then every change, until the server
+/// stops.
+async fn events(
+    State(shared): State<Shared>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let updates = shared.state.subscribe();
+    let stream = futures_util::stream::unfold(
+        (updates, shared.cancelled, true),
+        |(mut updates, mut cancelled, first)| async move {
+            if !first {
+                tokio::select! {
+                    changed = updates.changed() => changed.ok()?,
+                    () = stopped(&mut cancelled) => return None,
+                }
+            }
+            let data = updates.borrow_and_update().clone();
+            let event = Event::default().event("state").data(data);
+            Some((Ok(event), (updates, cancelled, false)))
+        },
+    );
+    Sse::new(stream).keep_alive(KeepAlive::new().interval(KEEP_ALIVE_INTERVAL))
+}
+
+/// Poll the player and publish the state on every change until cancelled.
+async fn publish(
+    mut changes: Changes,
+    state: watch::Sender<String>,
+    artwork_size: u32,
+    interval: Duration,
+    mut cancelled: watch::Receiver<bool>,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            () = stopped(&mut cancelled) => return,
+        }
+        if changes.poll().await {
+            state.send_replace(overlay_state(changes.snapshot(), artwork_size).to_string());
+        }
+    }
+}
+
+/// Polls the player and tells when what an overlay shows has changed.
+struct Changes {
+    watcher: PlaybackWatcher,
+    away: bool,
+    polled: bool,
+}
+
+impl Changes {
+    fn new(watcher: PlaybackWatcher) -> Self {
+        Self {
+            watcher,
+            away: false,
+            polled: false,
+        }
+    }
+
+    /// Poll once. Returns `true` on the first poll and when the track,
+    /// play state, position or Cider's availability changed.
+    async fn poll(&mut self) -> bool {
+        let mut changed = !self.polled;
+        self.polled = true;
+        for event in self.watcher.poll().await {
+            match event {
+                WatchEvent::CiderWentAway(_) => self.away = true,
+                WatchEvent::CiderCameBack => self.away = false,
+                WatchEvent::VolumeChanged { .. }
+                | WatchEvent::RepeatChanged { .. }
+                | WatchEvent::ShuffleChanged { .. } => continue,
+                WatchEvent::TrackChanged { .. }
+                | WatchEvent::Paused
+                | WatchEvent::Resumed
+                | WatchEvent::Seeked { .. } => {}
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    /// The player state, `None` while Cider is unreachable.
+    fn snapshot(&self) -> Option<&PlaybackSnapshot> {
+        self.watcher.snapshot().filter(|_| !self.away)
+    }
+}
+
+/// The overlay's view of a snapshot, `None` meaning Cider is unreachable.
+fn overlay_state(snapshot: Option<&PlaybackSnapshot>, artwork_size: u32) -> Value {
+    let track = snapshot.and_then(|s| s.track.as_ref()).map(|track| {
+        let artwork = &track.artwork;
+        json!({
+            "name": track.name,
+            "artist": track.artist_name,
+            "album": track.album_name,
+            "url": track.url,
+            "duration_ms": track.duration_in_millis,
+            "position_ms": track.current_position_ms(),
+            "audio_traits": track.audio_traits,
+            "artwork_url": (!artwork.url.is_empty()).then(|| artwork.url_for_size(artwork_size)),
+            "colors": {
+                "bg": css_color(artwork.bg_color.as_deref()),
+                "text1": css_color(artwork.text_color1.as_deref()),
+                "text2": css_color(artwork.text_color2.as_deref()),
+                "text3": css_color(artwork.text_color3.as_deref()),
+                "text4": css_color(artwork.text_color4.as_deref()),
+            },
+        })
+    });
+    let updated_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis());
+    json!({
+        "state": StatusClass::of(snapshot).as_str(),
+        "track": track,
+        "updated_ms": updated_ms,
+    })
+}
+
+/// `#rrggbb` for one of Apple's hex colors (`"0c0e0d"`), or `None` if it is
+/// missing or malformed.
+fn css_color(hex: Option<&str>) -> Option<String> {
+    let hex = hex?.trim_start_matches('#');
+    (hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit())).then(|| format!("#{hex}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{RepeatMode, ShuffleMode};
+
+    fn snapshot(is_playing: bool) -> PlaybackSnapshot {
+        let track = serde_json::from_value(json!({
+            "name": "Never Be Like You",
+            "artistName": "Flume",
+            "albumName": "Skin",
+            "durationInMillis": 234_000,
+            "currentPlaybackTime": 42.5,
+            "artwork": {
+                "width": 3000,
+                "height": 3000,
+                "url": "https://example.com/{w}x{h}bb.jpg",
+                "bgColor": "0c0e0d",
+                "textColor1": "F4F5F5",
+                "textColor2": "not-a-color",
+            },
+        }))
+        .unwrap();
+        PlaybackSnapshot {
+            track: Some(track),
+            is_playing,
+            volume: 0.5,
+            repeat_mode: RepeatMode::Off,
+            shuffle_mode: ShuffleMode::Off,
+        }
+    }
+
+    #[test]
+    fn state_carries_track_and_artwork_colors() {
+        let state = overlay_state(Some(&snapshot(false)), 300);
+        assert_eq!(state["state"], "paused");
+        assert!(state["updated_ms"].as_u64().unwrap() > 0);
+        let track = &state["track"];
+        assert_eq!(track["name"], "Never Be Like You");
+        assert_eq!(track["position_ms"], 42_500);
+        assert_eq!(track["artwork_url"], "https://example.com/300x300bb.jpg");
+        assert_eq!(
+            track["colors"],
+            json!({
+                "bg": "#0c0e0d",
+                "text1": "#F4F5F5",
+                "text2": null,
+                "text3": null,
+                "text4": null,
+            })
+        );
+    }
+
+    #[test]
+    fn state_without_track() {
+        let mut stopped = snapshot(true);
+        stopped.track = None;
+        let state = overlay_state(Some(&stopped), 300);
+        assert_eq!(state["state"], "stopped");
+        assert!(state["track"].is_null());
+        assert_eq!(overlay_state(None, 300)["state"], "offline");
+    }
+
+    #[test]
+    fn files_are_replaced_atomically() {
+        let dir = std::env::temp_dir().join(format!("cider-overlay-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_atomic(&dir, TEXT_FILE, b"first").unwrap();
+        write_atomic(&dir, TEXT_FILE, b"second").unwrap();
+        assert_eq!(std::fs::read(dir.join(TEXT_FILE)).unwrap(), b"second");
+        assert!(!dir.join(format!(".{TEXT_FILE}.tmp")).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn page_has_stylesheet_marker() {
+        assert!(OVERLAY_HTML.contains(STYLESHEET_MARKER));
+    }
+}
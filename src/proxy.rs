@@ -0,0 +1,1189 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! LAN proxy: re-exposes the playback API with per-client tokens and
+//! scopes, an audit log of commands, and a WebSocket state feed.
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::future::IntoFuture;
+use std::io::Write as _;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::body::Bytes;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{ConnectInfo, State};
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, warn};
+
+use crate::client::CiderClient;
+use crate::error::CiderError;
+use crate::types::Rating;
+use crate::watcher::{stopped, PlaybackSnapshot, PlaybackWatcher, WatchEvent};
+
+/// Default time between player polls for the WebSocket feed.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Path of the WebSocket state feed.
+const FEED_PATH: &str = "/ws";
+
+/// Feed messages buffered per connection before it is resynchronised.
+const FEED_CAPACITY: usize = 64;
+
+/// What a proxy token may do.
+///
+/// | Scope | Grants |
+/// |---|---|
+/// | `read` | Status reads (`GET` endpoints) and the WebSocket feed |
+/// | `playback` | Play, pause, skip, seek, play items, repeat/shuffle/autoplay, ratings, add to library |
+/// | `queue` | `play-next`, `play-later`, moving, removing and clearing queue items |
+/// | `volume` | `POST /volume` |
+/// | `amapi` | `POST /api/v1/amapi/run-v3` |
+///
+/// Scopes are independent: a token with `playback` but not `read` can skip
+/// tracks but not see what is playing.
+///
+/// ```
+/// # use cider_api::ProxyScope;
+/// assert_eq!("queue".parse::<ProxyScope>()?, ProxyScope::Queue);
+/// assert_eq!(ProxyScope::AmApi.to_string(), "amapi");
+/// # Ok::<(), cider_api::CiderError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProxyScope {
+    /// Read the player state.
+    Read,
+    /// Control playback.
+    Playback,
+    /// Edit the queue.
+    Queue,
+    /// Change the volume.
+    Volume,
+    /// Run Apple Music API requests through Cider.
+    AmApi,
+}
+
+impl ProxyScope {
+    /// Every scope.
+    pub const ALL: [Self; 5] = [
+        Self::Read,
+        Self::Playback,
+        Self::Queue,
+        Self::Volume,
+        Self::AmApi,
+    ];
+
+    /// The scope's name, as used in token files.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Playback => "playback",
+            Self::Queue => "queue",
+            Self::Volume => "volume",
+            Self::AmApi => "amapi",
+        }
+    }
+}
+
+impl fmt::Display for ProxyScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ProxyScope {
+    type Err = CiderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| CiderError::Config(format!("unknown proxy scope {s:?}")))
+    }
+}
+
+/// A client token and what it may do.
+#[derive(Clone)]
+struct Grant {
+    /// Who the token belongs to, as recorded in the audit log.
+    name: String,
+    token: String,
+    scopes: Vec<ProxyScope>,
+}
+
+impl fmt::Debug for Grant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Grant")
+            .field("name", &self.name)
+            .field("token", &"<redacted>")
+            .field("scopes", &self.scopes)
+            .finish()
+    }
+}
+
+/// Re-exposes Cider's playback API on the network behind per-client
+/// tokens.
+///
+/// Cider has a single token that allows everything. The proxy holds it (in
+/// the [`CiderClient`]) and hands out its own tokens instead, each with a
+/// name and a set of [`ProxyScope`]s, so a guest can see what is playing
+/// without being able to clear the queue.
+///
+/// The proxy serves the same `/api/v1/playback/...` and
+/// `/api/v1/amapi/run-v3` routes as Cider, so a [`CiderClient`] pointed at
+/// it works unchanged. Clients authenticate with the `apptoken` header (as
+/// with Cider), `Authorization: Bearer <token>`, or a `?token=` query
+/// parameter. A missing or unknown token gets `401`, a missing scope `403`.
+///
+/// Every command (any non-`GET` request) and every refused request is
+/// written to the [audit log](Self::audit_log) as a JSON line naming the
+/// token holder, their address, the route, its arguments and the outcome.
+///
+/// `GET /ws` (the `read` scope) is a WebSocket feed of the player state.
+/// Each message is a JSON object with an `event` (`state` on connect, then
+/// `track_changed`, `paused`, `resumed`, `seeked`, `volume_changed`,
+/// `repeat_changed`, `shuffle_changed`, `cider_went_away` or
+/// `cider_came_back`) and the full `state` after it.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), cider_api::CiderError> {
+/// use cider_api::{CiderClient, ProxyScope, ProxyServer};
+///
+/// let client = CiderClient::new().with_token("cider-app-token");
+/// let proxy = ProxyServer::new(client)
+///     .allow("guests", "guest-secret", [ProxyScope::Read])
+///     .allow("phone", "phone-secret", ProxyScope::ALL)
+///     .audit_log("proxy-audit.jsonl")
+///     .start("0.0.0.0:10768")
+///     .await?;
+/// proxy.wait().await;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct ProxyServer {
+    client: CiderClient,
+    grants: Vec<Grant>,
+    audit_log: Option<PathBuf>,
+    poll_interval: Duration,
+}
+
+impl ProxyServer {
+    /// Proxy `client`, which holds the Cider token.
+    pub fn new(client: CiderClient) -> Self {
+        Self {
+            client,
+            grants: Vec::new(),
+            audit_log: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Accept `token`, recorded as `name` in the audit log, for `scopes`.
+    pub fn allow(
+        mut self,
+        name: impl Into<String>,
+        token: impl Into<String>,
+        scopes: impl IntoIterator<Item = ProxyScope>,
+    ) -> Self {
+        let mut grant = Grant {
+            name: name.into(),
+            token: token.into(),
+            scopes: Vec::new(),
+        };
+        for scope in scopes {
+            if !grant.scopes.contains(&scope) {
+                grant.scopes.push(scope);
+            }
+        }
+        self.grants.push(grant);
+        self
+    }
+
+    /// Add the tokens listed in the file at `path`.
+    ///
+    /// Each line holds a name, a token and comma-separated scopes (or
+    /// `all`), separated by whitespace. Blank lines and lines starting with
+    /// `#` are skipped:
+    ///
+    /// ```text
+    /// # name   token          scopes
+    /// guests   guest-secret   read
+    /// phone    phone-secret   read,playback,queue,volume
+    /// admin    admin-secret   all
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::Io`] if the file cannot be read and
+    /// [`CiderError::Config`] for a malformed line.
+    pub fn load_tokens(mut self, path: impl AsRef<Path>) -> Result<Self, CiderError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|source| CiderError::Io {
+            context: format!("cannot read proxy tokens from {}", path.display()),
+            source,
+        })?;
+        for (name, token, scopes) in parse_tokens(&text)
+            .map_err(|e| CiderError::Config(format!("{}:{e}", path.display())))?
+        {
+            self = self.allow(name, token, scopes);
+        }
+        Ok(self)
+    }
+
+    /// Append audit entries to the file at `path`, creating it if needed.
+    pub fn audit_log(mut self, path: impl Into<PathBuf>) -> Self {
+        self.audit_log = Some(path.into());
+        self
+    }
+
+    /// Time between player polls for the WebSocket feed. Defaults to 1s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "poll interval must be non-zero");
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Listen on `addr` and start proxying.
+    ///
+    /// # Errors
+    ///
+    /// - [`CiderError::Config`] if no tokens were added, or a token is
+    ///   empty or shared by two names.
+    /// - [`CiderError::Io`] if the audit log cannot be opened or the address
+    ///   cannot be bound.
+    pub async fn start(self, addr: impl ToSocketAddrs) -> Result<ProxyHandle, CiderError> {
+        self.check_grants()?;
+        let audit = match &self.audit_log {
+            Some(path) => Some(Mutex::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|source| CiderError::Io {
+                        context: format!("cannot open the audit log {}", path.display()),
+                        source,
+                    })?,
+            )),
+            None => None,
+        };
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|source| CiderError::Io {
+                context: "cannot bind the proxy".to_owned(),
+                source,
+            })?;
+        let local_addr = listener.local_addr().map_err(|source| CiderError::Io {
+            context: "cannot read the proxy address".to_owned(),
+            source,
+        })?;
+        debug!(%local_addr, tokens = self.grants.len(), "proxy listening");
+
+        let (cancel, cancelled) = watch::channel(false);
+        let shared = Arc::new(Shared {
+            client: self.client.clone(),
+            grants: self.grants,
+            audit,
+            feed: broadcast::channel(FEED_CAPACITY).0,
+            state: Mutex::new(None),
+            cancelled: cancelled.clone(),
+        });
+        let app = Router::new()
+            .route(FEED_PATH, get(feed))
+            .fallback(dispatch)
+            .with_state(Arc::clone(&shared));
+        let mut shutdown = cancelled.clone();
+        let server = axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(async move { stopped(&mut shutdown).await });
+        let watcher = self.client.watch().interval(self.poll_interval);
+        let interval = self.poll_interval;
+        let task = tokio::spawn(async move {
+            let (result, ()) = tokio::join!(
+                server.into_future(),
+                poll(watcher, shared, interval, cancelled)
+            );
+            if let Err(e) = result {
+                warn!(error = %e, "proxy failed");
+            }
+            debug!("proxy stopped");
+        });
+        Ok(ProxyHandle {
+            local_addr,
+            cancel,
+            task,
+        })
+    }
+
+    fn check_grants(&self) -> Result<(), CiderError> {
+        if self.grants.is_empty() {
+            return Err(CiderError::Config(
+                "the proxy needs at least one token".to_owned(),
+            ));
+        }
+        for (i, grant) in self.grants.iter().enumerate() {
+            if grant.token.is_empty() {
+                return Err(CiderError::Config(format!(
+                    "the proxy token of {:?} is empty",
+                    grant.name
+                )));
+            }
+            if let Some(other) = self.grants[..i].iter().find(|g| g.token == grant.token) {
+                return Err(CiderError::Config(format!(
+                    "{:?} and {:?} have the same proxy token",
+                    other.name, grant.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A token file line: name, token and scopes.
+type TokenLine<'a> = (&'a str, &'a str, Vec<ProxyScope>);
+
+/// Parse a token file. Errors start with the 1-based line number.
+fn parse_tokens(text: &str) -> Result<Vec<TokenLine<'_>>, String> {
+    let mut entries = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [name, token, scopes] = fields[..] else {
+            return Err(format!(
+                "{}: expected `name token scopes`, found {} fields",
+                i + 1,
+                fields.len()
+            ));
+        };
+        let scopes = if scopes == "all" {
+            ProxyScope::ALL.to_vec()
+        } else {
+            scopes
+                .split(',')
+                .map(|scope| scope.parse().map_err(|e| format!("{}: {e}", i + 1)))
+                .collect::<Result<_, _>>()?
+        };
+        entries.push((name, token, scopes));
+    }
+    Ok(entries)
+}
+
+/// A running [`ProxyServer`]. Dropping the handle stops it.
+#[derive(Debug)]
+#[must_use = "dropping a ProxyHandle stops the proxy"]
+pub struct ProxyHandle {
+    local_addr: SocketAddr,
+    cancel: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl ProxyHandle {
+    /// The address the proxy listens on.
+    #[must_use]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop the proxy and close WebSocket feeds.
+    pub fn stop(&self) {
+        self.cancel.send_replace(true);
+    }
+
+    /// Returns `true` once the proxy has stopped.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Wait for the proxy to stop.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the proxy task panicked.
+    pub async fn wait(self) {
+        let Self { cancel, task, .. } = self;
+        task.await.expect("proxy task panicked");
+        drop(cancel);
+    }
+}
+
+/// State shared by the request handlers and the feed poller.
+struct Shared {
+    client: CiderClient,
+    grants: Vec<Grant>,
+    audit: Option<Mutex<File>>,
+    /// Serialized feed messages.
+    feed: broadcast::Sender<String>,
+    /// The latest feed state, `None` until the first poll.
+    state: Mutex<Option<Value>>,
+    cancelled: watch::Receiver<bool>,
+}
+
+/// Why a request was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Denial {
+    /// No token, or an unknown one.
+    Unauthorized,
+    /// The token lacks the route's scope.
+    Forbidden,
+}
+
+/// One audit log line.
+struct Audit<'a> {
+    client: Option<&'a str>,
+    addr: SocketAddr,
+    method: &'a Method,
+    path: &'a str,
+    body: &'a [u8],
+}
+
+impl Shared {
+    /// Find the grant for the request's token and check it has `scope`.
+    fn authorize(
+        &self,
+        headers: &HeaderMap,
+        uri: &Uri,
+        scope: ProxyScope,
+    ) -> Result<&Grant, (Denial, Option<&Grant>)> {
+        let token = request_token(headers, uri).ok_or((Denial::Unauthorized, None))?;
+        let grant = self
+            .grants
+            .iter()
+            .find(|grant| same_token(&grant.token, token))
+            .ok_or((Denial::Unauthorized, None))?;
+        if grant.scopes.contains(&scope) {
+            Ok(grant)
+        } else {
+            Err((Denial::Forbidden, Some(grant)))
+        }
+    }
+
+    /// Record a request and its outcome.
+    fn audit(&self, entry: &Audit<'_>, outcome: &str, status: StatusCode, error: Option<&str>) {
+        debug!(
+            client = entry.client,
+            addr = %entry.addr,
+            method = %entry.method,
+            path = entry.path,
+            outcome,
+            "proxy request"
+        );
+        let Some(file) = &self.audit else {
+            return;
+        };
+        #[allow(clippy::cast_possible_truncation)] // milliseconds fit in u64 until year 584 million
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        let mut line = json!({
+            "timestamp_ms": timestamp_ms,
+            "client": entry.client,
+            "addr": entry.addr.to_string(),
+            "method": entry.method.as_str(),
+            "path": entry.path,
+            "outcome": outcome,
+            "status": status.as_u16(),
+        });
+        if let Ok(args) = serde_json::from_slice::<Value>(entry.body) {
+            line["args"] = args;
+        }
+        if let Some(error) = error {
+            line["error"] = error.into();
+        }
+        let mut file = file.lock().expect("audit log lock poisoned");
+        if let Err(e) = writeln!(file, "{line}") {
+            warn!(error = %e, "cannot write the audit log");
+        }
+    }
+}
+
+/// The token from `apptoken`, `Authorization: Bearer` or `?token=`.
+fn request_token<'a>(headers: &'a HeaderMap, uri: &'a Uri) -> Option<&'a str> {
+    if let Some(token) = headers.get("apptoken").and_then(|v| v.to_str().ok()) {
+        return Some(token);
+    }
+    if let Some(token) = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    {
+        return Some(token.trim());
+    }
+    uri.query()?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+}
+
+/// Compare tokens in time independent of where they differ.
+fn same_token(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// A proxied API route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Route {
+    Active,
+    IsPlaying,
+    NowPlaying,
+    Queue,
+    Volume,
+    RepeatMode,
+    ShuffleMode,
+    Autoplay,
+    Play,
+    Pause,
+    PlayPause,
+    Stop,
+    Next,
+    Previous,
+    Seek,
+    PlayUrl,
+    PlayItem,
+    PlayItemHref,
+    ToggleRepeat,
+    ToggleShuffle,
+    ToggleAutoplay,
+    SetRating,
+    AddToLibrary,
+    PlayNext,
+    PlayLater,
+    QueueMove,
+    QueueRemove,
+    QueueClear,
+    SetVolume,
+    AmApi,
+}
+
+impl Route {
+    fn find(method: &Method, path: &str) -> Option<Self> {
+        if *method == Method::POST && path == "/api/v1/amapi/run-v3" {
+            return Some(Self::AmApi);
+        }
+        let path = path.strip_prefix("/api/v1/playback")?;
+        let route = match (method.as_str(), path) {
+            ("GET", "/active") => Self::Active,
+            ("GET", "/is-playing") => Self::IsPlaying,
+            ("GET", "/now-playing") => Self::NowPlaying,
+            ("GET", "/queue") => Self::Queue,
+            ("GET", "/volume") => Self::Volume,
+            ("GET", "/repeat-mode") => Self::RepeatMode,
+            ("GET", "/shuffle-mode") => Self::ShuffleMode,
+            ("GET", "/autoplay") => Self::Autoplay,
+            ("POST", "/play") => Self::Play,
+            ("POST", "/pause") => Self::Pause,
+            ("POST", "/playpause") => Self::PlayPause,
+            ("POST", "/stop") => Self::Stop,
+            ("POST", "/next") => Self::Next,
+            ("POST", "/previous") => Self::Previous,
+            ("POST", "/seek") => Self::Seek,
+            ("POST", "/play-url") => Self::PlayUrl,
+            ("POST", "/play-item") => Self::PlayItem,
+            ("POST", "/play-item-href") => Self::PlayItemHref,
+            ("POST", "/toggle-repeat") => Self::ToggleRepeat,
+            ("POST", "/toggle-shuffle") => Self::ToggleShuffle,
+            ("POST", "/toggle-autoplay") => Self::ToggleAutoplay,
+            ("POST", "/set-rating") => Self::SetRating,
+            ("POST", "/add-to-library") => Self::AddToLibrary,
+            ("POST", "/play-next") => Self::PlayNext,
+            ("POST", "/play-later") => Self::PlayLater,
+            ("POST", "/queue/move-to-position") => Self::QueueMove,
+            ("POST", "/queue/remove-by-index") => Self::QueueRemove,
+            ("POST", "/queue/clear-queue") => Self::QueueClear,
+            ("POST", "/volume") => Self::SetVolume,
+            _ => return None,
+        };
+        Some(route)
+    }
+
+    fn scope(self) -> ProxyScope {
+        match self {
+            Self::Active
+            | Self::IsPlaying
+            | Self::NowPlaying
+            | Self::Queue
+            | Self::Volume
+            | Self::RepeatMode
+            | Self::ShuffleMode
+            | Self::Autoplay => ProxyScope::Read,
+            Self::Play
+            | Self::Pause
+            | Self::PlayPause
+            | Self::Stop
+            | Self::Next
+            | Self::Previous
+            | Self::Seek
+            | Self::PlayUrl
+            | Self::PlayItem
+            | Self::PlayItemHref
+            | Self::ToggleRepeat
+            | Self::ToggleShuffle
+            | Self::ToggleAutoplay
+            | Self::SetRating
+            | Self::AddToLibrary => ProxyScope::Playback,
+            Self::PlayNext
+            | Self::PlayLater
+            | Self::QueueMove
+            | Self::QueueRemove
+            | Self::QueueClear => ProxyScope::Queue,
+            Self::SetVolume => ProxyScope::Volume,
+            Self::AmApi => ProxyScope::AmApi,
+        }
+    }
+
+    /// Call Cider and build the response it would have sent.
+    async fn run(self, client: &CiderClient, body: &[u8]) -> Result<Response, Failure> {
+        let response = match self {
+            Self::Active => {
+                client.is_active().await?;
+                StatusCode::NO_CONTENT.into_response()
+            }
+            Self::IsPlaying => ok(json!({ "is_playing": client.is_playing().await? })),
+            Self::NowPlaying => match client.now_playing().await? {
+                Some(info) => ok(json!({ "info": info })),
+                None => StatusCode::NO_CONTENT.into_response(),
+            },
+            Self::Queue => json_response(StatusCode::OK, &json!(client.get_queue().await?)),
+            Self::Volume => ok(json!({ "volume": client.get_volume().await? })),
+            Self::RepeatMode => ok(json!({ "value": client.get_repeat_mode().await? })),
+            Self::ShuffleMode => ok(json!({ "value": client.get_shuffle_mode().await? })),
+            Self::Autoplay => ok(json!({ "value": client.get_autoplay().await? })),
+            Self::AmApi => {
+                let PathBody { path } = parse(body)?;
+                json_response(StatusCode::OK, &client.amapi_run_v3(&path).await?)
+            }
+            command => {
+                command.execute(client, body).await?;
+                ok(json!({}))
+            }
+        };
+        Ok(response)
+    }
+
+    /// Run a command route.
+    async fn execute(self, client: &CiderClient, body: &[u8]) -> Result<(), Failure> {
+        match self {
+            Self::Play => client.play().await?,
+            Self::Pause => client.pause().await?,
+            Self::PlayPause => client.play_pause().await?,
+            Self::Stop => client.stop().await?,
+            Self::Next => client.next().await?,
+            Self::Previous => client.previous().await?,
+            Self::Seek => {
+                let SeekBody { position } = parse(body)?;
+                client.seek(position).await?;
+            }
+            Self::PlayUrl => {
+                let UrlBody { url } = parse(body)?;
+                client.play_url(&url).await?;
+            }
+            Self::PlayItem => client.play_item(&parse(body)?).await?,
+            Self::PlayItemHref => {
+                let HrefBody { href } = parse(body)?;
+                client.play_item_href(&href).await?;
+            }
+            Self::ToggleRepeat => client.toggle_repeat().await?,
+            Self::ToggleShuffle => client.toggle_shuffle().await?,
+            Self::ToggleAutoplay => client.toggle_autoplay().await?,
+            Self::SetRating => {
+                let RatingBody { rating } = parse(body)?;
+                client.set_rating(rating).await?;
+            }
+            Self::AddToLibrary => client.add_to_library().await?,
+            Self::PlayNext => client.play_next(&parse(body)?).await?,
+            Self::PlayLater => client.play_later(&parse(body)?).await?,
+            Self::QueueMove => {
+                let MoveBody {
+                    start_index,
+                    destination_index,
+                } = parse(body)?;
+                client
+                    .queue_move_to_position(start_index, destination_index)
+                    .await?;
+            }
+            Self::QueueRemove => {
+                let IndexBody { index } = parse(body)?;
+                client.queue_remove_by_index(index).await?;
+            }
+            Self::QueueClear => client.clear_queue().await?,
+            Self::SetVolume => {
+                let VolumeBody { volume } = parse(body)?;
+                client.set_volume(volume).await?;
+            }
+            Self::Active
+            | Self::IsPlaying
+            | Self::NowPlaying
+            | Self::Queue
+            | Self::Volume
+            | Self::RepeatMode
+            | Self::ShuffleMode
+            | Self::Autoplay
+            | Self::AmApi => unreachable!("{self:?} is not a command"),
+        }
+        Ok(())
+    }
+}
+
+// ── Request bodies ──
+
+#[derive(Deserialize)]
+struct SeekBody {
+    position: f64,
+}
+
+#[derive(Deserialize)]
+struct UrlBody {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct HrefBody {
+    href: String,
+}
+
+#[derive(Deserialize)]
+struct RatingBody {
+    rating: Rating,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MoveBody {
+    start_index: u32,
+    destination_index: u32,
+}
+
+#[derive(Deserialize)]
+struct IndexBody {
+    index: u32,
+}
+
+#[derive(Deserialize)]
+struct VolumeBody {
+    volume: f32,
+}
+
+#[derive(Deserialize)]
+struct PathBody {
+    path: String,
+}
+
+/// Why a proxied call failed.
+#[derive(Debug)]
+enum Failure {
+    /// The request body is not what the route expects.
+    BadRequest(String),
+    Cider(Box<CiderError>),
+}
+
+impl From<CiderError> for Failure {
+    fn from(e: CiderError) -> Self {
+        Self::Cider(Box::new(e))
+    }
+}
+
+impl Failure {
+    fn status(&self) -> StatusCode {
+        let Self::Cider(e) = self else {
+            return StatusCode::BAD_REQUEST;
+        };
+        match **e {
+            CiderError::InvalidItem(_) | CiderError::Config(_) => StatusCode::BAD_REQUEST,
+            CiderError::NothingPlaying { .. } => StatusCode::NOT_FOUND,
+            CiderError::Timeout { .. } => StatusCode::GATEWAY_TIMEOUT,
+            // Cider's own status, unless it rejected the proxy's token.
+            CiderError::Api { status, .. } => status,
+            _ => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            Self::BadRequest(message) => message.clone(),
+            Self::Cider(e) => e.to_string(),
+        }
+    }
+}
+
+fn parse<T: DeserializeOwned>(body: &[u8]) -> Result<T, Failure> {
+    serde_json::from_slice(body).map_err(|e| Failure::BadRequest(format!("invalid body: {e}")))
+}
+
+fn json_response(status: StatusCode, body: &Value) -> Response {
+    (
+        status,
+        [(CONTENT_TYPE, "application/json")],
+        body.to_string(),
+    )
+        .into_response()
+}
+
+/// `{"status": "ok", ...fields}`.
+fn ok(mut fields: Value) -> Response {
+    fields["status"] = "ok".into();
+    json_response(StatusCode::OK, &fields)
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    json_response(status, &json!({ "status": "error", "message": message }))
+}
+
+fn denial_response(denial: Denial) -> Response {
+    match denial {
+        Denial::Unauthorized => {
+            error_response(StatusCode::UNAUTHORIZED, "missing or unknown proxy token")
+        }
+        Denial::Forbidden => error_response(
+            StatusCode::FORBIDDEN,
+            "this token may not use this endpoint",
+        ),
+    }
+}
+
+/// Handle an API request: authorize, call Cider, audit.
+async fn dispatch(
+    State(shared): State<Arc<Shared>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let Some(route) = Route::find(&method, uri.path()) else {
+        return error_response(StatusCode::NOT_FOUND, "unknown endpoint");
+    };
+    let mut entry = Audit {
+        client: None,
+        addr,
+        method: &method,
+        path: uri.path(),
+        body: &body,
+    };
+    let grant = match shared.authorize(&headers, &uri, route.scope()) {
+        Ok(grant) => grant,
+        Err((denial, grant)) => {
+            entry.client = grant.map(|g| g.name.as_str());
+            let response = denial_response(denial);
+            let outcome = match denial {
+                Denial::Unauthorized => "unauthorized",
+                Denial::Forbidden => "forbidden",
+            };
+            shared.audit(&entry, outcome, response.status(), None);
+            return response;
+        }
+    };
+    entry.client = Some(&grant.name);
+
+    let (response, outcome, error) = match route.run(&shared.client, &body).await {
+        Ok(response) => (response, "ok", None),
+        Err(failure) => {
+            let message = failure.message();
+            let outcome = match failure {
+                Failure::BadRequest(_) => "invalid",
+                Failure::Cider(_) => "failed",
+            };
+            (
+                error_response(failure.status(), &message),
+                outcome,
+                Some(message),
+            )
+        }
+    };
+    if method != Method::GET || error.is_some() {
+        shared.audit(&entry, outcome, response.status(), error.as_deref());
+    }
+    response
+}
+
+/// Upgrade `GET /ws` to the state feed.
+async fn feed(
+    State(shared): State<Arc<Shared>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    uri: Uri,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    match shared.authorize(&headers, &uri, ProxyScope::Read) {
+        Ok(grant) => {
+            debug!(client = grant.name, %addr, "feed connected");
+            upgrade.on_upgrade(move |socket| stream_feed(socket, shared))
+        }
+        Err((denial, grant)) => {
+            let response = denial_response(denial);
+            let entry = Audit {
+                client: grant.map(|g| g.name.as_str()),
+                addr,
+                method: &Method::GET,
+                path: uri.path(),
+                body: &[],
+            };
+            let outcome = match denial {
+                Denial::Unauthorized => "unauthorized",
+                Denial::Forbidden => "forbidden",
+            };
+            shared.audit(&entry, outcome, response.status(), None);
+            response
+        }
+    }
+}
+
+/// Send the current state, then every change, until the client leaves or
+/// the proxy stops.
+async fn stream_feed(mut socket: WebSocket, shared: Arc<Shared>) {
+    let mut messages = shared.feed.subscribe();
+    let mut cancelled = shared.cancelled.clone();
+    if !send_state(&mut socket, &shared).await {
+        return;
+    }
+    loop {
+        tokio::select! {
+            message = messages.recv() => {
+                let sent = match message {
+                    Ok(text) => socket.send(Message::Text(text.into())).await.is_ok(),
+                    // Too slow to keep up: skip to the current state.
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        send_state(&mut socket, &shared).await
+                    }
+                    Err(broadcast::error::RecvError::Closed) => false,
+                };
+                if !sent {
+                    return;
+                }
+            }
+            incoming = socket.recv() => {
+                if matches!(incoming, None | Some(Err(_) | Ok(Message::Close(_)))) {
+                    return;
+                }
+            }
+            () = stopped(&mut cancelled) => {
+                let _ = socket.send(Message::Close(None)).await;
+                return;
+            }
+        }
+    }
+}
+
+/// Send a `state` message if the feed has polled yet. Returns `false` if
+/// the socket is gone.
+async fn send_state(socket: &mut WebSocket, shared: &Shared) -> bool {
+    let state = shared
+        .state
+        .lock()
+        .expect("feed state lock poisoned")
+        .clone();
+    match state {
+        Some(state) => socket
+            .send(Message::Text(feed_message("state", &state).into()))
+            .await
+            .is_ok(),
+        None => true,
+    }
+}
+
+fn feed_message(event: &str, state: &Value) -> String {
+    json!({ "event": event, "state": state }).to_string()
+}
+
+/// The feed's view of a snapshot; `None` while Cider is unreachable.
+fn feed_state(snapshot: Option<&PlaybackSnapshot>) -> Value {
+    match snapshot {
+        Some(snapshot) => json!({
+            "available": true,
+            "is_playing": snapshot.is_playing,
+            "volume": snapshot.volume,
+            "repeat_mode": snapshot.repeat_mode,
+            "shuffle_mode": snapshot.shuffle_mode,
+            "track": snapshot.track,
+        }),
+        None => json!({ "available": false }),
+    }
+}
+
+fn event_name(event: &WatchEvent) -> &'static str {
+    match event {
+        WatchEvent::TrackChanged { .. } => "track_changed",
+        WatchEvent::Paused => "paused",
+        WatchEvent::Resumed => "resumed",
+        WatchEvent::Seeked { .. } => "seeked",
+        WatchEvent::VolumeChanged { .. } => "volume_changed",
+        WatchEvent::RepeatChanged { .. } => "repeat_changed",
+        WatchEvent::ShuffleChanged { .. } => "shuffle_changed",
+        WatchEvent::CiderWentAway(_) => "cider_went_away",
+        WatchEvent::CiderCameBack => "cider_came_back",
+    }
+}
+
+/// Poll the player and publish feed messages until cancelled.
+async fn poll(
+    mut watcher: PlaybackWatcher,
+    shared: Arc<Shared>,
+    interval: Duration,
+    mut cancelled: watch::Receiver<bool>,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut away = false;
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            () = stopped(&mut cancelled) => return,
+        }
+        let events = watcher.poll().await;
+        for event in &events {
+            match event {
+                WatchEvent::CiderWentAway(_) => away = true,
+                WatchEvent::CiderCameBack => away = false,
+                _ => {}
+            }
+        }
+        let state = feed_state(watcher.snapshot().filter(|_| !away));
+        let first = shared
+            .state
+            .lock()
+            .expect("feed state lock poisoned")
+            .replace(state.clone())
+            .is_none();
+        // Sending fails only while nobody is subscribed.
+        if first {
+            let _ = shared.feed.send(feed_message("state", &state));
+        }
+        for event in &events {
+            let _ = shared.feed.send(feed_message(event_name(event), &state));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_have_scopes() {
+        let route = |method, path| Route::find(&method, path).map(Route::scope);
+        assert_eq!(
+            route(Method::GET, "/api/v1/playback/now-playing"),
+            Some(ProxyScope::Read)
+        );
+        assert_eq!(
+            route(Method::POST, "/api/v1/playback/next"),
+            Some(ProxyScope::Playback)
+        );
+        assert_eq!(
+            route(Method::POST, "/api/v1/playback/queue/clear-queue"),
+            Some(ProxyScope::Queue)
+        );
+        assert_eq!(
+            route(Method::POST, "/api/v1/playback/volume"),
+            Some(ProxyScope::Volume)
+        );
+        assert_eq!(
+            route(Method::POST, "/api/v1/amapi/run-v3"),
+            Some(ProxyScope::AmApi)
+        );
+        assert_eq!(route(Method::GET, "/api/v1/playback/next"), None);
+        assert_eq!(route(Method::GET, "/api/v1/playback"), None);
+    }
+
+    #[test]
+    fn tokens_are_read_from_headers_or_query() {
+        let uri: Uri = "/ws?x=1&token=from-query".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        assert_eq!(request_token(&headers, &uri), Some("from-query"));
+        headers.insert(AUTHORIZATION, "Bearer from-bearer".parse().unwrap());
+        assert_eq!(request_token(&headers, &uri), Some("from-bearer"));
+        headers.insert("apptoken", "from-apptoken".parse().unwrap());
+        assert_eq!(request_token(&headers, &uri), Some("from-apptoken"));
+        assert_eq!(
+            request_token(&HeaderMap::new(), &Uri::from_static("/")),
+            None
+        );
+    }
+
+    #[test]
+    fn token_comparison() {
+        assert!(same_token("secret", "secret"));
+        assert!(!same_token("secret", "secreT"));
+        assert!(!same_token("secret", "secret2"));
+    }
+
+    #[test]
+    fn token_files_are_parsed() {
+        let entries = parse_tokens(
+            "# guests may look\n\
+             guests  g-secret  read\n\
+             \n\
+             phone p-secret read,volume\n\
+             admin a-secret all\n",
+        )
+        .unwrap();
+        assert_eq!(
+            entries,
+            [
+                ("guests", "g-secret", vec![ProxyScope::Read]),
+                (
+                    "phone",
+                    "p-secret",
+                    vec![ProxyScope::Read, ProxyScope::Volume]
+                ),
+                ("admin", "a-secret", ProxyScope::ALL.to_vec()),
+            ]
+        );
+        assert_eq!(
+            parse_tokens("guests g-secret\n").unwrap_err(),
+            "1: expected `name token scopes`, found 2 fields"
+        );
+        assert!(parse_tokens("x y read,admin")
+            .unwrap_err()
+            .contains(r#"unknown proxy scope "admin""#));
+    }
+
+    #[test]
+    fn grants_are_checked() {
+        let proxy = ProxyServer::new(CiderClient::new());
+        assert!(proxy.check_grants().is_err());
+        let proxy = proxy.allow("a", "same", [ProxyScope::Read]);
+        assert!(proxy.check_grants().is_ok());
+        let proxy = proxy.allow("b", "same", [ProxyScope::Read]);
+        let err = proxy.check_grants().unwrap_err();
+        assert!(err.to_string().contains(r#""a" and "b""#), "{err}");
+    }
+
+    #[test]
+    fn grant_debug_redacts_the_token() {
+        let proxy = ProxyServer::new(CiderClient::new()).allow("a", "hunter2", ProxyScope::ALL);
+        assert!(!format!("{proxy:?}").contains("hunter2"));
+    }
+}
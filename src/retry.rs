@@ -0,0 +1,289 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Retry policy with exponential backoff for transient request failures.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::CiderError;
+
+/// Default number of attempts, including the first one.
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// Default delay before the first retry.
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Default upper bound for a single delay.
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Default factor the delay grows by after each retry.
+const DEFAULT_MULTIPLIER: f64 = 2.0;
+
+type RetryPredicate = Arc<dyn Fn(&CiderError) -> bool + Send + Sync>;
+
+/// How a [`CiderClient`](crate::CiderClient) retries failed requests.
+///
+/// Retries are opt-in: install a policy with
+/// [`CiderClientBuilder::retry_policy`](crate::CiderClientBuilder::retry_policy)
+/// or [`CiderClient::with_retry_policy`](crate::CiderClient::with_retry_policy).
+/// It then applies automatically to the safe `GET` calls (`is_active`,
+/// `is_playing`, `now_playing`, `get_queue`, `get_volume`, ...). Commands
+/// sent with `POST` (`next`, `queue_remove_by_index`, ...) are not
+/// idempotent and are only retried through
+/// [`CiderClient::retrying`](crate::CiderClient::retrying).
+///
+/// The delay before retry `n` is `initial_backoff * multiplier^(n - 1)`,
+/// capped at `max_backoff`. With jitter enabled (the default) each delay is
+/// drawn uniformly from the upper half of that value, so clients that failed
+/// together do not retry in lockstep.
+///
+/// By default a request is retried when [`CiderError::is_retryable`] returns
+/// `true`: Cider unreachable, timeouts, dropped connections and `408`, `429`
+/// and `5xx` statuses.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use cider_api::{CiderClient, RetryPolicy};
+///
+/// let client = CiderClient::builder()
+///     .retry_policy(
+///         RetryPolicy::new()
+///             .max_attempts(5)
+///             .initial_backoff(Duration::from_millis(50))
+///             .retry_on(|e| e.is_retryable() && !e.is_not_running()),
+///     )
+///     .build()?;
+/// # Ok::<(), cider_api::CiderError>(())
+/// ```
+#[derive(Clone)]
+#[must_use]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: bool,
+    retry_on: Option<RetryPredicate>,
+}
+
+impl RetryPolicy {
+    /// A policy with 3 attempts, a 100ms initial backoff doubling up to 2s,
+    /// and jitter.
+    pub fn new() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            multiplier: DEFAULT_MULTIPLIER,
+            jitter: true,
+            retry_on: None,
+        }
+    }
+
+    /// Total number of attempts, including the first one. Defaults to `3`.
+    ///
+    /// Values below `1` are treated as `1` (no retries).
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry. Defaults to 100ms.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Upper bound for a single delay. Defaults to 2s.
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Factor the delay grows by after each retry. Defaults to `2.0`.
+    ///
+    /// Values below `1.0` are treated as `1.0` (constant delay).
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Randomise each delay within the upper half of its value. Defaults to
+    /// `true`.
+    pub fn jitter(mut self, enabled: bool) -> Self {
+        self.jitter = enabled;
+        self
+    }
+
+    /// Decide which errors are retried. Defaults to
+    /// [`CiderError::is_retryable`].
+    pub fn retry_on<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&CiderError) -> bool + Send + Sync + 'static,
+    {
+        self.retry_on = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Total number of attempts, including the first one.
+    #[must_use]
+    pub fn attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// The delay before retry number `retry` (1-based), before jitter.
+    #[must_use]
+    pub fn backoff(&self, retry: u32) -> Duration {
+        if self.initial_backoff.is_zero() {
+            return Duration::ZERO;
+        }
+        let exponent = i32::try_from(retry.saturating_sub(1)).unwrap_or(i32::MAX);
+        let factor = self.multiplier.powi(exponent);
+        let max = self.max_backoff.as_secs_f64();
+        let secs = (self.initial_backoff.as_secs_f64() * factor).min(max);
+        // A huge `max_backoff` can round past what a `Duration` holds.
+        Duration::try_from_secs_f64(secs).unwrap_or(self.max_backoff)
+    }
+
+    /// The delay to sleep before retry number `retry`, with jitter applied.
+    pub(crate) fn delay(&self, retry: u32) -> Duration {
+        let backoff = self.backoff(retry);
+        if self.jitter {
+            let secs = backoff.as_secs_f64() * (0.5 + fastrand::f64() / 2.0);
+            Duration::try_from_secs_f64(secs).unwrap_or(backoff)
+        } else {
+            backoff
+        }
+    }
+
+    /// Whether `err` on attempt number `attempt` (1-based) should be retried.
+    pub(crate) fn should_retry(&self, err: &CiderError, attempt: u32) -> bool {
+        if attempt >= self.max_attempts {
+            return false;
+        }
+        match &self.retry_on {
+            Some(predicate) => predicate(err),
+            None => err.is_retryable(),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("multiplier", &self.multiplier)
+            .field("jitter", &self.jitter)
+            .field("retry_on", &self.retry_on.as_ref().map(|_| "<fn>"))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Endpoint;
+    use reqwest::StatusCode;
+
+    fn api_error(status: StatusCode) -> CiderError {
+        CiderError::Api {
+            endpoint: Endpoint::playback(reqwest::Method::GET, "/volume"),
+            status,
+            body: None,
+        }
+    }
+
+    #[test]
+    fn defaults() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.attempts(), 3);
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn backoff_grows_exponentially() {
+        let policy = RetryPolicy::new()
+            .initial_backoff(Duration::from_millis(100))
+            .multiplier(3.0)
+            .max_backoff(Duration::from_secs(60));
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(300));
+        assert_eq!(policy.backoff(3), Duration::from_millis(900));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = RetryPolicy::new().max_backoff(Duration::from_millis(250));
+        assert_eq!(policy.backoff(10), Duration::from_millis(250));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(250));
+
+        let policy = RetryPolicy::new().max_backoff(Duration::MAX);
+        assert_eq!(policy.backoff(u32::MAX), Duration::MAX);
+        assert!(policy.delay(u32::MAX) >= Duration::from_secs(u64::MAX / 2));
+    }
+
+    #[test]
+    fn jitter_stays_in_upper_half() {
+        let policy = RetryPolicy::new().initial_backoff(Duration::from_millis(100));
+        for _ in 0..100 {
+            let delay = policy.delay(1);
+            assert!(delay >= Duration::from_millis(50));
+            assert!(delay <= Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn no_jitter_is_exact() {
+        let policy = RetryPolicy::new().jitter(false);
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+    }
+
+    #[test]
+    fn max_attempts_is_at_least_one() {
+        let policy = RetryPolicy::new().max_attempts(0);
+        assert_eq!(policy.attempts(), 1);
+        assert!(!policy.should_retry(&api_error(StatusCode::SERVICE_UNAVAILABLE), 1));
+    }
+
+    #[test]
+    fn should_retry_uses_is_retryable_by_default() {
+        let policy = RetryPolicy::new();
+        assert!(policy.should_retry(&api_error(StatusCode::SERVICE_UNAVAILABLE), 1));
+        assert!(!policy.should_retry(&api_error(StatusCode::BAD_REQUEST), 1));
+    }
+
+    #[test]
+    fn should_retry_stops_at_max_attempts() {
+        let policy = RetryPolicy::new().max_attempts(2);
+        let err = api_error(StatusCode::SERVICE_UNAVAILABLE);
+        assert!(policy.should_retry(&err, 1));
+        assert!(!policy.should_retry(&err, 2));
+    }
+
+    #[test]
+    fn custom_predicate() {
+        let policy =
+            RetryPolicy::new().retry_on(|e| e.status_code() == Some(StatusCode::BAD_REQUEST));
+        assert!(policy.should_retry(&api_error(StatusCode::BAD_REQUEST), 1));
+        assert!(!policy.should_retry(&api_error(StatusCode::SERVICE_UNAVAILABLE), 1));
+    }
+
+    #[test]
+    fn debug_hides_predicate() {
+        let policy = RetryPolicy::new().retry_on(|_| true);
+        assert!(format!("{policy:?}").contains("<fn>"));
+    }
+}
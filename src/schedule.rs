@@ -0,0 +1,851 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Alarm-clock style scheduling of playback actions.
+
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::client::CiderClient;
+use crate::cron::Cron;
+use crate::error::CiderError;
+use crate::fade::FadeCurve;
+use crate::item::ItemRef;
+use crate::watcher::stopped;
+
+/// Default time a run may start late, or wait for Cider, before it counts
+/// as missed.
+const DEFAULT_GRACE: Duration = Duration::from_secs(60);
+
+/// Default time between checks while waiting for Cider to come up.
+const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Longest single sleep. The wall clock is re-read after each one, so
+/// suspends and clock changes are noticed within this time.
+const MAX_SLEEP: Duration = Duration::from_secs(30);
+
+/// When a [`Job`] runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trigger {
+    /// Once, at a wall-clock time.
+    At(DateTime<Local>),
+    /// Whenever the cron expression matches.
+    Cron(Cron),
+}
+
+impl Trigger {
+    /// The first run strictly after `after`, if any.
+    #[must_use]
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        match self {
+            Self::At(at) => (*at > after).then_some(*at),
+            Self::Cron(cron) => cron.next_after(after),
+        }
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::At(at) => write!(f, "at {}", at.format("%Y-%m-%d %H:%M:%S")),
+            Self::Cron(cron) => write!(f, "cron `{cron}`"),
+        }
+    }
+}
+
+/// One step of a [`Job`].
+///
+/// In schedule files, actions are tables tagged with `action`:
+///
+/// ```toml
+/// actions = [
+///     { action = "set_volume", volume = 0.0 },
+///     { action = "play_item", item = { type = "playlists", id = "pl.u-xxxx" } },
+///     { action = "shuffle", on = true },
+///     { action = "fade_in", to = 0.6, duration = "5m" },
+/// ]
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Action {
+    /// [`CiderClient::play_item`].
+    PlayItem {
+        /// The item to play.
+        item: ItemRef,
+    },
+    /// [`CiderClient::play_parsed`] with an Apple Music link.
+    PlayUrl {
+        /// A `music.apple.com` link.
+        url: String,
+    },
+    /// [`CiderClient::play`].
+    Play,
+    /// [`CiderClient::pause`].
+    Pause,
+    /// [`CiderClient::stop`].
+    Stop,
+    /// [`CiderClient::set_volume`].
+    SetVolume {
+        /// Volume in `0.0..=1.0`.
+        volume: f32,
+    },
+    /// Set the volume to 0, then [ramp](CiderClient::ramp_volume) it up to
+    /// `to`. Waits for the ramp before the next action runs.
+    FadeIn {
+        /// Volume to end at.
+        to: f32,
+        /// Length of the fade, e.g. `"30s"`, `"5m"` or a number of seconds.
+        #[serde(with = "duration_serde")]
+        duration: Duration,
+        /// Shape of the fade.
+        #[serde(default)]
+        curve: FadeCurve,
+    },
+    /// [`CiderClient::set_shuffle_mode`].
+    Shuffle {
+        /// `true` to shuffle.
+        on: bool,
+    },
+    /// Wait before the next action.
+    Wait {
+        /// How long, e.g. `"10s"` or a number of seconds.
+        #[serde(with = "duration_serde")]
+        duration: Duration,
+    },
+}
+
+impl Action {
+    async fn run(&self, client: &CiderClient) -> Result<(), CiderError> {
+        match self {
+            Self::PlayItem { item } => client.play_item(item).await,
+            Self::PlayUrl { url } => client.play_parsed(url).await.map(drop),
+            Self::Play => client.play().await,
+            Self::Pause => client.pause().await,
+            Self::Stop => client.stop().await,
+            Self::SetVolume { volume } => client.set_volume(*volume).await,
+            Self::FadeIn {
+                to,
+                duration,
+                curve,
+            } => {
+                client.set_volume(0.0).await?;
+                client.ramp_volume(*to, *duration, *curve).await
+            }
+            Self::Shuffle { on } => client.set_shuffle_mode(*on).await,
+            Self::Wait { duration } => {
+                tokio::time::sleep(*duration).await;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A named list of [`Action`]s run on a [`Trigger`].
+#[derive(Debug, Clone, PartialEq)]
+#[must_use]
+pub struct Job {
+    name: String,
+    trigger: Trigger,
+    actions: Vec<Action>,
+}
+
+impl Job {
+    /// Create a job with no actions.
+    pub fn new(name: impl Into<String>, trigger: Trigger) -> Self {
+        Self {
+            name: name.into(),
+            trigger,
+            actions: Vec::new(),
+        }
+    }
+
+    /// Append an action.
+    pub fn action(mut self, action: Action) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    /// The job's name, used in [`ScheduleEvent`]s.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// When the job runs.
+    #[must_use]
+    pub fn trigger(&self) -> &Trigger {
+        &self.trigger
+    }
+
+    /// What the job does, in order.
+    #[must_use]
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+}
+
+/// A job as written in a schedule file: exactly one of `at` and `cron`.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawJob {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cron: Option<Cron>,
+    actions: Vec<Action>,
+}
+
+impl TryFrom<RawJob> for Job {
+    type Error = String;
+
+    fn try_from(raw: RawJob) -> Result<Self, Self::Error> {
+        let trigger = match (raw.at, raw.cron) {
+            (Some(at), None) => Trigger::At(parse_local_time(&at)?),
+            (None, Some(cron)) => Trigger::Cron(cron),
+            _ => {
+                return Err(format!(
+                    "job `{}` needs exactly one of `at` and `cron`",
+                    raw.name
+                ))
+            }
+        };
+        Ok(Self {
+            name: raw.name,
+            trigger,
+            actions: raw.actions,
+        })
+    }
+}
+
+impl From<&Job> for RawJob {
+    fn from(job: &Job) -> Self {
+        let (at, cron) = match &job.trigger {
+            Trigger::At(at) => (Some(at.to_rfc3339()), None),
+            Trigger::Cron(cron) => (None, Some(cron.clone())),
+        };
+        Self {
+            name: job.name.clone(),
+            at,
+            cron,
+            actions: job.actions.clone(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Job {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        RawJob::deserialize(deserializer)?
+            .try_into()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for Job {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RawJob::from(self).serialize(serializer)
+    }
+}
+
+/// Parse an RFC 3339 time, or a local time without offset
+/// (`2026-10-17T07:30:00`, `2026-10-17 07:30`).
+fn parse_local_time(s: &str) -> Result<DateTime<Local>, String> {
+    if let Ok(time) = DateTime::<FixedOffset>::parse_from_rfc3339(s) {
+        return Ok(time.with_timezone(&Local));
+    }
+    [
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+    .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+    .ok_or_else(|| format!("invalid time `{s}`, expected e.g. `2026-10-17T07:30:00`"))
+}
+
+/// A set of [`Job`]s, loadable from TOML or JSON.
+///
+/// ```toml
+/// [[job]]
+/// name = "wake up"
+/// cron = "30 7 * * mon-fri"
+/// actions = [
+///     { action = "set_volume", volume = 0.0 },
+///     { action = "play_item", item = { type = "playlists", id = "pl.u-xxxx" } },
+///     { action = "fade_in", to = 0.6, duration = "5m" },
+/// ]
+///
+/// [[job]]
+/// name = "lights out"
+/// at = "2026-10-17T23:30:00"
+/// actions = [{ action = "stop" }]
+/// ```
+///
+/// The JSON form is `{"job": [{"name": ..., "cron": ..., "actions": [...]}]}`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub struct Schedule {
+    #[serde(default, rename = "job")]
+    jobs: Vec<Job>,
+}
+
+impl Schedule {
+    /// Create an empty schedule.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a job.
+    pub fn job(mut self, job: Job) -> Self {
+        self.jobs.push(job);
+        self
+    }
+
+    /// The jobs, in the order they were added.
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    /// Parse a TOML schedule.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::Config`] if the schedule is malformed.
+    pub fn from_toml(s: &str) -> Result<Self, CiderError> {
+        toml::from_str(s).map_err(|e| CiderError::Config(format!("invalid schedule: {e}")))
+    }
+
+    /// Parse a JSON schedule.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::Config`] if the schedule is malformed.
+    pub fn from_json(s: &str) -> Result<Self, CiderError> {
+        serde_json::from_str(s).map_err(|e| CiderError::Config(format!("invalid schedule: {e}")))
+    }
+
+    /// Load a schedule file, as JSON if its extension is `.json` and as
+    /// TOML otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::Config`] if the file cannot be read or is
+    /// malformed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CiderError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| CiderError::Config(format!("cannot read {}: {e}", path.display())))?;
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        if is_json {
+            Self::from_json(&text)
+        } else {
+            Self::from_toml(&text)
+        }
+    }
+}
+
+/// Why a scheduled run did not happen.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum MissReason {
+    /// Cider could not be reached, or kept failing with a transient error,
+    /// for the whole grace period.
+    NotReachable(CiderError),
+    /// The scheduler woke up too late, e.g. after the machine was
+    /// suspended.
+    Late(Duration),
+}
+
+impl fmt::Display for MissReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotReachable(e) => write!(f, "Cider was not reachable ({e})"),
+            Self::Late(late) => write!(f, "woke up {}s late", late.as_secs()),
+        }
+    }
+}
+
+/// What happened to a scheduled run, from [`SchedulerHandle::next_event`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ScheduleEvent {
+    /// All actions of the job ran.
+    Ran {
+        /// The job's name.
+        job: String,
+        /// When the run was due.
+        scheduled: DateTime<Local>,
+    },
+    /// The run was skipped.
+    Missed {
+        /// The job's name.
+        job: String,
+        /// When the run was due. With `missed > 1`, the first of the runs.
+        scheduled: DateTime<Local>,
+        /// How many runs were skipped. A scheduler that slept through
+        /// several runs of a job, e.g. while the machine was suspended,
+        /// reports them in one event starting at `scheduled`.
+        missed: u32,
+        /// Why it was skipped.
+        reason: MissReason,
+    },
+    /// An action failed; the remaining actions were not run.
+    Failed {
+        /// The job's name.
+        job: String,
+        /// When the run was due.
+        scheduled: DateTime<Local>,
+        /// The error from the failed action.
+        error: CiderError,
+    },
+}
+
+/// Runs a [`Schedule`] against a [`CiderClient`].
+///
+/// At each due time the scheduler checks that Cider is reachable, waiting
+/// up to [`grace`](Self::grace) for it to come up, then runs the job's
+/// actions in order. Runs that cannot happen — Cider stays unreachable, or
+/// the machine was asleep past the grace period — are reported as
+/// [`ScheduleEvent::Missed`] instead of being run late. Jobs run
+/// concurrently, so a long fade-in does not delay a later job.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), cider_api::CiderError> {
+/// use cider_api::{CiderClient, Schedule, ScheduleEvent};
+///
+/// let schedule = Schedule::load("alarms.toml")?;
+/// let mut scheduler = CiderClient::new().scheduler(schedule).start();
+/// while let Some(event) = scheduler.next_event().await {
+///     if let ScheduleEvent::Missed { job, reason, .. } = event {
+///         eprintln!("missed {job}: {reason}");
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[must_use = "a Scheduler does nothing until `start` is called"]
+pub struct Scheduler {
+    client: CiderClient,
+    schedule: Schedule,
+    grace: Duration,
+    retry_interval: Duration,
+}
+
+impl Scheduler {
+    /// Create a scheduler for `client`.
+    pub fn new(client: CiderClient, schedule: Schedule) -> Self {
+        Self {
+            client,
+            schedule,
+            grace: DEFAULT_GRACE,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+        }
+    }
+
+    /// How late a run may start, including time spent waiting for Cider,
+    /// before it is reported as missed (default 60s).
+    ///
+    /// Runs due up to this long before [`start`](Self::start) still run.
+    pub fn grace(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    /// Time between reachability checks while Cider is down (default 5s).
+    pub fn retry_interval(mut self, interval: Duration) -> Self {
+        self.retry_interval = interval;
+        self
+    }
+
+    /// Start the scheduler on the current Tokio runtime.
+    ///
+    /// Dropping the returned handle stops it; runs already in progress
+    /// finish.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside a Tokio runtime.
+    pub fn start(self) -> SchedulerHandle {
+        let (cancel, cancelled) = watch::channel(false);
+        let (events, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(self.run(cancelled, events));
+        SchedulerHandle {
+            events: rx,
+            cancel,
+            task,
+        }
+    }
+
+    async fn run(
+        self,
+        mut cancel: watch::Receiver<bool>,
+        events: mpsc::UnboundedSender<ScheduleEvent>,
+    ) {
+        let now = Local::now();
+        let start = chrono::Duration::from_std(self.grace)
+            .ok()
+            .and_then(|grace| now.checked_sub_signed(grace))
+            .unwrap_or(now);
+        let mut due: Vec<Option<DateTime<Local>>> = self
+            .schedule
+            .jobs
+            .iter()
+            .map(|job| job.trigger.next_after(start))
+            .collect();
+
+        loop {
+            let now = Local::now();
+            for (job, next) in self.schedule.jobs.iter().zip(&mut due) {
+                let Some(scheduled) = *next else { continue };
+                if scheduled > now {
+                    continue;
+                }
+                *next = job.trigger.next_after(now);
+                let late = (now - scheduled).to_std().unwrap_or_default();
+                if late > self.grace {
+                    let missed = missed_runs(&job.trigger, scheduled, now);
+                    warn!(job = %job.name, ?late, missed, "scheduled run missed");
+                    let _ = events.send(ScheduleEvent::Missed {
+                        job: job.name.clone(),
+                        scheduled,
+                        missed,
+                        reason: MissReason::Late(late),
+                    });
+                    continue;
+                }
+                tokio::spawn(run_job(
+                    self.client.clone(),
+                    job.clone(),
+                    scheduled,
+                    self.grace.saturating_sub(late),
+                    self.retry_interval,
+                    events.clone(),
+                ));
+            }
+
+            let Some(next) = due.iter().flatten().min() else {
+                debug!("schedule has no more runs");
+                return;
+            };
+            let wait = (*next - Local::now()).to_std().unwrap_or_default();
+            tokio::select! {
+                () = tokio::time::sleep(wait.min(MAX_SLEEP)) => {}
+                () = stopped(&mut cancel) => return,
+            }
+        }
+    }
+}
+
+/// The number of runs of `trigger` from `first` up to `now`, both included.
+fn missed_runs(trigger: &Trigger, first: DateTime<Local>, now: DateTime<Local>) -> u32 {
+    let mut missed: u32 = 1;
+    let mut last = first;
+    while let Some(next) = trigger.next_after(last).filter(|&next| next <= now) {
+        missed = missed.saturating_add(1);
+        last = next;
+    }
+    missed
+}
+
+/// Wait for Cider for up to `grace`, then run `job`'s actions.
+async fn run_job(
+    client: CiderClient,
+    job: Job,
+    scheduled: DateTime<Local>,
+    grace: Duration,
+    retry_interval: Duration,
+    events: mpsc::UnboundedSender<ScheduleEvent>,
+) {
+    let deadline = tokio::time::Instant::now() + grace;
+    loop {
+        match client.is_active().await {
+            Ok(()) => break,
+            Err(e)
+                if e.is_retryable()
+                    && tokio::time::Instant::now() + retry_interval <= deadline =>
+            {
+                debug!(job = %job.name, error = %e, "waiting for Cider");
+                tokio::time::sleep(retry_interval).await;
+            }
+            Err(e) if e.is_retryable() => {
+                warn!(job = %job.name, error = %e, "scheduled run missed");
+                let _ = events.send(ScheduleEvent::Missed {
+                    job: job.name,
+                    scheduled,
+                    missed: 1,
+                    reason: MissReason::NotReachable(e),
+                });
+                return;
+            }
+            Err(error) => {
+                let _ = events.send(ScheduleEvent::Failed {
+                    job: job.name,
+                    scheduled,
+                    error,
+                });
+                return;
+            }
+        }
+    }
+
+    debug!(job = %job.name, "running scheduled job");
+    for action in &job.actions {
+        if let Err(error) = action.run(&client).await {
+            warn!(job = %job.name, error = %error, "scheduled action failed");
+            let _ = events.send(ScheduleEvent::Failed {
+                job: job.name,
+                scheduled,
+                error,
+            });
+            return;
+        }
+    }
+    let _ = events.send(ScheduleEvent::Ran {
+        job: job.name,
+        scheduled,
+    });
+}
+
+/// A running [`Scheduler`].
+///
+/// Dropping the handle stops the scheduler.
+#[derive(Debug)]
+#[must_use = "dropping a SchedulerHandle stops the scheduler"]
+pub struct SchedulerHandle {
+    events: mpsc::UnboundedReceiver<ScheduleEvent>,
+    cancel: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl SchedulerHandle {
+    /// The next run outcome. Returns `None` once the scheduler has stopped
+    /// and every run it started has finished.
+    pub async fn next_event(&mut self) -> Option<ScheduleEvent> {
+        self.events.recv().await
+    }
+
+    /// Stop scheduling new runs. Runs already in progress finish.
+    pub fn stop(&self) {
+        self.cancel.send_replace(true);
+    }
+
+    /// Returns `true` once the scheduler has stopped, either through
+    /// [`stop`](Self::stop) or because no job has runs left.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl CiderClient {
+    /// Create a [`Scheduler`] running `schedule` against this client. Call
+    /// [`start`](Scheduler::start) to run it.
+    pub fn scheduler(&self, schedule: Schedule) -> Scheduler {
+        Scheduler::new(self.clone(), schedule)
+    }
+}
+
+/// Parse a duration such as `90`, `"90"`, `"30s"`, `"5m"`, `"1h30m"` or
+/// `"250ms"`. Bare numbers are seconds.
+pub(crate) fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    if let Ok(secs) = s.parse::<f64>() {
+        return Duration::try_from_secs_f64(secs).ok();
+    }
+    let mut total = Duration::ZERO;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .filter(|&i| i > 0)?;
+        let value: f64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let unit = match &rest[..unit_len] {
+            "ms" => 0.001,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            _ => return None,
+        };
+        rest = &rest[unit_len..];
+        total += Duration::try_from_secs_f64(value * unit).ok()?;
+    }
+    (!s.is_empty()).then_some(total)
+}
+
+/// Serde for durations written as seconds or with units (`"5m"`).
+mod duration_serde {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::parse_duration;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Seconds(f64),
+        Text(String),
+    }
+
+    pub(super) fn serialize<S: Serializer>(
+        duration: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(duration.as_secs_f64())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        match Raw::deserialize(deserializer)? {
+            Raw::Seconds(secs) => {
+                Duration::try_from_secs_f64(secs).map_err(serde::de::Error::custom)
+            }
+            Raw::Text(text) => parse_duration(&text)
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid duration `{text}`"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::ItemKind;
+
+    const ALARMS: &str = r#"
+[[job]]
+name = "wake up"
+cron = "30 7 * * mon-fri"
+actions = [
+    { action = "set_volume", volume = 0.0 },
+    { action = "play_item", item = { type = "playlists", id = "pl.u-abc" } },
+    { action = "shuffle", on = true },
+    { action = "fade_in", to = 0.6, duration = "5m", curve = "ease_in" },
+]
+
+[[job]]
+name = "lights out"
+at = "2026-10-17T23:30:00"
+actions = [{ action = "stop" }]
+"#;
+
+    #[test]
+    fn parses_toml_schedule() {
+        let schedule = Schedule::from_toml(ALARMS).unwrap();
+        let [wake, stop] = schedule.jobs() else {
+            panic!("expected two jobs");
+        };
+        assert_eq!(wake.name(), "wake up");
+        assert!(matches!(wake.trigger(), Trigger::Cron(_)));
+        assert_eq!(
+            wake.actions()[1],
+            Action::PlayItem {
+                item: ItemRef::new(ItemKind::Playlists, "pl.u-abc").unwrap()
+            }
+        );
+        assert_eq!(
+            wake.actions()[3],
+            Action::FadeIn {
+                to: 0.6,
+                duration: Duration::from_secs(300),
+                curve: FadeCurve::EaseIn,
+            }
+        );
+        let Trigger::At(at) = stop.trigger() else {
+            panic!("expected a one-shot job");
+        };
+        assert_eq!(at.naive_local().to_string(), "2026-10-17 23:30:00");
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let schedule = Schedule::from_toml(ALARMS).unwrap();
+        let json = serde_json::to_string(&schedule).unwrap();
+        assert_eq!(Schedule::from_json(&json).unwrap(), schedule);
+    }
+
+    #[test]
+    fn rejects_bad_jobs() {
+        for (toml, needle) in [
+            ("[[job]]\nname = \"x\"\nactions = []", "exactly one of"),
+            ("[[job]]\nname = \"x\"\nat = \"tomorrow\"\nactions = []", "invalid time"),
+            ("[[job]]\nname = \"x\"\ncron = \"* *\"\nactions = []", "cron"),
+            (
+                "[[job]]\nname = \"x\"\ncron = \"@daily\"\nactions = [{ action = \"dance\" }]",
+                "dance",
+            ),
+            (
+                "[[job]]\nname = \"x\"\ncron = \"@daily\"\nactions = [{ action = \"play_item\", item = { type = \"songs\", id = \"pl.1\" } }]",
+                "pl.1",
+            ),
+        ] {
+            let err = Schedule::from_toml(toml).unwrap_err().to_string();
+            assert!(err.contains(needle), "{err}");
+        }
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1.5"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("5 minutes"), None);
+        assert_eq!(parse_duration("m"), None);
+    }
+
+    #[test]
+    fn one_shot_trigger_fires_once() {
+        let now = Local::now();
+        let trigger = Trigger::At(now);
+        assert_eq!(
+            trigger.next_after(now - chrono::Duration::seconds(1)),
+            Some(now)
+        );
+        assert_eq!(trigger.next_after(now), None);
+    }
+
+    #[test]
+    fn missed_runs_are_counted() {
+        let first = Local.with_ymd_and_hms(2026, 10, 17, 7, 0, 0).unwrap();
+        let every_five = Trigger::Cron("*/5 * * * *".parse().unwrap());
+        assert_eq!(missed_runs(&every_five, first, first), 1);
+        assert_eq!(
+            missed_runs(&every_five, first, first + chrono::Duration::minutes(4)),
+            1
+        );
+        // A suspend from 7:00 to 8:02 sleeps through 7:00, 7:05, ..., 8:00.
+        assert_eq!(
+            missed_runs(&every_five, first, first + chrono::Duration::minutes(62)),
+            13
+        );
+        let once = Trigger::At(first);
+        assert_eq!(
+            missed_runs(&once, first, first + chrono::Duration::days(1)),
+            1
+        );
+    }
+}
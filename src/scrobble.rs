@@ -0,0 +1,889 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Scrobbling: deciding when a track counts as played, and delivering the
+//! plays through a persistent retry queue.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, MissedTickBehavior};
+use tracing::{debug, warn};
+
+use crate::client::CiderClient;
+use crate::error::{CiderError, Endpoint};
+use crate::types::NowPlaying;
+use crate::watcher::{same_track, stopped};
+
+/// Tracks shorter than this are never scrobbled.
+const MIN_TRACK_LENGTH: Duration = Duration::from_secs(30);
+
+/// Listening for this long always counts, even for long tracks.
+const MAX_THRESHOLD: Duration = Duration::from_secs(4 * 60);
+
+/// Position jitter between two observations that is not treated as a seek.
+const SEEK_TOLERANCE: Duration = Duration::from_secs(3);
+
+/// A track ending this close to its end and starting over is a repeat.
+const END_WINDOW: Duration = Duration::from_secs(10);
+
+/// Default time between player polls.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Default time between attempts to deliver queued scrobbles.
+const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Default cap on queued scrobbles; the oldest are dropped beyond it.
+const DEFAULT_MAX_QUEUED: usize = 10_000;
+
+/// A played track, ready to submit to a scrobbling service.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scrobble {
+    /// Artist name.
+    pub artist_name: String,
+    /// Song name.
+    pub name: String,
+    /// Album name.
+    pub album_name: String,
+    /// International Standard Recording Code, if Cider reported one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isrc: Option<String>,
+    /// When playback of the track started.
+    #[serde(with = "unix_seconds")]
+    pub started_at: SystemTime,
+    /// Length of the track.
+    #[serde(rename = "duration_ms", with = "millis")]
+    pub duration: Duration,
+}
+
+impl Scrobble {
+    /// Describe `track`, started at `started_at`.
+    #[must_use]
+    pub fn new(track: &NowPlaying, started_at: SystemTime) -> Self {
+        Self {
+            artist_name: track.artist_name.clone(),
+            name: track.name.clone(),
+            album_name: track.album_name.clone(),
+            isrc: track.isrc.clone().filter(|isrc| !isrc.is_empty()),
+            started_at,
+            duration: track.duration(),
+        }
+    }
+
+    /// [`started_at`](Self::started_at) as seconds since the Unix epoch.
+    #[must_use]
+    pub fn started_at_unix(&self) -> u64 {
+        self.started_at
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs())
+    }
+}
+
+/// What [`PlayTracker::observe`] noticed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ScrobbleEvent {
+    /// A track started playing. Services show it as "now playing".
+    NowPlaying(Scrobble),
+    /// The current track has been listened to long enough to count.
+    Scrobbled(Scrobble),
+}
+
+/// The play in progress.
+#[derive(Debug)]
+struct Play {
+    track: NowPlaying,
+    started_at: SystemTime,
+    listened: Duration,
+    position: f64,
+    seen_at: Instant,
+    scrobbled: bool,
+}
+
+/// Decides when a track counts as played, following Last.fm's rules.
+///
+/// Feed it the player state every few seconds with
+/// [`observe`](Self::observe). A track is scrobbled once it has actually
+/// been listened to for half its length or four minutes, whichever comes
+/// first; tracks under 30 seconds never count. Listening time only grows
+/// while the position advances at playback speed, so pauses and seeks
+/// (forward or back) are not credited. A track that ends and starts over
+/// on repeat is a new play.
+///
+/// ```
+/// # use cider_api::{NowPlaying, PlayTracker, ScrobbleEvent};
+/// # use std::time::Duration;
+/// # use tokio::time::Instant;
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let mut track: NowPlaying = serde_json::from_str(
+///     r#"{"name": "Song", "artistName": "Artist", "durationInMillis": 60000}"#,
+/// ).unwrap();
+/// let mut tracker = PlayTracker::new();
+/// let start = Instant::now();
+///
+/// let events = tracker.observe(start, Some(&track), true);
+/// assert!(matches!(events[..], [ScrobbleEvent::NowPlaying(_)]));
+///
+/// track.current_playback_time = 30.0;
+/// let events = tracker.observe(start + Duration::from_secs(30), Some(&track), true);
+/// assert!(matches!(events[..], [ScrobbleEvent::Scrobbled(_)]));
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct PlayTracker {
+    play: Option<Play>,
+}
+
+impl PlayTracker {
+    /// Create a tracker that has not seen anything yet.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the player state at `at`: the loaded track (if any) and
+    /// whether it is playing.
+    pub fn observe(
+        &mut self,
+        at: Instant,
+        track: Option<&NowPlaying>,
+        is_playing: bool,
+    ) -> Vec<ScrobbleEvent> {
+        let Some(track) = track else {
+            self.play = None;
+            return Vec::new();
+        };
+        let mut events = Vec::new();
+
+        let restarted = match &mut self.play {
+            Some(play) if same_track(&play.track, track) => {
+                let elapsed = at.saturating_duration_since(play.seen_at);
+                let advanced = track.current_playback_time - play.position;
+                let limit = (elapsed + SEEK_TOLERANCE).as_secs_f64();
+                let repeated = advanced < 0.0
+                    && track.current_playback_time <= limit
+                    && (play.scrobbled
+                        || play.track.duration().as_secs_f64() - play.position
+                            <= END_WINDOW.as_secs_f64());
+                if !repeated && (0.0..=limit).contains(&advanced) {
+                    play.listened += Duration::from_secs_f64(advanced);
+                }
+                play.position = track.current_playback_time;
+                play.seen_at = at;
+                play.track = track.clone();
+                repeated
+            }
+            _ => true,
+        };
+
+        if restarted {
+            if !is_playing && track.current_playback_time <= 0.0 {
+                // Loaded but not started: wait for playback to begin.
+                self.play = None;
+                return events;
+            }
+            let position =
+                Duration::try_from_secs_f64(track.current_playback_time).unwrap_or_default();
+            let started_at = SystemTime::now()
+                .checked_sub(position)
+                .unwrap_or_else(SystemTime::now);
+            debug!(track = %track.name, "play started");
+            events.push(ScrobbleEvent::NowPlaying(Scrobble::new(track, started_at)));
+            self.play = Some(Play {
+                track: track.clone(),
+                started_at,
+                listened: Duration::ZERO,
+                position: track.current_playback_time,
+                seen_at: at,
+                scrobbled: false,
+            });
+        }
+
+        if let Some(play) = &mut self.play {
+            let length = play.track.duration();
+            let threshold = (length / 2).min(MAX_THRESHOLD);
+            if !play.scrobbled && length >= MIN_TRACK_LENGTH && play.listened >= threshold {
+                play.scrobbled = true;
+                debug!(track = %play.track.name, listened = ?play.listened, "scrobbled");
+                events.push(ScrobbleEvent::Scrobbled(Scrobble::new(
+                    &play.track,
+                    play.started_at,
+                )));
+            }
+        }
+        events
+    }
+
+    /// How long the current track has been listened to.
+    #[must_use]
+    pub fn listened(&self) -> Duration {
+        self.play
+            .as_ref()
+            .map_or(Duration::ZERO, |play| play.listened)
+    }
+}
+
+/// A scrobbling service.
+///
+/// Implemented by the built-in submitters; implement it to send plays
+/// anywhere else.
+pub trait ScrobbleSink {
+    /// Short name for logs, e.g. `"ListenBrainz"`.
+    fn name(&self) -> &str;
+
+    /// Announce the track that just started. Failures are logged and
+    /// otherwise ignored.
+    fn now_playing(&self, track: &Scrobble) -> impl Future<Output = Result<(), CiderError>> + Send;
+
+    /// Submit completed plays, at most [`max_batch`](Self::max_batch) at a
+    /// time.
+    ///
+    /// Return a [retryable](CiderError::is_retryable) or
+    /// [auth](CiderError::is_auth) error to keep the plays queued for a
+    /// later attempt; any other error drops them.
+    fn submit(&self, scrobbles: &[Scrobble])
+        -> impl Future<Output = Result<(), CiderError>> + Send;
+
+    /// Largest batch [`submit`](Self::submit) accepts (default 50).
+    fn max_batch(&self) -> usize {
+        50
+    }
+}
+
+/// Scrobbles waiting to be submitted, optionally persisted to a JSON file
+/// so plays survive restarts while the service is down.
+#[derive(Debug)]
+pub struct ScrobbleQueue {
+    path: Option<PathBuf>,
+    pending: VecDeque<Scrobble>,
+    max_len: usize,
+}
+
+impl ScrobbleQueue {
+    /// A queue that lives only as long as the process.
+    #[must_use]
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            pending: VecDeque::new(),
+            max_len: DEFAULT_MAX_QUEUED,
+        }
+    }
+
+    /// Open the queue persisted at `path`, creating it on the first write.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::Io`] if the file exists but cannot be read,
+    /// or [`CiderError::Config`] if it is not a valid queue.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, CiderError> {
+        let path = path.into();
+        let pending = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| {
+                CiderError::Config(format!("invalid scrobble queue {}: {e}", path.display()))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => VecDeque::new(),
+            Err(source) => {
+                return Err(CiderError::Io {
+                    context: format!("cannot read {}", path.display()),
+                    source,
+                })
+            }
+        };
+        Ok(Self {
+            path: Some(path),
+            pending,
+            max_len: DEFAULT_MAX_QUEUED,
+        })
+    }
+
+    /// Keep at most `max_len` scrobbles, dropping the oldest (default
+    /// 10 000).
+    #[must_use]
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len.max(1);
+        self
+    }
+
+    /// The file the queue is persisted to, if any.
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Number of scrobbles waiting.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns `true` if nothing is waiting.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// The scrobbles waiting, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &Scrobble> {
+        self.pending.iter()
+    }
+
+    /// Queue a scrobble and persist the queue.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::Io`] if the queue cannot be saved. The
+    /// scrobble stays queued in memory.
+    pub fn push(&mut self, scrobble: Scrobble) -> Result<(), CiderError> {
+        self.pending.push_back(scrobble);
+        while self.pending.len() > self.max_len {
+            let dropped = self.pending.pop_front();
+            warn!(?dropped, "scrobble queue full, dropped the oldest play");
+        }
+        self.save()
+    }
+
+    /// Submit queued scrobbles to `sink`, oldest first, and return how many
+    /// were delivered.
+    ///
+    /// Stops at the first batch that fails with an error worth retrying
+    /// (see [`ScrobbleSink::submit`]); batches rejected for other reasons
+    /// are dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error that stopped delivery, or [`CiderError::Io`] if the
+    /// queue cannot be saved.
+    pub async fn flush<S: ScrobbleSink>(&mut self, sink: &S) -> Result<usize, CiderError> {
+        let mut delivered = 0;
+        while !self.pending.is_empty() {
+            let batch_len = self.pending.len().min(sink.max_batch().max(1));
+            let batch: Vec<Scrobble> = self.pending.iter().take(batch_len).cloned().collect();
+            match sink.submit(&batch).await {
+                Ok(()) => delivered += batch_len,
+                Err(e) if e.is_retryable() || e.is_auth() => {
+                    self.save()?;
+                    return Err(e);
+                }
+                Err(e) => {
+                    warn!(sink = sink.name(), error = %e, count = batch_len, "scrobbles rejected, dropping");
+                }
+            }
+            self.pending.drain(..batch_len);
+        }
+        self.save()?;
+        Ok(delivered)
+    }
+
+    /// Write the queue to its file, atomically.
+    fn save(&self) -> Result<(), CiderError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_vec(&self.pending).expect("scrobbles always serialize");
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json)
+            .and_then(|()| std::fs::rename(&tmp, path))
+            .map_err(|source| CiderError::Io {
+                context: format!("cannot save {}", path.display()),
+                source,
+            })
+    }
+}
+
+/// Watches Cider and submits plays to a [`ScrobbleSink`].
+///
+/// Polls the player, decides what counts as a play with a
+/// [`PlayTracker`], and delivers plays through a
+/// [`ScrobbleQueue`]. Plays that cannot be delivered stay queued and are
+/// retried every [`retry_interval`](Self::retry_interval), and on the next
+/// start if the queue is persisted.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example(sink: impl cider_api::ScrobbleSink + Send + Sync + 'static) -> Result<(), cider_api::CiderError> {
+/// use cider_api::{CiderClient, ScrobbleQueue};
+///
+/// let queue = ScrobbleQueue::open("scrobbles.json")?;
+/// let scrobbler = CiderClient::new().scrobbler(sink, queue).start();
+/// scrobbler.wait().await;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+#[must_use = "a Scrobbler does nothing until `start` is called"]
+pub struct Scrobbler<S> {
+    client: CiderClient,
+    sink: S,
+    queue: ScrobbleQueue,
+    poll_interval: Duration,
+    retry_interval: Duration,
+}
+
+impl<S: ScrobbleSink + Send + Sync + 'static> Scrobbler<S> {
+    /// Create a scrobbler for `client`.
+    pub fn new(client: CiderClient, sink: S, queue: ScrobbleQueue) -> Self {
+        Self {
+            client,
+            sink,
+            queue,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+        }
+    }
+
+    /// Time between player polls (default 2s).
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Time between attempts to deliver queued plays (default 60s).
+    pub fn retry_interval(mut self, interval: Duration) -> Self {
+        self.retry_interval = interval;
+        self
+    }
+
+    /// Start scrobbling on the current Tokio runtime.
+    ///
+    /// Dropping the returned handle stops it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside a Tokio runtime.
+    pub fn start(self) -> ScrobblerHandle {
+        let (cancel, cancelled) = watch::channel(false);
+        let (events, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(self.run(cancelled, events));
+        ScrobblerHandle {
+            events: rx,
+            cancel,
+            task,
+        }
+    }
+
+    async fn run(
+        mut self,
+        mut cancel: watch::Receiver<bool>,
+        events: mpsc::UnboundedSender<ScrobbleEvent>,
+    ) -> ScrobbleQueue {
+        let mut tracker = PlayTracker::new();
+        let mut poll = tokio::time::interval(self.poll_interval);
+        poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut retry =
+            tokio::time::interval_at(Instant::now() + self.retry_interval, self.retry_interval);
+        retry.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // Plays left over from a previous run.
+        if !self.queue.is_empty() {
+            self.flush().await;
+        }
+
+        loop {
+            tokio::select! {
+                biased;
+                () = stopped(&mut cancel) => return self.queue,
+                _ = retry.tick(), if !self.queue.is_empty() => self.flush().await,
+                _ = poll.tick() => {
+                    let (track, is_playing) = match self.fetch().await {
+                        Ok(state) => state,
+                        Err(e) => {
+                            debug!(error = %e, "scrobbler poll failed");
+                            continue;
+                        }
+                    };
+                    for event in tracker.observe(Instant::now(), track.as_ref(), is_playing) {
+                        self.handle(&event).await;
+                        let _ = events.send(event);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn fetch(&self) -> Result<(Option<NowPlaying>, bool), CiderError> {
+        let track = self.client.now_playing().await?;
+        let is_playing = track.is_some() && self.client.is_playing().await?;
+        Ok((track, is_playing))
+    }
+
+    async fn handle(&mut self, event: &ScrobbleEvent) {
+        match event {
+            ScrobbleEvent::NowPlaying(track) => {
+                if let Err(e) = self.sink.now_playing(track).await {
+                    debug!(sink = self.sink.name(), error = %e, "now-playing update failed");
+                }
+            }
+            ScrobbleEvent::Scrobbled(scrobble) => {
+                if let Err(e) = self.queue.push(scrobble.clone()) {
+                    warn!(error = %e, "cannot persist scrobble queue");
+                }
+                self.flush().await;
+            }
+        }
+    }
+
+    async fn flush(&mut self) {
+        match self.queue.flush(&self.sink).await {
+            Ok(0) => {}
+            Ok(delivered) => debug!(sink = self.sink.name(), delivered, "scrobbles submitted"),
+            Err(e) => warn!(
+                sink = self.sink.name(),
+                error = %e,
+                queued = self.queue.len(),
+                "scrobble submission failed, will retry"
+            ),
+        }
+    }
+}
+
+/// A running [`Scrobbler`].
+///
+/// Dropping the handle stops the scrobbler.
+#[derive(Debug)]
+#[must_use = "dropping a ScrobblerHandle stops the scrobbler"]
+pub struct ScrobblerHandle {
+    events: mpsc::UnboundedReceiver<ScrobbleEvent>,
+    cancel: watch::Sender<bool>,
+    task: JoinHandle<ScrobbleQueue>,
+}
+
+impl ScrobblerHandle {
+    /// The next track started or play counted. Returns `None` once the
+    /// scrobbler has stopped.
+    pub async fn next_event(&mut self) -> Option<ScrobbleEvent> {
+        self.events.recv().await
+    }
+
+    /// Stop the scrobbler.
+    pub fn stop(&self) {
+        self.cancel.send_replace(true);
+    }
+
+    /// Wait for the scrobbler to stop and return its queue, with any plays
+    /// still undelivered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scrobbler task panicked.
+    pub async fn wait(self) -> ScrobbleQueue {
+        let Self { cancel, task, .. } = self;
+        let queue = task.await.expect("scrobbler task panicked");
+        drop(cancel);
+        queue
+    }
+}
+
+impl CiderClient {
+    /// Create a [`Scrobbler`] submitting this client's plays to `sink`.
+    /// Call [`start`](Scrobbler::start) to run it.
+    pub fn scrobbler<S: ScrobbleSink + Send + Sync + 'static>(
+        &self,
+        sink: S,
+        queue: ScrobbleQueue,
+    ) -> Scrobbler<S> {
+        Scrobbler::new(self.clone(), sink, queue)
+    }
+}
+
+/// Send a request to a scrobbling service and read the response.
+///
+/// Transport failures become [`CiderError::Service`]; the status is
+/// returned as-is for the caller to interpret alongside the body.
+pub(crate) async fn send_to_service(
+    service: &'static str,
+    endpoint: &Endpoint,
+    req: reqwest::RequestBuilder,
+) -> Result<(StatusCode, String), CiderError> {
+    let transport = |source: reqwest::Error| CiderError::Service {
+        service,
+        endpoint: endpoint.clone(),
+        status: None,
+        message: source.to_string(),
+        retryable: source.is_connect()
+            || source.is_timeout()
+            || source.is_request()
+            || source.is_body(),
+        source: Some(source),
+    };
+    let resp = req.send().await.map_err(transport)?;
+    let status = resp.status();
+    let text = resp.text().await.map_err(transport)?;
+    Ok((status, text))
+}
+
+/// A [`CiderError::Service`] for an error response.
+///
+/// `408`, `429` and `5xx` statuses are retryable.
+pub(crate) fn service_error(
+    service: &'static str,
+    endpoint: &Endpoint,
+    status: StatusCode,
+    message: String,
+) -> CiderError {
+    CiderError::Service {
+        service,
+        endpoint: endpoint.clone(),
+        status: Some(status),
+        message,
+        retryable: status.is_server_error()
+            || status == StatusCode::REQUEST_TIMEOUT
+            || status == StatusCode::TOO_MANY_REQUESTS,
+        source: None,
+    }
+}
+
+/// Serde for [`SystemTime`] as whole seconds since the Unix epoch.
+mod unix_seconds {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        time: &SystemTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        serializer.serialize_u64(secs)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SystemTime, D::Error> {
+        Ok(UNIX_EPOCH + Duration::from_secs(u64::deserialize(deserializer)?))
+    }
+}
+
+/// Serde for [`Duration`] as whole milliseconds.
+mod millis {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        duration: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(u64::try_from(duration.as_millis()).unwrap_or(u64::MAX))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        Ok(Duration::from_millis(u64::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PlayParams;
+
+    fn track(id: &str, length_secs: u64, position: f64) -> NowPlaying {
+        let mut track: NowPlaying = serde_json::from_value(serde_json::json!({
+            "name": format!("Song {id}"),
+            "artistName": "Artist",
+            "albumName": "Album",
+            "durationInMillis": length_secs * 1000,
+            "isrc": "USRC17607839",
+        }))
+        .unwrap();
+        track.play_params = Some(PlayParams {
+            id: id.to_owned(),
+            kind: "song".to_owned(),
+        });
+        track.current_playback_time = position;
+        track
+    }
+
+    /// Observe `track` at `secs` seconds after `start`.
+    fn at(
+        tracker: &mut PlayTracker,
+        start: Instant,
+        secs: u64,
+        track: &NowPlaying,
+        playing: bool,
+    ) -> Vec<ScrobbleEvent> {
+        tracker.observe(start + Duration::from_secs(secs), Some(track), playing)
+    }
+
+    fn scrobbled(events: &[ScrobbleEvent]) -> bool {
+        events
+            .iter()
+            .any(|e| matches!(e, ScrobbleEvent::Scrobbled(_)))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn scrobbles_at_half_the_track() {
+        let mut tracker = PlayTracker::new();
+        let start = Instant::now();
+        let events = at(&mut tracker, start, 0, &track("1", 200, 0.0), true);
+        assert!(matches!(events[..], [ScrobbleEvent::NowPlaying(_)]));
+        for secs in (10..100).step_by(10) {
+            let events = at(
+                &mut tracker,
+                start,
+                secs,
+                &track("1", 200, secs as f64),
+                true,
+            );
+            assert!(!scrobbled(&events), "too early at {secs}s");
+        }
+        let events = at(&mut tracker, start, 100, &track("1", 200, 100.0), true);
+        let [ScrobbleEvent::Scrobbled(scrobble)] = &events[..] else {
+            panic!("expected a scrobble, got {events:?}");
+        };
+        assert_eq!(scrobble.name, "Song 1");
+        assert_eq!(scrobble.isrc.as_deref(), Some("USRC17607839"));
+        assert_eq!(scrobble.duration, Duration::from_secs(200));
+        // Only once per play.
+        assert!(at(&mut tracker, start, 110, &track("1", 200, 110.0), true).is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn long_tracks_scrobble_after_four_minutes() {
+        let mut tracker = PlayTracker::new();
+        let start = Instant::now();
+        at(&mut tracker, start, 0, &track("1", 3600, 0.0), true);
+        for secs in (5..240).step_by(5) {
+            assert!(!scrobbled(&at(
+                &mut tracker,
+                start,
+                secs,
+                &track("1", 3600, secs as f64),
+                true
+            )));
+        }
+        assert!(scrobbled(&at(
+            &mut tracker,
+            start,
+            240,
+            &track("1", 3600, 240.0),
+            true
+        )));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn short_tracks_never_scrobble() {
+        let mut tracker = PlayTracker::new();
+        let start = Instant::now();
+        for secs in 0..=29 {
+            assert!(!scrobbled(&at(
+                &mut tracker,
+                start,
+                secs,
+                &track("1", 29, secs as f64),
+                true
+            )));
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pauses_are_not_credited() {
+        let mut tracker = PlayTracker::new();
+        let start = Instant::now();
+        at(&mut tracker, start, 0, &track("1", 100, 0.0), true);
+        at(&mut tracker, start, 20, &track("1", 100, 20.0), true);
+        // Paused for ten minutes at 20s.
+        at(&mut tracker, start, 620, &track("1", 100, 20.0), false);
+        assert_eq!(tracker.listened(), Duration::from_secs(20));
+        assert!(scrobbled(&at(
+            &mut tracker,
+            start,
+            650,
+            &track("1", 100, 50.0),
+            true
+        )));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn seeks_are_not_credited() {
+        let mut tracker = PlayTracker::new();
+        let start = Instant::now();
+        at(&mut tracker, start, 0, &track("1", 200, 0.0), true);
+        at(&mut tracker, start, 10, &track("1", 200, 10.0), true);
+        // Seek forward to 150s: the skipped part was not heard.
+        assert!(!scrobbled(&at(
+            &mut tracker,
+            start,
+            12,
+            &track("1", 200, 150.0),
+            true
+        )));
+        assert_eq!(tracker.listened(), Duration::from_secs(10));
+        // Seek back to 5s: not a new play.
+        let events = at(&mut tracker, start, 14, &track("1", 200, 5.0), true);
+        assert!(events.is_empty(), "{events:?}");
+        assert_eq!(tracker.listened(), Duration::from_secs(10));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn repeat_of_the_same_track_is_a_new_play() {
+        let mut tracker = PlayTracker::new();
+        let start = Instant::now();
+        at(&mut tracker, start, 0, &track("1", 60, 0.0), true);
+        at(&mut tracker, start, 30, &track("1", 60, 30.0), true);
+        at(&mut tracker, start, 58, &track("1", 60, 58.0), true);
+        let events = at(&mut tracker, start, 62, &track("1", 60, 2.0), true);
+        assert!(
+            matches!(events[..], [ScrobbleEvent::NowPlaying(_)]),
+            "{events:?}"
+        );
+        assert!(scrobbled(&at(
+            &mut tracker,
+            start,
+            92,
+            &track("1", 60, 32.0),
+            true
+        )));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn track_change_starts_a_new_play() {
+        let mut tracker = PlayTracker::new();
+        let start = Instant::now();
+        at(&mut tracker, start, 0, &track("1", 200, 0.0), true);
+        at(&mut tracker, start, 20, &track("1", 200, 20.0), true);
+        let events = at(&mut tracker, start, 22, &track("2", 200, 1.0), true);
+        let [ScrobbleEvent::NowPlaying(now)] = &events[..] else {
+            panic!("expected now playing, got {events:?}");
+        };
+        assert_eq!(now.name, "Song 2");
+        assert_eq!(tracker.listened(), Duration::ZERO);
+    }
+
+    #[test]
+    fn scrobble_serializes_compactly() {
+        let scrobble = Scrobble {
+            artist_name: "A".into(),
+            name: "N".into(),
+            album_name: "B".into(),
+            isrc: None,
+            started_at: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            duration: Duration::from_millis(215_000),
+        };
+        let json = serde_json::to_value(&scrobble).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "artist_name": "A",
+                "name": "N",
+                "album_name": "B",
+                "started_at": 1_700_000_000,
+                "duration_ms": 215_000,
+            })
+        );
+        assert_eq!(serde_json::from_value::<Scrobble>(json).unwrap(), scrobble);
+        assert_eq!(scrobble.started_at_unix(), 1_700_000_000);
+    }
+}
@@ -0,0 +1,365 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Sleep timer that fades the volume out, pauses, and restores the volume.
+
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::client::CiderClient;
+use crate::error::CiderError;
+use crate::fade::{ramp_step_count, ramp_steps, step_interval, FadeCurve};
+use crate::retry::RetryPolicy;
+use crate::types::NowPlaying;
+use crate::watcher::{same_track, stopped};
+
+/// Default length of the fade-out.
+const DEFAULT_FADE: Duration = Duration::from_secs(30);
+
+/// Default time between volume updates during the fade.
+const DEFAULT_STEP: Duration = Duration::from_millis(500);
+
+/// Default time between track checks for [`SleepAfter::Tracks`].
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// When a [`SleepTimer`] stops playback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SleepAfter {
+    /// After a fixed time from [`start`](SleepTimer::start).
+    Duration(Duration),
+    /// At the end of the current track.
+    EndOfTrack,
+    /// At the end of the `n`th track, counting the current one as the
+    /// first. `Tracks(1)` is the same as [`EndOfTrack`](Self::EndOfTrack).
+    Tracks(u32),
+}
+
+/// How a [`SleepTimer`] finished.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum SleepOutcome {
+    /// Playback was faded out and paused, then the volume was restored.
+    Paused {
+        /// The volume that was restored after pausing.
+        volume: f32,
+    },
+    /// Nothing was playing when the timer went off, so nothing was touched.
+    NotPlaying,
+    /// The timer was cancelled. If the fade had started, the original
+    /// volume was restored.
+    Cancelled,
+}
+
+/// Fades playback out and pauses it after a delay, at the end of the
+/// current track, or after a number of tracks.
+///
+/// When the timer fires it reads the current volume, ramps it down to zero
+/// over [`fade`](Self::fade) along [`curve`](Self::curve), pauses, and then
+/// sets the original volume again so the next play starts at the usual
+/// level. The fade ends when the timer fires: with
+/// `SleepAfter::Duration(30 min)` and a 30s fade, the fade starts at
+/// 29:30 and playback is paused at 30:00.
+///
+/// Requests that fail with a retryable error (see
+/// [`CiderError::is_retryable`]) are retried according to
+/// [`retry_policy`](Self::retry_policy); a volume step that still fails is
+/// skipped. Other errors end the timer, restoring the volume if possible.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), cider_api::CiderError> {
+/// use std::time::Duration;
+/// use cider_api::{CiderClient, FadeCurve, SleepAfter, SleepOutcome};
+///
+/// let client = CiderClient::new();
+/// let timer = client
+///     .sleep_timer(SleepAfter::Duration(Duration::from_secs(30 * 60)))
+///     .fade(Duration::from_secs(60))
+///     .curve(FadeCurve::EaseIn)
+///     .start();
+///
+/// // ... later, from a "cancel" button:
+/// // timer.cancel();
+///
+/// if let SleepOutcome::Paused { volume } = timer.wait().await? {
+///     println!("Good night! Volume reset to {volume}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[must_use = "a SleepTimer does nothing until `start` is called"]
+pub struct SleepTimer {
+    client: CiderClient,
+    after: SleepAfter,
+    fade: Duration,
+    curve: FadeCurve,
+    step: Duration,
+    poll_interval: Duration,
+    retry_policy: RetryPolicy,
+}
+
+impl SleepTimer {
+    /// Create a timer for `client` with a 30s linear fade.
+    pub fn new(client: CiderClient, after: SleepAfter) -> Self {
+        Self {
+            client,
+            after,
+            fade: DEFAULT_FADE,
+            curve: FadeCurve::default(),
+            step: DEFAULT_STEP,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            retry_policy: RetryPolicy::new()
+                .max_attempts(10)
+                .initial_backoff(Duration::from_millis(500))
+                .max_backoff(Duration::from_secs(10)),
+        }
+    }
+
+    /// Length of the fade-out (default 30s). `Duration::ZERO` pauses
+    /// without fading.
+    pub fn fade(mut self, fade: Duration) -> Self {
+        self.fade = fade;
+        self
+    }
+
+    /// Shape of the fade-out (default [`FadeCurve::Linear`]).
+    pub fn curve(mut self, curve: FadeCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Time between volume updates during the fade (default 500ms).
+    pub fn step(mut self, step: Duration) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// How often the current track is checked for
+    /// [`SleepAfter::EndOfTrack`] and [`SleepAfter::Tracks`] (default 5s).
+    ///
+    /// Shorter intervals react faster to skips and seeks.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// How requests that fail with a transient error are retried (default:
+    /// 10 attempts, backing off from 500ms to 10s).
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Start the timer on the current Tokio runtime.
+    ///
+    /// Dropping the returned handle cancels the timer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside a Tokio runtime.
+    pub fn start(self) -> SleepTimerHandle {
+        let (cancel, cancelled) = watch::channel(false);
+        let task = tokio::spawn(self.run(cancelled));
+        SleepTimerHandle { cancel, task }
+    }
+
+    async fn run(self, mut cancel: watch::Receiver<bool>) -> Result<SleepOutcome, CiderError> {
+        let playing = tokio::select! {
+            biased;
+            () = stopped(&mut cancel) => return Ok(SleepOutcome::Cancelled),
+            playing = self.wait_for_fade() => playing?,
+        };
+        if !playing {
+            debug!("sleep timer fired with nothing playing");
+            return Ok(SleepOutcome::NotPlaying);
+        }
+
+        let volume = self.retry(|| self.client.get_volume()).await?;
+        debug!(volume, fade = ?self.fade, "sleep timer fading out");
+        let faded = tokio::select! {
+            biased;
+            () = stopped(&mut cancel) => None,
+            faded = self.fade_out(volume) => Some(faded),
+        };
+        let result = match faded {
+            None => Ok(SleepOutcome::Cancelled),
+            Some(Ok(())) => self
+                .retry(|| self.client.pause())
+                .await
+                .map(|()| SleepOutcome::Paused { volume }),
+            Some(Err(e)) => Err(e),
+        };
+
+        // Restore the volume whatever happened; a failed fade or pause
+        // takes precedence over a failed restore.
+        let restored = self.retry(|| self.client.set_volume(volume)).await;
+        match (result, restored) {
+            (Err(e), _) | (Ok(_), Err(e)) => Err(e),
+            (Ok(outcome), Ok(())) => Ok(outcome),
+        }
+    }
+
+    /// Wait until the fade should start. Returns `false` if nothing is
+    /// playing by then.
+    async fn wait_for_fade(&self) -> Result<bool, CiderError> {
+        let tracks = match self.after {
+            SleepAfter::Duration(after) => {
+                tokio::time::sleep(after.saturating_sub(self.fade)).await;
+                return self.retry(|| self.client.is_playing()).await;
+            }
+            SleepAfter::EndOfTrack => 1,
+            SleepAfter::Tracks(tracks) => tracks.max(1),
+        };
+
+        let mut left = tracks;
+        let mut previous: Option<NowPlaying> = None;
+        loop {
+            let Some(track) = self.retry(|| self.client.now_playing()).await? else {
+                return Ok(false);
+            };
+            if previous.as_ref().is_some_and(|p| !same_track(p, &track)) {
+                left -= 1;
+                debug!(left, track = %track.name, "sleep timer counted a track");
+                if left == 0 {
+                    // The last track ended between two polls.
+                    return self.retry(|| self.client.is_playing()).await;
+                }
+            }
+
+            let remaining = track.remaining();
+            if left == 1 && remaining <= self.fade {
+                return self.retry(|| self.client.is_playing()).await;
+            }
+            let until_due = if left == 1 {
+                remaining.saturating_sub(self.fade)
+            } else {
+                remaining
+            };
+            previous = Some(track);
+            tokio::time::sleep(until_due.clamp(self.step, self.poll_interval.max(self.step))).await;
+        }
+    }
+
+    /// Ramp the volume from `volume` down to zero.
+    async fn fade_out(&self, volume: f32) -> Result<(), CiderError> {
+        let count = ramp_step_count(self.fade, self.step);
+        let interval = step_interval(self.fade, count);
+        let steps = ramp_steps(volume, 0.0, self.fade, self.step, self.curve);
+        for (level, n) in steps.zip(1..=count) {
+            tokio::time::sleep(interval).await;
+            if n == count {
+                return self.retry(|| self.client.set_volume(level)).await;
+            }
+            match self.client.set_volume(level).await {
+                Err(e) if self.retry_policy.should_retry(&e, 1) => {
+                    warn!(error = %e, "sleep timer skipped a volume step");
+                }
+                other => other?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Run `op`, retrying transient failures per the timer's policy.
+    async fn retry<T, F, Fut>(&self, mut op: F) -> Result<T, CiderError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, CiderError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Err(e) if self.retry_policy.should_retry(&e, attempt) => {
+                    let delay = self.retry_policy.delay(attempt);
+                    warn!(error = %e, attempt, ?delay, "sleep timer retrying");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// A running [`SleepTimer`].
+///
+/// Dropping the handle cancels the timer, restoring the volume if the fade
+/// had started.
+#[derive(Debug)]
+#[must_use = "dropping a SleepTimerHandle cancels the timer"]
+pub struct SleepTimerHandle {
+    cancel: watch::Sender<bool>,
+    task: JoinHandle<Result<SleepOutcome, CiderError>>,
+}
+
+impl SleepTimerHandle {
+    /// Cancel the timer. If the fade has started, the original volume is
+    /// restored. Has no effect once the timer has paused playback.
+    pub fn cancel(&self) {
+        self.cancel.send_replace(true);
+    }
+
+    /// Returns `true` once the timer has finished.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Wait for the timer to finish.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`CiderError`] that ended the timer early.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the timer task panicked.
+    pub async fn wait(self) -> Result<SleepOutcome, CiderError> {
+        let Self { cancel, task } = self;
+        let result = task.await.expect("sleep timer task panicked");
+        drop(cancel);
+        result
+    }
+}
+
+impl CiderClient {
+    /// Create a [`SleepTimer`] for this client. Call
+    /// [`start`](SleepTimer::start) to run it.
+    pub fn sleep_timer(&self, after: SleepAfter) -> SleepTimer {
+        SleepTimer::new(self.clone(), after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn dropping_the_handle_cancels() {
+        let client = CiderClient::with_port(1);
+        let (cancel, cancelled_rx) = watch::channel(false);
+        let timer = client.sleep_timer(SleepAfter::Duration(Duration::from_secs(3600)));
+        let task = tokio::spawn(timer.run(cancelled_rx));
+        drop(cancel);
+        assert!(matches!(task.await.unwrap(), Ok(SleepOutcome::Cancelled)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancel_before_firing_touches_nothing() {
+        // Port 1 is never Cider: any request would fail.
+        let client = CiderClient::with_port(1);
+        let handle = client
+            .sleep_timer(SleepAfter::Duration(Duration::from_secs(3600)))
+            .start();
+        handle.cancel();
+        assert!(matches!(handle.wait().await, Ok(SleepOutcome::Cancelled)));
+    }
+}
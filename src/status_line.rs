@@ -0,0 +1,736 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Templated one-line status output for bars such as waybar, polybar and
+//! tmux.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::error::CiderError;
+use crate::types::NowPlaying;
+use crate::watcher::{same_track, PlaybackSnapshot};
+
+/// Shown between the end and the start of scrolling text.
+const MARQUEE_GAP: &str = "   ";
+
+/// Marks text cut off at the maximum width.
+const ELLIPSIS: char = '…';
+
+/// A value a template can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Name,
+    Artist,
+    Album,
+    Composer,
+    Genre,
+    Position,
+    Duration,
+    Remaining,
+    Progress,
+    Volume,
+    State,
+    Icon,
+    Repeat,
+    Shuffle,
+    Lossless,
+    HiRes,
+    Atmos,
+    Adm,
+    Loved,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        let field = match name {
+            "name" | "title" => Self::Name,
+            "artist" => Self::Artist,
+            "album" => Self::Album,
+            "composer" => Self::Composer,
+            "genre" => Self::Genre,
+            "position" => Self::Position,
+            "duration" => Self::Duration,
+            "remaining" => Self::Remaining,
+            "progress" => Self::Progress,
+            "volume" => Self::Volume,
+            "state" => Self::State,
+            "icon" => Self::Icon,
+            "repeat" => Self::Repeat,
+            "shuffle" => Self::Shuffle,
+            "lossless" => Self::Lossless,
+            "hires" => Self::HiRes,
+            "atmos" => Self::Atmos,
+            "adm" => Self::Adm,
+            "loved" => Self::Loved,
+            _ => return None,
+        };
+        Some(field)
+    }
+
+    fn render(self, snapshot: &PlaybackSnapshot, track: &NowPlaying) -> String {
+        let flag = |on: bool, label: &str| if on { label.to_owned() } else { String::new() };
+        let has_trait = |name: &str| track.audio_traits.iter().any(|t| t == name);
+        match self {
+            Self::Name => track.name.clone(),
+            Self::Artist => track.artist_name.clone(),
+            Self::Album => track.album_name.clone(),
+            Self::Composer => track.composer_name.clone().unwrap_or_default(),
+            Self::Genre => track.genre_names.first().cloned().unwrap_or_default(),
+            Self::Position => format_duration(track.position()),
+            Self::Duration => format_duration(track.duration()),
+            Self::Remaining => format_duration(track.remaining()),
+            Self::Progress => whole_percent(track.progress_fraction().unwrap_or(0.0)).to_string(),
+            Self::Volume => whole_percent(f64::from(snapshot.volume)).to_string(),
+            Self::State => StatusClass::of(Some(snapshot)).to_string(),
+            Self::Icon => if snapshot.is_playing { "▶" } else { "⏸" }.to_owned(),
+            Self::Repeat => snapshot.repeat_mode.to_string(),
+            Self::Shuffle => if snapshot.shuffle_mode.is_on() {
+                "on"
+            } else {
+                "off"
+            }
+            .to_owned(),
+            Self::Lossless => flag(
+                has_trait("lossless") || has_trait("hi-res-lossless"),
+                "Lossless",
+            ),
+            Self::HiRes => flag(has_trait("hi-res-lossless"), "Hi-Res Lossless"),
+            Self::Atmos => flag(has_trait("atmos"), "Dolby Atmos"),
+            Self::Adm => flag(track.is_apple_digital_master, "Apple Digital Master"),
+            Self::Loved => flag(track.in_favorites, "♥"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Field {
+        field: Field,
+        /// `{field?}`: when empty, also drop the space before it.
+        optional: bool,
+    },
+}
+
+/// A parsed status line template such as
+/// `{artist} — {name} [{position}/{duration}] {lossless?}`.
+///
+/// Placeholders are written `{field}`, and `{{` / `}}` stand for literal
+/// braces:
+///
+/// | Field | Value |
+/// |---|---|
+/// | `name` (or `title`), `artist`, `album`, `composer`, `genre` | Track metadata |
+/// | `position`, `duration`, `remaining` | `M:SS`, or `H:MM:SS` from an hour up |
+/// | `progress`, `volume` | Whole percent, without the `%` |
+/// | `state` | `playing` or `paused` |
+/// | `icon` | `▶` or `⏸` |
+/// | `repeat` | `off`, `one` or `all` |
+/// | `shuffle` | `on` or `off` |
+/// | `lossless`, `hires`, `atmos`, `adm` | `Lossless`, `Hi-Res Lossless`, `Dolby Atmos`, `Apple Digital Master`, or nothing |
+/// | `loved` | `♥` for favorited tracks, or nothing |
+///
+/// A `?` after the name (`{lossless?}`) makes the placeholder swallow the
+/// space before it when it renders nothing, so optional badges do not
+/// leave double spaces behind.
+///
+/// # Errors
+///
+/// Parsing fails with [`CiderError::Config`] on unknown fields and
+/// unbalanced braces.
+///
+/// # Examples
+///
+/// ```
+/// use cider_api::StatusTemplate;
+///
+/// let template: StatusTemplate = "{artist} — {name} {lossless?}".parse()?;
+/// assert!("{nope}".parse::<StatusTemplate>().is_err());
+/// # Ok::<(), cider_api::CiderError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusTemplate {
+    parts: Vec<Part>,
+}
+
+impl FromStr for StatusTemplate {
+    type Err = CiderError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            |reason: String| CiderError::Config(format!("status template {template:?}: {reason}"));
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '}' => return Err(invalid("unmatched `}` (write `}}` for a brace)".to_owned())),
+                '{' => {
+                    let mut name = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        name.push(c);
+                    }
+                    if !closed {
+                        return Err(invalid("unclosed `{` (write `{{` for a brace)".to_owned()));
+                    }
+                    let optional = name.ends_with('?');
+                    if optional {
+                        name.pop();
+                    }
+                    let field = Field::parse(name.trim())
+                        .ok_or_else(|| invalid(format!("unknown field {{{name}}}")))?;
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field { field, optional });
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self { parts })
+    }
+}
+
+impl Default for StatusTemplate {
+    /// `{artist} — {name}`.
+    fn default() -> Self {
+        Self {
+            parts: vec![
+                Part::Field {
+                    field: Field::Artist,
+                    optional: false,
+                },
+                Part::Text(" — ".to_owned()),
+                Part::Field {
+                    field: Field::Name,
+                    optional: false,
+                },
+            ],
+        }
+    }
+}
+
+impl StatusTemplate {
+    /// Fill in the template for `track`.
+    fn render(&self, snapshot: &PlaybackSnapshot, track: &NowPlaying) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Field { field, optional } => {
+                    let value = field.render(snapshot, track);
+                    if value.is_empty() && *optional && out.ends_with(' ') {
+                        out.pop();
+                    }
+                    out.push_str(&value);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// How [`StatusLine`] escapes its output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StatusEscape {
+    /// Plain text (tmux, polybar).
+    #[default]
+    None,
+    /// Pango markup (`&amp;`, `&lt;`, ...), for waybar and other GTK bars.
+    Pango,
+    /// The inside of a JSON string, for templates that are JSON themselves.
+    Json,
+}
+
+impl StatusEscape {
+    fn apply(self, text: &str) -> String {
+        match self {
+            Self::None => text.to_owned(),
+            Self::Pango => {
+                let mut out = String::with_capacity(text.len());
+                for c in text.chars() {
+                    match c {
+                        '&' => out.push_str("&amp;"),
+                        '<' => out.push_str("&lt;"),
+                        '>' => out.push_str("&gt;"),
+                        '"' => out.push_str("&quot;"),
+                        '\'' => out.push_str("&#39;"),
+                        c => out.push(c),
+                    }
+                }
+                out
+            }
+            Self::Json => {
+                let quoted = serde_json::Value::from(text).to_string();
+                quoted[1..quoted.len() - 1].to_owned()
+            }
+        }
+    }
+}
+
+/// The player state, used as waybar's CSS class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusClass {
+    /// A track is playing.
+    Playing,
+    /// A track is loaded but paused.
+    Paused,
+    /// Nothing is loaded.
+    Stopped,
+    /// Cider is not reachable.
+    Offline,
+}
+
+impl StatusClass {
+    /// The class for a snapshot, `None` meaning Cider is unreachable.
+    #[must_use]
+    pub fn of(snapshot: Option<&PlaybackSnapshot>) -> Self {
+        match snapshot {
+            None => Self::Offline,
+            Some(s) if s.track.is_none() => Self::Stopped,
+            Some(s) if s.is_playing => Self::Playing,
+            Some(_) => Self::Paused,
+        }
+    }
+
+    /// `playing`, `paused`, `stopped` or `offline`.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Playing => "playing",
+            Self::Paused => "paused",
+            Self::Stopped => "stopped",
+            Self::Offline => "offline",
+        }
+    }
+}
+
+impl fmt::Display for StatusClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One rendering of a [`StatusLine`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct StatusOutput {
+    /// The status line, fitted and escaped.
+    pub text: String,
+    /// The tooltip, escaped; empty without a tooltip template or track.
+    pub tooltip: String,
+    /// The player state.
+    pub class: StatusClass,
+    /// Track progress in whole percent, if a track is loaded.
+    pub percentage: Option<u8>,
+}
+
+impl StatusOutput {
+    /// The output in waybar's `return-type: json` format: `text`,
+    /// `tooltip`, `class` and `alt` (both the state) and `percentage`.
+    #[must_use]
+    pub fn to_waybar_json(&self) -> String {
+        let mut value = json!({
+            "text": self.text,
+            "tooltip": self.tooltip,
+            "class": self.class.as_str(),
+            "alt": self.class.as_str(),
+        });
+        if let Some(percentage) = self.percentage {
+            value["percentage"] = percentage.into();
+        }
+        value.to_string()
+    }
+}
+
+/// Renders player state into a single line for status bars.
+///
+/// The template fills in the line while a track is loaded; fixed texts
+/// (empty by default, which hides most bar modules) stand in when nothing
+/// is loaded or Cider is unreachable. Text longer than
+/// [`max_width`](Self::max_width) is cut with `…`, or scrolled one
+/// character per [`render`](Self::render) with [`marquee`](Self::marquee).
+/// Escaping happens last, so the width counts visible characters.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), cider_api::CiderError> {
+/// use cider_api::{CiderClient, StatusEscape, StatusLine, WatchEvent};
+///
+/// let mut line = StatusLine::new("{artist} — {name} [{position}/{duration}]".parse()?)
+///     .tooltip("{name}\n{album}".parse()?)
+///     .max_width(40)
+///     .marquee(true)
+///     .escape(StatusEscape::Pango);
+///
+/// let mut watcher = CiderClient::new().watch();
+/// loop {
+///     // Without a snapshot, the line shows the offline text.
+///     let snapshot = match watcher.next().await {
+///         WatchEvent::CiderWentAway(_) => None,
+///         _ => watcher.snapshot(),
+///     };
+///     println!("{}", line.render(snapshot).to_waybar_json());
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct StatusLine {
+    template: StatusTemplate,
+    tooltip: Option<StatusTemplate>,
+    stopped_text: String,
+    offline_text: String,
+    max_width: Option<usize>,
+    marquee: bool,
+    escape: StatusEscape,
+    /// Scroll position, and the track it belongs to.
+    marquee_offset: usize,
+    marquee_track: Option<NowPlaying>,
+}
+
+impl StatusLine {
+    /// A status line rendering `template`.
+    pub fn new(template: StatusTemplate) -> Self {
+        Self {
+            template,
+            tooltip: None,
+            stopped_text: String::new(),
+            offline_text: String::new(),
+            max_width: None,
+            marquee: false,
+            escape: StatusEscape::None,
+            marquee_offset: 0,
+            marquee_track: None,
+        }
+    }
+
+    /// Render a tooltip too. It is escaped but never shortened.
+    pub fn tooltip(mut self, template: StatusTemplate) -> Self {
+        self.tooltip = Some(template);
+        self
+    }
+
+    /// Text to show when nothing is loaded (default: empty).
+    pub fn stopped_text(mut self, text: impl Into<String>) -> Self {
+        self.stopped_text = text.into();
+        self
+    }
+
+    /// Text to show when Cider is unreachable (default: empty).
+    pub fn offline_text(mut self, text: impl Into<String>) -> Self {
+        self.offline_text = text.into();
+        self
+    }
+
+    /// Limit the line to `width` characters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is zero.
+    pub fn max_width(mut self, width: usize) -> Self {
+        assert!(width > 0, "max_width must be at least 1");
+        self.max_width = Some(width);
+        self
+    }
+
+    /// Scroll text longer than [`max_width`](Self::max_width) instead of
+    /// cutting it (default: off).
+    pub fn marquee(mut self, marquee: bool) -> Self {
+        self.marquee = marquee;
+        self
+    }
+
+    /// How to escape the text and tooltip (default: [`StatusEscape::None`]).
+    pub fn escape(mut self, escape: StatusEscape) -> Self {
+        self.escape = escape;
+        self
+    }
+
+    /// Render `snapshot`, `None` meaning Cider is unreachable.
+    ///
+    /// Each call advances the marquee by one character; it starts over
+    /// when the track changes.
+    pub fn render(&mut self, snapshot: Option<&PlaybackSnapshot>) -> StatusOutput {
+        let class = StatusClass::of(snapshot);
+        let track = snapshot.and_then(|s| Some((s, s.track.as_ref()?)));
+        let (text, tooltip, percentage) = match track {
+            Some((snapshot, track)) => (
+                self.template.render(snapshot, track),
+                self.tooltip
+                    .as_ref()
+                    .map(|t| t.render(snapshot, track))
+                    .unwrap_or_default(),
+                Some(whole_percent(track.progress_fraction().unwrap_or(0.0))),
+            ),
+            None if class == StatusClass::Offline => {
+                (self.offline_text.clone(), String::new(), None)
+            }
+            None => (self.stopped_text.clone(), String::new(), None),
+        };
+
+        let track = track.map(|(_, track)| track);
+        let same = match (&self.marquee_track, track) {
+            (Some(a), Some(b)) => same_track(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        if !same {
+            self.marquee_offset = 0;
+            self.marquee_track = track.cloned();
+        }
+
+        StatusOutput {
+            text: self.escape.apply(&self.fit(&text)),
+            tooltip: self.escape.apply(&tooltip),
+            class,
+            percentage,
+        }
+    }
+
+    /// Cut or scroll `text` to the maximum width.
+    fn fit(&mut self, text: &str) -> String {
+        let Some(width) = self.max_width else {
+            return text.to_owned();
+        };
+        let chars: Vec<char> = text.chars().collect();
+        if chars.len() <= width {
+            return text.to_owned();
+        }
+        if self.marquee {
+            let looped: Vec<char> = chars.into_iter().chain(MARQUEE_GAP.chars()).collect();
+            let start = self.marquee_offset % looped.len();
+            self.marquee_offset = start + 1;
+            looped.iter().cycle().skip(start).take(width).collect()
+        } else {
+            let mut cut: String = chars[..width - 1].iter().collect();
+            cut.push(ELLIPSIS);
+            cut
+        }
+    }
+}
+
+/// A fraction such as a volume or track progress as whole percent,
+/// clamped to `0..=100`: the `progress` and `volume` fields of a
+/// [`StatusTemplate`].
+///
+/// ```
+/// assert_eq!(cider_api::whole_percent(0.425), 43);
+/// ```
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn whole_percent(fraction: f64) -> u8 {
+    (fraction.clamp(0.0, 1.0) * 100.0).round() as u8
+}
+
+/// A track time as `M:SS`, or `H:MM:SS` from an hour up: the `position`,
+/// `duration` and `remaining` fields of a [`StatusTemplate`].
+///
+/// ```
+/// use std::time::Duration;
+///
+/// assert_eq!(cider_api::format_duration(Duration::from_secs(234)), "3:54");
+/// ```
+#[must_use]
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{RepeatMode, ShuffleMode};
+
+    fn track(name: &str, traits: &[&str]) -> NowPlaying {
+        serde_json::from_value(json!({
+            "name": name,
+            "artistName": "Flume",
+            "albumName": "Skin",
+            "durationInMillis": 234_000,
+            "currentPlaybackTime": 42.5,
+            "audioTraits": traits,
+        }))
+        .unwrap()
+    }
+
+    fn snapshot(track: Option<NowPlaying>, is_playing: bool) -> PlaybackSnapshot {
+        PlaybackSnapshot {
+            track,
+            is_playing,
+            volume: 0.5,
+            repeat_mode: RepeatMode::All,
+            shuffle_mode: ShuffleMode::Off,
+        }
+    }
+
+    fn status_line(template: &str) -> StatusLine {
+        StatusLine::new(template.parse().unwrap())
+    }
+
+    fn text(line: &mut StatusLine, snapshot: &PlaybackSnapshot) -> String {
+        line.render(Some(snapshot)).text
+    }
+
+    #[test]
+    fn formats_durations_and_percent() {
+        assert_eq!(format_duration(Duration::from_secs(42)), "0:42");
+        assert_eq!(format_duration(Duration::from_secs(234)), "3:54");
+        assert_eq!(format_duration(Duration::from_secs(3723)), "1:02:03");
+        assert_eq!(whole_percent(0.5), 50);
+        assert_eq!(whole_percent(1.5), 100);
+        assert_eq!(whole_percent(-0.1), 0);
+    }
+
+    #[test]
+    fn renders_fields() {
+        let s = snapshot(Some(track("Never Be Like You", &["lossless"])), true);
+        let mut line =
+            status_line("{icon} {artist} — {name} [{position}/{duration}] {lossless?} {atmos?}");
+        assert_eq!(
+            text(&mut line, &s),
+            "▶ Flume — Never Be Like You [0:42/3:54] Lossless"
+        );
+        let mut line =
+            status_line("{state} {progress}% vol {volume} repeat {repeat} shuffle {shuffle}");
+        assert_eq!(
+            text(&mut line, &s),
+            "playing 18% vol 50 repeat all shuffle off"
+        );
+        assert_eq!(
+            text(&mut status_line("{{{name}}}"), &s),
+            "{Never Be Like You}"
+        );
+        assert_eq!(
+            StatusTemplate::default(),
+            "{artist} — {name}".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn optional_fields_swallow_one_space() {
+        let s = snapshot(Some(track("A", &[])), false);
+        assert_eq!(
+            text(&mut status_line("{name} {lossless?} | {album}"), &s),
+            "A | Skin"
+        );
+        assert_eq!(text(&mut status_line("{name} {lossless} |"), &s), "A  |");
+    }
+
+    #[test]
+    fn invalid_templates() {
+        for template in ["{nope}", "{name", "name}", "{}"] {
+            let err = template.parse::<StatusTemplate>().unwrap_err();
+            assert!(matches!(err, CiderError::Config(_)), "{template}: {err}");
+        }
+    }
+
+    #[test]
+    fn states_and_classes() {
+        let mut line = status_line("{name}").stopped_text("■").offline_text("off");
+        let stopped = line.render(Some(&snapshot(None, false)));
+        assert_eq!(
+            (stopped.text.as_str(), stopped.class),
+            ("■", StatusClass::Stopped)
+        );
+        assert_eq!(stopped.percentage, None);
+        let offline = line.render(None);
+        assert_eq!(
+            (offline.text.as_str(), offline.class),
+            ("off", StatusClass::Offline)
+        );
+        let paused = line.render(Some(&snapshot(Some(track("A", &[])), false)));
+        assert_eq!(paused.class, StatusClass::Paused);
+        assert_eq!(paused.percentage, Some(18));
+    }
+
+    #[test]
+    fn truncates_and_scrolls() {
+        let s = snapshot(Some(track("Never Be Like You", &[])), true);
+        assert_eq!(
+            text(&mut status_line("{name}").max_width(8), &s),
+            "Never B…"
+        );
+        assert_eq!(
+            text(&mut status_line("{name}").max_width(40), &s),
+            "Never Be Like You"
+        );
+
+        let mut line = status_line("{name}").max_width(5).marquee(true);
+        let frames: Vec<String> = (0..3).map(|_| text(&mut line, &s)).collect();
+        assert_eq!(frames, ["Never", "ever ", "ver B"]);
+        // Wraps around through the gap.
+        for _ in 3..17 {
+            text(&mut line, &s);
+        }
+        assert_eq!(text(&mut line, &s), "   Ne");
+        // A new track starts from the beginning.
+        let other = snapshot(Some(track("Say It Again", &[])), true);
+        assert_eq!(text(&mut line, &other), "Say I");
+    }
+
+    #[test]
+    fn escapes_after_fitting() {
+        let s = snapshot(Some(track("Rock & <Roll>", &[])), true);
+        let mut line = status_line("{name}")
+            .max_width(9)
+            .escape(StatusEscape::Pango);
+        assert_eq!(text(&mut line, &s), "Rock &amp; &lt;…");
+        let s = snapshot(Some(track("Say \"Hi\"\n", &[])), true);
+        let mut line = status_line("{name}").escape(StatusEscape::Json);
+        assert_eq!(text(&mut line, &s), r#"Say \"Hi\"\n"#);
+    }
+
+    #[test]
+    fn waybar_json() {
+        let s = snapshot(Some(track("A & B", &[])), false);
+        let output = status_line("{name}")
+            .tooltip("{name}\n{album}".parse().unwrap())
+            .escape(StatusEscape::Pango)
+            .render(Some(&s));
+        let value: serde_json::Value = serde_json::from_str(&output.to_waybar_json()).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "text": "A &amp; B",
+                "tooltip": "A &amp; B\nSkin",
+                "class": "paused",
+                "alt": "paused",
+                "percentage": 18,
+            })
+        );
+        let offline: serde_json::Value =
+            serde_json::from_str(&status_line("{name}").render(None).to_waybar_json()).unwrap();
+        assert_eq!(
+            offline,
+            json!({ "text": "", "tooltip": "", "class": "offline", "alt": "offline" })
+        );
+    }
+}
@@ -11,8 +11,13 @@
 //!
 //! The response shapes match the [Cider RPC documentation](https://cider.sh/docs/client/rpc).
 
+use std::fmt;
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
+use crate::item::{ItemKind, ItemRef};
+
 // ─── Response wrapper ────────────────────────────────────────────────────────
 
 /// Generic wrapper for Cider API JSON responses.
@@ -35,6 +40,55 @@ pub struct ApiResponse<T> {
     pub data: T,
 }
 
+/// Error body returned by Cider when a command fails.
+///
+/// Cider reports failures as `{ "status": "error", "message": "..." }`,
+/// sometimes with an `error` field instead of `message`. Exposed through
+/// [`CiderError::Api`](crate::CiderError::Api) and
+/// [`CiderError::Unauthorized`](crate::CiderError::Unauthorized).
+///
+/// # Example (JSON)
+///
+/// ```json
+/// { "status": "error", "message": "No item provided" }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    /// Status string, typically `"error"`.
+    #[serde(default)]
+    pub status: String,
+
+    /// Human-readable error message.
+    #[serde(default)]
+    pub message: Option<String>,
+
+    /// Alternative error field used by some endpoints.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl ErrorResponse {
+    /// Returns `true` if `status` is `"error"`.
+    #[must_use]
+    pub fn is_error(&self) -> bool {
+        self.status.eq_ignore_ascii_case("error")
+    }
+
+    /// The error message, preferring `message` over `error`.
+    #[must_use]
+    pub fn description(&self) -> Option<&str> {
+        self.message.as_deref().or(self.error.as_deref())
+    }
+
+    /// Parse an error body, returning `None` unless it is JSON carrying at
+    /// least one of `status`, `message` or `error`.
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(text)
+            .ok()
+            .filter(|body| !body.status.is_empty() || body.description().is_some())
+    }
+}
+
 // ─── Common types ────────────────────────────────────────────────────────────
 
 /// Artwork metadata for a track, album, or station.
@@ -115,6 +169,8 @@ impl Artwork {
 ///
 /// Every playable track, album, or station carries an `id` (Apple Music
 /// catalog ID) and a `kind` (e.g. `"song"`, `"album"`, `"radioStation"`).
+/// Use [`item_ref`](Self::item_ref) to turn them into an [`ItemRef`] for
+/// [`play_item`](crate::CiderClient::play_item) and friends.
 ///
 /// # Examples
 ///
@@ -141,6 +197,195 @@ pub struct Preview {
     pub url: String,
 }
 
+// ─── Player modes ────────────────────────────────────────────────────────────
+
+/// Repeat mode, as reported by `GET /repeat-mode` and
+/// [`NowPlaying::repeat_mode`].
+///
+/// Serialized as Cider's integer codes (`0`, `1`, `2`). Codes this crate
+/// does not know are kept as [`Unknown`](Self::Unknown) instead of failing.
+///
+/// ```
+/// # use cider_api::RepeatMode;
+/// assert_eq!(RepeatMode::from(2), RepeatMode::All);
+/// assert_eq!(RepeatMode::from(7), RepeatMode::Unknown(7));
+/// assert_eq!(u8::from(RepeatMode::One), 1);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "u8", into = "u8")]
+pub enum RepeatMode {
+    /// No repeat (`0`).
+    #[default]
+    Off,
+    /// Repeat the current song (`1`).
+    One,
+    /// Repeat the whole queue (`2`).
+    All,
+    /// A code this crate does not know about.
+    Unknown(u8),
+}
+
+impl From<u8> for RepeatMode {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Off,
+            1 => Self::One,
+            2 => Self::All,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+impl From<RepeatMode> for u8 {
+    fn from(mode: RepeatMode) -> Self {
+        match mode {
+            RepeatMode::Off => 0,
+            RepeatMode::One => 1,
+            RepeatMode::All => 2,
+            RepeatMode::Unknown(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for RepeatMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Off => f.write_str("off"),
+            Self::One => f.write_str("one"),
+            Self::All => f.write_str("all"),
+            Self::Unknown(value) => write!(f, "unknown ({value})"),
+        }
+    }
+}
+
+/// Shuffle mode, as reported by `GET /shuffle-mode` and
+/// [`NowPlaying::shuffle_mode`].
+///
+/// Serialized as Cider's integer codes (`0`, `1`). Codes this crate does not
+/// know are kept as [`Unknown`](Self::Unknown) instead of failing.
+///
+/// ```
+/// # use cider_api::ShuffleMode;
+/// assert_eq!(ShuffleMode::from(1), ShuffleMode::On);
+/// assert!(ShuffleMode::On.is_on());
+/// assert_eq!(ShuffleMode::from(false), ShuffleMode::Off);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "u8", into = "u8")]
+pub enum ShuffleMode {
+    /// Play in order (`0`).
+    #[default]
+    Off,
+    /// Shuffle (`1`).
+    On,
+    /// A code this crate does not know about.
+    Unknown(u8),
+}
+
+impl ShuffleMode {
+    /// Returns `true` for [`ShuffleMode::On`].
+    #[must_use]
+    pub fn is_on(self) -> bool {
+        self == Self::On
+    }
+}
+
+impl From<u8> for ShuffleMode {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Off,
+            1 => Self::On,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+impl From<ShuffleMode> for u8 {
+    fn from(mode: ShuffleMode) -> Self {
+        match mode {
+            ShuffleMode::Off => 0,
+            ShuffleMode::On => 1,
+            ShuffleMode::Unknown(value) => value,
+        }
+    }
+}
+
+impl From<bool> for ShuffleMode {
+    fn from(on: bool) -> Self {
+        if on {
+            Self::On
+        } else {
+            Self::Off
+        }
+    }
+}
+
+impl fmt::Display for ShuffleMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Off => f.write_str("off"),
+            Self::On => f.write_str("on"),
+            Self::Unknown(value) => write!(f, "unknown ({value})"),
+        }
+    }
+}
+
+/// Track rating sent with `POST /set-rating`.
+///
+/// Serialized as Cider's integer codes (`-1`, `0`, `1`). Deserializing any
+/// other value fails rather than guessing.
+///
+/// ```
+/// # use cider_api::Rating;
+/// assert_eq!(i8::from(Rating::Dislike), -1);
+/// assert_eq!(Rating::try_from(1), Ok(Rating::Like));
+/// assert!(Rating::try_from(5).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "i8", into = "i8")]
+pub enum Rating {
+    /// Dislike (`-1`).
+    Dislike,
+    /// No rating (`0`).
+    #[default]
+    None,
+    /// Like (`1`).
+    Like,
+}
+
+impl TryFrom<i8> for Rating {
+    type Error = String;
+
+    fn try_from(value: i8) -> Result<Self, Self::Error> {
+        match value {
+            -1 => Ok(Self::Dislike),
+            0 => Ok(Self::None),
+            1 => Ok(Self::Like),
+            other => Err(format!("invalid rating {other}, expected -1, 0 or 1")),
+        }
+    }
+}
+
+impl From<Rating> for i8 {
+    fn from(rating: Rating) -> Self {
+        match rating {
+            Rating::Dislike => -1,
+            Rating::None => 0,
+            Rating::Like => 1,
+        }
+    }
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Dislike => "dislike",
+            Self::None => "none",
+            Self::Like => "like",
+        })
+    }
+}
+
 // ─── Now Playing ─────────────────────────────────────────────────────────────
 
 /// Currently playing track information returned by `GET /now-playing`.
@@ -212,13 +457,13 @@ pub struct NowPlaying {
     #[serde(default)]
     pub remaining_time: f64,
 
-    /// Shuffle mode — `0` = off, `1` = on.
+    /// Shuffle mode.
     #[serde(default)]
-    pub shuffle_mode: u8,
+    pub shuffle_mode: ShuffleMode,
 
-    /// Repeat mode — `0` = off, `1` = repeat one, `2` = repeat all.
+    /// Repeat mode.
     #[serde(default)]
-    pub repeat_mode: u8,
+    pub repeat_mode: RepeatMode,
 
     /// Whether the track is in the user's favorites.
     #[serde(default)]
@@ -301,6 +546,46 @@ impl NowPlaying {
         (self.current_playback_time.max(0.0) * 1000.0).round() as u64
     }
 
+    /// Current playback position.
+    ///
+    /// Negative or non-finite values are reported as zero.
+    #[must_use]
+    pub fn position(&self) -> Duration {
+        secs_to_duration(self.current_playback_time)
+    }
+
+    /// Total track duration, or [`Duration::ZERO`] if Cider did not report one.
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.duration_in_millis)
+    }
+
+    /// Time left in the track.
+    ///
+    /// Computed from [`duration`](Self::duration) and
+    /// [`position`](Self::position) when the duration is known, otherwise
+    /// taken from [`remaining_time`](Self::remaining_time).
+    #[must_use]
+    pub fn remaining(&self) -> Duration {
+        if self.duration_in_millis == 0 {
+            secs_to_duration(self.remaining_time)
+        } else {
+            self.duration().saturating_sub(self.position())
+        }
+    }
+
+    /// How far into the track playback is, from `0.0` to `1.0`.
+    ///
+    /// Returns `None` if the duration is unknown.
+    #[must_use]
+    pub fn progress_fraction(&self) -> Option<f64> {
+        let duration = self.duration();
+        if duration.is_zero() {
+            return None;
+        }
+        Some((self.position().as_secs_f64() / duration.as_secs_f64()).clamp(0.0, 1.0))
+    }
+
     /// Get the artwork URL at the specified square size (in pixels).
     ///
     /// Shorthand for `self.artwork.url_for_size(size)`.
@@ -310,6 +595,12 @@ impl NowPlaying {
     }
 }
 
+/// Convert Cider's floating-point seconds to a [`Duration`], mapping
+/// negative and non-finite values to zero.
+fn secs_to_duration(secs: f64) -> Duration {
+    Duration::try_from_secs_f64(secs).unwrap_or(Duration::ZERO)
+}
+
 // ─── Queue types ─────────────────────────────────────────────────────────────
 
 /// A single item in the Cider playback queue.
@@ -606,15 +897,15 @@ pub struct VolumeResponse {
 /// Payload for `GET /repeat-mode`.
 #[derive(Debug, Clone, Deserialize)]
 pub struct RepeatModeResponse {
-    /// `0` = off, `1` = repeat one, `2` = repeat all.
-    pub value: u8,
+    /// The current repeat mode.
+    pub value: RepeatMode,
 }
 
 /// Payload for `GET /shuffle-mode`.
 #[derive(Debug, Clone, Deserialize)]
 pub struct ShuffleModeResponse {
-    /// `0` = off, `1` = on.
-    pub value: u8,
+    /// The current shuffle mode.
+    pub value: ShuffleMode,
 }
 
 /// Payload for `GET /autoplay`.
@@ -638,12 +929,21 @@ pub struct PlayUrlRequest {
 pub struct PlayItemRequest {
     /// Item type (e.g. `"songs"`, `"albums"`, `"playlists"`).
     #[serde(rename = "type")]
-    pub item_type: String,
+    pub item_type: ItemKind,
 
-    /// Apple Music catalog ID (must be a string, not a number).
+    /// Apple Music catalog or library ID (must be a string, not a number).
     pub id: String,
 }
 
+impl From<&ItemRef> for PlayItemRequest {
+    fn from(item: &ItemRef) -> Self {
+        Self {
+            item_type: item.kind(),
+            id: item.id().to_string(),
+        }
+    }
+}
+
 /// Request body for `POST /play-item-href`.
 #[derive(Debug, Clone, Serialize)]
 pub struct PlayItemHrefRequest {
@@ -668,8 +968,8 @@ pub struct VolumeRequest {
 /// Request body for `POST /set-rating`.
 #[derive(Debug, Clone, Serialize)]
 pub struct RatingRequest {
-    /// `-1` = dislike, `0` = unset, `1` = like.
-    pub rating: i8,
+    /// The rating to apply.
+    pub rating: Rating,
 }
 
 /// Request body for `POST /queue/move-to-position`.
@@ -812,6 +1112,37 @@ mod tests {
         assert_eq!(track.current_position_ms(), 0);
     }
 
+    #[test]
+    fn now_playing_duration_accessors() {
+        let track: NowPlaying = serde_json::from_str(
+            r#"{"currentPlaybackTime": 60.5, "durationInMillis": 240000, "remainingTime": 1.0}"#,
+        )
+        .unwrap();
+        assert_eq!(track.position(), Duration::from_millis(60_500));
+        assert_eq!(track.duration(), Duration::from_secs(240));
+        assert_eq!(track.remaining(), Duration::from_millis(179_500));
+        let progress = track.progress_fraction().unwrap();
+        assert!((progress - 60.5 / 240.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn now_playing_duration_accessors_without_duration() {
+        let track: NowPlaying =
+            serde_json::from_str(r#"{"currentPlaybackTime": -0.5, "remainingTime": 12.0}"#).unwrap();
+        assert_eq!(track.position(), Duration::ZERO);
+        assert_eq!(track.remaining(), Duration::from_secs(12));
+        assert_eq!(track.progress_fraction(), None);
+    }
+
+    #[test]
+    fn now_playing_progress_is_clamped() {
+        let track: NowPlaying =
+            serde_json::from_str(r#"{"currentPlaybackTime": 300.0, "durationInMillis": 240000}"#)
+                .unwrap();
+        assert_eq!(track.progress_fraction(), Some(1.0));
+        assert_eq!(track.remaining(), Duration::ZERO);
+    }
+
     #[test]
     fn now_playing_artwork_url_delegates() {
         let track: NowPlaying = serde_json::from_str(
@@ -849,7 +1180,7 @@ mod tests {
     #[test]
     fn play_item_request_renames_type() {
         let req = PlayItemRequest {
-            item_type: "songs".into(),
+            item_type: ItemKind::Songs,
             id: "123".into(),
         };
         let json: serde_json::Value = serde_json::to_value(&req).unwrap();
@@ -874,7 +1205,9 @@ mod tests {
 
     #[test]
     fn rating_request_serialization() {
-        let req = RatingRequest { rating: -1 };
+        let req = RatingRequest {
+            rating: Rating::Dislike,
+        };
         let json: serde_json::Value = serde_json::to_value(&req).unwrap();
         assert_eq!(json["rating"], -1);
     }
@@ -962,14 +1295,38 @@ mod tests {
     fn api_response_repeat_mode() {
         let json = r#"{"status":"ok","value":2}"#;
         let resp: ApiResponse<RepeatModeResponse> = serde_json::from_str(json).unwrap();
-        assert_eq!(resp.data.value, 2);
+        assert_eq!(resp.data.value, RepeatMode::All);
+    }
+
+    #[test]
+    fn repeat_mode_round_trips_unknown_values() {
+        for code in 0..=3u8 {
+            let mode: RepeatMode = serde_json::from_str(&code.to_string()).unwrap();
+            assert_eq!(serde_json::to_string(&mode).unwrap(), code.to_string());
+        }
+        assert_eq!(RepeatMode::from(3), RepeatMode::Unknown(3));
+        assert_eq!(RepeatMode::Unknown(3).to_string(), "unknown (3)");
+    }
+
+    #[test]
+    fn shuffle_mode_from_bool() {
+        assert_eq!(ShuffleMode::from(true), ShuffleMode::On);
+        assert!(!ShuffleMode::from(false).is_on());
+        assert_eq!(ShuffleMode::from(4), ShuffleMode::Unknown(4));
+    }
+
+    #[test]
+    fn rating_rejects_out_of_range() {
+        assert_eq!(serde_json::from_str::<Rating>("-1").unwrap(), Rating::Dislike);
+        assert_eq!(serde_json::to_string(&Rating::Like).unwrap(), "1");
+        assert!(serde_json::from_str::<Rating>("2").is_err());
     }
 
     #[test]
     fn api_response_shuffle_mode() {
         let json = r#"{"status":"ok","value":1}"#;
         let resp: ApiResponse<ShuffleModeResponse> = serde_json::from_str(json).unwrap();
-        assert_eq!(resp.data.value, 1);
+        assert_eq!(resp.data.value, ShuffleMode::On);
     }
 
     #[test]
@@ -987,6 +1344,35 @@ mod tests {
         assert_eq!(resp.data.info.artist_name, "Artist");
     }
 
+    // ── Error body ──
+
+    #[test]
+    fn error_response_parses_message() {
+        let body = ErrorResponse::parse(r#"{"status":"error","message":"No item"}"#).unwrap();
+        assert!(body.is_error());
+        assert_eq!(body.description(), Some("No item"));
+    }
+
+    #[test]
+    fn error_response_falls_back_to_error_field() {
+        let body = ErrorResponse::parse(r#"{"error":"Bad token"}"#).unwrap();
+        assert!(!body.is_error());
+        assert_eq!(body.description(), Some("Bad token"));
+    }
+
+    #[test]
+    fn error_response_rejects_unrelated_bodies() {
+        assert!(ErrorResponse::parse("").is_none());
+        assert!(ErrorResponse::parse("Internal Server Error").is_none());
+        assert!(ErrorResponse::parse(r#"{"volume":0.5}"#).is_none());
+    }
+
+    #[test]
+    fn error_response_ok_status_is_not_error() {
+        let body = ErrorResponse::parse(r#"{"status":"ok"}"#).unwrap();
+        assert!(!body.is_error());
+    }
+
     // ── Queue item deserialization ──
 
     #[test]
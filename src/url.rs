@@ -0,0 +1,420 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Parsing and building `music.apple.com` links.
+
+use std::fmt;
+use std::str::FromStr;
+
+use reqwest::Url;
+
+use crate::error::CiderError;
+use crate::item::{ItemKind, ItemRef};
+use crate::types::NowPlaying;
+
+/// Hosts that serve Apple Music web links.
+const HOSTS: [&str; 2] = ["music.apple.com", "geo.music.apple.com"];
+
+/// The page type in an Apple Music web URL (`/{storefront}/{kind}/...`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum UrlKind {
+    /// `/album/` — also used for songs, with the song in the `i` parameter.
+    Album,
+    /// `/song/`.
+    Song,
+    /// `/playlist/`.
+    Playlist,
+    /// `/artist/`. Not playable through [`ItemRef`].
+    Artist,
+    /// `/station/`.
+    Station,
+    /// `/music-video/`.
+    MusicVideo,
+}
+
+impl UrlKind {
+    /// The path segment (e.g. `"music-video"`).
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Album => "album",
+            Self::Song => "song",
+            Self::Playlist => "playlist",
+            Self::Artist => "artist",
+            Self::Station => "station",
+            Self::MusicVideo => "music-video",
+        }
+    }
+
+    fn from_segment(segment: &str) -> Option<Self> {
+        match segment {
+            "album" => Some(Self::Album),
+            "song" => Some(Self::Song),
+            "playlist" => Some(Self::Playlist),
+            "artist" => Some(Self::Artist),
+            "station" => Some(Self::Station),
+            "music-video" => Some(Self::MusicVideo),
+            _ => None,
+        }
+    }
+
+    /// The [`ItemKind`] whose ID shape this page's ID has. Artists share
+    /// the numeric catalog shape.
+    fn id_kind(self) -> ItemKind {
+        match self {
+            Self::Album => ItemKind::Albums,
+            Self::Song | Self::Artist => ItemKind::Songs,
+            Self::Playlist => ItemKind::Playlists,
+            Self::Station => ItemKind::Stations,
+            Self::MusicVideo => ItemKind::MusicVideos,
+        }
+    }
+}
+
+impl fmt::Display for UrlKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A parsed Apple Music web link, as shared from Cider or
+/// `music.apple.com`.
+///
+/// Understands album (optionally with a song in `?i=`), song, playlist
+/// (`pl.`), artist, station (`ra.`) and music-video links. The slug is kept
+/// so [`Display`](fmt::Display) reproduces the canonical link, but is not
+/// required when building one.
+///
+/// # Examples
+///
+/// ```
+/// # use cider_api::{AppleMusicUrl, ItemRef, UrlKind};
+/// let url: AppleMusicUrl =
+///     "https://music.apple.com/ca/album/skin/1719860281?i=1719861213".parse()?;
+/// assert_eq!(url.storefront, "ca");
+/// assert_eq!(url.kind, UrlKind::Album);
+/// assert_eq!(url.id, "1719860281");
+/// assert_eq!(url.song_id.as_deref(), Some("1719861213"));
+/// assert_eq!(url.item_ref(), Some(ItemRef::song("1719861213")?));
+///
+/// let built = AppleMusicUrl::new("us", UrlKind::Playlist, "pl.f4d106fed2bd41149aaacabb233eb5eb")?;
+/// assert_eq!(
+///     built.to_string(),
+///     "https://music.apple.com/us/playlist/pl.f4d106fed2bd41149aaacabb233eb5eb"
+/// );
+/// # Ok::<(), cider_api::CiderError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AppleMusicUrl {
+    /// Two-letter storefront (country) code, e.g. `"us"`.
+    pub storefront: String,
+
+    /// Page type.
+    pub kind: UrlKind,
+
+    /// Catalog ID of the album, song, playlist, artist, station or video.
+    pub id: String,
+
+    /// Song selected on an album page (the `i` query parameter).
+    pub song_id: Option<String>,
+
+    /// Human-readable slug (e.g. `"skin"`), if present.
+    pub slug: Option<String>,
+}
+
+impl AppleMusicUrl {
+    /// Build a link from its parts, validating the storefront and the ID
+    /// shape.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::InvalidItem`] if `storefront` is not a
+    /// two-letter code or `id` does not match `kind`.
+    pub fn new(
+        storefront: impl Into<String>,
+        kind: UrlKind,
+        id: impl Into<String>,
+    ) -> Result<Self, CiderError> {
+        let storefront = storefront.into();
+        if storefront.len() != 2 || !storefront.bytes().all(|b| b.is_ascii_lowercase()) {
+            return Err(invalid(format!(
+                "storefront {storefront:?} is not a two-letter code"
+            )));
+        }
+        let id = id.into();
+        if !kind.id_kind().accepts(&id) {
+            return Err(invalid(format!("{id:?} is not a valid {kind} ID")));
+        }
+        Ok(Self {
+            storefront,
+            kind,
+            id,
+            song_id: None,
+            slug: None,
+        })
+    }
+
+    /// Build the link for a catalog item. Returns `None` for library items,
+    /// which have no public link.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::InvalidItem`] if `storefront` is not a
+    /// two-letter code.
+    pub fn from_item_ref(
+        storefront: impl Into<String>,
+        item: &ItemRef,
+    ) -> Result<Option<Self>, CiderError> {
+        let kind = match item.kind() {
+            ItemKind::Songs => UrlKind::Song,
+            ItemKind::Albums => UrlKind::Album,
+            ItemKind::Playlists => UrlKind::Playlist,
+            ItemKind::Stations => UrlKind::Station,
+            ItemKind::MusicVideos => UrlKind::MusicVideo,
+            _ => return Ok(None),
+        };
+        Self::new(storefront, kind, item.id()).map(Some)
+    }
+
+    /// Select a song on an album page.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError::InvalidItem`] if this is not an album link or
+    /// `song_id` is not numeric.
+    pub fn with_song_id(mut self, song_id: impl Into<String>) -> Result<Self, CiderError> {
+        let song_id = song_id.into();
+        if self.kind != UrlKind::Album {
+            return Err(invalid(format!(
+                "only album links take a song, not {}",
+                self.kind
+            )));
+        }
+        if !ItemKind::Songs.accepts(&song_id) {
+            return Err(invalid(format!("{song_id:?} is not a valid song ID")));
+        }
+        self.song_id = Some(song_id);
+        Ok(self)
+    }
+
+    /// Set the slug shown in the link.
+    #[must_use]
+    pub fn with_slug(mut self, slug: impl Into<String>) -> Self {
+        self.slug = Some(slug.into());
+        self
+    }
+
+    /// The item this link plays: the selected song of an album link, or the
+    /// page itself. `None` for artist pages.
+    #[must_use]
+    pub fn item_ref(&self) -> Option<ItemRef> {
+        if let Some(song_id) = &self.song_id {
+            return ItemRef::song(song_id.clone()).ok();
+        }
+        let kind = match self.kind {
+            UrlKind::Artist => return None,
+            kind => kind.id_kind(),
+        };
+        ItemRef::new(kind, self.id.clone()).ok()
+    }
+}
+
+impl FromStr for AppleMusicUrl {
+    type Err = CiderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let url = Url::parse(s.trim()).map_err(|e| invalid(format!("{s:?} is not a URL: {e}")))?;
+        if !matches!(url.scheme(), "http" | "https")
+            || !url.host_str().is_some_and(|host| HOSTS.contains(&host))
+        {
+            return Err(invalid(format!("{s:?} is not an Apple Music link")));
+        }
+
+        let segments: Vec<&str> = url
+            .path_segments()
+            .into_iter()
+            .flatten()
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        let (storefront, kind, slug, id) = match segments[..] {
+            [storefront, kind, id] => (storefront, kind, None, id),
+            [storefront, kind, slug, id] => (storefront, kind, Some(slug), id),
+            _ => return Err(invalid(format!("unrecognized Apple Music link {s:?}"))),
+        };
+        let kind = UrlKind::from_segment(kind)
+            .ok_or_else(|| invalid(format!("unsupported Apple Music page type {kind:?}")))?;
+
+        let mut parsed = Self::new(storefront, kind, id)?;
+        parsed.slug = slug.map(str::to_string);
+        if let Some((_, song_id)) = url.query_pairs().find(|(key, _)| key == "i") {
+            parsed = parsed.with_song_id(song_id)?;
+        }
+        Ok(parsed)
+    }
+}
+
+impl fmt::Display for AppleMusicUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "https://{}/{}/{}", HOSTS[0], self.storefront, self.kind)?;
+        if let Some(slug) = &self.slug {
+            write!(f, "/{slug}")?;
+        }
+        write!(f, "/{}", self.id)?;
+        if let Some(song_id) = &self.song_id {
+            write!(f, "?i={song_id}")?;
+        }
+        Ok(())
+    }
+}
+
+impl NowPlaying {
+    /// Parse [`url`](Self::url) into an [`AppleMusicUrl`], if present and
+    /// understood.
+    #[must_use]
+    pub fn apple_music_url(&self) -> Option<AppleMusicUrl> {
+        self.url.as_deref()?.parse().ok()
+    }
+}
+
+fn invalid(message: String) -> CiderError {
+    CiderError::InvalidItem(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> AppleMusicUrl {
+        s.parse().unwrap_or_else(|e| panic!("{s}: {e}"))
+    }
+
+    #[test]
+    fn album_with_song() {
+        let url = parse("https://music.apple.com/ca/album/skin/1719860281?i=1719861213");
+        assert_eq!(url.storefront, "ca");
+        assert_eq!(url.kind, UrlKind::Album);
+        assert_eq!(url.slug.as_deref(), Some("skin"));
+        assert_eq!(url.id, "1719860281");
+        assert_eq!(url.song_id.as_deref(), Some("1719861213"));
+        assert_eq!(url.item_ref().unwrap().kind(), ItemKind::Songs);
+    }
+
+    #[test]
+    fn album_without_song_plays_album() {
+        let url = parse("https://music.apple.com/us/album/1719860281");
+        assert_eq!(url.slug, None);
+        assert_eq!(url.item_ref(), Some(ItemRef::album("1719860281").unwrap()));
+    }
+
+    #[test]
+    fn every_page_type() {
+        let cases = [
+            (
+                "https://music.apple.com/us/song/say-it/1719861213",
+                UrlKind::Song,
+                Some(ItemKind::Songs),
+            ),
+            (
+                "https://music.apple.com/us/playlist/todays-hits/pl.f4d106fed2bd41149aaacabb233eb5eb",
+                UrlKind::Playlist,
+                Some(ItemKind::Playlists),
+            ),
+            (
+                "https://music.apple.com/gb/artist/flume/591256593",
+                UrlKind::Artist,
+                None,
+            ),
+            (
+                "https://music.apple.com/us/station/flume-radio/ra.978194965",
+                UrlKind::Station,
+                Some(ItemKind::Stations),
+            ),
+            (
+                "https://music.apple.com/us/music-video/rushing-back/1479853843",
+                UrlKind::MusicVideo,
+                Some(ItemKind::MusicVideos),
+            ),
+        ];
+        for (link, kind, item_kind) in cases {
+            let url = parse(link);
+            assert_eq!(url.kind, kind, "{link}");
+            assert_eq!(url.item_ref().map(|item| item.kind()), item_kind, "{link}");
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for link in [
+            "https://music.apple.com/ca/album/skin/1719860281?i=1719861213",
+            "https://music.apple.com/us/playlist/pl.f4d106fed2bd41149aaacabb233eb5eb",
+            "https://music.apple.com/gb/artist/flume/591256593",
+        ] {
+            assert_eq!(parse(link).to_string(), link);
+        }
+    }
+
+    #[test]
+    fn geo_host_and_trailing_slash_are_accepted() {
+        let url = parse("https://geo.music.apple.com/us/album/skin/1719860281/");
+        assert_eq!(url.id, "1719860281");
+        assert!(url.to_string().starts_with("https://music.apple.com/"));
+    }
+
+    #[test]
+    fn extra_query_parameters_are_ignored() {
+        let url = parse("https://music.apple.com/us/album/skin/1719860281?l=fr&i=1719861213");
+        assert_eq!(url.song_id.as_deref(), Some("1719861213"));
+    }
+
+    #[test]
+    fn rejects_invalid_links() {
+        for link in [
+            "not a url",
+            "https://open.spotify.com/album/1719860281",
+            "ftp://music.apple.com/us/album/skin/1719860281",
+            "https://music.apple.com/us/browse",
+            "https://music.apple.com/us/podcast/show/1234",
+            "https://music.apple.com/usa/album/skin/1719860281",
+            "https://music.apple.com/us/album/skin/i.PkdZbQXsPJ4DX",
+            "https://music.apple.com/us/playlist/mix/1234",
+            "https://music.apple.com/us/album/skin/1719860281?i=abc",
+            "https://music.apple.com/us/song/say-it/1719861213?i=1",
+        ] {
+            let err = link.parse::<AppleMusicUrl>().unwrap_err();
+            assert!(matches!(err, CiderError::InvalidItem(_)), "{link}");
+        }
+    }
+
+    #[test]
+    fn builds_from_item_ref() {
+        let item = ItemRef::station("ra.978194965").unwrap();
+        let url = AppleMusicUrl::from_item_ref("us", &item).unwrap().unwrap();
+        assert_eq!(
+            url.to_string(),
+            "https://music.apple.com/us/station/ra.978194965"
+        );
+
+        let library = ItemRef::new(ItemKind::LibraryAlbums, "l.gACheFi").unwrap();
+        assert!(AppleMusicUrl::from_item_ref("us", &library)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn builder_validates_parts() {
+        assert!(AppleMusicUrl::new("US", UrlKind::Album, "1").is_err());
+        let song = AppleMusicUrl::new("us", UrlKind::Song, "1719861213").unwrap();
+        assert!(song.with_song_id("1").is_err());
+        let album = AppleMusicUrl::new("us", UrlKind::Album, "1719860281")
+            .unwrap()
+            .with_slug("skin")
+            .with_song_id("1719861213")
+            .unwrap();
+        assert_eq!(
+            album.to_string(),
+            "https://music.apple.com/us/album/skin/1719860281?i=1719861213"
+        );
+    }
+}
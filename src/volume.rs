@@ -0,0 +1,377 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Volume ramps, mute/unmute and ducking.
+//!
+//! All three share one piece of state per client (clones included), guarded
+//! by an async mutex so that concurrent calls apply one at a time.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use tokio::runtime::Handle;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::client::CiderClient;
+use crate::error::CiderError;
+use crate::fade::{ramp_step_count, ramp_steps, step_interval, FadeCurve};
+
+/// Time between volume updates during [`CiderClient::ramp_volume`].
+const RAMP_STEP: Duration = Duration::from_millis(50);
+
+/// Volumes closer than this are considered equal. Cider rounds the volume
+/// it reports, so an exact comparison would see changes that did not happen.
+const VOLUME_TOLERANCE: f32 = 0.005;
+
+/// Volume state shared by a client and its clones.
+#[derive(Debug, Default)]
+pub(crate) struct VolumeControl {
+    state: Mutex<State>,
+    /// Bumped by every level change made through this state; a running
+    /// ramp stops once it no longer matches the value it started with.
+    generation: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The level to return to on [`CiderClient::unmute`].
+    muted_from: Option<f32>,
+    duck: Option<Duck>,
+    next_duck_id: u64,
+}
+
+/// Active ducks.
+#[derive(Debug)]
+struct Duck {
+    /// The level the user wants, restored when the last duck ends.
+    saved: f32,
+    /// The volume last set by a duck, to detect changes made elsewhere.
+    applied: f32,
+    /// Level of each active [`DuckGuard`], by id.
+    levels: Vec<(u64, f32)>,
+}
+
+impl Duck {
+    /// The volume to play at: the lowest duck level, never above `saved`.
+    fn target(&self) -> f32 {
+        self.levels
+            .iter()
+            .map(|&(_, level)| level)
+            .fold(self.saved, f32::min)
+    }
+}
+
+impl VolumeControl {
+    fn bump(&self) -> u64 {
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    fn is_current(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::SeqCst) == generation
+    }
+}
+
+impl CiderClient {
+    /// Gradually change the volume to `target` over `duration`.
+    ///
+    /// The volume is updated every 50ms along `curve`, starting from the
+    /// current volume. The ramp stops early, without an error, when another
+    /// `ramp_volume`, [`mute`](Self::mute), [`unmute`](Self::unmute) or
+    /// [`duck`](Self::duck) call on this client (or a clone) changes the
+    /// volume, so the newest call always wins.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError`] if a request fails. The volume is left where
+    /// the failed step found it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn example() -> Result<(), cider_api::CiderError> {
+    /// use std::time::Duration;
+    /// use cider_api::{CiderClient, FadeCurve};
+    ///
+    /// let client = CiderClient::new();
+    /// client.ramp_volume(0.8, Duration::from_secs(3), FadeCurve::EaseOut).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn ramp_volume(
+        &self,
+        target: f32,
+        duration: Duration,
+        curve: FadeCurve,
+    ) -> Result<(), CiderError> {
+        let target = target.clamp(0.0, 1.0);
+        let generation = self.volume.bump();
+        let from = self.get_volume().await?;
+        let interval = step_interval(duration, ramp_step_count(duration, RAMP_STEP));
+        debug!(from, target, ?duration, "ramping volume");
+
+        for level in ramp_steps(from, target, duration, RAMP_STEP, curve) {
+            tokio::time::sleep(interval).await;
+            let _state = self.volume.state.lock().await;
+            if !self.volume.is_current(generation) {
+                debug!("volume ramp superseded");
+                return Ok(());
+            }
+            self.set_volume(level).await?;
+        }
+        Ok(())
+    }
+
+    /// Mute playback, remembering the current volume for
+    /// [`unmute`](Self::unmute).
+    ///
+    /// Muting twice keeps the level from before the first call. While
+    /// [ducked](Self::duck), the level remembered is the one the duck will
+    /// restore, not the ducked volume.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError`] if a request fails.
+    pub async fn mute(&self) -> Result<(), CiderError> {
+        let mut state = self.volume.state.lock().await;
+        self.volume.bump();
+        let level = self.user_level(&mut state).await?;
+        if level > VOLUME_TOLERANCE {
+            state.muted_from = Some(level);
+        } else if state.muted_from.is_some() {
+            return Ok(());
+        }
+        self.set_user_level(&mut state, 0.0).await
+    }
+
+    /// Restore the volume saved by [`mute`](Self::mute) and return the
+    /// volume now in effect.
+    ///
+    /// If the volume was raised since muting, or nothing was muted, the
+    /// volume is left alone and the remembered level is forgotten.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError`] if a request fails.
+    pub async fn unmute(&self) -> Result<f32, CiderError> {
+        let mut state = self.volume.state.lock().await;
+        self.volume.bump();
+        let level = self.user_level(&mut state).await?;
+        match state.muted_from.take() {
+            Some(previous) if level <= VOLUME_TOLERANCE => {
+                self.set_user_level(&mut state, previous).await?;
+                Ok(previous)
+            }
+            _ => Ok(level),
+        }
+    }
+
+    /// Lower the volume to `level` until the returned guard is dropped or
+    /// [released](DuckGuard::release), e.g. while an announcement plays.
+    ///
+    /// Ducks on the same client (clones included) cooperate:
+    ///
+    /// - A duck never raises the volume; a `level` above the current
+    ///   volume leaves it unchanged.
+    /// - Overlapping ducks share the level from before the first one. The
+    ///   volume follows the lowest active duck and goes back to that level
+    ///   when the last guard is released, in whatever order they end.
+    /// - A change made elsewhere while ducked (the Cider UI,
+    ///   [`set_volume`](Self::set_volume), a [`ramp_volume`](Self::ramp_volume))
+    ///   is taken as the new level to restore rather than overwritten with
+    ///   the old one, and [`mute`](Self::mute)/[`unmute`](Self::unmute)
+    ///   while ducked apply to that level too.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError`] if a request fails. No duck is registered in
+    /// that case.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn example() -> Result<(), cider_api::CiderError> {
+    /// # use cider_api::CiderClient;
+    /// let client = CiderClient::new();
+    /// let duck = client.duck(0.2).await?;
+    /// // ... play the announcement ...
+    /// duck.release().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn duck(&self, level: f32) -> Result<DuckGuard, CiderError> {
+        let level = level.clamp(0.0, 1.0);
+        let mut state = self.volume.state.lock().await;
+        self.volume.bump();
+        let saved = self.user_level(&mut state).await?;
+        let id = state.next_duck_id;
+        let mut duck = state.duck.take().unwrap_or(Duck {
+            saved,
+            applied: saved,
+            levels: Vec::new(),
+        });
+        duck.levels.push((id, level));
+        let result = self.apply_duck(&mut duck).await;
+        if result.is_err() {
+            duck.levels.pop();
+        }
+        if !duck.levels.is_empty() {
+            state.duck = Some(duck);
+        }
+        result?;
+
+        state.next_duck_id += 1;
+        debug!(id, level, saved, "ducked volume");
+        Ok(DuckGuard {
+            client: self.clone(),
+            id: Some(id),
+        })
+    }
+
+    /// End the duck `id`, restoring or re-applying the volume.
+    async fn release_duck(&self, id: u64) -> Result<(), CiderError> {
+        let mut state = self.volume.state.lock().await;
+        self.volume.bump();
+        // Forget the duck before anything can fail, so it never outlives
+        // its guard.
+        let Some(duck) = &mut state.duck else {
+            return Ok(());
+        };
+        duck.levels.retain(|&(other, _)| other != id);
+        // Pick up changes made elsewhere before deciding what to restore;
+        // if the volume cannot be read, go by the level last known.
+        let read = self.user_level(&mut state).await.map(drop);
+        let Some(mut duck) = state.duck.take() else {
+            return read;
+        };
+        let result = if duck.levels.is_empty() {
+            debug!(id, volume = duck.saved, "restoring volume after duck");
+            self.set_volume(duck.saved).await
+        } else {
+            self.apply_duck(&mut duck).await
+        };
+        // A failed restore keeps the saved level for the next duck to end.
+        if !duck.levels.is_empty() || result.is_err() {
+            state.duck = Some(duck);
+        }
+        result.and(read)
+    }
+
+    /// The level the user wants: the volume, or the level a duck will
+    /// restore. Folds changes made elsewhere while ducked into that level.
+    async fn user_level(&self, state: &mut State) -> Result<f32, CiderError> {
+        let volume = self.get_volume().await?;
+        match &mut state.duck {
+            Some(duck) => {
+                if (volume - duck.applied).abs() > VOLUME_TOLERANCE {
+                    debug!(volume, "volume changed while ducked");
+                    duck.saved = volume;
+                    duck.applied = volume;
+                }
+                Ok(duck.saved)
+            }
+            None => Ok(volume),
+        }
+    }
+
+    /// Set the level the user wants, keeping any active duck in effect.
+    async fn set_user_level(&self, state: &mut State, level: f32) -> Result<(), CiderError> {
+        match &mut state.duck {
+            Some(duck) => {
+                duck.saved = level;
+                self.apply_duck(duck).await
+            }
+            None => self.set_volume(level).await,
+        }
+    }
+
+    /// Set the volume to what the active ducks call for, if it differs.
+    async fn apply_duck(&self, duck: &mut Duck) -> Result<(), CiderError> {
+        let target = duck.target();
+        if (target - duck.applied).abs() > VOLUME_TOLERANCE {
+            self.set_volume(target).await?;
+            duck.applied = target;
+        }
+        Ok(())
+    }
+}
+
+/// A lowered volume from [`CiderClient::duck`].
+///
+/// Dropping the guard restores the volume in a background task; use
+/// [`release`](Self::release) to wait for it and see errors.
+#[derive(Debug)]
+#[must_use = "dropping a DuckGuard restores the volume immediately"]
+pub struct DuckGuard {
+    client: CiderClient,
+    /// `None` once released.
+    id: Option<u64>,
+}
+
+impl DuckGuard {
+    /// End this duck and wait for the volume to be restored (or lowered to
+    /// the next active duck).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CiderError`] if a request fails. The duck is ended either
+    /// way; if the volume could not be restored, the next duck to end
+    /// restores it.
+    pub async fn release(mut self) -> Result<(), CiderError> {
+        match self.id.take() {
+            Some(id) => self.client.release_duck(id).await,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for DuckGuard {
+    fn drop(&mut self) {
+        let Some(id) = self.id.take() else {
+            return;
+        };
+        let Ok(handle) = Handle::try_current() else {
+            warn!(id, "DuckGuard dropped outside a Tokio runtime; volume not restored");
+            return;
+        };
+        let client = self.client.clone();
+        handle.spawn(async move {
+            if let Err(e) = client.release_duck(id).await {
+                warn!(error = %e, id, "failed to restore volume after duck");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn duck(saved: f32, levels: &[f32]) -> Duck {
+        Duck {
+            saved,
+            applied: saved,
+            levels: levels.iter().copied().zip(0..).map(|(l, id)| (id, l)).collect(),
+        }
+    }
+
+    #[test]
+    fn target_follows_the_lowest_duck() {
+        assert!((duck(0.8, &[0.3, 0.1, 0.5]).target() - 0.1).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn target_never_raises_the_volume() {
+        assert!((duck(0.2, &[0.5]).target() - 0.2).abs() < f32::EPSILON);
+        assert!((duck(0.6, &[]).target() - 0.6).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn clones_share_volume_state() {
+        let client = CiderClient::new();
+        let clone = client.clone();
+        client.volume.bump();
+        assert!(clone.volume.is_current(1));
+    }
+}
@@ -0,0 +1,496 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Polling-based playback watcher that reports only what changed.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+use tracing::debug;
+
+use crate::client::CiderClient;
+use crate::error::CiderError;
+use crate::types::{NowPlaying, RepeatMode, ShuffleMode};
+
+/// Default time between polls.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default drift between expected and reported position that counts as a seek.
+const DEFAULT_SEEK_THRESHOLD: Duration = Duration::from_secs(3);
+
+/// Volume differences below this are rounding noise, not changes.
+const VOLUME_EPSILON: f32 = 0.001;
+
+/// A change observed by [`PlaybackWatcher`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WatchEvent {
+    /// A different track is loaded. `None` means nothing was / is playing.
+    TrackChanged {
+        /// The previous track.
+        from: Option<Box<NowPlaying>>,
+        /// The new track.
+        to: Option<Box<NowPlaying>>,
+    },
+
+    /// Playback was paused.
+    Paused,
+
+    /// Playback was resumed.
+    Resumed,
+
+    /// The position jumped within the same track.
+    Seeked {
+        /// Where playback was expected to be.
+        from: Duration,
+        /// Where playback actually is.
+        to: Duration,
+    },
+
+    /// The volume changed (`0.0`–`1.0`).
+    VolumeChanged {
+        /// The previous volume.
+        from: f32,
+        /// The new volume.
+        to: f32,
+    },
+
+    /// The repeat mode changed.
+    RepeatChanged {
+        /// The previous mode.
+        from: RepeatMode,
+        /// The new mode.
+        to: RepeatMode,
+    },
+
+    /// The shuffle mode changed.
+    ShuffleChanged {
+        /// The previous mode.
+        from: ShuffleMode,
+        /// The new mode.
+        to: ShuffleMode,
+    },
+
+    /// Polling started failing — Cider quit, crashed or became unreachable.
+    /// Reported once per outage.
+    CiderWentAway(CiderError),
+
+    /// Polling succeeds again after [`CiderWentAway`](Self::CiderWentAway).
+    /// Changes that happened during the outage follow as regular events.
+    CiderCameBack,
+}
+
+/// Player state captured by one poll.
+#[derive(Debug, Clone)]
+pub struct PlaybackSnapshot {
+    /// The loaded track, if any.
+    pub track: Option<NowPlaying>,
+    /// Whether a track is playing.
+    pub is_playing: bool,
+    /// Volume, `0.0`–`1.0`.
+    pub volume: f32,
+    /// Repeat mode.
+    pub repeat_mode: RepeatMode,
+    /// Shuffle mode.
+    pub shuffle_mode: ShuffleMode,
+}
+
+/// Polls Cider and reports changes as [`WatchEvent`]s.
+///
+/// Each poll calls [`now_playing`](CiderClient::now_playing),
+/// [`is_playing`](CiderClient::is_playing),
+/// [`get_volume`](CiderClient::get_volume),
+/// [`get_repeat_mode`](CiderClient::get_repeat_mode) and
+/// [`get_shuffle_mode`](CiderClient::get_shuffle_mode), and diffs the result
+/// against the previous poll. The first successful poll only records a
+/// baseline; read it with [`snapshot`](Self::snapshot).
+///
+/// Use this where the push channel (`events` feature) is unavailable, e.g.
+/// behind a reverse proxy that does not forward `WebSocket` upgrades.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() {
+/// use std::time::Duration;
+/// use cider_api::{CiderClient, PlaybackWatcher, WatchEvent};
+///
+/// let mut watcher = PlaybackWatcher::new(CiderClient::new())
+///     .interval(Duration::from_millis(500));
+/// loop {
+///     match watcher.next().await {
+///         WatchEvent::TrackChanged { to: Some(track), .. } => {
+///             println!("Now playing: {} — {}", track.name, track.artist_name);
+///         }
+///         WatchEvent::CiderWentAway(e) => eprintln!("Cider went away: {e}"),
+///         other => println!("{other:?}"),
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct PlaybackWatcher {
+    client: CiderClient,
+    interval: Duration,
+    seek_threshold: Duration,
+    ticker: Option<Interval>,
+    last: Option<(PlaybackSnapshot, Instant)>,
+    away: bool,
+    pending: VecDeque<WatchEvent>,
+}
+
+impl PlaybackWatcher {
+    /// Watch the Cider instance behind `client`, polling every second.
+    #[must_use]
+    pub fn new(client: CiderClient) -> Self {
+        Self {
+            client,
+            interval: DEFAULT_INTERVAL,
+            seek_threshold: DEFAULT_SEEK_THRESHOLD,
+            ticker: None,
+            last: None,
+            away: false,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Time between polls. Defaults to 1s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    #[must_use]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "poll interval must be non-zero");
+        self.interval = interval;
+        self.ticker = None;
+        self
+    }
+
+    /// How far the position may drift from where it should be before the
+    /// difference is reported as [`WatchEvent::Seeked`]. Defaults to 3s.
+    ///
+    /// Keep this above the poll interval's jitter; Cider only updates the
+    /// position a few times per second.
+    #[must_use]
+    pub fn seek_threshold(mut self, threshold: Duration) -> Self {
+        self.seek_threshold = threshold;
+        self
+    }
+
+    /// The state recorded by the last successful poll.
+    #[must_use]
+    pub fn snapshot(&self) -> Option<&PlaybackSnapshot> {
+        self.last.as_ref().map(|(snapshot, _)| snapshot)
+    }
+
+    /// Wait for the next change.
+    ///
+    /// Polls on the configured interval until something changes. The first
+    /// poll happens immediately.
+    pub async fn next(&mut self) -> WatchEvent {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return event;
+            }
+            let period = self.interval;
+            let ticker = self.ticker.get_or_insert_with(|| {
+                let mut ticker = tokio::time::interval(period);
+                ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                ticker
+            });
+            ticker.tick().await;
+            let events = self.poll().await;
+            self.pending.extend(events);
+        }
+    }
+
+    /// Poll once and return the changes since the previous poll.
+    ///
+    /// Useful to drive the watcher from your own loop instead of
+    /// [`next`](Self::next). Events already queued by `next` are not
+    /// included.
+    pub async fn poll(&mut self) -> Vec<WatchEvent> {
+        let snapshot = match self.fetch().await {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                if self.away {
+                    debug!("Cider still unreachable: {e}");
+                    return Vec::new();
+                }
+                self.away = true;
+                return vec![WatchEvent::CiderWentAway(e)];
+            }
+        };
+
+        let now = Instant::now();
+        let mut events = Vec::new();
+        if self.away {
+            self.away = false;
+            events.push(WatchEvent::CiderCameBack);
+        }
+        if let Some((previous, polled_at)) = &self.last {
+            self.diff(previous, &snapshot, now - *polled_at, &mut events);
+        }
+        self.last = Some((snapshot, now));
+        events
+    }
+
+    /// Query the full player state.
+    async fn fetch(&self) -> Result<PlaybackSnapshot, CiderError> {
+        let (track, is_playing, volume, repeat_mode, shuffle_mode) = tokio::join!(
+            self.client.now_playing(),
+            self.client.is_playing(),
+            self.client.get_volume(),
+            self.client.get_repeat_mode(),
+            self.client.get_shuffle_mode(),
+        );
+        Ok(PlaybackSnapshot {
+            track: track?,
+            is_playing: is_playing?,
+            volume: volume?,
+            repeat_mode: repeat_mode?,
+            shuffle_mode: shuffle_mode?,
+        })
+    }
+
+    /// Append the events that turn `old` into `new`.
+    fn diff(
+        &self,
+        old: &PlaybackSnapshot,
+        new: &PlaybackSnapshot,
+        elapsed: Duration,
+        events: &mut Vec<WatchEvent>,
+    ) {
+        let same_track = match (&old.track, &new.track) {
+            (Some(a), Some(b)) => same_track(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        if !same_track {
+            events.push(WatchEvent::TrackChanged {
+                from: old.track.clone().map(Box::new),
+                to: new.track.clone().map(Box::new),
+            });
+        }
+
+        match (old.is_playing, new.is_playing) {
+            (true, false) => events.push(WatchEvent::Paused),
+            (false, true) => events.push(WatchEvent::Resumed),
+            _ => {}
+        }
+
+        if let (true, Some(a), Some(b)) = (same_track, &old.track, &new.track) {
+            // Only time spent playing on both ends moves the position.
+            let advanced = if old.is_playing && new.is_playing {
+                elapsed
+            } else {
+                Duration::ZERO
+            };
+            let expected = a.position().saturating_add(advanced);
+            let actual = b.position();
+            let drift = actual
+                .saturating_sub(expected)
+                .max(expected.saturating_sub(actual));
+            if drift > self.seek_threshold {
+                events.push(WatchEvent::Seeked {
+                    from: expected,
+                    to: actual,
+                });
+            }
+        }
+
+        if (old.volume - new.volume).abs() > VOLUME_EPSILON {
+            events.push(WatchEvent::VolumeChanged {
+                from: old.volume,
+                to: new.volume,
+            });
+        }
+        if old.repeat_mode != new.repeat_mode {
+            events.push(WatchEvent::RepeatChanged {
+                from: old.repeat_mode,
+                to: new.repeat_mode,
+            });
+        }
+        if old.shuffle_mode != new.shuffle_mode {
+            events.push(WatchEvent::ShuffleChanged {
+                from: old.shuffle_mode,
+                to: new.shuffle_mode,
+            });
+        }
+    }
+}
+
+impl CiderClient {
+    /// Start a [`PlaybackWatcher`] polling this client every second.
+    #[must_use]
+    pub fn watch(&self) -> PlaybackWatcher {
+        PlaybackWatcher::new(self.clone())
+    }
+}
+
+/// Wait until `cancelled` is set or its sender is dropped.
+///
+/// The stop signal of the background services built on [`PlaybackWatcher`]:
+/// their handles hold the sender and set it to `true` to stop them.
+pub(crate) async fn stopped(cancelled: &mut watch::Receiver<bool>) {
+    let _ = cancelled.wait_for(|&cancelled| cancelled).await;
+}
+
+/// Whether two snapshots show the same track.
+///
+/// Compares catalog IDs when both have one, otherwise name, artist and album.
+pub(crate) fn same_track(a: &NowPlaying, b: &NowPlaying) -> bool {
+    match (a.song_id(), b.song_id()) {
+        (Some(x), Some(y)) => x == y,
+        _ => a.name == b.name && a.artist_name == b.artist_name && a.album_name == b.album_name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PlayParams;
+
+    fn track(id: Option<&str>, name: &str, position: f64) -> NowPlaying {
+        let mut track: NowPlaying = serde_json::from_value(serde_json::json!({
+            "name": name,
+            "artistName": "Flume",
+            "albumName": "Skin",
+            "currentPlaybackTime": position
+        }))
+        .unwrap();
+        track.play_params = id.map(|id| PlayParams {
+            id: id.into(),
+            kind: "song".into(),
+        });
+        track
+    }
+
+    fn snapshot(track: Option<NowPlaying>, is_playing: bool) -> PlaybackSnapshot {
+        PlaybackSnapshot {
+            track,
+            is_playing,
+            volume: 0.5,
+            repeat_mode: RepeatMode::Off,
+            shuffle_mode: ShuffleMode::Off,
+        }
+    }
+
+    fn diff(old: &PlaybackSnapshot, new: &PlaybackSnapshot, elapsed: Duration) -> Vec<WatchEvent> {
+        let watcher = PlaybackWatcher::new(CiderClient::new());
+        let mut events = Vec::new();
+        watcher.diff(old, new, elapsed, &mut events);
+        events
+    }
+
+    #[test]
+    fn identical_snapshots_produce_no_events() {
+        let s = snapshot(Some(track(Some("1"), "A", 10.0)), false);
+        assert!(diff(&s, &s, Duration::from_secs(1)).is_empty());
+    }
+
+    #[test]
+    fn same_track_by_id_ignores_metadata() {
+        assert!(same_track(
+            &track(Some("1"), "A", 0.0),
+            &track(Some("1"), "A (Remastered)", 0.0)
+        ));
+        assert!(!same_track(
+            &track(Some("1"), "A", 0.0),
+            &track(Some("2"), "A", 0.0)
+        ));
+    }
+
+    #[test]
+    fn same_track_without_id_compares_metadata() {
+        assert!(same_track(&track(None, "A", 0.0), &track(None, "A", 5.0)));
+        assert!(!same_track(&track(None, "A", 0.0), &track(None, "B", 0.0)));
+    }
+
+    #[test]
+    fn track_change_and_stop() {
+        let a = snapshot(Some(track(Some("1"), "A", 10.0)), true);
+        let b = snapshot(Some(track(Some("2"), "B", 0.0)), true);
+        let events = diff(&a, &b, Duration::from_secs(1));
+        let [WatchEvent::TrackChanged {
+            from: Some(from),
+            to: Some(to),
+        }] = &events[..]
+        else {
+            panic!("unexpected events {events:?}");
+        };
+        assert_eq!((from.name.as_str(), to.name.as_str()), ("A", "B"));
+
+        let stopped = snapshot(None, false);
+        let events = diff(&b, &stopped, Duration::from_secs(1));
+        assert!(matches!(
+            &events[..],
+            [
+                WatchEvent::TrackChanged { to: None, .. },
+                WatchEvent::Paused
+            ]
+        ));
+    }
+
+    #[test]
+    fn normal_playback_is_not_a_seek() {
+        let a = snapshot(Some(track(Some("1"), "A", 10.0)), true);
+        let b = snapshot(Some(track(Some("1"), "A", 15.2)), true);
+        assert!(diff(&a, &b, Duration::from_secs(5)).is_empty());
+    }
+
+    #[test]
+    fn jump_is_a_seek() {
+        let a = snapshot(Some(track(Some("1"), "A", 10.0)), true);
+        let b = snapshot(Some(track(Some("1"), "A", 90.0)), true);
+        let events = diff(&a, &b, Duration::from_secs(1));
+        let [WatchEvent::Seeked { from, to }] = &events[..] else {
+            panic!("unexpected events {events:?}");
+        };
+        assert_eq!(*from, Duration::from_secs(11));
+        assert_eq!(*to, Duration::from_secs(90));
+    }
+
+    #[test]
+    fn paused_position_does_not_advance() {
+        let a = snapshot(Some(track(Some("1"), "A", 10.0)), false);
+        let b = snapshot(Some(track(Some("1"), "A", 10.0)), false);
+        assert!(diff(&a, &b, Duration::from_secs(60)).is_empty());
+    }
+
+    #[test]
+    fn modes_and_volume() {
+        let a = snapshot(None, false);
+        let mut b = a.clone();
+        b.volume = 0.8;
+        b.repeat_mode = RepeatMode::All;
+        b.shuffle_mode = ShuffleMode::On;
+        let events = diff(&a, &b, Duration::from_secs(1));
+        assert!(matches!(
+            &events[..],
+            [
+                WatchEvent::VolumeChanged { .. },
+                WatchEvent::RepeatChanged {
+                    from: RepeatMode::Off,
+                    to: RepeatMode::All
+                },
+                WatchEvent::ShuffleChanged {
+                    from: ShuffleMode::Off,
+                    to: ShuffleMode::On
+                },
+            ]
+        ));
+    }
+
+    #[test]
+    fn volume_noise_is_ignored() {
+        let a = snapshot(None, false);
+        let mut b = a.clone();
+        b.volume += 0.0001;
+        assert!(diff(&a, &b, Duration::from_secs(1)).is_empty());
+    }
+}
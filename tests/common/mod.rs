@@ -2,8 +2,46 @@
 
 pub mod fixtures;
 
-use cider_api::CiderClient;
-use wiremock::MockServer;
+use cider_api::{CiderClient, DecodeMode};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// A `200 OK` JSON response.
+pub fn json(body: impl Into<String>) -> ResponseTemplate {
+    ResponseTemplate::new(200)
+        .set_body_string(body.into())
+        .insert_header("content-type", "application/json")
+}
+
+/// Mount the player state endpoints: the fixture track playing at half
+/// volume, with the given repeat and shuffle modes.
+pub async fn mount_playing_state(server: &MockServer, repeat: u8, shuffle: u8) {
+    for (route, body) in [
+        (
+            "/api/v1/playback/now-playing",
+            fixtures::now_playing_json().to_owned(),
+        ),
+        (
+            "/api/v1/playback/is-playing",
+            fixtures::is_playing_json(true),
+        ),
+        ("/api/v1/playback/volume", fixtures::volume_json(0.5)),
+        (
+            "/api/v1/playback/repeat-mode",
+            fixtures::repeat_mode_json(repeat),
+        ),
+        (
+            "/api/v1/playback/shuffle-mode",
+            fixtures::shuffle_mode_json(shuffle),
+        ),
+    ] {
+        Mock::given(method("GET"))
+            .and(path(route))
+            .respond_with(json(body))
+            .mount(server)
+            .await;
+    }
+}
 
 pub async fn setup() -> (MockServer, CiderClient) {
     let server = MockServer::start().await;
@@ -16,3 +54,23 @@ pub async fn setup_with_token(token: &str) -> (MockServer, CiderClient) {
     let client = CiderClient::with_base_url(server.uri()).with_token(token);
     (server, client)
 }
+
+pub async fn setup_strict() -> (MockServer, CiderClient) {
+    let server = MockServer::start().await;
+    let client = CiderClient::with_base_url(server.uri()).with_decode_mode(DecodeMode::Strict);
+    (server, client)
+}
+
+/// A client without request timeouts or pool timers, for tests on a paused
+/// clock: the clock then only advances through the sleeps under test.
+pub fn paused_clock_client(server: &MockServer) -> CiderClient {
+    let http = reqwest::Client::builder()
+        .pool_idle_timeout(None)
+        .build()
+        .unwrap();
+    CiderClient::builder()
+        .base_url(server.uri())
+        .http_client(http)
+        .build()
+        .unwrap()
+}
@@ -44,5 +44,9 @@ async fn amapi_run_v3_error_on_500() {
         .amapi_run_v3("/v1/me/library/songs")
         .await
         .unwrap_err();
-    assert!(matches!(err, cider_api::CiderError::Http(_)));
+    assert!(matches!(
+        err,
+        cider_api::CiderError::Api { ref endpoint, status, .. }
+            if endpoint.path == "/api/v1/amapi/run-v3" && status == 500
+    ));
 }
@@ -0,0 +1,120 @@
+mod common;
+
+use std::time::Duration;
+
+use cider_api::CiderClient;
+use reqwest::header::HeaderMap;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn default_user_agent_is_sent() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/active"))
+        .and(header(
+            "user-agent",
+            concat!("cider-api/", env!("CARGO_PKG_VERSION")),
+        ))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+    client.is_active().await.unwrap();
+}
+
+#[tokio::test]
+async fn custom_user_agent_and_headers_are_sent() {
+    let server = MockServer::start().await;
+    let mut headers = HeaderMap::new();
+    headers.insert("x-team", "living-room".parse().unwrap());
+    let client = CiderClient::builder()
+        .base_url(server.uri())
+        .user_agent("my-app/1.0")
+        .default_headers(headers)
+        .token("tok")
+        .build()
+        .unwrap();
+
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/play"))
+        .and(header("user-agent", "my-app/1.0"))
+        .and(header("x-team", "living-room"))
+        .and(header("apptoken", "tok"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    client.play().await.unwrap();
+}
+
+#[tokio::test]
+async fn request_timeout_is_applied() {
+    let server = MockServer::start().await;
+    let client = CiderClient::builder()
+        .base_url(server.uri())
+        .timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/volume"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(common::fixtures::volume_json(0.5))
+                .set_delay(Duration::from_millis(500)),
+        )
+        .mount(&server)
+        .await;
+    let err = client.get_volume().await.unwrap_err();
+    assert!(matches!(err, cider_api::CiderError::Timeout { .. }));
+    assert!(err.is_retryable());
+}
+
+#[tokio::test]
+async fn longer_timeout_tolerates_slow_responses() {
+    let server = MockServer::start().await;
+    let client = CiderClient::builder()
+        .base_url(server.uri())
+        .timeout(Duration::from_secs(5))
+        .build()
+        .unwrap();
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/volume"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(common::fixtures::volume_json(0.5))
+                .set_delay(Duration::from_millis(300)),
+        )
+        .mount(&server)
+        .await;
+    let vol = client.get_volume().await.unwrap();
+    assert!((vol - 0.5).abs() < 0.01);
+}
+
+#[tokio::test]
+async fn injected_http_client_is_used() {
+    let server = MockServer::start().await;
+    let http = reqwest::Client::builder()
+        .user_agent("injected/2.0")
+        .build()
+        .unwrap();
+    let client = CiderClient::builder()
+        .base_url(server.uri())
+        .user_agent("ignored/1.0")
+        .http_client(http)
+        .token("tok")
+        .build()
+        .unwrap();
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/active"))
+        .and(header("user-agent", "injected/2.0"))
+        .and(header("apptoken", "tok"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+    client.is_active().await.unwrap();
+}
@@ -0,0 +1,236 @@
+#![cfg(feature = "cli")]
+
+mod common;
+
+use std::process::{Command, Output};
+
+use common::{fixtures, json, mount_playing_state};
+use wiremock::matchers::{body_json, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn mount_get(server: &MockServer, route: &str, body: impl Into<String>) {
+    Mock::given(method("GET"))
+        .and(path(route))
+        .respond_with(json(body))
+        .mount(server)
+        .await;
+}
+
+async fn mount_post(server: &MockServer, route: &str, body: serde_json::Value) {
+    Mock::given(method("POST"))
+        .and(path(route))
+        .and(body_json(body))
+        .respond_with(json(r#"{"status":"ok"}"#))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+/// A Cider playing the fixture track at half volume.
+async fn playing_cider() -> MockServer {
+    let server = MockServer::start().await;
+    mount_playing_state(&server, 2, 0).await;
+    mount_get(&server, "/api/v1/playback/queue", fixtures::queue_json()).await;
+    server
+}
+
+/// Run `cider` against `base_url` (`http://host:port`).
+async fn cider(base_url: &str, args: &[&str]) -> Output {
+    let (host, port) = base_url
+        .trim_start_matches("http://")
+        .rsplit_once(':')
+        .unwrap();
+    let mut command = Command::new(env!("CARGO_BIN_EXE_cider"));
+    command
+        .args(["--host", host, "--port", port])
+        .args(args)
+        .env_remove("CIDER_TOKEN");
+    tokio::task::spawn_blocking(move || command.output().unwrap())
+        .await
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[tokio::test]
+async fn status_as_text_and_json() {
+    let server = playing_cider().await;
+
+    let output = cider(&server.uri(), &["status"]).await;
+    assert!(output.status.success(), "{output:?}");
+    let text = stdout(&output);
+    assert!(text.starts_with("▶ Never Be Like You — Flume\n  Skin\n  0:42 / 3:54"));
+    assert!(
+        text.contains("volume 50% · repeat all · shuffle off"),
+        "{text}"
+    );
+
+    let output = cider(&server.uri(), &["--json", "status"]).await;
+    let status: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(status["is_playing"], true);
+    assert_eq!(status["repeat"], "all");
+    assert_eq!(status["track"]["name"], "Never Be Like You");
+}
+
+#[tokio::test]
+async fn commands_send_requests() {
+    let server = playing_cider().await;
+    mount_post(
+        &server,
+        "/api/v1/playback/seek",
+        serde_json::json!({"position": 57.5}),
+    )
+    .await;
+    mount_post(
+        &server,
+        "/api/v1/playback/volume",
+        serde_json::json!({"volume": 0.4}),
+    )
+    .await;
+    mount_post(
+        &server,
+        "/api/v1/playback/queue/remove-by-index",
+        serde_json::json!({"index": 2}),
+    )
+    .await;
+    mount_post(
+        &server,
+        "/api/v1/playback/play-next",
+        serde_json::json!({"type": "songs", "id": "1719861214"}),
+    )
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/next"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    // The stand-in Cider stays at 0:42 whatever is seeked to, and `seek`
+    // prints where playback is after seeking.
+    let output = cider(&server.uri(), &["seek", "+15s"]).await;
+    assert_eq!(stdout(&output), "0:42\n", "{output:?}");
+    for args in [
+        &["vol", "40%"][..],
+        &["queue", "rm", "2"],
+        &["play-next", "songs:1719861214"],
+        &["next"],
+    ] {
+        let output = cider(&server.uri(), args).await;
+        assert!(output.status.success(), "{args:?}: {output:?}");
+        assert!(output.stdout.is_empty());
+    }
+}
+
+#[tokio::test]
+async fn queue_and_volume_listing() {
+    let server = playing_cider().await;
+
+    let output = cider(&server.uri(), &["queue", "list"]).await;
+    assert_eq!(
+        stdout(&output),
+        "▶ 1  Never Be Like You — Flume (3:54)\n  2  Say It — Flume (4:12)\n"
+    );
+    assert_eq!(stdout(&cider(&server.uri(), &["vol"]).await), "50%\n");
+    assert_eq!(
+        stdout(&cider(&server.uri(), &["--json", "vol"]).await),
+        "{\"volume\":0.5}\n"
+    );
+}
+
+#[tokio::test]
+async fn token_is_sent() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/pause"))
+        .and(header("apptoken", "secret"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = cider(&server.uri(), &["pause", "--token", "secret"]).await;
+    assert!(output.status.success(), "{output:?}");
+}
+
+#[tokio::test]
+async fn exit_codes() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/play"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/stop"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/now-playing"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&server)
+        .await;
+
+    let code = |output: Output| output.status.code().unwrap();
+    assert_eq!(code(cider(&server.uri(), &["stop"]).await), 1);
+    assert_eq!(code(cider(&server.uri(), &["vol", "loud"]).await), 2);
+    assert_eq!(code(cider(&server.uri(), &["play", "songs:pl.x"]).await), 2);
+    assert_eq!(code(cider("http://127.0.0.1:1", &["play"]).await), 3);
+    assert_eq!(code(cider(&server.uri(), &["play"]).await), 4);
+    assert_eq!(code(cider(&server.uri(), &["now-playing"]).await), 5);
+    assert_eq!(code(cider(&server.uri(), &["seek", "+10s"]).await), 5);
+
+    let output = cider(&server.uri(), &["--json", "play"]).await;
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["exit_code"], 4);
+}
+
+#[tokio::test]
+async fn status_line_text_and_waybar() {
+    let server = playing_cider().await;
+
+    let output = cider(
+        &server.uri(),
+        &[
+            "status-line",
+            "--format",
+            "{artist} — {name} [{position}/{duration}] {lossless?} {atmos?}",
+        ],
+    )
+    .await;
+    assert_eq!(
+        stdout(&output),
+        "Flume — Never Be Like You [0:42/3:54] Lossless\n",
+        "{output:?}"
+    );
+
+    let output = cider(
+        &server.uri(),
+        &["status-line", "--waybar", "--max-width", "10"],
+    )
+    .await;
+    let line: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(line["text"], "Flume — N…");
+    assert_eq!(line["class"], "playing");
+    assert_eq!(line["percentage"], 18);
+    assert!(line["tooltip"]
+        .as_str()
+        .unwrap()
+        .starts_with("Never Be Like You\n"));
+
+    let output = cider(
+        "http://127.0.0.1:1",
+        &["status-line", "--waybar", "--offline", "Cider is off"],
+    )
+    .await;
+    assert!(output.status.success(), "{output:?}");
+    let line: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(line["text"], "Cider is off");
+    assert_eq!(line["class"], "offline");
+
+    let output = cider(&server.uri(), &["status-line", "--format", "{nope}"]).await;
+    assert_eq!(output.status.code(), Some(2));
+}
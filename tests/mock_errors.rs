@@ -1,5 +1,6 @@
 mod common;
 
+use cider_api::ItemRef;
 use wiremock::matchers::{header, method, path};
 use wiremock::{Mock, ResponseTemplate};
 
@@ -42,7 +43,9 @@ async fn fire_and_forget_error_on_500() {
         .mount(&server)
         .await;
     let err = client.play().await.unwrap_err();
-    assert!(matches!(err, cider_api::CiderError::Http(_)));
+    assert!(matches!(err, cider_api::CiderError::Api { status, .. } if status == 500));
+    assert_eq!(err.endpoint().unwrap().to_string(), "POST /api/v1/playback/play");
+    assert!(err.is_retryable());
 }
 
 #[tokio::test]
@@ -77,3 +80,112 @@ async fn set_volume_error_on_server_error() {
         .await;
     assert!(client.set_volume(0.5).await.is_err());
 }
+
+#[tokio::test]
+async fn error_body_is_parsed() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/play-item"))
+        .respond_with(
+            ResponseTemplate::new(400)
+                .set_body_string(r#"{"status":"error","message":"Invalid item type"}"#)
+                .insert_header("content-type", "application/json"),
+        )
+        .mount(&server)
+        .await;
+    let item = ItemRef::song("123").unwrap();
+    let err = client.play_item(&item).await.unwrap_err();
+    assert_eq!(err.status_code(), Some(reqwest::StatusCode::BAD_REQUEST));
+    assert_eq!(err.body().unwrap().description(), Some("Invalid item type"));
+    assert!(!err.is_retryable());
+    assert!(err.to_string().ends_with(": Invalid item type"));
+}
+
+#[tokio::test]
+async fn error_body_on_success_status_is_an_error() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/play"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(r#"{"status":"error","error":"Player not ready"}"#)
+                .insert_header("content-type", "application/json"),
+        )
+        .mount(&server)
+        .await;
+    let err = client.play().await.unwrap_err();
+    assert!(matches!(err, cider_api::CiderError::Api { status, .. } if status == 200));
+    assert_eq!(err.body().unwrap().description(), Some("Player not ready"));
+}
+
+#[tokio::test]
+async fn service_unavailable_is_retryable() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/volume"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&server)
+        .await;
+    let err = client.get_volume().await.unwrap_err();
+    assert!(err.is_retryable());
+    assert_eq!(err.endpoint().unwrap().to_string(), "GET /api/v1/playback/volume");
+}
+
+#[tokio::test]
+async fn seek_404_is_nothing_playing() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/seek"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+    let err = client.seek(10.0).await.unwrap_err();
+    assert!(err.is_nothing_playing());
+}
+
+#[tokio::test]
+async fn connection_refused_is_not_running() {
+    let port = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let client = cider_api::CiderClient::with_port(port);
+    let err = client.is_active().await.unwrap_err();
+    assert!(err.is_not_running());
+    assert!(err.is_retryable());
+    assert_eq!(err.endpoint().unwrap().path, "/api/v1/playback/active");
+}
+
+// ── Malformed responses ──
+
+#[tokio::test]
+async fn get_volume_decode_error_on_malformed_body() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/volume"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(r#"{"status":"ok","volume":"loud"}"#)
+                .insert_header("content-type", "application/json"),
+        )
+        .mount(&server)
+        .await;
+    let err = client.get_volume().await.unwrap_err();
+    assert!(matches!(err, cider_api::CiderError::Decode { path, .. } if path == "volume"));
+}
+
+#[tokio::test]
+async fn is_playing_decode_error_on_missing_field() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/is-playing"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(r#"{"status":"ok"}"#)
+                .insert_header("content-type", "application/json"),
+        )
+        .mount(&server)
+        .await;
+    let err = client.is_playing().await.unwrap_err();
+    assert!(matches!(err, cider_api::CiderError::Decode { .. }));
+}
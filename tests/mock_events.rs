@@ -0,0 +1,359 @@
+#![cfg(feature = "events")]
+// tungstenite's handshake callback signature returns a large `Err` type.
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use std::time::Duration;
+
+use cider_api::{
+    CiderClient, CiderError, DecodeMode, EventStream, PlaybackEvent, PlaybackState, RepeatMode,
+    RetryPolicy, ShuffleMode,
+};
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+// ── Socket.IO stand-in ──
+
+/// A minimal Engine.IO v4 / Socket.IO v5 server standing in for Cider.
+struct StandIn {
+    listener: TcpListener,
+}
+
+type ServerSocket = WebSocketStream<TcpStream>;
+
+impl StandIn {
+    async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        Self { listener }
+    }
+
+    fn url(&self) -> String {
+        format!("http://{}", self.listener.local_addr().unwrap())
+    }
+
+    /// Accept one client and complete the handshake, returning the socket
+    /// and the `apptoken` header it sent.
+    async fn accept(&self, ping_interval_ms: u64) -> (ServerSocket, Option<String>) {
+        let (tcp, _) = self.listener.accept().await.unwrap();
+        let mut token = None;
+        let mut socket =
+            tokio_tungstenite::accept_hdr_async(tcp, |req: &Request, resp: Response| {
+                assert_eq!(req.uri().path(), "/socket.io/");
+                assert!(req.uri().query().unwrap().contains("EIO=4"));
+                token = req
+                    .headers()
+                    .get("apptoken")
+                    .map(|v| v.to_str().unwrap().to_string());
+                Ok(resp)
+            })
+            .await
+            .unwrap();
+
+        let open = format!(
+            r#"0{{"sid":"abc","upgrades":[],"pingInterval":{ping_interval_ms},"pingTimeout":{ping_interval_ms},"maxPayload":1000000}}"#
+        );
+        socket.send(Message::Text(open)).await.unwrap();
+        assert_eq!(recv_text(&mut socket).await, "40");
+        socket
+            .send(Message::Text(r#"40{"sid":"def"}"#.into()))
+            .await
+            .unwrap();
+        (socket, token)
+    }
+}
+
+async fn recv_text(socket: &mut ServerSocket) -> String {
+    loop {
+        match socket.next().await.unwrap().unwrap() {
+            Message::Text(text) => return text,
+            Message::Close(_) => panic!("client closed the socket"),
+            _ => {}
+        }
+    }
+}
+
+async fn emit(socket: &mut ServerSocket, kind: &str, data: serde_json::Value) {
+    let packet = serde_json::json!(["API:Playback", { "type": kind, "data": data }]);
+    socket
+        .send(Message::Text(format!("42{packet}")))
+        .await
+        .unwrap();
+}
+
+async fn next(events: &mut EventStream) -> Result<PlaybackEvent, CiderError> {
+    tokio::time::timeout(Duration::from_secs(5), events.next())
+        .await
+        .expect("timed out waiting for an event")
+        .expect("stream ended")
+}
+
+fn fast_reconnect() -> RetryPolicy {
+    RetryPolicy::new()
+        .max_attempts(5)
+        .initial_backoff(Duration::from_millis(10))
+        .max_backoff(Duration::from_millis(50))
+}
+
+fn now_playing_info() -> serde_json::Value {
+    let response: serde_json::Value =
+        serde_json::from_str(common::fixtures::now_playing_json()).unwrap();
+    response["info"].clone()
+}
+
+// ── Decoding ──
+
+#[tokio::test]
+async fn events_are_decoded() {
+    let server = StandIn::start().await;
+    let client = CiderClient::with_base_url(server.url());
+    let mut events = client.events();
+    let (mut socket, _) = server.accept(25_000).await;
+
+    assert!(matches!(
+        next(&mut events).await,
+        Ok(PlaybackEvent::Connected)
+    ));
+
+    emit(
+        &mut socket,
+        "playbackStatus.nowPlayingItemDidChange",
+        now_playing_info(),
+    )
+    .await;
+    let Ok(PlaybackEvent::NowPlayingChanged(track)) = next(&mut events).await else {
+        panic!("expected NowPlayingChanged");
+    };
+    assert_eq!(track.name, "Never Be Like You");
+    assert_eq!(track.artist_name, "Flume");
+
+    emit(
+        &mut socket,
+        "playbackStatus.playbackTimeDidChange",
+        serde_json::json!({
+            "currentPlaybackDuration": 234.0,
+            "currentPlaybackTime": 43.0,
+            "currentPlaybackTimeRemaining": 191.0,
+            "isPlaying": true
+        }),
+    )
+    .await;
+    let Ok(PlaybackEvent::TimeChanged(time)) = next(&mut events).await else {
+        panic!("expected TimeChanged");
+    };
+    assert!((time.current_playback_time - 43.0).abs() < f64::EPSILON);
+
+    emit(
+        &mut socket,
+        "playbackStatus.playbackStateDidChange",
+        serde_json::json!({ "state": "paused", "attributes": now_playing_info() }),
+    )
+    .await;
+    let Ok(PlaybackEvent::StateChanged { state, track }) = next(&mut events).await else {
+        panic!("expected StateChanged");
+    };
+    assert_eq!(state, PlaybackState::Paused);
+    assert_eq!(track.unwrap().album_name, "Skin");
+
+    emit(
+        &mut socket,
+        "playerStatus.volumeDidChange",
+        serde_json::json!(0.4),
+    )
+    .await;
+    assert!(matches!(
+        next(&mut events).await,
+        Ok(PlaybackEvent::VolumeChanged(v)) if (v - 0.4).abs() < 1e-6
+    ));
+
+    emit(
+        &mut socket,
+        "playerStatus.shuffleModeDidChange",
+        serde_json::json!(1),
+    )
+    .await;
+    assert!(matches!(
+        next(&mut events).await,
+        Ok(PlaybackEvent::ShuffleModeChanged(ShuffleMode::On))
+    ));
+
+    emit(
+        &mut socket,
+        "playerStatus.autoplayDidChange",
+        serde_json::json!(true),
+    )
+    .await;
+    assert!(matches!(
+        next(&mut events).await,
+        Ok(PlaybackEvent::Other { kind, .. }) if kind == "playerStatus.autoplayDidChange"
+    ));
+}
+
+#[tokio::test]
+async fn strict_mode_reports_malformed_events() {
+    let server = StandIn::start().await;
+    let client = CiderClient::with_base_url(server.url()).with_decode_mode(DecodeMode::Strict);
+    let mut events = client.events();
+    let (mut socket, _) = server.accept(25_000).await;
+    assert!(matches!(
+        next(&mut events).await,
+        Ok(PlaybackEvent::Connected)
+    ));
+
+    emit(
+        &mut socket,
+        "playerStatus.volumeDidChange",
+        serde_json::json!("loud"),
+    )
+    .await;
+    let err = next(&mut events).await.unwrap_err();
+    assert!(matches!(err, CiderError::Decode { .. }));
+    // Socket frames have no HTTP status.
+    assert_eq!(err.status_code(), None);
+}
+
+// ── Connection handling ──
+
+#[tokio::test]
+async fn token_is_sent_in_handshake() {
+    let server = StandIn::start().await;
+    let client = CiderClient::with_base_url(server.url()).with_token("secret-123");
+    let mut events = client.events();
+    let (_socket, token) = server.accept(25_000).await;
+    assert!(matches!(
+        next(&mut events).await,
+        Ok(PlaybackEvent::Connected)
+    ));
+    assert_eq!(token.as_deref(), Some("secret-123"));
+}
+
+#[tokio::test]
+async fn pings_are_answered() {
+    let server = StandIn::start().await;
+    let client = CiderClient::with_base_url(server.url());
+    let mut events = client.events();
+    let (mut socket, _) = server.accept(25_000).await;
+    assert!(matches!(
+        next(&mut events).await,
+        Ok(PlaybackEvent::Connected)
+    ));
+
+    socket.send(Message::Text("2".into())).await.unwrap();
+    assert_eq!(recv_text(&mut socket).await, "3");
+}
+
+#[tokio::test]
+async fn reconnects_after_server_closes() {
+    let server = StandIn::start().await;
+    let client = CiderClient::with_base_url(server.url());
+    let mut events = client.events_with(fast_reconnect());
+
+    let (socket, _) = server.accept(25_000).await;
+    assert!(matches!(
+        next(&mut events).await,
+        Ok(PlaybackEvent::Connected)
+    ));
+    drop(socket);
+    assert!(matches!(
+        next(&mut events).await,
+        Ok(PlaybackEvent::Disconnected)
+    ));
+
+    let (mut socket, _) = server.accept(25_000).await;
+    assert!(matches!(
+        next(&mut events).await,
+        Ok(PlaybackEvent::Connected)
+    ));
+    emit(
+        &mut socket,
+        "playerStatus.repeatModeDidChange",
+        serde_json::json!(2),
+    )
+    .await;
+    assert!(matches!(
+        next(&mut events).await,
+        Ok(PlaybackEvent::RepeatModeChanged(RepeatMode::All))
+    ));
+}
+
+#[tokio::test]
+async fn lost_connection_error_is_yielded_when_policy_gives_up() {
+    let server = StandIn::start().await;
+    let client = CiderClient::with_base_url(server.url());
+    let mut events = client.events_with(fast_reconnect().max_attempts(1));
+
+    let (socket, _) = server.accept(25_000).await;
+    assert!(matches!(
+        next(&mut events).await,
+        Ok(PlaybackEvent::Connected)
+    ));
+    drop(socket);
+    assert!(matches!(
+        next(&mut events).await,
+        Ok(PlaybackEvent::Disconnected)
+    ));
+    let err = next(&mut events).await.unwrap_err();
+    assert!(matches!(err, CiderError::EventStream { .. }), "{err:?}");
+    assert!(events.next().await.is_none());
+}
+
+#[tokio::test]
+async fn silent_server_is_treated_as_gone() {
+    let server = StandIn::start().await;
+    let client = CiderClient::with_base_url(server.url());
+    let mut events = client.events_with(fast_reconnect());
+
+    // 50ms ping interval + 50ms timeout, and the stand-in never pings.
+    let (_socket, _) = server.accept(50).await;
+    assert!(matches!(
+        next(&mut events).await,
+        Ok(PlaybackEvent::Connected)
+    ));
+    assert!(matches!(
+        next(&mut events).await,
+        Ok(PlaybackEvent::Disconnected)
+    ));
+}
+
+#[tokio::test]
+async fn not_running_ends_after_policy_gives_up() {
+    let port = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let client = CiderClient::with_port(port);
+    let mut events = client.events_with(fast_reconnect().max_attempts(2));
+
+    for _ in 0..2 {
+        let err = next(&mut events).await.unwrap_err();
+        assert!(err.is_not_running());
+        assert_eq!(err.endpoint().unwrap().path, "/socket.io/");
+    }
+    assert!(events.next().await.is_none());
+}
+
+#[tokio::test]
+async fn rejected_token_is_not_retried() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (tcp, _) = listener.accept().await.unwrap();
+        let _ = tokio_tungstenite::accept_hdr_async(tcp, |_: &Request, _: Response| {
+            let mut reject = tokio_tungstenite::tungstenite::handshake::server::ErrorResponse::new(
+                Some(r#"{"status":"error","message":"Unauthorized"}"#.into()),
+            );
+            *reject.status_mut() = reqwest::StatusCode::UNAUTHORIZED;
+            Err(reject)
+        })
+        .await;
+    });
+
+    let client = CiderClient::with_base_url(url).with_token("wrong");
+    let mut events = client.events_with(fast_reconnect());
+    let err = next(&mut events).await.unwrap_err();
+    assert!(err.is_auth());
+    assert!(events.next().await.is_none());
+}
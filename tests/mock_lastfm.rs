@@ -0,0 +1,169 @@
+#![cfg(feature = "scrobble")]
+
+use std::collections::HashMap;
+use std::time::{Duration, UNIX_EPOCH};
+
+use cider_api::{LastFm, Scrobble, ScrobbleSink};
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+fn scrobble(name: &str, started_at: u64) -> Scrobble {
+    Scrobble {
+        artist_name: "Artist".into(),
+        name: name.into(),
+        album_name: "Album".into(),
+        isrc: None,
+        started_at: UNIX_EPOCH + Duration::from_secs(started_at),
+        duration: Duration::from_secs(200),
+    }
+}
+
+async fn setup() -> (MockServer, LastFm) {
+    let server = MockServer::start().await;
+    let sink = LastFm::new("api-key", "api-secret", "session-key")
+        .base_url(format!("{}/2.0/", server.uri()));
+    (server, sink)
+}
+
+/// The form parameters of `request`.
+fn form(request: &Request) -> HashMap<String, String> {
+    let body = std::str::from_utf8(&request.body).unwrap();
+    reqwest::Url::parse(&format!("http://form/?{body}"))
+        .unwrap()
+        .query_pairs()
+        .into_owned()
+        .collect()
+}
+
+/// The signature Last.fm expects for `params`.
+fn expected_signature(params: &HashMap<String, String>) -> String {
+    let mut names: Vec<&String> = params
+        .keys()
+        .filter(|name| *name != "api_sig" && *name != "format")
+        .collect();
+    names.sort();
+    let mut input = String::new();
+    for name in names {
+        input.push_str(name);
+        input.push_str(&params[name]);
+    }
+    input.push_str("api-secret");
+    format!("{:x}", md5::compute(input))
+}
+
+#[tokio::test]
+async fn update_now_playing_is_signed() {
+    let (server, sink) = setup().await;
+    Mock::given(method("POST"))
+        .and(path("/2.0/"))
+        .and(body_string_contains("method=track.updateNowPlaying"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({"nowplaying": {}})),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    sink.now_playing(&scrobble("Song", 1_700_000_000))
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let params = form(&requests[0]);
+    assert_eq!(params["artist"], "Artist");
+    assert_eq!(params["track"], "Song");
+    assert_eq!(params["album"], "Album");
+    assert_eq!(params["duration"], "200");
+    assert_eq!(params["api_key"], "api-key");
+    assert_eq!(params["sk"], "session-key");
+    assert_eq!(params["format"], "json");
+    assert!(!params.contains_key("timestamp"));
+    assert_eq!(params["api_sig"], expected_signature(&params));
+}
+
+#[tokio::test]
+async fn scrobble_batches_are_indexed_and_signed() {
+    let (server, sink) = setup().await;
+    Mock::given(method("POST"))
+        .and(path("/2.0/"))
+        .and(body_string_contains("method=track.scrobble"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "scrobbles": {"@attr": {"accepted": 2, "ignored": 0}}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    sink.submit(&[
+        scrobble("One", 1_700_000_000),
+        scrobble("Two", 1_700_000_300),
+    ])
+    .await
+    .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let params = form(&requests[0]);
+    assert_eq!(params["track[0]"], "One");
+    assert_eq!(params["timestamp[0]"], "1700000000");
+    assert_eq!(params["track[1]"], "Two");
+    assert_eq!(params["timestamp[1]"], "1700000300");
+    assert_eq!(params["api_sig"], expected_signature(&params));
+    assert_eq!(sink.max_batch(), 50);
+}
+
+#[tokio::test]
+async fn invalid_session_is_an_auth_error() {
+    let (server, sink) = setup().await;
+    Mock::given(method("POST"))
+        .and(path("/2.0/"))
+        .respond_with(ResponseTemplate::new(403).set_body_json(serde_json::json!({
+            "error": 9,
+            "message": "Invalid session key - Please re-authenticate"
+        })))
+        .mount(&server)
+        .await;
+
+    let err = sink.submit(&[scrobble("Song", 0)]).await.unwrap_err();
+    assert!(err.is_auth());
+    assert!(!err.is_retryable());
+    assert_eq!(
+        err.endpoint().unwrap().to_string(),
+        "POST /2.0/?method=track.scrobble"
+    );
+    assert!(err.to_string().contains("Invalid session key"), "{err}");
+}
+
+#[tokio::test]
+async fn temporary_error_codes_are_retryable() {
+    let (server, sink) = setup().await;
+    // Last.fm sometimes reports errors with a success status.
+    Mock::given(method("POST"))
+        .and(path("/2.0/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "error": 16,
+            "message": "The service is temporarily unavailable, please try again."
+        })))
+        .mount(&server)
+        .await;
+
+    let err = sink.submit(&[scrobble("Song", 0)]).await.unwrap_err();
+    assert!(err.is_retryable());
+    assert!(!err.is_auth());
+}
+
+#[tokio::test]
+async fn invalid_parameters_are_not_retryable() {
+    let (server, sink) = setup().await;
+    Mock::given(method("POST"))
+        .and(path("/2.0/"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+            "error": 6,
+            "message": "Invalid parameters"
+        })))
+        .mount(&server)
+        .await;
+
+    let err = sink.now_playing(&scrobble("Song", 0)).await.unwrap_err();
+    assert!(!err.is_retryable());
+    assert!(!err.is_auth());
+}
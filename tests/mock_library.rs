@@ -1,5 +1,6 @@
 mod common;
 
+use cider_api::Rating;
 use wiremock::matchers::{body_json, method, path};
 use wiremock::{Mock, ResponseTemplate};
 
@@ -25,7 +26,7 @@ async fn set_rating_like() {
         .expect(1)
         .mount(&server)
         .await;
-    client.set_rating(1).await.unwrap();
+    client.set_rating(Rating::Like).await.unwrap();
 }
 
 #[tokio::test]
@@ -38,31 +39,18 @@ async fn set_rating_dislike() {
         .expect(1)
         .mount(&server)
         .await;
-    client.set_rating(-1).await.unwrap();
+    client.set_rating(Rating::Dislike).await.unwrap();
 }
 
 #[tokio::test]
-async fn set_rating_clamps_above_1() {
+async fn set_rating_none_clears() {
     let (server, client) = common::setup().await;
     Mock::given(method("POST"))
         .and(path("/api/v1/playback/set-rating"))
-        .and(body_json(serde_json::json!({"rating": 1})))
-        .respond_with(ResponseTemplate::new(200))
-        .expect(1)
-        .mount(&server)
-        .await;
-    client.set_rating(5).await.unwrap();
-}
-
-#[tokio::test]
-async fn set_rating_clamps_below_neg1() {
-    let (server, client) = common::setup().await;
-    Mock::given(method("POST"))
-        .and(path("/api/v1/playback/set-rating"))
-        .and(body_json(serde_json::json!({"rating": -1})))
+        .and(body_json(serde_json::json!({"rating": 0})))
         .respond_with(ResponseTemplate::new(200))
         .expect(1)
         .mount(&server)
         .await;
-    client.set_rating(-10).await.unwrap();
+    client.set_rating(Rating::None).await.unwrap();
 }
@@ -0,0 +1,149 @@
+#![cfg(feature = "scrobble")]
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use cider_api::{ListenBrainz, Scrobble, ScrobbleQueue, ScrobbleSink};
+use wiremock::matchers::{body_partial_json, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn scrobble(name: &str, started_at: u64) -> Scrobble {
+    Scrobble {
+        artist_name: "Artist".into(),
+        name: name.into(),
+        album_name: "Album".into(),
+        isrc: Some("USRC17607839".into()),
+        started_at: UNIX_EPOCH + Duration::from_secs(started_at),
+        duration: Duration::from_secs(200),
+    }
+}
+
+async fn setup() -> (MockServer, ListenBrainz) {
+    let server = MockServer::start().await;
+    let sink = ListenBrainz::new("user-token").base_url(server.uri());
+    (server, sink)
+}
+
+fn accepted() -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(serde_json::json!({"status": "ok"}))
+}
+
+#[tokio::test]
+async fn playing_now_has_no_timestamp() {
+    let (server, sink) = setup().await;
+    Mock::given(method("POST"))
+        .and(path("/1/submit-listens"))
+        .and(header("authorization", "Token user-token"))
+        .and(body_partial_json(serde_json::json!({
+            "listen_type": "playing_now",
+            "payload": [{
+                "track_metadata": {
+                    "artist_name": "Artist",
+                    "track_name": "Song",
+                    "release_name": "Album",
+                    "additional_info": {"isrc": "USRC17607839", "duration_ms": 200_000}
+                }
+            }]
+        })))
+        .respond_with(accepted())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    sink.now_playing(&scrobble("Song", 1_700_000_000))
+        .await
+        .unwrap();
+    let requests = server.received_requests().await.unwrap();
+    let body: serde_json::Value = requests[0].body_json().unwrap();
+    assert!(body["payload"][0].get("listened_at").is_none());
+}
+
+#[tokio::test]
+async fn one_play_is_single_and_a_backlog_is_import() {
+    let (server, sink) = setup().await;
+    Mock::given(method("POST"))
+        .and(path("/1/submit-listens"))
+        .respond_with(accepted())
+        .mount(&server)
+        .await;
+
+    sink.submit(&[scrobble("One", 1_700_000_000)])
+        .await
+        .unwrap();
+    sink.submit(&[
+        scrobble("Two", 1_700_000_300),
+        scrobble("Three", 1_700_000_600),
+    ])
+    .await
+    .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let single: serde_json::Value = requests[0].body_json().unwrap();
+    assert_eq!(single["listen_type"], "single");
+    assert_eq!(single["payload"][0]["listened_at"], 1_700_000_000);
+    let import: serde_json::Value = requests[1].body_json().unwrap();
+    assert_eq!(import["listen_type"], "import");
+    assert_eq!(import["payload"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        import["payload"][1]["track_metadata"]["track_name"],
+        "Three"
+    );
+}
+
+#[tokio::test]
+async fn invalid_token_is_an_auth_error() {
+    let (server, sink) = setup().await;
+    Mock::given(method("POST"))
+        .and(path("/1/submit-listens"))
+        .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+            "code": 401,
+            "error": "Invalid authorization token."
+        })))
+        .mount(&server)
+        .await;
+
+    let err = sink.submit(&[scrobble("Song", 0)]).await.unwrap_err();
+    assert!(err.is_auth());
+    assert!(!err.is_retryable());
+    assert_eq!(err.status_code(), Some(reqwest::StatusCode::UNAUTHORIZED));
+    assert_eq!(
+        err.endpoint().unwrap().to_string(),
+        "POST /1/submit-listens"
+    );
+    assert!(
+        err.to_string().ends_with("Invalid authorization token."),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn outages_keep_plays_queued() {
+    let (server, sink) = setup().await;
+    Mock::given(method("POST"))
+        .and(path("/1/submit-listens"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/1/submit-listens"))
+        .respond_with(accepted())
+        .mount(&server)
+        .await;
+
+    let mut queue = ScrobbleQueue::in_memory();
+    queue.push(scrobble("Song", 1_700_000_000)).unwrap();
+    let err = queue.flush(&sink).await.unwrap_err();
+    assert!(err.is_retryable());
+    assert_eq!(queue.len(), 1);
+
+    assert_eq!(queue.flush(&sink).await.unwrap(), 1);
+    assert!(queue.is_empty());
+}
+
+#[tokio::test]
+async fn unreachable_server_is_retryable() {
+    let sink = ListenBrainz::new("user-token").base_url("http://127.0.0.1:1");
+    let err = sink.submit(&[scrobble("Song", 0)]).await.unwrap_err();
+    assert!(err.is_retryable());
+    assert_eq!(err.status_code(), None);
+}
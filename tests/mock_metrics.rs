@@ -0,0 +1,125 @@
+#![cfg(feature = "metrics")]
+
+mod common;
+
+use std::time::Duration;
+
+use cider_api::{CiderClient, Metrics, MetricsHandle, MetricsServer};
+use common::{fixtures, json, mount_playing_state};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// A Cider playing the fixture track at half volume.
+async fn playing_cider() -> MockServer {
+    let server = MockServer::start().await;
+    mount_playing_state(&server, 0, 0).await;
+    server
+}
+
+async fn serve(client: CiderClient) -> MetricsHandle {
+    MetricsServer::new(client)
+        .poll_interval(Duration::from_millis(50))
+        .start("127.0.0.1:0")
+        .await
+        .unwrap()
+}
+
+/// Scrape `/metrics` until it contains every line in `expected`.
+async fn scrape_until(server: &MetricsHandle, expected: &[&str]) -> String {
+    let url = format!("http://{}/metrics", server.local_addr());
+    let mut text = String::new();
+    for _ in 0..100 {
+        let resp = reqwest::get(&url).await.unwrap();
+        assert_eq!(
+            resp.headers()["content-type"],
+            "text/plain; version=0.0.4; charset=utf-8"
+        );
+        text = resp.text().await.unwrap();
+        if expected.iter().all(|line| text.lines().any(|l| l == *line)) {
+            return text;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("expected {expected:?} in:\n{text}");
+}
+
+#[tokio::test]
+async fn client_calls_are_counted() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/volume"))
+        .respond_with(json(fixtures::volume_json(0.5)))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/next"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+    let metrics = Metrics::new();
+    let client = client.with_metrics(metrics.clone());
+
+    client.get_volume().await.unwrap();
+    client.get_volume().await.unwrap();
+    client.next().await.unwrap_err();
+    CiderClient::builder()
+        .base_url("http://127.0.0.1:1")
+        .metrics(metrics.clone())
+        .build()
+        .unwrap()
+        .play()
+        .await
+        .unwrap_err();
+
+    let text = metrics.render();
+    for line in [
+        r#"cider_requests_total{method="GET",path="/api/v1/playback/volume",outcome="ok"} 2"#,
+        r#"cider_requests_total{method="POST",path="/api/v1/playback/next",outcome="api"} 1"#,
+        r#"cider_requests_total{method="POST",path="/api/v1/playback/play",outcome="not_reachable"} 1"#,
+        r#"cider_request_duration_seconds_count{method="GET",path="/api/v1/playback/volume"} 2"#,
+    ] {
+        assert!(
+            text.lines().any(|l| l == line),
+            "missing {line} in:\n{text}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn serves_player_gauges() {
+    let cider = playing_cider().await;
+    let metrics = Metrics::new();
+    let client = CiderClient::with_base_url(cider.uri()).with_metrics(metrics.clone());
+    let server = serve(client.clone()).await;
+
+    let text = scrape_until(
+        &server,
+        &[
+            "cider_up 1",
+            "cider_playing 1",
+            "cider_volume 0.5",
+            "cider_position_seconds 42.5",
+            "cider_track_duration_seconds 234",
+        ],
+    )
+    .await;
+    // The server's polls go through the shared metrics.
+    assert!(text.contains(r#"path="/api/v1/playback/now-playing",outcome="ok"}"#));
+    assert!(metrics.render().contains("cider_up 1"));
+
+    let resp = reqwest::get(format!("http://{}/other", server.local_addr()))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+
+    server.stop();
+    server.wait().await;
+}
+
+#[tokio::test]
+async fn unreachable_cider_is_down() {
+    let server = serve(CiderClient::with_base_url("http://127.0.0.1:1")).await;
+    let text = scrape_until(&server, &["cider_up 0"]).await;
+    assert!(!text.contains("cider_volume"));
+    assert!(text.contains(r#"outcome="not_reachable"}"#));
+}
@@ -0,0 +1,422 @@
+#![cfg(feature = "mpd")]
+
+mod common;
+
+use std::time::Duration;
+
+use cider_api::{CiderClient, MpdHandle, MpdServer};
+use common::{fixtures, json, mount_playing_state};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use wiremock::matchers::{body_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// A Cider playing the first of the two fixture queue items at half volume.
+async fn playing_cider() -> MockServer {
+    let server = MockServer::start().await;
+    mount_playing_state(&server, 2, 0).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/queue"))
+        .respond_with(json(fixtures::queue_json()))
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn serve(cider: &MockServer) -> MpdHandle {
+    MpdServer::new(CiderClient::with_base_url(cider.uri()))
+        .idle_interval(Duration::from_millis(50))
+        .start("127.0.0.1:0")
+        .await
+        .unwrap()
+}
+
+/// An MPD client connection.
+struct Mpd {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    write: OwnedWriteHalf,
+}
+
+impl Mpd {
+    async fn connect(server: &MpdHandle) -> Self {
+        let (read, write) = TcpStream::connect(server.local_addr())
+            .await
+            .unwrap()
+            .into_split();
+        let mut mpd = Self {
+            lines: BufReader::new(read).lines(),
+            write,
+        };
+        let greeting = mpd.line().await;
+        assert!(greeting.starts_with("OK MPD "), "{greeting}");
+        mpd
+    }
+
+    async fn line(&mut self) -> String {
+        tokio::time::timeout(Duration::from_secs(5), self.lines.next_line())
+            .await
+            .expect("MPD server did not answer")
+            .unwrap()
+            .expect("MPD server closed the connection")
+    }
+
+    async fn send(&mut self, command: &str) {
+        self.write
+            .write_all(format!("{command}\n").as_bytes())
+            .await
+            .unwrap();
+    }
+
+    /// Send `command` and read the response up to and including the
+    /// final `OK` or `ACK` line.
+    async fn command(&mut self, command: &str) -> Vec<String> {
+        self.send(command).await;
+        self.response().await
+    }
+
+    async fn response(&mut self) -> Vec<String> {
+        let mut response = Vec::new();
+        loop {
+            let line = self.line().await;
+            let done = line == "OK" || line.starts_with("ACK ");
+            response.push(line);
+            if done {
+                return response;
+            }
+        }
+    }
+}
+
+fn field<'a>(response: &'a [String], name: &str) -> Option<&'a str> {
+    response
+        .iter()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(": "))
+}
+
+#[tokio::test]
+async fn status_and_currentsong() {
+    let cider = playing_cider().await;
+    let server = serve(&cider).await;
+    let mut mpd = Mpd::connect(&server).await;
+
+    let status = mpd.command("status").await;
+    assert_eq!(status.last().unwrap(), "OK");
+    assert_eq!(field(&status, "state"), Some("play"));
+    assert_eq!(field(&status, "volume"), Some("50"));
+    assert_eq!(field(&status, "repeat"), Some("1"));
+    assert_eq!(field(&status, "single"), Some("0"));
+    assert_eq!(field(&status, "random"), Some("0"));
+    assert_eq!(field(&status, "playlistlength"), Some("2"));
+    assert_eq!(field(&status, "song"), Some("0"));
+    assert_eq!(field(&status, "nextsong"), Some("1"));
+    assert_eq!(field(&status, "elapsed"), Some("42.500"));
+    assert_eq!(field(&status, "duration"), Some("234.000"));
+    assert_eq!(field(&status, "time"), Some("43:234"));
+
+    let song = mpd.command("currentsong").await;
+    assert_eq!(
+        field(&song, "file"),
+        Some("https://music.apple.com/ca/album/skin/1719860281")
+    );
+    assert_eq!(field(&song, "Title"), Some("Never Be Like You"));
+    assert_eq!(field(&song, "Artist"), Some("Flume"));
+    assert_eq!(field(&song, "Album"), Some("Skin"));
+    assert_eq!(field(&song, "Time"), Some("234"));
+    assert_eq!(field(&song, "Pos"), Some("0"));
+}
+
+#[tokio::test]
+async fn playlistinfo_lists_the_queue() {
+    let cider = playing_cider().await;
+    let server = serve(&cider).await;
+    let mut mpd = Mpd::connect(&server).await;
+
+    let all = mpd.command("playlistinfo").await;
+    let titles: Vec<_> = all.iter().filter(|l| l.starts_with("Title: ")).collect();
+    assert_eq!(titles, ["Title: Never Be Like You", "Title: Say It"]);
+    assert!(all.contains(&"file: cider:1719861214".to_owned()));
+
+    let second = mpd.command("playlistinfo 1").await;
+    assert_eq!(field(&second, "Title"), Some("Say It"));
+    assert_eq!(field(&second, "Pos"), Some("1"));
+    assert!(field(&second, "Id").is_some());
+
+    let range = mpd.command("playlistinfo 0:").await;
+    assert_eq!(range.iter().filter(|l| l.starts_with("Pos: ")).count(), 2);
+
+    let bad = mpd.command("playlistinfo 7").await;
+    assert_eq!(bad, ["ACK [2@0] {playlistinfo} Bad song index"]);
+}
+
+#[tokio::test]
+async fn queue_edits_reach_cider() {
+    let cider = playing_cider().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/queue/move-to-position"))
+        .and(body_json(serde_json::json!({
+            "startIndex": 2,
+            "destinationIndex": 1
+        })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&cider)
+        .await;
+    // `delete 0:2` removes from the end.
+    for index in [2, 1] {
+        Mock::given(method("POST"))
+            .and(path("/api/v1/playback/queue/remove-by-index"))
+            .and(body_json(serde_json::json!({ "index": index })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&cider)
+            .await;
+    }
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/queue/clear-queue"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&cider)
+        .await;
+    let server = serve(&cider).await;
+    let mut mpd = Mpd::connect(&server).await;
+
+    assert_eq!(mpd.command("move 1 0").await, ["OK"]);
+    assert_eq!(mpd.command("delete 0:2").await, ["OK"]);
+    assert_eq!(mpd.command("clear").await, ["OK"]);
+    assert_eq!(
+        mpd.command("move 0 5").await,
+        ["ACK [2@0] {move} Bad song index"]
+    );
+    assert_eq!(
+        mpd.command(&format!("delete {}", usize::MAX)).await,
+        ["ACK [2@0] {delete} Bad song index"]
+    );
+}
+
+#[tokio::test]
+async fn id_commands_find_songs_by_id() {
+    let cider = playing_cider().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/queue/move-to-position"))
+        .and(body_json(serde_json::json!({
+            "startIndex": 2,
+            "destinationIndex": 1
+        })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&cider)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/queue/remove-by-index"))
+        .and(body_json(serde_json::json!({ "index": 2 })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&cider)
+        .await;
+    let server = serve(&cider).await;
+    let mut mpd = Mpd::connect(&server).await;
+
+    let queue = mpd.command("playlistinfo").await;
+    let ids: Vec<&str> = queue
+        .iter()
+        .filter_map(|line| line.strip_prefix("Id: "))
+        .collect();
+    assert_eq!(ids.len(), 2);
+    let status = mpd.command("status").await;
+    assert_eq!(field(&status, "songid"), Some(ids[0]));
+    assert_eq!(field(&status, "nextsongid"), Some(ids[1]));
+    let song = mpd.command("currentsong").await;
+    assert_eq!(field(&song, "Id"), Some(ids[0]));
+
+    let found = mpd.command(&format!("playlistid {}", ids[1])).await;
+    assert_eq!(field(&found, "Title"), Some("Say It"));
+    assert_eq!(field(&found, "Pos"), Some("1"));
+    assert_eq!(mpd.command(&format!("moveid {} 0", ids[1])).await, ["OK"]);
+    assert_eq!(mpd.command(&format!("deleteid {}", ids[1])).await, ["OK"]);
+    assert_eq!(
+        mpd.command(&format!("playid {}", ids[1])).await,
+        ["ACK [2@0] {playid} Cider cannot jump to another song in the queue"]
+    );
+
+    let unknown = (0..)
+        .find(|id| !ids.contains(&id.to_string().as_str()))
+        .unwrap();
+    assert_eq!(
+        mpd.command(&format!("deleteid {unknown}")).await,
+        ["ACK [50@0] {deleteid} No such song"]
+    );
+}
+
+#[tokio::test]
+async fn extreme_volume_changes_are_clamped() {
+    let cider = playing_cider().await;
+    for volume in [1.0, 0.0] {
+        Mock::given(method("POST"))
+            .and(path("/api/v1/playback/volume"))
+            .and(body_json(serde_json::json!({ "volume": volume })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&cider)
+            .await;
+    }
+    let server = serve(&cider).await;
+    let mut mpd = Mpd::connect(&server).await;
+
+    assert_eq!(mpd.command("volume 32767").await, ["OK"]);
+    assert_eq!(mpd.command("volume -32768").await, ["OK"]);
+}
+
+#[tokio::test]
+async fn playback_options_reach_cider() {
+    let cider = playing_cider().await;
+    for (route, body) in [
+        (
+            "/api/v1/playback/volume",
+            serde_json::json!({"volume": 0.3}),
+        ),
+        (
+            "/api/v1/playback/seek",
+            serde_json::json!({"position": 90.0}),
+        ),
+    ] {
+        Mock::given(method("POST"))
+            .and(path(route))
+            .and(body_json(body))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&cider)
+            .await;
+    }
+    // `seekcur -10` seeks relative to the fixture's 42.5s.
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/seek"))
+        .and(body_json(serde_json::json!({"position": 32.5})))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&cider)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/toggle-shuffle"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&cider)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/next"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&cider)
+        .await;
+    let server = serve(&cider).await;
+    let mut mpd = Mpd::connect(&server).await;
+
+    assert_eq!(mpd.command("setvol 30").await, ["OK"]);
+    assert_eq!(mpd.command("seekcur 90").await, ["OK"]);
+    assert_eq!(mpd.command("seekcur -10").await, ["OK"]);
+    // Shuffle is already off.
+    assert_eq!(mpd.command("random 0").await, ["OK"]);
+    assert_eq!(mpd.command("next").await, ["OK"]);
+    assert_eq!(
+        mpd.command("setvol 150").await,
+        ["ACK [2@0] {setvol} Invalid volume value"]
+    );
+    assert_eq!(
+        mpd.command("play 1").await,
+        ["ACK [2@0] {play} Cider cannot jump to another song in the queue"]
+    );
+}
+
+#[tokio::test]
+async fn unsupported_commands_are_acked() {
+    let cider = playing_cider().await;
+    let server = serve(&cider).await;
+    let mut mpd = Mpd::connect(&server).await;
+
+    assert_eq!(
+        mpd.command("listall").await,
+        ["ACK [5@0] {listall} unknown command \"listall\""]
+    );
+    assert_eq!(
+        mpd.command("setvol").await,
+        ["ACK [2@0] {setvol} wrong number of arguments for \"setvol\""]
+    );
+    assert_eq!(mpd.command("consume 0").await, ["OK"]);
+    assert_eq!(
+        mpd.command("consume 1").await,
+        ["ACK [2@0] {consume} Cider has no consume mode"]
+    );
+    // The connection is still usable.
+    assert_eq!(mpd.command("ping").await, ["OK"]);
+}
+
+#[tokio::test]
+async fn command_lists() {
+    let cider = playing_cider().await;
+    let server = serve(&cider).await;
+    let mut mpd = Mpd::connect(&server).await;
+
+    for line in [
+        "command_list_ok_begin",
+        "ping",
+        "getvol",
+        "command_list_end",
+    ] {
+        mpd.send(line).await;
+    }
+    assert_eq!(
+        mpd.response().await,
+        ["list_OK", "volume: 50", "list_OK", "OK"]
+    );
+
+    // A failure stops the list and reports the failing command's index.
+    for line in [
+        "command_list_begin",
+        "ping",
+        "bogus",
+        "ping",
+        "command_list_end",
+    ] {
+        mpd.send(line).await;
+    }
+    assert_eq!(
+        mpd.response().await,
+        ["ACK [5@1] {bogus} unknown command \"bogus\""]
+    );
+}
+
+#[tokio::test]
+async fn idle_reports_changes_and_noidle_ends_it() {
+    let cider = playing_cider().await;
+    let server = serve(&cider).await;
+    let mut mpd = Mpd::connect(&server).await;
+
+    mpd.send("idle").await;
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert_eq!(mpd.command("noidle").await, ["OK"]);
+
+    mpd.send("idle mixer player").await;
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/volume"))
+        .respond_with(json(fixtures::volume_json(0.8)))
+        .with_priority(1)
+        .mount(&cider)
+        .await;
+    assert_eq!(mpd.response().await, ["changed: mixer", "OK"]);
+}
+
+#[tokio::test]
+async fn stopping_closes_connections() {
+    let cider = playing_cider().await;
+    let server = serve(&cider).await;
+    let mut mpd = Mpd::connect(&server).await;
+    assert_eq!(mpd.command("ping").await, ["OK"]);
+
+    server.stop();
+    server.wait().await;
+    let closed = tokio::time::timeout(Duration::from_secs(5), mpd.lines.next_line())
+        .await
+        .unwrap();
+    assert!(matches!(closed, Ok(None) | Err(_)), "{closed:?}");
+}
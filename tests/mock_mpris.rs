@@ -0,0 +1,277 @@
+#![cfg(feature = "mpris")]
+
+mod common;
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+use cider_api::{CiderClient, CiderError, MprisHandle, MprisServer};
+use common::mount_playing_state;
+use wiremock::matchers::{body_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zbus::fdo::PropertiesProxy;
+use zbus::names::InterfaceName;
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+
+const PLAYER: &str = "org.mpris.MediaPlayer2.Player";
+
+/// A private `dbus-daemon`, killed on drop.
+struct Bus {
+    daemon: Child,
+    address: String,
+}
+
+impl Bus {
+    /// Start a bus, or `None` if `dbus-daemon` is not installed.
+    fn start() -> Option<Self> {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address=1"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| eprintln!("skipping: cannot start dbus-daemon: {e}"))
+            .ok()?;
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+        Some(Self {
+            daemon,
+            address: address.trim().to_owned(),
+        })
+    }
+
+    async fn connect(&self) -> zbus::Connection {
+        zbus::connection::Builder::address(self.address.as_str())
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+/// A Cider playing the fixture track at half volume, repeating the queue.
+async fn playing_cider() -> MockServer {
+    let server = MockServer::start().await;
+    mount_playing_state(&server, 2, 1).await;
+    server
+}
+
+async fn serve(bus: &Bus, client: CiderClient) -> MprisHandle {
+    MprisServer::new(client)
+        .address(bus.address.as_str())
+        .poll_interval(Duration::from_millis(50))
+        .start()
+        .await
+        .unwrap()
+}
+
+async fn properties(bus: &Bus) -> PropertiesProxy<'static> {
+    PropertiesProxy::builder(&bus.connect().await)
+        .destination("org.mpris.MediaPlayer2.cider")
+        .unwrap()
+        .path("/org/mpris/MediaPlayer2")
+        .unwrap()
+        .build()
+        .await
+        .unwrap()
+}
+
+async fn get(props: &PropertiesProxy<'_>, name: &str) -> OwnedValue {
+    props
+        .get(InterfaceName::from_static_str_unchecked(PLAYER), name)
+        .await
+        .unwrap()
+}
+
+async fn metadata(props: &PropertiesProxy<'_>) -> HashMap<String, OwnedValue> {
+    HashMap::try_from(get(props, "Metadata").await).unwrap()
+}
+
+/// Wait until `PlaybackStatus` is `status`.
+async fn wait_for_status(props: &PropertiesProxy<'_>, status: &str) {
+    for _ in 0..100 {
+        if String::try_from(get(props, "PlaybackStatus").await).unwrap() == status {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("PlaybackStatus never became {status}");
+}
+
+async fn call(bus: &Bus, name: &str, body: &(impl serde::Serialize + zbus::zvariant::DynamicType)) {
+    bus.connect()
+        .await
+        .call_method(
+            Some("org.mpris.MediaPlayer2.cider"),
+            "/org/mpris/MediaPlayer2",
+            Some(PLAYER),
+            name,
+            body,
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn properties_mirror_cider() {
+    let Some(bus) = Bus::start() else { return };
+    let cider = playing_cider().await;
+    let _mpris = serve(&bus, CiderClient::with_base_url(cider.uri())).await;
+    let props = properties(&bus).await;
+
+    wait_for_status(&props, "Playing").await;
+    assert_eq!(f64::try_from(get(&props, "Volume").await).unwrap(), 0.5);
+    assert_eq!(
+        String::try_from(get(&props, "LoopStatus").await).unwrap(),
+        "Playlist"
+    );
+    assert!(bool::try_from(get(&props, "Shuffle").await).unwrap());
+    assert_eq!(
+        i64::try_from(get(&props, "Position").await).unwrap(),
+        42_500_000
+    );
+
+    let metadata = metadata(&props).await;
+    assert_eq!(*metadata["xesam:title"], Value::from("Never Be Like You"));
+    assert_eq!(*metadata["xesam:album"], Value::from("Skin"));
+    assert_eq!(*metadata["mpris:length"], Value::from(234_000_000i64));
+    assert_eq!(
+        *metadata["mpris:artUrl"],
+        Value::from("https://example.com/600x600bb.jpg")
+    );
+    assert_eq!(
+        *metadata["mpris:trackid"],
+        Value::from(ObjectPath::try_from("/sh/cider/track/1719861213").unwrap())
+    );
+
+    let root = props.inner().connection().clone();
+    let identity: OwnedValue = PropertiesProxy::builder(&root)
+        .destination("org.mpris.MediaPlayer2.cider")
+        .unwrap()
+        .path("/org/mpris/MediaPlayer2")
+        .unwrap()
+        .build()
+        .await
+        .unwrap()
+        .get(
+            InterfaceName::from_static_str_unchecked("org.mpris.MediaPlayer2"),
+            "Identity",
+        )
+        .await
+        .unwrap();
+    assert_eq!(String::try_from(identity).unwrap(), "Cider");
+}
+
+#[tokio::test]
+async fn methods_drive_cider() {
+    let Some(bus) = Bus::start() else { return };
+    let cider = playing_cider().await;
+    for route in ["/api/v1/playback/playpause", "/api/v1/playback/next"] {
+        Mock::given(method("POST"))
+            .and(path(route))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&cider)
+            .await;
+    }
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/seek"))
+        .and(body_json(serde_json::json!({"position": 100.0})))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&cider)
+        .await;
+    let _mpris = serve(&bus, CiderClient::with_base_url(cider.uri())).await;
+    let props = properties(&bus).await;
+    wait_for_status(&props, "Playing").await;
+
+    call(&bus, "PlayPause", &()).await;
+    call(&bus, "Next", &()).await;
+    let track = ObjectPath::try_from("/sh/cider/track/1719861213").unwrap();
+    call(&bus, "SetPosition", &(track, 100_000_000i64)).await;
+    // A stale track ID is ignored.
+    let stale = ObjectPath::try_from("/sh/cider/track/1").unwrap();
+    call(&bus, "SetPosition", &(stale, 5_000_000i64)).await;
+}
+
+#[tokio::test]
+async fn setting_properties_reaches_cider() {
+    let Some(bus) = Bus::start() else { return };
+    let cider = playing_cider().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/volume"))
+        .and(body_json(serde_json::json!({"volume": 0.25})))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&cider)
+        .await;
+    let _mpris = serve(&bus, CiderClient::with_base_url(cider.uri())).await;
+    let props = properties(&bus).await;
+    wait_for_status(&props, "Playing").await;
+
+    props
+        .set(
+            InterfaceName::from_static_str_unchecked(PLAYER),
+            "Volume",
+            &Value::from(0.25f64),
+        )
+        .await
+        .unwrap();
+    let err = props
+        .set(
+            InterfaceName::from_static_str_unchecked(PLAYER),
+            "LoopStatus",
+            &Value::from("Sometimes"),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, zbus::fdo::Error::InvalidArgs(_)), "{err}");
+}
+
+#[tokio::test]
+async fn unreachable_cider_is_stopped() {
+    let Some(bus) = Bus::start() else { return };
+    let client = CiderClient::with_base_url("http://127.0.0.1:1");
+    let _mpris = serve(&bus, client).await;
+    let props = properties(&bus).await;
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(
+        String::try_from(get(&props, "PlaybackStatus").await).unwrap(),
+        "Stopped"
+    );
+    assert_eq!(
+        *metadata(&props).await["mpris:trackid"],
+        Value::from(ObjectPath::from_static_str_unchecked(
+            "/org/mpris/MediaPlayer2/TrackList/NoTrack"
+        ))
+    );
+}
+
+#[tokio::test]
+async fn bus_name_taken_is_an_error() {
+    let Some(bus) = Bus::start() else { return };
+    let cider = playing_cider().await;
+    let first = serve(&bus, CiderClient::with_base_url(cider.uri())).await;
+    assert_eq!(first.bus_name(), "org.mpris.MediaPlayer2.cider");
+
+    let err = MprisServer::new(CiderClient::with_base_url(cider.uri()))
+        .address(bus.address.as_str())
+        .start()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, CiderError::DBus { .. }), "{err}");
+
+    first.stop();
+    first.wait().await.unwrap();
+}
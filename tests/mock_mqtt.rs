@@ -0,0 +1,299 @@
+#![cfg(feature = "mqtt")]
+
+mod common;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use cider_api::{CiderClient, CiderError, MqttBridge, MqttHandle};
+use common::{fixtures, json, mount_playing_state};
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use tokio::sync::mpsc;
+use wiremock::matchers::{body_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Start an embedded `rumqttd` broker and return its port.
+fn start_broker() -> u16 {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let config: rumqttd::Config = serde_json::from_value(serde_json::json!({
+        "id": 0,
+        "router": {
+            "id": 0,
+            "max_connections": 100,
+            "max_outgoing_packet_count": 200,
+            "max_segment_size": 1_048_576,
+            "max_segment_count": 10,
+        },
+        "v4": {
+            "1": {
+                "name": "v4-1",
+                "listen": format!("127.0.0.1:{port}"),
+                "next_connection_delay_ms": 1,
+                "connections": {
+                    "connection_timeout_ms": 5000,
+                    "max_payload_size": 20480,
+                    "max_inflight_count": 100,
+                    "dynamic_filters": true,
+                },
+            },
+        },
+    }))
+    .unwrap();
+    std::thread::spawn(move || rumqttd::Broker::new(config).start().unwrap());
+    // Wait for the listener.
+    for _ in 0..100 {
+        if std::net::TcpStream::connect(("127.0.0.1", port)).is_ok() {
+            return port;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    panic!("MQTT broker did not start");
+}
+
+/// An MQTT client recording the latest payload of every topic under
+/// `filters`.
+struct Observer {
+    client: AsyncClient,
+    messages: mpsc::UnboundedReceiver<(String, String)>,
+    latest: HashMap<String, String>,
+}
+
+impl Observer {
+    async fn connect(port: u16, filters: &[&str]) -> Self {
+        let (client, mut eventloop) =
+            AsyncClient::new(MqttOptions::new("observer", "127.0.0.1", port), 64);
+        for filter in filters {
+            client.subscribe(*filter, QoS::AtLeastOnce).await.unwrap();
+        }
+        let (tx, messages) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok(event) = eventloop.poll().await {
+                if let Event::Incoming(Packet::Publish(publish)) = event {
+                    let payload = String::from_utf8_lossy(&publish.payload).into_owned();
+                    if tx.send((publish.topic, payload)).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Self {
+            client,
+            messages,
+            latest: HashMap::new(),
+        }
+    }
+
+    /// Wait until `topic` holds `payload`.
+    async fn wait_for(&mut self, topic: &str, payload: &str) {
+        let wait = async {
+            while self.latest.get(topic).map(String::as_str) != Some(payload) {
+                let (topic, payload) = self.messages.recv().await.unwrap();
+                self.latest.insert(topic, payload);
+            }
+        };
+        if tokio::time::timeout(Duration::from_secs(5), wait)
+            .await
+            .is_err()
+        {
+            panic!(
+                "{topic} never became {payload:?} (last: {:?})",
+                self.latest.get(topic)
+            );
+        }
+    }
+
+    async fn send(&self, topic: &str, payload: &str) {
+        self.client
+            .publish(topic, QoS::AtLeastOnce, false, payload)
+            .await
+            .unwrap();
+    }
+}
+
+/// A Cider playing the fixture track at half volume, repeating the queue.
+async fn playing_cider() -> MockServer {
+    let server = MockServer::start().await;
+    mount_playing_state(&server, 2, 1).await;
+    server
+}
+
+async fn bridge(port: u16, cider: &MockServer) -> MqttHandle {
+    MqttBridge::new(CiderClient::with_base_url(cider.uri()), "127.0.0.1", port)
+        .poll_interval(Duration::from_millis(50))
+        .start()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn publishes_state_and_discovery() {
+    let port = start_broker();
+    let cider = playing_cider().await;
+    let _bridge = bridge(port, &cider).await;
+    let mut observer = Observer::connect(port, &["cider/#", "homeassistant/#"]).await;
+
+    for (topic, payload) in [
+        ("cider/available", "online"),
+        ("cider/state", "playing"),
+        ("cider/title", "Never Be Like You"),
+        ("cider/artist", "Flume"),
+        ("cider/album", "Skin"),
+        ("cider/artwork", "https://example.com/600x600bb.jpg"),
+        ("cider/duration", "234"),
+        ("cider/position", "42"),
+        ("cider/volume", "0.50"),
+        ("cider/repeat", "all"),
+        ("cider/shuffle", "on"),
+        ("cider/mediatype", "music"),
+    ] {
+        observer.wait_for(topic, payload).await;
+    }
+
+    let topic = "homeassistant/media_player/cider/config";
+    while !observer.latest.contains_key(topic) {
+        let (topic, payload) = observer.messages.recv().await.unwrap();
+        observer.latest.insert(topic, payload);
+    }
+    let config: serde_json::Value = serde_json::from_str(&observer.latest[topic]).unwrap();
+    assert_eq!(config["name"], "Cider");
+    assert_eq!(config["state_state_topic"], "cider/state");
+    assert_eq!(config["command_play_topic"], "cider/play");
+    assert_eq!(config["availability"]["topic"], "cider/available");
+}
+
+#[tokio::test]
+async fn commands_reach_cider() {
+    let port = start_broker();
+    let cider = playing_cider().await;
+    for route in ["/api/v1/playback/playpause", "/api/v1/playback/next"] {
+        Mock::given(method("POST"))
+            .and(path(route))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&cider)
+            .await;
+    }
+    for (route, body) in [
+        (
+            "/api/v1/playback/volume",
+            serde_json::json!({"volume": 0.25}),
+        ),
+        (
+            "/api/v1/playback/seek",
+            serde_json::json!({"position": 90.0}),
+        ),
+    ] {
+        Mock::given(method("POST"))
+            .and(path(route))
+            .and(body_json(body))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&cider)
+            .await;
+    }
+    let _bridge = bridge(port, &cider).await;
+    let mut observer = Observer::connect(port, &["cider/available"]).await;
+    observer.wait_for("cider/available", "online").await;
+
+    observer.send("cider/playpause", "").await;
+    observer.send("cider/next", "").await;
+    observer.send("cider/set_volume", "0.25").await;
+    observer.send("cider/seek", "90").await;
+    // Invalid payloads are ignored.
+    observer.send("cider/set_volume", "loud").await;
+
+    for _ in 0..100 {
+        let posts = cider
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|r| r.method == wiremock::http::Method::POST)
+            .count();
+        if posts >= 4 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    // Give a stray fifth command time to arrive before the expectations
+    // are verified.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+}
+
+#[tokio::test]
+async fn command_flood_does_not_stall_the_bridge() {
+    let port = start_broker();
+    let cider = playing_cider().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/next"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&cider)
+        .await;
+    let _bridge = bridge(port, &cider).await;
+    let mut observer = Observer::connect(port, &["cider/#"]).await;
+    observer.wait_for("cider/volume", "0.50").await;
+
+    // More than the bridge's request queue and any small hand-off buffer.
+    for _ in 0..200 {
+        observer.send("cider/next", "").await;
+    }
+
+    // State changes are still published once the flood is handled.
+    cider.reset().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/volume"))
+        .respond_with(json(fixtures::volume_json(0.8)))
+        .with_priority(1)
+        .mount(&cider)
+        .await;
+    mount_playing_state(&cider, 2, 1).await;
+    observer.wait_for("cider/volume", "0.80").await;
+}
+
+#[tokio::test]
+async fn stopping_publishes_offline() {
+    let port = start_broker();
+    let cider = playing_cider().await;
+    let bridge = bridge(port, &cider).await;
+    let mut observer = Observer::connect(port, &["cider/available"]).await;
+    observer.wait_for("cider/available", "online").await;
+
+    bridge.stop();
+    bridge.wait().await;
+    observer.wait_for("cider/available", "offline").await;
+}
+
+#[tokio::test]
+async fn unreachable_cider_is_off() {
+    let port = start_broker();
+    let _bridge = MqttBridge::new(
+        CiderClient::with_base_url("http://127.0.0.1:1"),
+        "127.0.0.1",
+        port,
+    )
+    .poll_interval(Duration::from_millis(50))
+    .start()
+    .await
+    .unwrap();
+    let mut observer = Observer::connect(port, &["cider/#"]).await;
+    observer.wait_for("cider/available", "online").await;
+    observer.wait_for("cider/state", "off").await;
+}
+
+#[tokio::test]
+async fn unreachable_broker_is_an_error() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let err = MqttBridge::new(CiderClient::new(), "127.0.0.1", port)
+        .start()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, CiderError::Mqtt { .. }), "{err}");
+}
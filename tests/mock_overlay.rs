@@ -0,0 +1,170 @@
+#![cfg(feature = "overlay")]
+
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use cider_api::{CiderClient, NowPlayingFiles, OverlayHandle, OverlayServer};
+use common::{fixtures, json, mount_playing_state};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// A Cider playing the fixture track, with its artwork served by the mock
+/// server too.
+async fn playing_cider() -> MockServer {
+    let server = MockServer::start().await;
+    let now_playing = fixtures::now_playing_json().replace(
+        "https://example.com/{w}x{h}bb.jpg",
+        &format!("{}/artwork/{{w}}x{{h}}bb.jpg", server.uri()),
+    );
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/now-playing"))
+        .respond_with(json(now_playing))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    mount_playing_state(&server, 0, 0).await;
+    Mock::given(method("GET"))
+        .and(path("/artwork/300x300bb.jpg"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(b"jpeg".to_vec()))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cider-overlay-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// Wait for `path` to exist and return its contents.
+async fn read_when_written(path: &Path) -> Vec<u8> {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Ok(contents) = std::fs::read(path) {
+                return contents;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("{} was not written", path.display()))
+}
+
+#[tokio::test]
+async fn files_are_written() {
+    let cider = playing_cider().await;
+    let dir = temp_dir("files");
+    let files = NowPlayingFiles::new(CiderClient::with_base_url(cider.uri()), &dir)
+        .text_template("{name} by {artist} {lossless?}".parse().unwrap())
+        .artwork(300)
+        .poll_interval(Duration::from_millis(50))
+        .start()
+        .unwrap();
+
+    let artwork = read_when_written(&dir.join("artwork.jpg")).await;
+    assert_eq!(artwork, b"jpeg");
+    let text = std::fs::read_to_string(dir.join("now-playing.txt")).unwrap();
+    assert_eq!(text, "Never Be Like You by Flume Lossless");
+    let state: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.join("now-playing.json")).unwrap()).unwrap();
+    assert_eq!(state["state"], "playing");
+    assert_eq!(state["track"]["name"], "Never Be Like You");
+    assert_eq!(state["track"]["position_ms"], 42_500);
+
+    // Unchanged state downloads the artwork only once.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    files.stop();
+    files.wait().await;
+    let downloads = cider
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|r| r.url.path().starts_with("/artwork/"))
+        .count();
+    assert_eq!(downloads, 1);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+async fn overlay(cider: &MockServer) -> OverlayHandle {
+    OverlayServer::new(CiderClient::with_base_url(cider.uri()))
+        .stylesheet(":root { --accent: #ff9500; }")
+        .artwork_size(300)
+        .poll_interval(Duration::from_millis(50))
+        .start("127.0.0.1:0")
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn overlay_serves_page_and_events() {
+    let cider = playing_cider().await;
+    let overlay = overlay(&cider).await;
+    let url = |route: &str| format!("http://{}{route}", overlay.local_addr());
+    let http = reqwest::Client::new();
+
+    let page = http.get(url("/")).send().await.unwrap();
+    assert!(page.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+    let page = page.text().await.unwrap();
+    assert!(page.contains("new EventSource(\"events\")"));
+    assert!(page.contains(":root { --accent: #ff9500; }"));
+
+    let mut events = http.get(url("/events")).send().await.unwrap();
+    assert_eq!(events.headers()["content-type"], "text/event-stream");
+    let playing = tokio::time::timeout(Duration::from_secs(5), async {
+        let mut received = String::new();
+        while let Some(chunk) = events.chunk().await.unwrap() {
+            received.push_str(&String::from_utf8_lossy(&chunk));
+            if received.contains(r#""state":"playing""#) {
+                return received;
+            }
+        }
+        panic!("event stream ended: {received}");
+    })
+    .await
+    .expect("no playing event");
+    assert!(playing.contains("event: state\n"), "{playing}");
+    assert!(playing.contains("300x300bb.jpg"), "{playing}");
+
+    let state: serde_json::Value = http
+        .get(url("/now-playing.json"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(state["track"]["artist"], "Flume");
+
+    // Stopping ends open event streams.
+    overlay.stop();
+    tokio::time::timeout(Duration::from_secs(5), overlay.wait())
+        .await
+        .expect("overlay did not stop");
+    assert!(matches!(events.chunk().await, Ok(None) | Err(_)));
+}
+
+#[tokio::test]
+async fn unreachable_cider_is_offline() {
+    let overlay = OverlayServer::new(CiderClient::with_base_url("http://127.0.0.1:1"))
+        .poll_interval(Duration::from_millis(50))
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let state: serde_json::Value =
+        reqwest::get(format!("http://{}/now-playing.json", overlay.local_addr()))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+    assert_eq!(state["state"], "offline");
+    assert!(state["track"].is_null());
+}
@@ -1,5 +1,6 @@
 mod common;
 
+use cider_api::{CiderError, ItemKind, ItemRef};
 use wiremock::matchers::{body_json, method, path};
 use wiremock::{Mock, ResponseTemplate};
 
@@ -34,7 +35,8 @@ async fn play_item_sends_type_and_id() {
         .expect(1)
         .mount(&server)
         .await;
-    client.play_item("songs", "1719861213").await.unwrap();
+    let item = ItemRef::song("1719861213").unwrap();
+    client.play_item(&item).await.unwrap();
 }
 
 #[tokio::test]
@@ -68,7 +70,10 @@ async fn play_next_sends_type_and_id() {
         .expect(1)
         .mount(&server)
         .await;
-    client.play_next("songs", "123").await.unwrap();
+    client
+        .play_next(&ItemRef::song("123").unwrap())
+        .await
+        .unwrap();
 }
 
 #[tokio::test]
@@ -84,5 +89,103 @@ async fn play_later_sends_type_and_id() {
         .expect(1)
         .mount(&server)
         .await;
-    client.play_later("albums", "456").await.unwrap();
+    client
+        .play_later(&ItemRef::album("456").unwrap())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn play_item_sends_library_kind() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/play-item"))
+        .and(body_json(serde_json::json!({
+            "type": "library-playlists",
+            "id": "p.V7VYlrDTxj3B"
+        })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let item = ItemRef::new(ItemKind::LibraryPlaylists, "p.V7VYlrDTxj3B").unwrap();
+    client.play_item(&item).await.unwrap();
+}
+
+#[tokio::test]
+async fn now_playing_params_can_be_queued() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/now-playing"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(common::fixtures::now_playing_json())
+                .insert_header("content-type", "application/json"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/play-later"))
+        .and(body_json(serde_json::json!({
+            "type": "songs",
+            "id": "1719861213"
+        })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let track = client.now_playing().await.unwrap().unwrap();
+    let item = track.play_params.unwrap().item_ref().unwrap();
+    client.play_later(&item).await.unwrap();
+}
+
+#[tokio::test]
+async fn play_parsed_uses_play_item_for_album_song() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/play-item"))
+        .and(body_json(serde_json::json!({
+            "type": "songs",
+            "id": "1719861213"
+        })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let parsed = client
+        .play_parsed("https://music.apple.com/ca/album/skin/1719860281?i=1719861213")
+        .await
+        .unwrap();
+    assert_eq!(parsed.storefront, "ca");
+}
+
+#[tokio::test]
+async fn play_parsed_rejects_artist_links_without_a_request() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let err = client
+        .play_parsed("https://music.apple.com/gb/artist/flume/591256593")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, CiderError::InvalidItem(_)), "{err}");
+    assert!(err.to_string().contains("artist"), "{err}");
+}
+
+#[tokio::test]
+async fn play_parsed_rejects_bad_links_without_a_request() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let err = client
+        .play_parsed("https://music.apple.com/us/album/skin/i.PkdZbQXsPJ4DX")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, CiderError::InvalidItem(_)));
 }
@@ -1,7 +1,9 @@
 mod common;
 
+use std::time::Duration;
+
 use wiremock::matchers::{body_json, method, path};
-use wiremock::{Mock, ResponseTemplate};
+use wiremock::{Mock, MockServer, ResponseTemplate};
 
 macro_rules! test_fire_and_forget {
     ($test_name:ident, $method_name:ident, $http_method:literal, $path:literal) => {
@@ -53,3 +55,93 @@ async fn seek_ms_converts_to_seconds() {
         .await;
     client.seek_ms(30_000).await.unwrap();
 }
+
+#[tokio::test]
+async fn seek_to_sends_seconds() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/seek"))
+        .and(body_json(serde_json::json!({"position": 90.25})))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    client
+        .seek_to(Duration::from_millis(90_250))
+        .await
+        .unwrap();
+}
+
+/// Serve the now-playing fixture (position 42.5s of 234s), expect one seek
+/// to `position` seconds, and report `landed` seconds after it.
+async fn expect_relative_seek(server: &MockServer, position: f64, landed: f64) {
+    let now_playing = |body: String| {
+        ResponseTemplate::new(200)
+            .set_body_string(body)
+            .insert_header("content-type", "application/json")
+    };
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/now-playing"))
+        .respond_with(now_playing(common::fixtures::now_playing_json().to_owned()))
+        .up_to_n_times(1)
+        .mount(server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/now-playing"))
+        .respond_with(now_playing(common::fixtures::now_playing_json().replace(
+            "\"currentPlaybackTime\": 42.5",
+            &format!("\"currentPlaybackTime\": {landed:?}"),
+        )))
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/seek"))
+        .and(body_json(serde_json::json!({ "position": position })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn seek_by_moves_relative_to_position() {
+    let (server, client) = common::setup().await;
+    // Cider lands slightly before the requested position.
+    expect_relative_seek(&server, 52.5, 52.25).await;
+    let landed = client.seek_by(10_000).await.unwrap();
+    assert_eq!(landed, Duration::from_millis(52_250));
+}
+
+#[tokio::test]
+async fn seek_by_clamps_to_start() {
+    let (server, client) = common::setup().await;
+    expect_relative_seek(&server, 0.0, 0.0).await;
+    assert_eq!(client.seek_by(-60_000).await.unwrap(), Duration::ZERO);
+}
+
+#[tokio::test]
+async fn seek_by_clamps_to_end() {
+    let (server, client) = common::setup().await;
+    expect_relative_seek(&server, 234.0, 234.0).await;
+    assert_eq!(
+        client.seek_by(600_000).await.unwrap(),
+        Duration::from_secs(234)
+    );
+}
+
+#[tokio::test]
+async fn seek_by_without_track_is_nothing_playing() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/now-playing"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/seek"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    assert!(client.seek_by(5_000).await.unwrap_err().is_nothing_playing());
+}
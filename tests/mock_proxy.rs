@@ -0,0 +1,290 @@
+#![cfg(feature = "proxy")]
+
+mod common;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use cider_api::{CiderClient, CiderError, ProxyHandle, ProxyScope, ProxyServer};
+use common::{fixtures, json, mount_playing_state};
+use futures_util::StreamExt;
+use reqwest::StatusCode;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use wiremock::matchers::{body_json, header, method, path};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+/// Mount the player state endpoints, answering only requests that carry the
+/// Cider token, with the fixture track at `volume`.
+async fn mount_state(server: &MockServer, volume: f32) {
+    Mock::given(|request: &Request| {
+        request
+            .headers
+            .get("apptoken")
+            .and_then(|token| token.to_str().ok())
+            != Some("cider-token")
+    })
+    .respond_with(ResponseTemplate::new(401))
+    .with_priority(1)
+    .mount(server)
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/volume"))
+        .respond_with(json(fixtures::volume_json(volume)))
+        .with_priority(2)
+        .mount(server)
+        .await;
+    mount_playing_state(server, 0, 0).await;
+}
+
+async fn playing_cider() -> MockServer {
+    let server = MockServer::start().await;
+    mount_state(&server, 0.5).await;
+    server
+}
+
+fn audit_path(name: &str) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("cider-proxy-{name}-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+async fn proxy(cider: &MockServer, audit_log: Option<&PathBuf>) -> ProxyHandle {
+    let mut proxy =
+        ProxyServer::new(CiderClient::with_base_url(cider.uri()).with_token("cider-token"))
+            .allow("guest", "guest-secret", [ProxyScope::Read])
+            .allow("dj", "dj-secret", ProxyScope::ALL)
+            .poll_interval(Duration::from_millis(50));
+    if let Some(path) = audit_log {
+        proxy = proxy.audit_log(path);
+    }
+    proxy.start("127.0.0.1:0").await.unwrap()
+}
+
+/// A client talking to the proxy with a proxy token.
+fn via(proxy: &ProxyHandle, token: &str) -> CiderClient {
+    CiderClient::with_base_url(format!("http://{}", proxy.local_addr())).with_token(token)
+}
+
+fn audit_lines(path: &PathBuf) -> Vec<serde_json::Value> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn read_scope_sees_but_cannot_command() {
+    let cider = playing_cider().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/queue/clear-queue"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&cider)
+        .await;
+    let proxy = proxy(&cider, None).await;
+    let guest = via(&proxy, "guest-secret");
+
+    let track = guest.now_playing().await.unwrap().unwrap();
+    assert_eq!(track.name, "Never Be Like You");
+    assert!((guest.get_volume().await.unwrap() - 0.5).abs() < f32::EPSILON);
+
+    let err = guest.clear_queue().await.unwrap_err();
+    assert!(err.is_auth(), "{err}");
+    assert_eq!(err.status_code(), Some(StatusCode::FORBIDDEN));
+}
+
+#[tokio::test]
+async fn unknown_tokens_are_refused() {
+    let cider = playing_cider().await;
+    let proxy = proxy(&cider, None).await;
+
+    for client in [
+        via(&proxy, "cider-token"),
+        CiderClient::with_base_url(format!("http://{}", proxy.local_addr())),
+    ] {
+        let err = client.get_volume().await.unwrap_err();
+        assert_eq!(err.status_code(), Some(StatusCode::UNAUTHORIZED), "{err}");
+    }
+
+    let resp = reqwest::Client::new()
+        .get(format!(
+            "http://{}/api/v1/playback/volume",
+            proxy.local_addr()
+        ))
+        .bearer_auth("guest-secret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn commands_are_forwarded_and_audited() {
+    let cider = playing_cider().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/volume"))
+        .and(header("apptoken", "cider-token"))
+        .and(body_json(serde_json::json!({"volume": 0.25})))
+        .respond_with(json(r#"{"status":"ok"}"#))
+        .expect(1)
+        .mount(&cider)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/next"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&cider)
+        .await;
+    let audit = audit_path("audit");
+    let proxy = proxy(&cider, Some(&audit)).await;
+    let dj = via(&proxy, "dj-secret");
+
+    dj.set_volume(0.25).await.unwrap();
+    let err = dj.next().await.unwrap_err();
+    assert_eq!(err.status_code(), Some(StatusCode::INTERNAL_SERVER_ERROR));
+    via(&proxy, "guest-secret").pause().await.unwrap_err();
+    via(&proxy, "wrong").play().await.unwrap_err();
+    // Reads are not audited.
+    dj.get_volume().await.unwrap();
+
+    let lines = audit_lines(&audit);
+    assert_eq!(lines.len(), 4, "{lines:?}");
+    assert_eq!(lines[0]["client"], "dj");
+    assert_eq!(lines[0]["path"], "/api/v1/playback/volume");
+    assert_eq!(lines[0]["args"]["volume"], 0.25);
+    assert_eq!(lines[0]["outcome"], "ok");
+    assert_eq!(lines[0]["status"], 200);
+    assert_eq!(lines[1]["path"], "/api/v1/playback/next");
+    assert_eq!(lines[1]["outcome"], "failed");
+    assert_eq!(lines[1]["status"], 500);
+    assert!(lines[1]["error"].is_string());
+    assert_eq!(lines[2]["client"], "guest");
+    assert_eq!(lines[2]["outcome"], "forbidden");
+    assert!(lines[3]["client"].is_null());
+    assert_eq!(lines[3]["outcome"], "unauthorized");
+    assert!(lines[3]["addr"].as_str().unwrap().starts_with("127.0.0.1:"));
+    let _ = std::fs::remove_file(&audit);
+}
+
+#[tokio::test]
+async fn invalid_bodies_and_unreachable_cider() {
+    let cider = playing_cider().await;
+    let proxy = proxy(&cider, None).await;
+    let http = reqwest::Client::new();
+    let url = |route: &str| format!("http://{}/api/v1/playback{route}", proxy.local_addr());
+
+    let resp = http
+        .post(url("/seek"))
+        .header("apptoken", "dj-secret")
+        .body("{}")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["status"], "error");
+
+    let resp = http
+        .post(url("/play-item"))
+        .header("apptoken", "dj-secret")
+        .json(&serde_json::json!({"type": "songs", "id": "pl.abc"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = http
+        .get(url("/nope"))
+        .header("apptoken", "dj-secret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let offline = ProxyServer::new(CiderClient::with_base_url("http://127.0.0.1:1"))
+        .allow("dj", "dj-secret", ProxyScope::ALL)
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    let err = via(&offline, "dj-secret").get_volume().await.unwrap_err();
+    assert_eq!(err.status_code(), Some(StatusCode::BAD_GATEWAY), "{err}");
+}
+
+type Feed = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+async fn next_message(feed: &mut Feed) -> serde_json::Value {
+    let message = tokio::time::timeout(Duration::from_secs(5), feed.next())
+        .await
+        .expect("no feed message")
+        .unwrap()
+        .unwrap();
+    match message {
+        Message::Text(text) => serde_json::from_str(&text).unwrap(),
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn websocket_feed_reports_changes() {
+    let cider = playing_cider().await;
+    let proxy = proxy(&cider, None).await;
+    let feed_url = |token: &str| format!("ws://{}/ws?token={token}", proxy.local_addr());
+
+    let err = tokio_tungstenite::connect_async(feed_url("wrong"))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("401"), "{err}");
+
+    let (mut feed, _) = tokio_tungstenite::connect_async(feed_url("guest-secret"))
+        .await
+        .unwrap();
+
+    let first = next_message(&mut feed).await;
+    assert_eq!(first["event"], "state");
+    assert_eq!(first["state"]["available"], true);
+    assert_eq!(first["state"]["is_playing"], true);
+    assert_eq!(first["state"]["track"]["name"], "Never Be Like You");
+
+    cider.reset().await;
+    mount_state(&cider, 0.75).await;
+    let changed = next_message(&mut feed).await;
+    assert_eq!(changed["event"], "volume_changed");
+    assert_eq!(changed["state"]["volume"], 0.75);
+
+    proxy.stop();
+    let closed = tokio::time::timeout(Duration::from_secs(5), async {
+        while let Some(Ok(message)) = feed.next().await {
+            if message.is_close() {
+                return;
+            }
+        }
+    })
+    .await;
+    assert!(closed.is_ok(), "feed stayed open");
+    proxy.wait().await;
+}
+
+#[tokio::test]
+async fn tokens_are_validated_at_start() {
+    let err = ProxyServer::new(CiderClient::new())
+        .start("127.0.0.1:0")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, CiderError::Config(_)), "{err}");
+
+    let err = ProxyServer::new(CiderClient::new())
+        .allow("a", "secret", [ProxyScope::Read])
+        .allow("b", "secret", [ProxyScope::Volume])
+        .start("127.0.0.1:0")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, CiderError::Config(_)), "{err}");
+
+    let err = ProxyServer::new(CiderClient::new())
+        .load_tokens("/nonexistent/cider-proxy-tokens")
+        .unwrap_err();
+    assert!(matches!(err, CiderError::Io { .. }), "{err}");
+}
@@ -59,6 +59,55 @@ async fn get_queue_returns_empty_on_bad_json() {
     assert!(client.get_queue().await.unwrap().is_empty());
 }
 
+#[tokio::test]
+async fn get_queue_strict_returns_decode_error_on_bad_json() {
+    let (server, client) = common::setup_strict().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/queue"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(r#"{"not": "an array"}"#)
+                .insert_header("content-type", "application/json"),
+        )
+        .mount(&server)
+        .await;
+    let err = client.get_queue().await.unwrap_err();
+    match err {
+        cider_api::CiderError::Decode { endpoint, body, .. } => {
+            assert_eq!(endpoint.path, "/api/v1/playback/queue");
+            assert_eq!(body, r#"{"not": "an array"}"#);
+        }
+        other => panic!("expected Decode, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn get_queue_strict_reports_item_path() {
+    let (server, client) = common::setup_strict().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/queue"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(r#"[{"id": "1"}, {"id": 2}]"#)
+                .insert_header("content-type", "application/json"),
+        )
+        .mount(&server)
+        .await;
+    let err = client.get_queue().await.unwrap_err();
+    assert!(matches!(err, cider_api::CiderError::Decode { path, .. } if path == "[1].id"));
+}
+
+#[tokio::test]
+async fn get_queue_strict_still_returns_empty_on_204() {
+    let (server, client) = common::setup_strict().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/queue"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&server)
+        .await;
+    assert!(client.get_queue().await.unwrap().is_empty());
+}
+
 #[tokio::test]
 async fn queue_move_to_position_sends_correct_body() {
     let (server, client) = common::setup().await;
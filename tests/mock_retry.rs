@@ -0,0 +1,179 @@
+mod common;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use cider_api::{CiderClient, RetryPolicy};
+use wiremock::matchers::{body_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn fast_policy() -> RetryPolicy {
+    RetryPolicy::new()
+        .max_attempts(3)
+        .initial_backoff(Duration::from_millis(1))
+        .max_backoff(Duration::from_millis(5))
+}
+
+async fn setup_retrying() -> (MockServer, CiderClient) {
+    let (server, client) = common::setup().await;
+    (server, client.with_retry_policy(fast_policy()))
+}
+
+/// Answer `status` for the first `failures` requests to `GET|POST path`,
+/// then fall through to the next mounted mock.
+async fn fail_first(server: &MockServer, verb: &str, route: &str, status: u16, failures: u64) {
+    Mock::given(method(verb))
+        .and(path(route))
+        .respond_with(ResponseTemplate::new(status))
+        .up_to_n_times(failures)
+        .expect(failures)
+        .mount(server)
+        .await;
+}
+
+// ── Idempotent GETs ──
+
+#[tokio::test]
+async fn get_is_retried_until_success() {
+    let (server, client) = setup_retrying().await;
+    fail_first(&server, "GET", "/api/v1/playback/volume", 503, 2).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/volume"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(common::fixtures::volume_json(0.5))
+                .insert_header("content-type", "application/json"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    assert!((client.get_volume().await.unwrap() - 0.5).abs() < f32::EPSILON);
+}
+
+#[tokio::test]
+async fn get_gives_up_after_max_attempts() {
+    let (server, client) = setup_retrying().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/is-playing"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(3)
+        .mount(&server)
+        .await;
+    let err = client.is_playing().await.unwrap_err();
+    assert_eq!(err.status_code(), Some(reqwest::StatusCode::INTERNAL_SERVER_ERROR));
+}
+
+#[tokio::test]
+async fn non_retryable_status_is_not_retried() {
+    let (server, client) = setup_retrying().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/active"))
+        .respond_with(ResponseTemplate::new(401))
+        .expect(1)
+        .mount(&server)
+        .await;
+    assert!(client.is_active().await.unwrap_err().is_auth());
+}
+
+#[tokio::test]
+async fn now_playing_is_retried() {
+    let (server, client) = setup_retrying().await;
+    fail_first(&server, "GET", "/api/v1/playback/now-playing", 502, 1).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/now-playing"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(common::fixtures::now_playing_json())
+                .insert_header("content-type", "application/json"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    assert!(client.now_playing().await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn without_policy_nothing_is_retried() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/volume"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(1)
+        .mount(&server)
+        .await;
+    assert!(client.get_volume().await.unwrap_err().is_retryable());
+}
+
+#[tokio::test]
+async fn custom_predicate_is_used() {
+    let (server, client) = common::setup().await;
+    let client = client.with_retry_policy(fast_policy().retry_on(|_| false));
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/volume"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(1)
+        .mount(&server)
+        .await;
+    assert!(client.get_volume().await.is_err());
+}
+
+#[tokio::test]
+async fn connection_refused_is_retried() {
+    let port = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let consulted = Arc::new(AtomicU32::new(0));
+    let counter = consulted.clone();
+    let policy = fast_policy().retry_on(move |e| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        e.is_not_running()
+    });
+    let client = CiderClient::with_port(port).with_retry_policy(policy);
+    assert!(client.is_active().await.unwrap_err().is_not_running());
+    // Consulted after attempts 1 and 2; attempt 3 is the last.
+    assert_eq!(consulted.load(Ordering::SeqCst), 2);
+}
+
+// ── Non-idempotent POSTs ──
+
+#[tokio::test]
+async fn post_is_not_retried_by_default() {
+    let (server, client) = setup_retrying().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/next"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(1)
+        .mount(&server)
+        .await;
+    assert!(client.next().await.is_err());
+}
+
+#[tokio::test]
+async fn post_is_retried_when_opted_in() {
+    let (server, client) = setup_retrying().await;
+    fail_first(&server, "POST", "/api/v1/playback/next", 503, 1).await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/next"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    client.retrying().next().await.unwrap();
+}
+
+#[tokio::test]
+async fn post_retry_resends_json_body() {
+    let (server, client) = common::setup().await;
+    fail_first(&server, "POST", "/api/v1/playback/queue/remove-by-index", 503, 1).await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/queue/remove-by-index"))
+        .and(body_json(serde_json::json!({"index": 3})))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    // No policy configured: `retrying()` falls back to the default policy.
+    client.retrying().queue_remove_by_index(3).await.unwrap();
+}
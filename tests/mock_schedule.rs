@@ -0,0 +1,181 @@
+#![cfg(feature = "schedule")]
+
+mod common;
+
+use std::time::Duration;
+
+use chrono::Local;
+use cider_api::{Action, CiderClient, ItemRef, Job, MissReason, Schedule, ScheduleEvent, Trigger};
+use wiremock::matchers::{body_json, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// A one-shot job that is due right away.
+fn due_now(name: &str) -> Job {
+    Job::new(name, Trigger::At(Local::now()))
+}
+
+async fn mount_active(server: &wiremock::MockServer) {
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/active"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn due_job_runs_its_actions_in_order() {
+    let (server, client) = common::setup().await;
+    mount_active(&server).await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/volume"))
+        .and(body_json(serde_json::json!({"volume": 0.0})))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/play-item"))
+        .and(body_json(
+            serde_json::json!({"type": "playlists", "id": "pl.u-abc"}),
+        ))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let job = due_now("wake up")
+        .action(Action::SetVolume { volume: 0.0 })
+        .action(Action::PlayItem {
+            item: ItemRef::playlist("pl.u-abc").unwrap(),
+        });
+    let mut scheduler = client.scheduler(Schedule::new().job(job)).start();
+
+    match scheduler.next_event().await.unwrap() {
+        ScheduleEvent::Ran { job, .. } => assert_eq!(job, "wake up"),
+        other => panic!("expected Ran, got {other:?}"),
+    }
+    // The only job was a one-shot, so the scheduler winds down.
+    assert!(scheduler.next_event().await.is_none());
+    assert!(scheduler.is_finished());
+}
+
+#[tokio::test]
+async fn unreachable_cider_reports_missed_run() {
+    // Port 1 refuses connections.
+    let client = CiderClient::with_port(1);
+    let job = due_now("alarm").action(Action::Play);
+    let mut scheduler = client
+        .scheduler(Schedule::new().job(job))
+        .grace(Duration::from_millis(300))
+        .retry_interval(Duration::from_millis(50))
+        .start();
+
+    match scheduler.next_event().await.unwrap() {
+        ScheduleEvent::Missed {
+            job,
+            missed: 1,
+            reason: MissReason::NotReachable(e),
+            ..
+        } => {
+            assert_eq!(job, "alarm");
+            assert!(e.is_not_running(), "{e}");
+        }
+        other => panic!("expected Missed, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn runs_due_before_start_within_grace_still_run() {
+    let (server, client) = common::setup().await;
+    mount_active(&server).await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/play"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let recent = Job::new(
+        "recent",
+        Trigger::At(Local::now() - chrono::Duration::seconds(5)),
+    )
+    .action(Action::Play);
+    // Outside the grace period: neither run nor reported.
+    let stale = Job::new(
+        "stale",
+        Trigger::At(Local::now() - chrono::Duration::minutes(5)),
+    )
+    .action(Action::Play);
+    let mut scheduler = client
+        .scheduler(Schedule::new().job(recent).job(stale))
+        .grace(Duration::from_secs(10))
+        .start();
+
+    match scheduler.next_event().await.unwrap() {
+        ScheduleEvent::Ran { job, .. } => assert_eq!(job, "recent"),
+        other => panic!("expected Ran, got {other:?}"),
+    }
+    assert!(scheduler.next_event().await.is_none());
+}
+
+#[tokio::test]
+async fn failing_action_stops_the_job() {
+    let (server, client) = common::setup().await;
+    mount_active(&server).await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/play"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/stop"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let job = due_now("broken").action(Action::Play).action(Action::Stop);
+    let mut scheduler = client.scheduler(Schedule::new().job(job)).start();
+    match scheduler.next_event().await.unwrap() {
+        ScheduleEvent::Failed { job, error, .. } => {
+            assert_eq!(job, "broken");
+            assert_eq!(error.status_code().map(|s| s.as_u16()), Some(500));
+        }
+        other => panic!("expected Failed, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn stop_ends_recurring_schedule() {
+    let (_server, client) = common::setup().await;
+    let job = Job::new("hourly", Trigger::Cron("@hourly".parse().unwrap())).action(Action::Play);
+    let mut scheduler = client.scheduler(Schedule::new().job(job)).start();
+    scheduler.stop();
+    assert!(scheduler.next_event().await.is_none());
+}
+
+#[test]
+fn loads_json_and_toml_files() {
+    let dir = std::env::temp_dir().join(format!("cider-api-schedule-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let json = dir.join("alarms.json");
+    std::fs::write(
+        &json,
+        r#"{"job": [{"name": "stop", "cron": "0 23 * * *", "actions": [{"action": "stop"}]}]}"#,
+    )
+    .unwrap();
+    let toml = dir.join("alarms.toml");
+    std::fs::write(
+        &toml,
+        "[[job]]\nname = \"stop\"\ncron = \"0 23 * * *\"\nactions = [{ action = \"stop\" }]\n",
+    )
+    .unwrap();
+
+    let from_json = Schedule::load(&json).unwrap();
+    assert_eq!(from_json, Schedule::load(&toml).unwrap());
+    assert_eq!(from_json.jobs()[0].actions(), [Action::Stop]);
+
+    let missing = Schedule::load(dir.join("missing.toml")).unwrap_err();
+    assert!(missing.to_string().contains("cannot read"), "{missing}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
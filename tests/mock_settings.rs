@@ -1,7 +1,11 @@
 mod common;
 
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+use cider_api::{CiderError, RepeatMode, ShuffleMode};
 use wiremock::matchers::{method, path};
-use wiremock::{Mock, ResponseTemplate};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
 
 macro_rules! test_fire_and_forget {
     ($test_name:ident, $method_name:ident, $http_method:literal, $path:literal) => {
@@ -35,7 +39,7 @@ async fn get_repeat_mode_returns_value() {
         )
         .mount(&server)
         .await;
-    assert_eq!(client.get_repeat_mode().await.unwrap(), 2);
+    assert_eq!(client.get_repeat_mode().await.unwrap(), RepeatMode::All);
 }
 
 #[tokio::test]
@@ -50,7 +54,7 @@ async fn get_shuffle_mode_returns_value() {
         )
         .mount(&server)
         .await;
-    assert_eq!(client.get_shuffle_mode().await.unwrap(), 1);
+    assert_eq!(client.get_shuffle_mode().await.unwrap(), ShuffleMode::On);
 }
 
 #[tokio::test]
@@ -82,3 +86,150 @@ async fn get_autoplay_returns_false() {
         .await;
     assert!(!client.get_autoplay().await.unwrap());
 }
+
+#[tokio::test]
+async fn unknown_repeat_mode_is_preserved() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/repeat-mode"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(common::fixtures::repeat_mode_json(7))
+                .insert_header("content-type", "application/json"),
+        )
+        .mount(&server)
+        .await;
+    assert_eq!(
+        client.get_repeat_mode().await.unwrap(),
+        RepeatMode::Unknown(7)
+    );
+}
+
+// ── Setters ──
+
+/// A mode that cycles through `0..modulo` on every toggle, like Cider's.
+#[derive(Clone)]
+struct Cycling {
+    value: Arc<AtomicU8>,
+    modulo: u8,
+}
+
+impl Cycling {
+    fn new(value: u8, modulo: u8) -> Self {
+        Self {
+            value: Arc::new(AtomicU8::new(value)),
+            modulo,
+        }
+    }
+
+    fn get(&self) -> u8 {
+        self.value.load(Ordering::SeqCst)
+    }
+}
+
+struct Read(Cycling);
+
+impl Respond for Read {
+    fn respond(&self, _: &Request) -> ResponseTemplate {
+        let body = serde_json::json!({ "status": "ok", "value": self.0.get() });
+        ResponseTemplate::new(200).set_body_json(body)
+    }
+}
+
+struct Toggle(Cycling);
+
+impl Respond for Toggle {
+    fn respond(&self, _: &Request) -> ResponseTemplate {
+        let next = (self.0.get() + 1) % self.0.modulo;
+        self.0.value.store(next, Ordering::SeqCst);
+        ResponseTemplate::new(200)
+    }
+}
+
+/// Mount a getter and a toggle backed by `mode`, expecting `toggles` toggles.
+async fn serve_mode(server: &MockServer, name: &str, mode: &Cycling, toggles: u64) {
+    Mock::given(method("GET"))
+        .and(path(format!("/api/v1/playback/{name}-mode")))
+        .respond_with(Read(mode.clone()))
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!("/api/v1/playback/toggle-{name}")))
+        .respond_with(Toggle(mode.clone()))
+        .expect(toggles)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn set_repeat_mode_cycles_to_target() {
+    let (server, client) = common::setup().await;
+    let mode = Cycling::new(2, 3);
+    serve_mode(&server, "repeat", &mode, 2).await;
+    client.set_repeat_mode(RepeatMode::One).await.unwrap();
+    assert_eq!(mode.get(), 1);
+}
+
+#[tokio::test]
+async fn set_repeat_mode_is_noop_when_already_set() {
+    let (server, client) = common::setup().await;
+    let mode = Cycling::new(2, 3);
+    serve_mode(&server, "repeat", &mode, 0).await;
+    client.set_repeat_mode(RepeatMode::All).await.unwrap();
+}
+
+#[tokio::test]
+async fn set_repeat_mode_reports_mode_that_never_changes() {
+    let (server, client) = common::setup().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/repeat-mode"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(common::fixtures::repeat_mode_json(0))
+                .insert_header("content-type", "application/json"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/toggle-repeat"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    let err = client.set_repeat_mode(RepeatMode::All).await.unwrap_err();
+    let CiderError::NotApplied {
+        expected, actual, ..
+    } = err
+    else {
+        panic!("expected NotApplied, got {err:?}");
+    };
+    assert_eq!((expected.as_str(), actual.as_str()), ("all", "off"));
+}
+
+#[tokio::test]
+async fn set_repeat_mode_rejects_unknown() {
+    let (server, client) = common::setup().await;
+    let mode = Cycling::new(0, 3);
+    serve_mode(&server, "repeat", &mode, 0).await;
+    let err = client
+        .set_repeat_mode(RepeatMode::Unknown(9))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, CiderError::Config(_)));
+}
+
+#[tokio::test]
+async fn set_shuffle_mode_toggles_once() {
+    let (server, client) = common::setup().await;
+    let mode = Cycling::new(0, 2);
+    serve_mode(&server, "shuffle", &mode, 1).await;
+    client.set_shuffle_mode(ShuffleMode::On).await.unwrap();
+    assert_eq!(mode.get(), 1);
+}
+
+#[tokio::test]
+async fn set_shuffle_mode_accepts_bool() {
+    let (server, client) = common::setup().await;
+    let mode = Cycling::new(1, 2);
+    serve_mode(&server, "shuffle", &mode, 0).await;
+    client.set_shuffle_mode(true).await.unwrap();
+}
@@ -0,0 +1,294 @@
+mod common;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use cider_api::{CiderClient, SleepAfter, SleepOutcome};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// Player state shared with the mocked endpoints.
+#[derive(Default)]
+struct Player {
+    volume: Mutex<f32>,
+    volume_sets: Mutex<Vec<f32>>,
+    volume_set: Notify,
+    pauses: AtomicU32,
+    now_playing_polls: AtomicU32,
+}
+
+impl Player {
+    fn sets(&self) -> Vec<f32> {
+        self.volume_sets.lock().unwrap().clone()
+    }
+}
+
+struct GetVolume(Arc<Player>);
+
+impl Respond for GetVolume {
+    fn respond(&self, _: &Request) -> ResponseTemplate {
+        let volume = *self.0.volume.lock().unwrap();
+        ResponseTemplate::new(200)
+            .set_body_json(serde_json::json!({"status": "ok", "volume": volume}))
+    }
+}
+
+struct SetVolume(Arc<Player>);
+
+impl Respond for SetVolume {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        #[allow(clippy::cast_possible_truncation)]
+        let volume = body["volume"].as_f64().unwrap() as f32;
+        *self.0.volume.lock().unwrap() = volume;
+        self.0.volume_sets.lock().unwrap().push(volume);
+        self.0.volume_set.notify_one();
+        ResponseTemplate::new(200)
+    }
+}
+
+struct Pause(Arc<Player>);
+
+impl Respond for Pause {
+    fn respond(&self, _: &Request) -> ResponseTemplate {
+        self.0.pauses.fetch_add(1, Ordering::SeqCst);
+        ResponseTemplate::new(200)
+    }
+}
+
+/// Serves the tracks in order, one per poll, repeating the last one.
+struct NowPlaying {
+    player: Arc<Player>,
+    tracks: Vec<(&'static str, f64)>,
+}
+
+impl Respond for NowPlaying {
+    fn respond(&self, _: &Request) -> ResponseTemplate {
+        let poll = self.player.now_playing_polls.fetch_add(1, Ordering::SeqCst) as usize;
+        let (id, position) = self.tracks[poll.min(self.tracks.len() - 1)];
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "status": "ok",
+            "info": {
+                "name": format!("Track {id}"),
+                "playParams": { "id": id, "kind": "song" },
+                "durationInMillis": 240_000,
+                "currentPlaybackTime": position
+            }
+        }))
+    }
+}
+
+async fn setup(volume: f32, playing: bool) -> (MockServer, CiderClient, Arc<Player>) {
+    let server = MockServer::start().await;
+    let player = Arc::new(Player::default());
+    *player.volume.lock().unwrap() = volume;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/volume"))
+        .respond_with(GetVolume(player.clone()))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/volume"))
+        .respond_with(SetVolume(player.clone()))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/pause"))
+        .respond_with(Pause(player.clone()))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/is-playing"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(common::fixtures::is_playing_json(playing))
+                .insert_header("content-type", "application/json"),
+        )
+        .mount(&server)
+        .await;
+
+    let client = common::paused_clock_client(&server);
+    (server, client, player)
+}
+
+async fn serve_tracks(server: &MockServer, player: &Arc<Player>, tracks: Vec<(&'static str, f64)>) {
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/now-playing"))
+        .respond_with(NowPlaying {
+            player: player.clone(),
+            tracks,
+        })
+        .mount(server)
+        .await;
+}
+
+fn assert_volumes(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len(), "{actual:?}");
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < 1e-5, "{actual:?} != {expected:?}");
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn fades_out_pauses_and_restores_at_deadline() {
+    let (_server, client, player) = setup(0.8, true).await;
+    let start = Instant::now();
+    let outcome = client
+        .sleep_timer(SleepAfter::Duration(Duration::from_secs(60)))
+        .fade(Duration::from_secs(2))
+        .step(Duration::from_millis(500))
+        .start()
+        .wait()
+        .await
+        .unwrap();
+
+    assert_eq!(outcome, SleepOutcome::Paused { volume: 0.8 });
+    assert_volumes(&player.sets(), &[0.6, 0.4, 0.2, 0.0, 0.8]);
+    assert_eq!(player.pauses.load(Ordering::SeqCst), 1);
+    let elapsed = start.elapsed();
+    assert!(
+        elapsed >= Duration::from_secs(60) && elapsed < Duration::from_secs(61),
+        "{elapsed:?}"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn cancel_during_fade_restores_volume() {
+    let (_server, client, player) = setup(0.5, true).await;
+    let timer = client
+        .sleep_timer(SleepAfter::Duration(Duration::from_secs(60)))
+        .fade(Duration::from_secs(10))
+        .step(Duration::from_secs(1))
+        .start();
+
+    player.volume_set.notified().await;
+    player.volume_set.notified().await;
+    timer.cancel();
+
+    assert_eq!(timer.wait().await.unwrap(), SleepOutcome::Cancelled);
+    let sets = player.sets();
+    assert_eq!(sets.len(), 3, "two fade steps and the restore: {sets:?}");
+    assert!((sets[2] - 0.5).abs() < f32::EPSILON);
+    assert_eq!(player.pauses.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn nothing_playing_touches_nothing() {
+    let (_server, client, player) = setup(0.5, false).await;
+    let outcome = client
+        .sleep_timer(SleepAfter::Duration(Duration::from_secs(5)))
+        .start()
+        .wait()
+        .await
+        .unwrap();
+    assert_eq!(outcome, SleepOutcome::NotPlaying);
+    assert!(player.sets().is_empty());
+    assert_eq!(player.pauses.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn transient_failures_are_survived() {
+    let (server, client, player) = setup(1.0, true).await;
+    // The first volume step and the first pause fail with 503.
+    for route in ["/api/v1/playback/volume", "/api/v1/playback/pause"] {
+        Mock::given(method("POST"))
+            .and(path(route))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+    }
+
+    let outcome = client
+        .sleep_timer(SleepAfter::Duration(Duration::from_secs(10)))
+        .fade(Duration::from_secs(2))
+        .step(Duration::from_millis(500))
+        .start()
+        .wait()
+        .await
+        .unwrap();
+
+    assert_eq!(outcome, SleepOutcome::Paused { volume: 1.0 });
+    assert_volumes(&player.sets(), &[0.5, 0.25, 0.0, 1.0]);
+    assert_eq!(player.pauses.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn fatal_error_restores_volume_and_reports() {
+    let (server, client, player) = setup(0.7, true).await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/playback/pause"))
+        .respond_with(ResponseTemplate::new(401))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+
+    let err = client
+        .sleep_timer(SleepAfter::Duration(Duration::from_secs(1)))
+        .fade(Duration::from_secs(1))
+        .start()
+        .wait()
+        .await
+        .unwrap_err();
+
+    assert!(err.is_auth());
+    assert!((player.sets().last().unwrap() - 0.7).abs() < f32::EPSILON);
+}
+
+#[tokio::test(start_paused = true)]
+async fn end_of_track_fades_into_the_end() {
+    let (server, client, player) = setup(0.4, true).await;
+    // 230s into a 240s track, then 236s when re-checked 6s later.
+    serve_tracks(&server, &player, vec![("1", 230.0), ("1", 236.0)]).await;
+
+    let start = Instant::now();
+    let outcome = client
+        .sleep_timer(SleepAfter::EndOfTrack)
+        .fade(Duration::from_secs(4))
+        .step(Duration::from_secs(1))
+        .poll_interval(Duration::from_secs(60))
+        .start()
+        .wait()
+        .await
+        .unwrap();
+
+    assert_eq!(outcome, SleepOutcome::Paused { volume: 0.4 });
+    assert_eq!(player.pauses.load(Ordering::SeqCst), 1);
+    // 6s until the fade window, then 4s of fade.
+    let elapsed = start.elapsed();
+    assert!(
+        elapsed >= Duration::from_secs(10) && elapsed < Duration::from_secs(11),
+        "{elapsed:?}"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn counts_tracks_before_stopping() {
+    let (server, client, player) = setup(0.6, true).await;
+    // Track 1 near its end, then track 2 from the start, then near its end.
+    serve_tracks(
+        &server,
+        &player,
+        vec![("1", 238.0), ("2", 0.0), ("2", 239.0)],
+    )
+    .await;
+
+    let outcome = client
+        .sleep_timer(SleepAfter::Tracks(2))
+        .fade(Duration::from_secs(2))
+        .step(Duration::from_secs(1))
+        .poll_interval(Duration::from_secs(5))
+        .start()
+        .wait()
+        .await
+        .unwrap();
+
+    assert_eq!(outcome, SleepOutcome::Paused { volume: 0.6 });
+    assert!(player.now_playing_polls.load(Ordering::SeqCst) >= 3);
+    assert_eq!(player.pauses.load(Ordering::SeqCst), 1);
+}
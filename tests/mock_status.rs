@@ -38,7 +38,8 @@ async fn is_active_unauthorized_on_401() {
         .mount(&server)
         .await;
     let err = client.is_active().await.unwrap_err();
-    assert!(matches!(err, cider_api::CiderError::Unauthorized));
+    assert!(matches!(err, cider_api::CiderError::Unauthorized { status, .. } if status == 401));
+    assert!(err.is_auth());
 }
 
 #[tokio::test]
@@ -50,7 +51,7 @@ async fn is_active_unauthorized_on_403() {
         .mount(&server)
         .await;
     let err = client.is_active().await.unwrap_err();
-    assert!(matches!(err, cider_api::CiderError::Unauthorized));
+    assert!(matches!(err, cider_api::CiderError::Unauthorized { status, .. } if status == 403));
 }
 
 #[tokio::test]
@@ -62,7 +63,7 @@ async fn is_active_api_error_on_unexpected_status() {
         .mount(&server)
         .await;
     let err = client.is_active().await.unwrap_err();
-    assert!(matches!(err, cider_api::CiderError::Api(_)));
+    assert!(matches!(err, cider_api::CiderError::Api { status, .. } if status == 418));
 }
 
 // ── is_playing ──
@@ -158,3 +159,87 @@ async fn now_playing_returns_none_on_unparseable_json() {
         .await;
     assert!(client.now_playing().await.unwrap().is_none());
 }
+
+#[tokio::test]
+async fn now_playing_strict_returns_decode_error() {
+    let (server, client) = common::setup_strict().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/now-playing"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(r#"{"status":"ok","info":{"name":"X","durationInMillis":"soon"}}"#)
+                .insert_header("content-type", "application/json"),
+        )
+        .mount(&server)
+        .await;
+    let err = client.now_playing().await.unwrap_err();
+    match err {
+        cider_api::CiderError::Decode {
+            endpoint,
+            status,
+            path,
+            body,
+            ..
+        } => {
+            assert_eq!(endpoint.to_string(), "GET /api/v1/playback/now-playing");
+            assert_eq!(status.map(|s| s.as_u16()), Some(200));
+            assert_eq!(path, "info.durationInMillis");
+            assert!(body.contains("soon"));
+        }
+        other => panic!("expected Decode, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn now_playing_strict_returns_decode_error_on_missing_info() {
+    let (server, client) = common::setup_strict().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/now-playing"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(r#"{"garbage": true}"#)
+                .insert_header("content-type", "application/json"),
+        )
+        .mount(&server)
+        .await;
+    let err = client.now_playing().await.unwrap_err();
+    assert!(matches!(err, cider_api::CiderError::Decode { .. }));
+}
+
+#[tokio::test]
+async fn now_playing_strict_still_returns_none_on_404() {
+    let (server, client) = common::setup_strict().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/now-playing"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+    assert!(client.now_playing().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn now_playing_strict_errors_on_500() {
+    let (server, client) = common::setup_strict().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/now-playing"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+    assert!(client.now_playing().await.is_err());
+}
+
+#[tokio::test]
+async fn now_playing_strict_returns_track() {
+    let (server, client) = common::setup_strict().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/playback/now-playing"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(common::fixtures::now_playing_json())
+                .insert_header("content-type", "application/json"),
+        )
+        .mount(&server)
+        .await;
+    let track = client.now_playing().await.unwrap().unwrap();
+    assert_eq!(track.name, "Never Be Like You");
+}
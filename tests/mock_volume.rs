@@ -1,7 +1,12 @@
 mod common;
 
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use cider_api::{CiderClient, FadeCurve};
 use wiremock::matchers::{body_json, method, path};
-use wiremock::{Mock, ResponseTemplate};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
 
 #[tokio::test]
 async fn get_volume_returns_value() {
@@ -58,3 +63,214 @@ async fn set_volume_clamps_below_0() {
         .await;
     client.set_volume(-0.5).await.unwrap();
 }
+
+// ── Ramps, mute and ducking ──────────────────────────────────────────────
+
+/// A volume knob: `GET` reads it, `POST` turns it and records the value.
+#[derive(Default)]
+struct Knob {
+    volume: Mutex<f32>,
+    sets: Mutex<Vec<f32>>,
+    /// Answer `GET` with a server error.
+    fail_reads: AtomicBool,
+}
+
+impl Knob {
+    fn volume(&self) -> f32 {
+        *self.volume.lock().unwrap()
+    }
+
+    /// Change the volume behind the client's back, like the Cider UI would.
+    fn turn(&self, volume: f32) {
+        *self.volume.lock().unwrap() = volume;
+    }
+
+    fn sets(&self) -> Vec<f32> {
+        self.sets.lock().unwrap().clone()
+    }
+}
+
+struct KnobResponder(Arc<Knob>);
+
+impl Respond for KnobResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        if request.method == wiremock::http::Method::POST {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            #[allow(clippy::cast_possible_truncation)]
+            let volume = body["volume"].as_f64().unwrap() as f32;
+            self.0.turn(volume);
+            self.0.sets.lock().unwrap().push(volume);
+            return ResponseTemplate::new(200);
+        }
+        if self.0.fail_reads.load(Ordering::SeqCst) {
+            return ResponseTemplate::new(500);
+        }
+        ResponseTemplate::new(200)
+            .set_body_string(common::fixtures::volume_json(self.0.volume()))
+            .insert_header("content-type", "application/json")
+    }
+}
+
+async fn setup_knob(volume: f32) -> (MockServer, CiderClient, Arc<Knob>) {
+    let server = MockServer::start().await;
+    let knob = Arc::new(Knob::default());
+    knob.turn(volume);
+    Mock::given(path("/api/v1/playback/volume"))
+        .respond_with(KnobResponder(knob.clone()))
+        .mount(&server)
+        .await;
+    let client = common::paused_clock_client(&server);
+    (server, client, knob)
+}
+
+fn assert_volume(actual: f32, expected: f32) {
+    assert!((actual - expected).abs() < 1e-5, "{actual} != {expected}");
+}
+
+#[tokio::test(start_paused = true)]
+async fn ramp_volume_steps_to_target() {
+    let (_server, client, knob) = setup_knob(0.2).await;
+    let start = tokio::time::Instant::now();
+    client
+        .ramp_volume(0.6, Duration::from_millis(200), FadeCurve::Linear)
+        .await
+        .unwrap();
+
+    let sets = knob.sets();
+    assert_eq!(sets.len(), 4, "{sets:?}");
+    for (actual, expected) in sets.iter().zip([0.3, 0.4, 0.5, 0.6]) {
+        assert_volume(*actual, expected);
+    }
+    assert_eq!(start.elapsed(), Duration::from_millis(200));
+}
+
+#[tokio::test(start_paused = true)]
+async fn newer_ramp_supersedes_older_one() {
+    let (_server, client, knob) = setup_knob(1.0).await;
+    let slow = client.ramp_volume(0.0, Duration::from_secs(10), FadeCurve::Linear);
+    let fast = async {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        client
+            .ramp_volume(0.5, Duration::from_millis(100), FadeCurve::Linear)
+            .await
+    };
+    let (slow, fast) = tokio::join!(slow, fast);
+    slow.unwrap();
+    fast.unwrap();
+
+    assert_volume(knob.volume(), 0.5);
+    // The slow ramp stopped around a tenth of the way down.
+    assert!(knob.sets().len() < 30, "{:?}", knob.sets());
+}
+
+#[tokio::test]
+async fn mute_then_unmute_restores_level() {
+    let (_server, client, knob) = setup_knob(0.7).await;
+    client.mute().await.unwrap();
+    assert_volume(knob.volume(), 0.0);
+    // Muting again keeps the level from before the first mute.
+    client.mute().await.unwrap();
+    assert_volume(client.unmute().await.unwrap(), 0.7);
+    assert_volume(knob.volume(), 0.7);
+}
+
+#[tokio::test]
+async fn unmute_after_volume_raised_elsewhere_leaves_it() {
+    let (_server, client, knob) = setup_knob(0.7).await;
+    client.mute().await.unwrap();
+    knob.turn(0.4);
+    assert_volume(client.unmute().await.unwrap(), 0.4);
+    assert_volume(knob.volume(), 0.4);
+    assert_eq!(knob.sets().len(), 1);
+}
+
+#[tokio::test]
+async fn overlapping_ducks_restore_original_level() {
+    let (_server, client, knob) = setup_knob(0.8).await;
+    let first = client.duck(0.3).await.unwrap();
+    assert_volume(knob.volume(), 0.3);
+    let second = client.clone().duck(0.1).await.unwrap();
+    assert_volume(knob.volume(), 0.1);
+
+    // Ending the first duck keeps the lower one in effect.
+    first.release().await.unwrap();
+    assert_volume(knob.volume(), 0.1);
+    second.release().await.unwrap();
+    assert_volume(knob.volume(), 0.8);
+}
+
+#[tokio::test]
+async fn ducks_released_in_reverse_order_restore_original_level() {
+    let (_server, client, knob) = setup_knob(0.8).await;
+    let first = client.duck(0.1).await.unwrap();
+    let second = client.duck(0.3).await.unwrap();
+    assert_volume(knob.volume(), 0.1);
+    first.release().await.unwrap();
+    assert_volume(knob.volume(), 0.3);
+    second.release().await.unwrap();
+    assert_volume(knob.volume(), 0.8);
+}
+
+#[tokio::test]
+async fn failed_read_still_ends_duck() {
+    let (_server, client, knob) = setup_knob(0.8).await;
+    let first = client.duck(0.1).await.unwrap();
+    let second = client.duck(0.3).await.unwrap();
+
+    knob.fail_reads.store(true, Ordering::SeqCst);
+    assert!(first.release().await.is_err());
+    assert_volume(knob.volume(), 0.3);
+
+    // The first duck is gone, so ending the second restores the volume.
+    knob.fail_reads.store(false, Ordering::SeqCst);
+    second.release().await.unwrap();
+    assert_volume(knob.volume(), 0.8);
+}
+
+#[tokio::test]
+async fn duck_never_raises_volume() {
+    let (_server, client, knob) = setup_knob(0.2).await;
+    let guard = client.duck(0.5).await.unwrap();
+    guard.release().await.unwrap();
+    assert_volume(knob.volume(), 0.2);
+    assert!(knob.sets().iter().all(|&v| (v - 0.2).abs() < 1e-5));
+}
+
+#[tokio::test]
+async fn change_during_duck_becomes_restored_level() {
+    let (_server, client, knob) = setup_knob(0.8).await;
+    let first = client.duck(0.2).await.unwrap();
+    knob.turn(0.5);
+    // The next duck operation picks up the new level as the one to restore.
+    let second = client.duck(0.3).await.unwrap();
+    assert_volume(knob.volume(), 0.2);
+    first.release().await.unwrap();
+    assert_volume(knob.volume(), 0.3);
+    second.release().await.unwrap();
+    assert_volume(knob.volume(), 0.5);
+}
+
+#[tokio::test]
+async fn mute_during_duck_unmutes_to_original_level() {
+    let (_server, client, knob) = setup_knob(0.8).await;
+    let guard = client.duck(0.2).await.unwrap();
+    client.mute().await.unwrap();
+    assert_volume(knob.volume(), 0.0);
+    guard.release().await.unwrap();
+    assert_volume(knob.volume(), 0.0);
+    assert_volume(client.unmute().await.unwrap(), 0.8);
+    assert_volume(knob.volume(), 0.8);
+}
+
+#[tokio::test]
+async fn dropping_duck_guard_restores_volume() {
+    let (_server, client, knob) = setup_knob(0.6).await;
+    drop(client.duck(0.1).await.unwrap());
+    for _ in 0..100 {
+        if (knob.volume() - 0.6).abs() < 1e-5 {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("volume not restored: {}", knob.volume());
+}
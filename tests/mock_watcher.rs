@@ -0,0 +1,246 @@
+mod common;
+
+use std::time::Duration;
+
+use cider_api::{PlaybackWatcher, RepeatMode, ShuffleMode, WatchEvent};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Player state served by the mock.
+#[derive(Clone)]
+struct State {
+    track: Option<(&'static str, &'static str, f64)>,
+    playing: bool,
+    volume: f32,
+    repeat: u8,
+    shuffle: u8,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            track: Some(("1719861213", "Never Be Like You", 42.5)),
+            playing: true,
+            volume: 0.5,
+            repeat: 0,
+            shuffle: 0,
+        }
+    }
+}
+
+async fn json(server: &MockServer, route: &str, body: String) {
+    Mock::given(method("GET"))
+        .and(path(route))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(body)
+                .insert_header("content-type", "application/json"),
+        )
+        .mount(server)
+        .await;
+}
+
+/// Replace every mocked endpoint with `state`.
+async fn serve(server: &MockServer, state: &State) {
+    server.reset().await;
+    match state.track {
+        Some((id, name, position)) => {
+            let body = serde_json::json!({
+                "status": "ok",
+                "info": {
+                    "name": name,
+                    "artistName": "Flume",
+                    "albumName": "Skin",
+                    "playParams": { "id": id, "kind": "song" },
+                    "currentPlaybackTime": position
+                }
+            });
+            json(server, "/api/v1/playback/now-playing", body.to_string()).await;
+        }
+        None => {
+            Mock::given(method("GET"))
+                .and(path("/api/v1/playback/now-playing"))
+                .respond_with(ResponseTemplate::new(404))
+                .mount(server)
+                .await;
+        }
+    }
+    let is_playing = common::fixtures::is_playing_json(state.playing);
+    json(server, "/api/v1/playback/is-playing", is_playing).await;
+    let volume = common::fixtures::volume_json(state.volume);
+    json(server, "/api/v1/playback/volume", volume).await;
+    let repeat = common::fixtures::repeat_mode_json(state.repeat);
+    json(server, "/api/v1/playback/repeat-mode", repeat).await;
+    let shuffle = common::fixtures::shuffle_mode_json(state.shuffle);
+    json(server, "/api/v1/playback/shuffle-mode", shuffle).await;
+}
+
+async fn setup(state: &State) -> (MockServer, PlaybackWatcher) {
+    let (server, client) = common::setup().await;
+    serve(&server, state).await;
+    let mut watcher = client.watch().interval(Duration::from_millis(10));
+    assert!(watcher.poll().await.is_empty(), "first poll is a baseline");
+    (server, watcher)
+}
+
+#[tokio::test]
+async fn baseline_snapshot_is_recorded() {
+    let (_server, watcher) = setup(&State::default()).await;
+    let snapshot = watcher.snapshot().unwrap();
+    assert_eq!(snapshot.track.as_ref().unwrap().name, "Never Be Like You");
+    assert!(snapshot.is_playing);
+    assert!((snapshot.volume - 0.5).abs() < f32::EPSILON);
+}
+
+#[tokio::test]
+async fn unchanged_state_emits_nothing() {
+    let (_server, mut watcher) = setup(&State::default()).await;
+    assert!(watcher.poll().await.is_empty());
+}
+
+#[tokio::test]
+async fn track_change_is_reported() {
+    let (server, mut watcher) = setup(&State::default()).await;
+    let state = State {
+        track: Some(("1", "Say It", 0.0)),
+        ..State::default()
+    };
+    serve(&server, &state).await;
+    let events = watcher.poll().await;
+    let [WatchEvent::TrackChanged {
+        from: Some(from),
+        to: Some(to),
+    }] = &events[..]
+    else {
+        panic!("unexpected events {events:?}");
+    };
+    assert_eq!(from.name, "Never Be Like You");
+    assert_eq!(to.name, "Say It");
+}
+
+#[tokio::test]
+async fn stop_reports_track_gone_and_pause() {
+    let (server, mut watcher) = setup(&State::default()).await;
+    let state = State {
+        track: None,
+        playing: false,
+        ..State::default()
+    };
+    serve(&server, &state).await;
+    let events = watcher.poll().await;
+    assert!(matches!(
+        &events[..],
+        [
+            WatchEvent::TrackChanged { to: None, .. },
+            WatchEvent::Paused
+        ]
+    ));
+}
+
+#[tokio::test]
+async fn pause_and_resume() {
+    let (server, mut watcher) = setup(&State::default()).await;
+    let paused = State {
+        playing: false,
+        ..State::default()
+    };
+    serve(&server, &paused).await;
+    assert!(matches!(&watcher.poll().await[..], [WatchEvent::Paused]));
+
+    serve(&server, &State::default()).await;
+    assert!(matches!(&watcher.poll().await[..], [WatchEvent::Resumed]));
+}
+
+#[tokio::test]
+async fn seek_is_reported() {
+    let (server, mut watcher) = setup(&State::default()).await;
+    let state = State {
+        track: Some(("1719861213", "Never Be Like You", 180.0)),
+        ..State::default()
+    };
+    serve(&server, &state).await;
+    let events = watcher.poll().await;
+    let [WatchEvent::Seeked { to, .. }] = &events[..] else {
+        panic!("unexpected events {events:?}");
+    };
+    assert_eq!(*to, Duration::from_secs(180));
+}
+
+#[tokio::test]
+async fn volume_repeat_and_shuffle_changes() {
+    let (server, mut watcher) = setup(&State::default()).await;
+    let state = State {
+        volume: 0.8,
+        repeat: 2,
+        shuffle: 1,
+        ..State::default()
+    };
+    serve(&server, &state).await;
+    let events = watcher.poll().await;
+    assert!(matches!(
+        &events[..],
+        [
+            WatchEvent::VolumeChanged { .. },
+            WatchEvent::RepeatChanged {
+                from: RepeatMode::Off,
+                to: RepeatMode::All
+            },
+            WatchEvent::ShuffleChanged {
+                from: ShuffleMode::Off,
+                to: ShuffleMode::On
+            },
+        ]
+    ));
+}
+
+#[tokio::test]
+async fn cider_going_away_and_coming_back() {
+    let (server, mut watcher) = setup(&State::default()).await;
+
+    server.reset().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&server)
+        .await;
+    let events = watcher.poll().await;
+    let [WatchEvent::CiderWentAway(err)] = &events[..] else {
+        panic!("unexpected events {events:?}");
+    };
+    assert!(err.is_retryable());
+    assert!(watcher.poll().await.is_empty(), "outage is reported once");
+
+    let state = State {
+        volume: 0.2,
+        ..State::default()
+    };
+    serve(&server, &state).await;
+    assert!(matches!(
+        &watcher.poll().await[..],
+        [WatchEvent::CiderCameBack, WatchEvent::VolumeChanged { .. }]
+    ));
+}
+
+#[tokio::test]
+async fn next_waits_for_a_change() {
+    let (server, mut watcher) = setup(&State::default()).await;
+    let state = State {
+        shuffle: 1,
+        ..State::default()
+    };
+    let changer = async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        serve(&server, &state).await;
+    };
+    let (event, ()) = tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::join!(watcher.next(), changer)
+    })
+    .await
+    .unwrap();
+    assert!(matches!(
+        event,
+        WatchEvent::ShuffleChanged {
+            from: ShuffleMode::Off,
+            to: ShuffleMode::On
+        }
+    ));
+}
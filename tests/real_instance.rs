@@ -6,7 +6,7 @@
 //! These tests run sequentially (`--test-threads=1` recommended) since they
 //! share playback state on a single Cider instance.
 
-use cider_api::CiderClient;
+use cider_api::{CiderClient, Rating, RepeatMode, ShuffleMode};
 
 fn live_client() -> Option<CiderClient> {
     let port: u16 = std::env::var("CIDER_TEST_PORT").ok()?.parse().ok()?;
@@ -112,6 +112,7 @@ async fn live_next_previous() {
 }
 
 #[tokio::test]
+#[allow(clippy::manual_range_contains)]
 async fn live_seek() {
     let client = skip_unless_live!();
 
@@ -188,7 +189,7 @@ async fn live_repeat_mode_toggle_and_restore() {
     let client = skip_unless_live!();
 
     let original = client.get_repeat_mode().await.unwrap();
-    assert!(original <= 2);
+    assert!(!matches!(original, RepeatMode::Unknown(_)));
 
     // Toggle three times to cycle through all modes and back
     client.toggle_repeat().await.unwrap();
@@ -213,7 +214,7 @@ async fn live_shuffle_mode_toggle_and_restore() {
     let client = skip_unless_live!();
 
     let original = client.get_shuffle_mode().await.unwrap();
-    assert!(original <= 1);
+    assert!(!matches!(original, ShuffleMode::Unknown(_)));
 
     client.toggle_shuffle().await.unwrap();
     tokio::time::sleep(std::time::Duration::from_millis(200)).await;
@@ -227,6 +228,18 @@ async fn live_shuffle_mode_toggle_and_restore() {
     assert_eq!(original, restored, "Shuffle should be back to original");
 }
 
+#[tokio::test]
+async fn live_set_repeat_mode_and_restore() {
+    let client = skip_unless_live!();
+
+    let original = client.get_repeat_mode().await.unwrap();
+    for mode in [RepeatMode::All, RepeatMode::One, RepeatMode::Off] {
+        client.set_repeat_mode(mode).await.unwrap();
+        assert_eq!(client.get_repeat_mode().await.unwrap(), mode);
+    }
+    client.set_repeat_mode(original).await.unwrap();
+}
+
 #[tokio::test]
 async fn live_autoplay_toggle_and_restore() {
     let client = skip_unless_live!();
@@ -261,11 +274,11 @@ async fn live_set_rating_and_clear() {
     let client = skip_unless_live!();
     if client.now_playing().await.unwrap().is_some() {
         // Like
-        client.set_rating(1).await.unwrap();
+        client.set_rating(Rating::Like).await.unwrap();
         tokio::time::sleep(std::time::Duration::from_millis(200)).await;
 
         // Clear rating
-        client.set_rating(0).await.unwrap();
+        client.set_rating(Rating::None).await.unwrap();
     }
 }
 